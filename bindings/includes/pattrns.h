@@ -12,7 +12,7 @@ namespace pattrns {
 constexpr static const uint32_t NO_INSTRUMENT_ID = UINT32_MAX;
 
 /// Parameter change value which refers to an empty, undefined parameter.
-constexpr static const uint32_t NO_PARAMETER_ID = UINT32_MAX;
+constexpr static const uint64_t NO_PARAMETER_ID = UINT64_MAX;
 
 /// Note value which refers to an empty, undefined note.
 constexpr static const uint8_t EMPTY_NOTE = 254;
@@ -20,6 +20,12 @@ constexpr static const uint8_t EMPTY_NOTE = 254;
 /// Note value which should turn off notes playing on the same column
 constexpr static const uint8_t NOTE_OFF = 255;
 
+/// Note speed value which refers to an unset speed: playback speed is derived from the note.
+constexpr static const float NO_SPEED = 0.0;
+
+/// Note ratchet value which refers to an unset ratchet: the note is triggered once.
+constexpr static const uint32_t NO_RATCHET = 0;
+
 /// C lang compatible representation of a rust `pattrns::ParameterType`.
 enum class ParameterType {
   Boolean,
@@ -28,6 +34,18 @@ enum class ParameterType {
   Enum,
 };
 
+/// C lang compatible representation of a rust `pattrns::CycleTokenKind`.
+enum class CycleTokenKind {
+  Value,
+  Rest,
+  Hold,
+  Reference,
+  Operator,
+  GroupOpen,
+  GroupClose,
+  Separator,
+};
+
 /// C lang compatible representation of a rust `pattrns::Pattern`.
 struct Pattern;
 
@@ -49,6 +67,7 @@ struct Parameter {
   double default_;
   double value;
   ValueStrings value_strings;
+  uint64_t id_hash;
 };
 
 /// C lang compatible representation of a rust `ParameterMap` using a C Array.
@@ -57,7 +76,18 @@ struct ParameterSet {
   uint32_t parameters_len;
 };
 
+/// C lang compatible representation of a rust `pattrns::ErrorRange`.
+/// Lines and columns are 1-based and count characters. The end column is exclusive.
+/// All values are 0 when the range is unknown.
+struct ErrorRange {
+  uint32_t start_line;
+  uint32_t start_column;
+  uint32_t end_line;
+  uint32_t end_column;
+};
+
 /// C lang compatible Result<Pattern, String> representation for new_pattern_from_string/file.
+/// Errors carry the error's source range, e.g. to highlight syntax errors in editors.
 /// Error Strings must be deleted with `drop_error_string`.
 /// Pattern values must be deleted with `drop_pattern`,
 struct PatternResult {
@@ -68,6 +98,7 @@ struct PatternResult {
 
   struct Error_Body {
     const char *_0;
+    ErrorRange _1;
   };
 
   struct Value_Body {
@@ -80,9 +111,10 @@ struct PatternResult {
     Value_Body value;
   };
 
-  static PatternResult Error(const char *const &_0) {
+  static PatternResult Error(const char *const &_0, const ErrorRange &_1) {
     PatternResult result;
     ::new (&result.error._0) (const char*)(_0);
+    ::new (&result.error._1) (ErrorRange)(_1);
     result.tag = Tag::Error;
     return result;
   }
@@ -91,9 +123,9 @@ struct PatternResult {
     return tag == Tag::Error;
   }
 
-  const char*const & AsError() const {
+  const Error_Body& AsError() const {
     assert(IsError());
-    return error._0;
+    return error;
   }
 
   static PatternResult Value(Pattern *const &_0) {
@@ -176,6 +208,118 @@ struct ParameterSetResult {
   }
 };
 
+/// C lang compatible representation of a rust `Result<ValueStrings>`.
+/// Error strings must be released manually with `drop_error_string`.
+/// Values must be released manually with `drop_value_strings`.
+struct ValueStringsResult {
+  enum class Tag {
+    Error,
+    Value,
+  };
+
+  struct Error_Body {
+    const char *_0;
+  };
+
+  struct Value_Body {
+    ValueStrings *_0;
+  };
+
+  Tag tag;
+  union {
+    Error_Body error;
+    Value_Body value;
+  };
+
+  static ValueStringsResult Error(const char *const &_0) {
+    ValueStringsResult result;
+    ::new (&result.error._0) (const char*)(_0);
+    result.tag = Tag::Error;
+    return result;
+  }
+
+  bool IsError() const {
+    return tag == Tag::Error;
+  }
+
+  const char*const & AsError() const {
+    assert(IsError());
+    return error._0;
+  }
+
+  static ValueStringsResult Value(ValueStrings *const &_0) {
+    ValueStringsResult result;
+    ::new (&result.value._0) (ValueStrings*)(_0);
+    result.tag = Tag::Value;
+    return result;
+  }
+
+  bool IsValue() const {
+    return tag == Tag::Value;
+  }
+
+  ValueStrings*const & AsValue() const {
+    assert(IsValue());
+    return value._0;
+  }
+};
+
+/// C lang compatible representation of a rust `Result<String>`.
+/// Error strings must be released manually with `drop_error_string`.
+/// Value strings must be released manually with `drop_string`.
+struct StringResult {
+  enum class Tag {
+    Error,
+    Value,
+  };
+
+  struct Error_Body {
+    const char *_0;
+  };
+
+  struct Value_Body {
+    const char *_0;
+  };
+
+  Tag tag;
+  union {
+    Error_Body error;
+    Value_Body value;
+  };
+
+  static StringResult Error(const char *const &_0) {
+    StringResult result;
+    ::new (&result.error._0) (const char*)(_0);
+    result.tag = Tag::Error;
+    return result;
+  }
+
+  bool IsError() const {
+    return tag == Tag::Error;
+  }
+
+  const char*const & AsError() const {
+    assert(IsError());
+    return error._0;
+  }
+
+  static StringResult Value(const char *const &_0) {
+    StringResult result;
+    ::new (&result.value._0) (const char*)(_0);
+    result.tag = Tag::Value;
+    return result;
+  }
+
+  bool IsValue() const {
+    return tag == Tag::Value;
+  }
+
+  const char*const & AsValue() const {
+    assert(IsValue());
+    return value._0;
+  }
+};
+
 /// C lang compatible representation of a rust `Result<()>`.
 /// Error strings must be released manually with `drop_error_string`.
 struct VoidResult {
@@ -335,6 +479,61 @@ struct UInt32Result {
   }
 };
 
+/// C lang compatible representation of a rust `Result<u64>`.
+/// Error strings must be released manually with `drop_error_string`.
+struct UInt64Result {
+  enum class Tag {
+    Error,
+    Value,
+  };
+
+  struct Error_Body {
+    const char *_0;
+  };
+
+  struct Value_Body {
+    uint64_t _0;
+  };
+
+  Tag tag;
+  union {
+    Error_Body error;
+    Value_Body value;
+  };
+
+  static UInt64Result Error(const char *const &_0) {
+    UInt64Result result;
+    ::new (&result.error._0) (const char*)(_0);
+    result.tag = Tag::Error;
+    return result;
+  }
+
+  bool IsError() const {
+    return tag == Tag::Error;
+  }
+
+  const char*const & AsError() const {
+    assert(IsError());
+    return error._0;
+  }
+
+  static UInt64Result Value(const uint64_t &_0) {
+    UInt64Result result;
+    ::new (&result.value._0) (uint64_t)(_0);
+    result.tag = Tag::Value;
+    return result;
+  }
+
+  bool IsValue() const {
+    return tag == Tag::Value;
+  }
+
+  const uint64_t& AsValue() const {
+    assert(IsValue());
+    return value._0;
+  }
+};
+
 /// C lang compatible representation of a rust `pattrns::NoteEvent`.
 struct NoteEvent {
   uint8_t note;
@@ -342,6 +541,8 @@ struct NoteEvent {
   float volume;
   float panning;
   float delay;
+  float speed;
+  uint32_t ratchet;
 };
 
 /// C lang compatible representation of a rust `Vec<pattrns::NoteEvent>`.
@@ -352,7 +553,7 @@ struct NoteEvents {
 
 /// C lang compatible representation of a rust `pattrns::ParameterChangeEvent`.
 struct ParameterChangeEvent {
-  uint32_t parameter;
+  uint64_t parameter;
   float value;
 };
 
@@ -369,6 +570,85 @@ struct PatternPlaybackEvent {
   uint64_t duration_in_samples;
   NoteEvents note_events;
   ParameterChangeEvents parameter_change_events;
+  /// Musical length of the event's notes in samples, when known, else 0.
+  uint64_t note_duration_in_samples;
+};
+
+/// Byte range in a tokenized mini-notation string. The end is exclusive.
+struct CycleRange {
+  uint32_t start;
+  uint32_t end;
+};
+
+/// C lang compatible representation of a rust `pattrns::CycleToken`.
+struct CycleToken {
+  CycleTokenKind kind;
+  CycleRange range;
+};
+
+/// C lang compatible representation of a rust `Vec<CycleToken>` using a C Array.
+struct CycleTokens {
+  const CycleToken *tokens_ptr;
+  uint32_t tokens_len;
+};
+
+/// C lang compatible representation of a rust `Result<CycleTokens>`.
+/// Errors carry the byte range of the syntax error in the tokenized string.
+/// Error strings must be released manually with `drop_error_string`.
+/// Values must be released manually with `drop_cycle_tokens`.
+struct CycleTokensResult {
+  enum class Tag {
+    Error,
+    Value,
+  };
+
+  struct Error_Body {
+    const char *_0;
+    CycleRange _1;
+  };
+
+  struct Value_Body {
+    CycleTokens *_0;
+  };
+
+  Tag tag;
+  union {
+    Error_Body error;
+    Value_Body value;
+  };
+
+  static CycleTokensResult Error(const char *const &_0, const CycleRange &_1) {
+    CycleTokensResult result;
+    ::new (&result.error._0) (const char*)(_0);
+    ::new (&result.error._1) (CycleRange)(_1);
+    result.tag = Tag::Error;
+    return result;
+  }
+
+  bool IsError() const {
+    return tag == Tag::Error;
+  }
+
+  const Error_Body& AsError() const {
+    assert(IsError());
+    return error;
+  }
+
+  static CycleTokensResult Value(CycleTokens *const &_0) {
+    CycleTokensResult result;
+    ::new (&result.value._0) (CycleTokens*)(_0);
+    result.tag = Tag::Value;
+    return result;
+  }
+
+  bool IsValue() const {
+    return tag == Tag::Value;
+  }
+
+  CycleTokens*const & AsValue() const {
+    assert(IsValue());
+    return value._0;
+  }
 };
 
 using AllocFn = void*(*)(uint32_t, uint32_t);
@@ -380,9 +660,15 @@ extern "C" {
 /// Delete an error string from the Result wrappers.
 void drop_error_string(const char *error);
 
+/// Delete a value string from a `StringResult`.
+void drop_string(const char *string);
+
 /// Drop array of input parameters, created via `pattern_parameters`
 void drop_parameter_set(ParameterSet *parameters);
 
+/// Drop array of strings, created via `pattern_warnings`
+void drop_value_strings(ValueStrings *strings);
+
 /// Create a new pattern from the given script file path, using the given beat time and instrument.
 /// The returned pattern result must be deleted via `drop_pattern` or `drop_error_string`.
 PatternResult new_pattern_from_file(Timebase time_base,
@@ -396,6 +682,24 @@ PatternResult new_pattern_from_string(Timebase time_base,
                                       const char *content,
                                       const char *content_name);
 
+/// Create a new pattern from the given script contents, using the given beat time and instrument,
+/// calling the given register callback before the script gets evaluated, so the host can register
+/// custom globals such as functions or libraries.
+///
+/// The callback gets called with the given context and a raw `lua_State*`, which must be accessed
+/// with the C API of the Lua interpreter this library got built with. The state pointer is only
+/// valid within the callback and must not be stored. Registered functions however are owned by the
+/// pattern and will be called, as long as the pattern or one of its instances is alive: anything
+/// they refer to must thus outlive all pattern instances.
+///
+/// The returned pattern result must be deleted via `drop_pattern` or `drop_error_string`.
+PatternResult new_pattern_from_string_with_env(Timebase time_base,
+                                               const uint32_t *instrument_id,
+                                               const char *content,
+                                               const char *content_name,
+                                               void *register_context,
+                                               void (*register_callback)(void*, void*));
+
 /// Create a new resetted clone from an existing pattern with the given timebase and instrument id.
 /// The returned pattern result must be deleted via `drop_pattern` or `drop_error_string`.
 PatternResult new_pattern_instance(Pattern *this_, Timebase time_base);
@@ -404,18 +708,69 @@ PatternResult new_pattern_instance(Pattern *this_, Timebase time_base);
 /// The returned result must be deleted via `drop_parameter_set` or `drop_error_string`.
 ParameterSetResult pattern_parameters(Pattern *this_);
 
+/// Get the current values of all parameters of a pattern, without allocating any strings,
+/// e.g. to frequently poll values for recording automation. Values are written in the order
+/// of `pattern_parameters`, together with the parameter's `id_hash` values.
+///
+/// `out_len` must point to the capacity of the given arrays. It is set to the total number of
+/// parameters, which may exceed the capacity. Only values which fit into the arrays get
+/// written then. `out_id_hashes` may be null to fetch values only.
+VoidResult pattern_parameter_values(Pattern *this_,
+                                    uint64_t *out_id_hashes,
+                                    double *out_values,
+                                    uint32_t *out_len);
+
+/// Get non-fatal warnings, such as invalid values which got clamped, which got raised while
+/// compiling the pattern's script.
+/// The returned result must be deleted via `drop_value_strings` or `drop_error_string`.
+ValueStringsResult pattern_warnings(Pattern *this_);
+
 /// Set a single parameter value of a pattern.
 VoidResult set_pattern_parameter_value(Pattern *this_, const char *id, double value);
 
+/// Get a single parameter value of a pattern, normalized to range [0 - 1].
+F64Result get_pattern_parameter_normalized(Pattern *this_, const char *id);
+
+/// Set a single parameter value of a pattern from a normalized value in range [0 - 1].
+VoidResult set_pattern_parameter_normalized(Pattern *this_, const char *id, double value);
+
+/// Get a human-readable string representation of the given parameter value, depending on the
+/// parameter's type. The returned string result must be deleted via `drop_string` or
+/// `drop_error_string`.
+StringResult pattern_parameter_value_string(Pattern *this_, const char *id, double value);
+
+/// Reset all parameter values of a pattern to their default values. This does not reset the
+/// pattern's playback state.
+VoidResult reset_pattern_parameters(Pattern *this_);
+
+/// Render the given number of pattern cycles into a JSON array of all emitted note and parameter
+/// change events, with their times in samples and beats. The pattern's playback state is not
+/// affected. The returned string result must be deleted via `drop_string` or `drop_error_string`.
+StringResult pattern_timeline_json(Pattern *this_, uint32_t cycles);
+
 /// Get length in samples of a pattern's step.
 F64Result pattern_samples_per_step(Pattern *this_);
 
 /// Get length of the pattern's rhythm (a full cycle, in steps).
 UInt32Result pattern_step_count(Pattern *this_);
 
+/// Get the sample time a pattern last got run or advanced to. This keeps advancing in rests,
+/// where the pattern emits no events, so it can be used to sync visuals with the pattern.
+UInt64Result pattern_current_sample_time(Pattern *this_);
+
 /// Set a new time base for a pattern.
 VoidResult set_pattern_time_base(Pattern *this_, Timebase time_base);
 
+/// Set a global velocity scale factor for a pattern, which gets applied to all emitted note volumes.
+VoidResult set_pattern_velocity_scale(Pattern *this_, float scale);
+
+/// Set or remove an output filter for a pattern, which drops all emitted notes for which the
+/// given filter predicate returns false. Pass a null filter to remove an existing filter.
+/// NB: The filter context must be valid as long as the filter is set.
+VoidResult set_pattern_output_filter(Pattern *this_,
+                                     void *filter_context,
+                                     bool (*filter)(void*, const NoteEvent*));
+
 /// Set trigger events for a pattern.
 VoidResult set_pattern_trigger_event(Pattern *this_,
                                      const NoteEvent *note_events_ptr,
@@ -439,9 +794,29 @@ VoidResult run_pattern_until_time(Pattern *this_,
 /// Run/seek pattern, discarding all events up to the given time.
 VoidResult advance_pattern_until_time(Pattern *this_, uint64_t time);
 
+/// Rewind the pattern and seek it to the start of the given step index, counted from 0,
+/// discarding all events of the previous steps.
+VoidResult seek_pattern_to_step(Pattern *this_, uint32_t step);
+
+/// Fetch and clear captured debug output, such as `print` calls, of a pattern's script.
+/// Calls the given callback with each line, oldest lines first.
+/// NB: Lines are only valid within the callback, so they must be consumed
+/// or copied when used outside of the callback.
+VoidResult pattern_debug_output(Pattern *this_,
+                                void *callback_context,
+                                void (*callback)(void*, const char*));
+
 /// Delete a pattern which got allocated via `new_pattern_from_string/file`.
 void drop_pattern(Pattern *pattern);
 
+/// Split the given mini-notation string into tokens, e.g. to highlight the notation in editors.
+/// Token ranges are byte ranges in the given UTF-8 string.
+/// The returned tokens result must be deleted via `drop_cycle_tokens` or `drop_error_string`.
+CycleTokensResult tokenize_cycle(const char *content);
+
+/// Drop array of cycle tokens, created via `tokenize_cycle`
+void drop_cycle_tokens(CycleTokens *tokens);
+
 /// Initialize lib and set external allocator, which should be used instead of the system
 /// allocator as global allocator (unless the "dhat-profiler" feature is enabled).
 VoidResult initialize(AllocFn alloc, DeallocFn dealloc);
@@ -15,7 +15,7 @@ use static_assertions::const_assert_eq;
 
 mod pattrns {
     // wrap pattrns types into a pattrns:: namespace
-    pub(super) use pattrns::prelude::*;
+    pub(super) use pattrns::{prelude::*, tokenize_cycle};
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -49,9 +49,21 @@ unsafe fn drop_raw_cstring(chars: *const c_char) {
 ///
 /// The result type we're using here in the bindings are unfortunately not a Result template,
 /// but a custom error to get them bound to C++, so the blocks Result type must be passed as
-/// first argument to be macro.
+/// first argument to be macro. Result types with custom error variants instead pass a function
+/// which creates the error from an error string.
 macro_rules! try_catch {
-    ($result_type:ident, $block:block) => {{
+    // pattern result errors carry an additional, here unknown error range
+    (PatternResult, $block:block) => {
+        try_catch!(
+            |error| PatternResult::Error(error, ErrorRange::default()),
+            $block
+        )
+    };
+    ($result_type:ident, $block:block) => {
+        try_catch!(|error| $result_type::Error(error), $block)
+    };
+    ($new_error:expr, $block:block) => {{
+        let new_error = $new_error;
         match panic::catch_unwind(panic::AssertUnwindSafe(|| {
             // clear previous lua callback errors, if any
             pattrns::clear_lua_callback_errors();
@@ -59,14 +71,14 @@ macro_rules! try_catch {
             let result = $block;
             // when the block caused a callback error, return the error
             if let Some(lua_error) = pattrns::has_lua_callback_errors() {
-                $result_type::Error(new_raw_cstring(&lua_error.to_string()))
+                new_error(new_raw_cstring(&lua_error.to_string()))
             } else {
                 // else return the block's return value
                 result
             }
         })) {
             Ok(value) => value,
-            Err(payload) => $result_type::Error(new_raw_cstring(&format!(
+            Err(payload) => new_error(new_raw_cstring(&format!(
                 "Ouch. Internal error, please report: {}",
                 panic_message(&payload)
             ))),
@@ -79,7 +91,7 @@ macro_rules! try_catch {
 /// Instrument_id value which refers to an unset, undefined id.
 pub const NO_INSTRUMENT_ID: u32 = u32::MAX;
 /// Parameter change value which refers to an empty, undefined parameter.
-pub const NO_PARAMETER_ID: u32 = u32::MAX;
+pub const NO_PARAMETER_ID: u64 = u64::MAX;
 
 /// Note value which refers to an empty, undefined note.
 pub const EMPTY_NOTE: u8 = 0xFE;
@@ -88,6 +100,11 @@ const_assert_eq!(EMPTY_NOTE, pattrns::Note::EMPTY as u8);
 pub const NOTE_OFF: u8 = 0xFF;
 const_assert_eq!(NOTE_OFF, pattrns::Note::OFF as u8);
 
+/// Note speed value which refers to an unset speed: playback speed is derived from the note.
+pub const NO_SPEED: f32 = 0.0;
+/// Note ratchet value which refers to an unset ratchet: the note is triggered once.
+pub const NO_RATCHET: u32 = 0;
+
 // -------------------------------------------------------------------------------------------------
 
 /// C lang compatible representation of a rust `Result<f64>`.
@@ -106,6 +123,23 @@ pub enum UInt32Result {
     Value(u32),
 }
 
+/// C lang compatible representation of a rust `Result<u64>`.
+/// Error strings must be released manually with `drop_error_string`.
+#[repr(C)]
+pub enum UInt64Result {
+    Error(*const c_char),
+    Value(u64),
+}
+
+/// C lang compatible representation of a rust `Result<String>`.
+/// Error strings must be released manually with `drop_error_string`.
+/// Value strings must be released manually with `drop_string`.
+#[repr(C)]
+pub enum StringResult {
+    Error(*const c_char),
+    Value(*const c_char),
+}
+
 /// C lang compatible representation of a rust `Result<()>`.
 /// Error strings must be released manually with `drop_error_string`.
 #[repr(C)]
@@ -120,6 +154,12 @@ pub unsafe extern "C" fn drop_error_string(error: *const c_char) {
     drop_raw_cstring(error)
 }
 
+/// Delete a value string from a `StringResult`.
+#[no_mangle]
+pub unsafe extern "C" fn drop_string(string: *const c_char) {
+    drop_raw_cstring(string)
+}
+
 // -------------------------------------------------------------------------------------------------
 
 /// C lang compatible representation of a rust `pattrns::NoteEvent`.
@@ -130,8 +170,18 @@ pub struct NoteEvent {
     pub volume: f32,
     pub panning: f32,
     pub delay: f32,
+    pub speed: f32,
+    pub ratchet: u32,
 }
 
+// new note event fields get appended, which keeps the offsets of existing fields, but changes
+// the struct's size and thus the stride of note event arrays: hosts must be rebuilt with the
+// new header, else they misread all events past the first one
+const_assert_eq!(std::mem::offset_of!(NoteEvent, delay), 16);
+const_assert_eq!(std::mem::offset_of!(NoteEvent, speed), 20);
+const_assert_eq!(std::mem::offset_of!(NoteEvent, ratchet), 24);
+const_assert_eq!(std::mem::size_of::<NoteEvent>(), 28);
+
 impl Default for NoteEvent {
     // create a new empty, note event
     fn default() -> Self {
@@ -141,6 +191,8 @@ impl Default for NoteEvent {
             volume: 1.0,
             panning: 0.0,
             delay: 0.0,
+            speed: NO_SPEED,
+            ratchet: NO_RATCHET,
         }
     }
 }
@@ -154,28 +206,42 @@ impl From<&pattrns::NoteEvent> for NoteEvent {
         let volume = value.volume;
         let panning = value.panning;
         let delay = value.delay;
+        let speed = value.speed.unwrap_or(NO_SPEED);
+        let ratchet = value.ratchet.unwrap_or(NO_RATCHET);
         Self {
             instrument,
             note,
             volume,
             panning,
             delay,
+            speed,
+            ratchet,
         }
     }
 }
 
 impl From<&NoteEvent> for pattrns::NoteEvent {
     fn from(value: &NoteEvent) -> Self {
-        pattrns::NoteEvent {
-            note: pattrns::Note::from(value.note),
-            instrument: match value.instrument {
-                NO_INSTRUMENT_ID => None,
-                _ => Some(pattrns::InstrumentId::from(value.instrument as usize)),
-            },
-            volume: value.volume,
-            panning: value.panning,
-            delay: value.delay,
-        }
+        let note = pattrns::Note::from(value.note);
+        let instrument = match value.instrument {
+            NO_INSTRUMENT_ID => None,
+            _ => Some(pattrns::InstrumentId::from(value.instrument as usize)),
+        };
+        let mut note_event = pattrns::NoteEvent::from((note, instrument));
+        note_event.volume = value.volume;
+        note_event.panning = value.panning;
+        note_event.delay = value.delay;
+        note_event
+            .with_speed(if value.speed > 0.0 {
+                Some(value.speed)
+            } else {
+                None
+            })
+            .with_ratchet(if value.ratchet > 0 {
+                Some(value.ratchet)
+            } else {
+                None
+            })
     }
 }
 
@@ -237,15 +303,13 @@ impl Drop for NoteEvents {
 #[repr(C)]
 /// C lang compatible representation of a rust `pattrns::ParameterChangeEvent`.
 pub struct ParameterChangeEvent {
-    pub parameter: u32,
+    pub parameter: u64,
     pub value: f32,
 }
 
 impl From<&pattrns::ParameterChangeEvent> for ParameterChangeEvent {
     fn from(value: &pattrns::ParameterChangeEvent) -> Self {
-        let parameter = value
-            .parameter
-            .map_or(NO_PARAMETER_ID, |id| usize::from(id) as u32);
+        let parameter = value.parameter.map_or(NO_PARAMETER_ID, u64::from);
         let value = value.value;
         Self { parameter, value }
     }
@@ -390,6 +454,23 @@ impl From<&[String]> for ValueStrings {
     }
 }
 
+/// C lang compatible representation of a rust `Result<ValueStrings>`.
+/// Error strings must be released manually with `drop_error_string`.
+/// Values must be released manually with `drop_value_strings`.
+#[repr(C)]
+pub enum ValueStringsResult {
+    Error(*const c_char),
+    Value(*mut ValueStrings),
+}
+
+#[no_mangle]
+/// Drop array of strings, created via `pattern_warnings`
+pub unsafe extern "C" fn drop_value_strings(strings: *mut ValueStrings) {
+    if !strings.is_null() {
+        drop(Box::from_raw(strings));
+    }
+}
+
 #[repr(C)]
 /// C lang compatible representation of a rust `pattrns::Parameter`.
 /// Ensure strings are not used after the parameters array got dropped.
@@ -403,6 +484,7 @@ pub struct Parameter {
     pub default: f64,
     pub value: f64,
     pub value_strings: ValueStrings,
+    pub id_hash: u64,
 }
 
 impl From<&Rc<RefCell<pattrns::Parameter>>> for Parameter {
@@ -419,6 +501,7 @@ impl From<&Rc<RefCell<pattrns::Parameter>>> for Parameter {
                 value: value.value(),
                 default: value.default(),
                 value_strings: value.value_strings().into(),
+                id_hash: value.id_hash(),
             }
         }
     }
@@ -498,8 +581,18 @@ pub struct PatternPlaybackEvent {
     pub duration_in_samples: u64,
     pub note_events: NoteEvents,
     pub parameter_change_events: ParameterChangeEvents,
+    /// Musical length of the event's notes in samples, when known, else 0.
+    pub note_duration_in_samples: u64,
 }
 
+// new playback event fields get appended, which keeps the offsets of existing fields, but
+// changes the struct's size: hosts must be rebuilt with the new header
+const_assert_eq!(std::mem::offset_of!(PatternPlaybackEvent, note_events), 16);
+const_assert_eq!(
+    std::mem::offset_of!(PatternPlaybackEvent, parameter_change_events),
+    16 + std::mem::size_of::<NoteEvents>()
+);
+
 impl PatternPlaybackEvent {
     /// Convert and forward a single event to the given callback
     fn forward_to_callback(
@@ -507,6 +600,7 @@ impl PatternPlaybackEvent {
         callback: extern "C" fn(*mut c_void, &Self),
         item: pattrns::PatternEvent,
     ) {
+        let note_duration = item.note_duration();
         // NB: make sure event wrappers are valid/alive as long as the callback is called
         let (note_events, parameter_change_events) = if let Some(event) = item.event {
             match event {
@@ -527,6 +621,7 @@ impl PatternPlaybackEvent {
             duration_in_samples: item.duration,
             note_events,
             parameter_change_events,
+            note_duration_in_samples: note_duration.unwrap_or(0),
         };
         callback(callback_context, &playback_event);
     }
@@ -536,17 +631,58 @@ impl PatternPlaybackEvent {
 // NB: not #[repr(C)] to force cbindgen to export an opaque type
 pub struct Pattern {
     pattern: Rc<RefCell<dyn pattrns::Pattern>>,
+    warnings: Vec<String>,
 }
 
 /// C lang compatible Result<Pattern, String> representation for new_pattern_from_string/file.
+/// Errors carry the error's source range, e.g. to highlight syntax errors in editors.
 /// Error Strings must be deleted with `drop_error_string`.
 /// Pattern values must be deleted with `drop_pattern`,
 #[repr(C)]
 pub enum PatternResult {
-    Error(*const c_char),
+    Error(*const c_char, ErrorRange),
     Value(*mut Pattern),
 }
 
+/// C lang compatible representation of a rust `pattrns::ErrorRange`.
+/// Lines and columns are 1-based and count characters. The end column is exclusive.
+/// All values are 0 when the range is unknown.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ErrorRange {
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+impl From<pattrns::ErrorRange> for ErrorRange {
+    fn from(range: pattrns::ErrorRange) -> Self {
+        Self {
+            start_line: range.start_line as u32,
+            start_column: range.start_column as u32,
+            end_line: range.end_line as u32,
+            end_column: range.end_column as u32,
+        }
+    }
+}
+
+// helper function to convert pattrns pattern constructor results into a PatternResult
+unsafe fn new_pattern_result(
+    result: Result<Rc<RefCell<dyn pattrns::Pattern>>, pattrns::PatternError>,
+) -> PatternResult {
+    match result {
+        Ok(pattern) => {
+            let warnings = pattrns::lua_callback_warnings();
+            PatternResult::Value(Box::into_raw(Box::new(Pattern { pattern, warnings })))
+        }
+        Err(err) => PatternResult::Error(
+            new_raw_cstring(&err.to_string()),
+            err.range().map(ErrorRange::from).unwrap_or_default(),
+        ),
+    }
+}
+
 #[no_mangle]
 /// Create a new pattern from the given script file path, using the given beat time and instrument.
 /// The returned pattern result must be deleted via `drop_pattern` or `drop_error_string`.
@@ -556,6 +692,7 @@ pub unsafe extern "C" fn new_pattern_from_file(
     file_name: *const c_char,
 ) -> PatternResult {
     try_catch!(PatternResult, {
+        pattrns::clear_lua_callback_warnings();
         let file_name = CStr::from_ptr(file_name).to_string_lossy();
         let result = pattrns::new_pattern_from_file(
             time_base.into(),
@@ -566,10 +703,7 @@ pub unsafe extern "C" fn new_pattern_from_file(
             },
             file_name.into_owned().as_str(),
         );
-        match result {
-            Ok(pattern) => PatternResult::Value(Box::into_raw(Box::new(Pattern { pattern }))),
-            Err(err) => PatternResult::Error(new_raw_cstring(&err.to_string())),
-        }
+        new_pattern_result(result)
     })
 }
 
@@ -583,6 +717,7 @@ pub unsafe extern "C" fn new_pattern_from_string(
     content_name: *const c_char,
 ) -> PatternResult {
     try_catch!(PatternResult, {
+        pattrns::clear_lua_callback_warnings();
         let result = pattrns::new_pattern_from_string(
             time_base.into(),
             if instrument_id.is_null() {
@@ -593,10 +728,48 @@ pub unsafe extern "C" fn new_pattern_from_string(
             unsafe { &CStr::from_ptr(content).to_string_lossy() },
             unsafe { &CStr::from_ptr(content_name).to_string_lossy() },
         );
-        match result {
-            Ok(pattern) => PatternResult::Value(Box::into_raw(Box::new(Pattern { pattern }))),
-            Err(err) => PatternResult::Error(new_raw_cstring(&err.to_string())),
-        }
+        new_pattern_result(result)
+    })
+}
+
+#[no_mangle]
+/// Create a new pattern from the given script contents, using the given beat time and instrument,
+/// calling the given register callback before the script gets evaluated, so the host can register
+/// custom globals such as functions or libraries.
+///
+/// The callback gets called with the given context and a raw `lua_State*`, which must be accessed
+/// with the C API of the Lua interpreter this library got built with. The state pointer is only
+/// valid within the callback and must not be stored. Registered functions however are owned by the
+/// pattern and will be called, as long as the pattern or one of its instances is alive: anything
+/// they refer to must thus outlive all pattern instances.
+///
+/// The returned pattern result must be deleted via `drop_pattern` or `drop_error_string`.
+pub unsafe extern "C" fn new_pattern_from_string_with_env(
+    time_base: Timebase,
+    instrument_id: *const u32,
+    content: *const c_char,
+    content_name: *const c_char,
+    register_context: *mut c_void,
+    register_callback: extern "C" fn(*mut c_void, *mut c_void),
+) -> PatternResult {
+    try_catch!(PatternResult, {
+        pattrns::clear_lua_callback_warnings();
+        let result = pattrns::new_pattern_from_string_with_env(
+            time_base.into(),
+            if instrument_id.is_null() {
+                None
+            } else {
+                Some(pattrns::InstrumentId::from(*instrument_id as usize))
+            },
+            unsafe { &CStr::from_ptr(content).to_string_lossy() },
+            unsafe { &CStr::from_ptr(content_name).to_string_lossy() },
+            |lua| unsafe {
+                lua.exec_raw::<()>((), |state| {
+                    register_callback(register_context, state as *mut c_void)
+                })
+            },
+        );
+        new_pattern_result(result)
     })
 }
 
@@ -608,7 +781,10 @@ pub unsafe extern "C" fn new_pattern_instance(
     time_base: Timebase,
 ) -> PatternResult {
     if this.is_null() {
-        return PatternResult::Error(new_raw_cstring("Trying to clone a pattern from a null ptr"));
+        return PatternResult::Error(
+            new_raw_cstring("Trying to clone a pattern from a null ptr"),
+            ErrorRange::default(),
+        );
     }
     try_catch!(PatternResult, {
         let this = ManuallyDrop::new(Box::from_raw(this));
@@ -621,7 +797,8 @@ pub unsafe extern "C" fn new_pattern_instance(
             pattern.reset();
         }
         // return result with the new boxed pattern
-        PatternResult::Value(Box::into_raw(Box::new(Pattern { pattern })))
+        let warnings = this.warnings.clone();
+        PatternResult::Value(Box::into_raw(Box::new(Pattern { pattern, warnings })))
     })
 }
 
@@ -643,6 +820,60 @@ pub unsafe extern "C" fn pattern_parameters(this: *mut Pattern) -> ParameterSetR
     })
 }
 
+#[no_mangle]
+/// Get the current values of all parameters of a pattern, without allocating any strings,
+/// e.g. to frequently poll values for recording automation. Values are written in the order
+/// of `pattern_parameters`, together with the parameter's `id_hash` values.
+///
+/// `out_len` must point to the capacity of the given arrays. It is set to the total number of
+/// parameters, which may exceed the capacity. Only values which fit into the arrays get
+/// written then. `out_id_hashes` may be null to fetch values only.
+pub unsafe extern "C" fn pattern_parameter_values(
+    this: *mut Pattern,
+    out_id_hashes: *mut u64,
+    out_values: *mut f64,
+    out_len: *mut u32,
+) -> VoidResult {
+    if this.is_null() || out_values.is_null() || out_len.is_null() {
+        return VoidResult::Error(new_raw_cstring(
+            "Trying to get input parameter values with a null ptr",
+        ));
+    }
+    try_catch!(VoidResult, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        let pattern = this.pattern.borrow();
+        let parameters = pattern.parameters();
+        let capacity = *out_len as usize;
+        for (index, parameter) in parameters.iter().take(capacity).enumerate() {
+            let parameter = parameter.borrow();
+            if !out_id_hashes.is_null() {
+                *out_id_hashes.add(index) = parameter.id_hash();
+            }
+            *out_values.add(index) = parameter.value();
+        }
+        *out_len = parameters.len() as u32;
+        VoidResult::Ok(())
+    })
+}
+
+#[no_mangle]
+/// Get non-fatal warnings, such as invalid values which got clamped, which got raised while
+/// compiling the pattern's script.
+/// The returned result must be deleted via `drop_value_strings` or `drop_error_string`.
+pub unsafe extern "C" fn pattern_warnings(this: *mut Pattern) -> ValueStringsResult {
+    if this.is_null() {
+        return ValueStringsResult::Error(new_raw_cstring(
+            "Trying to get warnings from a null ptr",
+        ));
+    }
+    try_catch!(ValueStringsResult, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        ValueStringsResult::Value(Box::into_raw(Box::new(ValueStrings::from(
+            this.warnings.as_slice(),
+        ))))
+    })
+}
+
 #[no_mangle]
 /// Set a single parameter value of a pattern.
 pub unsafe extern "C" fn set_pattern_parameter_value(
@@ -674,6 +905,125 @@ pub unsafe extern "C" fn set_pattern_parameter_value(
     })
 }
 
+#[no_mangle]
+/// Get a single parameter value of a pattern, normalized to range [0 - 1].
+pub unsafe extern "C" fn get_pattern_parameter_normalized(
+    this: *mut Pattern,
+    id: *const c_char,
+) -> F64Result {
+    if this.is_null() {
+        return F64Result::Error(new_raw_cstring(
+            "Trying to get an input parameter value from a null ptr",
+        ));
+    }
+    try_catch!(F64Result, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        let pattern = this.pattern.borrow();
+        let id = CStr::from_ptr(id).to_string_lossy();
+        if let Some(parameter) = pattern.parameters().iter().find(|p| p.borrow().id() == id) {
+            F64Result::Value(parameter.borrow().normalized_value())
+        } else {
+            F64Result::Error(new_raw_cstring(
+                "Trying to access and unknown input parameter",
+            ))
+        }
+    })
+}
+
+#[no_mangle]
+/// Set a single parameter value of a pattern from a normalized value in range [0 - 1].
+pub unsafe extern "C" fn set_pattern_parameter_normalized(
+    this: *mut Pattern,
+    id: *const c_char,
+    value: f64,
+) -> VoidResult {
+    if this.is_null() {
+        return VoidResult::Error(new_raw_cstring(
+            "Trying to set an input parameter value for a null ptr",
+        ));
+    }
+    try_catch!(VoidResult, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        let pattern = this.pattern.borrow();
+        let id = CStr::from_ptr(id).to_string_lossy();
+        if let Some(parameter) = pattern.parameters().iter().find(|p| p.borrow().id() == id) {
+            if !(0.0..=1.0).contains(&value) {
+                return VoidResult::Error(new_raw_cstring(
+                    "Normalized input parameter value is out of range",
+                ));
+            }
+            parameter.borrow_mut().set_normalized_value(value);
+            VoidResult::Ok(())
+        } else {
+            VoidResult::Error(new_raw_cstring(
+                "Trying to access and unknown input parameter",
+            ))
+        }
+    })
+}
+
+#[no_mangle]
+/// Get a human-readable string representation of the given parameter value, depending on the
+/// parameter's type. The returned string result must be deleted via `drop_string` or
+/// `drop_error_string`.
+pub unsafe extern "C" fn pattern_parameter_value_string(
+    this: *mut Pattern,
+    id: *const c_char,
+    value: f64,
+) -> StringResult {
+    if this.is_null() {
+        return StringResult::Error(new_raw_cstring(
+            "Trying to get an input parameter value string from a null ptr",
+        ));
+    }
+    try_catch!(StringResult, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        let pattern = this.pattern.borrow();
+        let id = CStr::from_ptr(id).to_string_lossy();
+        if let Some(parameter) = pattern.parameters().iter().find(|p| p.borrow().id() == id) {
+            let string = parameter.borrow().value_to_string(value);
+            StringResult::Value(new_raw_cstring(&string))
+        } else {
+            StringResult::Error(new_raw_cstring(
+                "Trying to access and unknown input parameter",
+            ))
+        }
+    })
+}
+
+#[no_mangle]
+/// Reset all parameter values of a pattern to their default values. This does not reset the
+/// pattern's playback state.
+pub unsafe extern "C" fn reset_pattern_parameters(this: *mut Pattern) -> VoidResult {
+    if this.is_null() {
+        return VoidResult::Error(new_raw_cstring(
+            "Trying to reset input parameters for a null ptr",
+        ));
+    }
+    try_catch!(VoidResult, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        this.pattern.borrow_mut().reset_parameters_to_defaults();
+        VoidResult::Ok(())
+    })
+}
+
+#[no_mangle]
+/// Render the given number of pattern cycles into a JSON array of all emitted note and parameter
+/// change events, with their times in samples and beats. The pattern's playback state is not
+/// affected. The returned string result must be deleted via `drop_string` or `drop_error_string`.
+pub unsafe extern "C" fn pattern_timeline_json(this: *mut Pattern, cycles: u32) -> StringResult {
+    if this.is_null() {
+        return StringResult::Error(new_raw_cstring(
+            "Trying to export the timeline of a null ptr",
+        ));
+    }
+    try_catch!(StringResult, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        let timeline = this.pattern.borrow().export_timeline_json(cycles as usize);
+        StringResult::Value(new_raw_cstring(&timeline))
+    })
+}
+
 #[no_mangle]
 /// Get length in samples of a pattern's step.
 pub unsafe extern "C" fn pattern_samples_per_step(this: *mut Pattern) -> F64Result {
@@ -706,6 +1056,23 @@ pub unsafe extern "C" fn pattern_step_count(this: *mut Pattern) -> UInt32Result
     })
 }
 
+#[no_mangle]
+/// Get the sample time a pattern last got run or advanced to. This keeps advancing in rests,
+/// where the pattern emits no events, so it can be used to sync visuals with the pattern.
+pub unsafe extern "C" fn pattern_current_sample_time(this: *mut Pattern) -> UInt64Result {
+    if this.is_null() {
+        return UInt64Result::Error(new_raw_cstring(
+            "Trying to get current sample time from a null ptr",
+        ));
+    }
+    try_catch!(UInt64Result, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        let pattern = this.pattern.borrow();
+        let sample_time = pattern.current_sample_time();
+        UInt64Result::Value(sample_time)
+    })
+}
+
 #[no_mangle]
 /// Set a new time base for a pattern.
 pub unsafe extern "C" fn set_pattern_time_base(
@@ -720,6 +1087,49 @@ pub unsafe extern "C" fn set_pattern_time_base(
     })
 }
 
+#[no_mangle]
+/// Set a global velocity scale factor for a pattern, which gets applied to all emitted note volumes.
+pub unsafe extern "C" fn set_pattern_velocity_scale(this: *mut Pattern, scale: f32) -> VoidResult {
+    if this.is_null() {
+        return VoidResult::Error(new_raw_cstring(
+            "Trying to set velocity scale for a null ptr",
+        ));
+    }
+    try_catch!(VoidResult, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        let mut pattern = this.pattern.borrow_mut();
+        pattern.set_velocity_scale(scale);
+        VoidResult::Ok(())
+    })
+}
+
+#[no_mangle]
+/// Set or remove an output filter for a pattern, which drops all emitted notes for which the
+/// given filter predicate returns false. Pass a null filter to remove an existing filter.
+/// NB: The filter context must be valid as long as the filter is set.
+pub unsafe extern "C" fn set_pattern_output_filter(
+    this: *mut Pattern,
+    filter_context: *mut c_void,
+    filter: Option<extern "C" fn(*mut c_void, &NoteEvent) -> bool>,
+) -> VoidResult {
+    if this.is_null() {
+        return VoidResult::Error(new_raw_cstring(
+            "Trying to set output filter for a null ptr",
+        ));
+    }
+    try_catch!(VoidResult, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        let mut pattern = this.pattern.borrow_mut();
+        let filter = filter.map(|filter| -> pattrns::NoteFilter {
+            Rc::new(move |note_event: &pattrns::NoteEvent| {
+                filter(filter_context, &NoteEvent::from(note_event))
+            })
+        });
+        pattern.set_output_filter(filter);
+        VoidResult::Ok(())
+    })
+}
+
 #[no_mangle]
 /// Set trigger events for a pattern.
 pub unsafe extern "C" fn set_pattern_trigger_event(
@@ -803,6 +1213,45 @@ pub unsafe extern "C" fn advance_pattern_until_time(this: *mut Pattern, time: u6
     })
 }
 
+#[no_mangle]
+/// Rewind the pattern and seek it to the start of the given step index, counted from 0,
+/// discarding all events of the previous steps.
+pub unsafe extern "C" fn seek_pattern_to_step(this: *mut Pattern, step: u32) -> VoidResult {
+    if this.is_null() {
+        return VoidResult::Error(new_raw_cstring("Trying to seek a null ptr"));
+    }
+    try_catch!(VoidResult, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        let mut pattern = this.pattern.borrow_mut();
+        pattern.seek_to_step(step as usize);
+        VoidResult::Ok(())
+    })
+}
+
+#[no_mangle]
+/// Fetch and clear captured debug output, such as `print` calls, of a pattern's script.
+/// Calls the given callback with each line, oldest lines first.
+/// NB: Lines are only valid within the callback, so they must be consumed
+/// or copied when used outside of the callback.
+pub unsafe extern "C" fn pattern_debug_output(
+    this: *mut Pattern,
+    callback_context: *mut c_void,
+    callback: extern "C" fn(*mut c_void, *const c_char),
+) -> VoidResult {
+    try_catch!(VoidResult, {
+        let this = ManuallyDrop::new(Box::from_raw(this));
+        let debug_output = this.pattern.borrow().debug_output();
+        if let Some(debug_output) = debug_output {
+            for line in debug_output.take() {
+                let line = new_raw_cstring(&line);
+                callback(callback_context, line);
+                drop_raw_cstring(line);
+            }
+        }
+        VoidResult::Ok(())
+    })
+}
+
 #[no_mangle]
 /// Delete a pattern which got allocated via `new_pattern_from_string/file`.
 pub unsafe extern "C" fn drop_pattern(pattern: *mut Pattern) {
@@ -810,3 +1259,334 @@ pub unsafe extern "C" fn drop_pattern(pattern: *mut Pattern) {
         drop(Box::from_raw(pattern));
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+/// C lang compatible representation of a rust `pattrns::CycleTokenKind`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CycleTokenKind {
+    Value,
+    Rest,
+    Hold,
+    Reference,
+    Operator,
+    GroupOpen,
+    GroupClose,
+    Separator,
+}
+
+impl From<pattrns::CycleTokenKind> for CycleTokenKind {
+    fn from(kind: pattrns::CycleTokenKind) -> Self {
+        match kind {
+            pattrns::CycleTokenKind::Value => CycleTokenKind::Value,
+            pattrns::CycleTokenKind::Rest => CycleTokenKind::Rest,
+            pattrns::CycleTokenKind::Hold => CycleTokenKind::Hold,
+            pattrns::CycleTokenKind::Reference => CycleTokenKind::Reference,
+            pattrns::CycleTokenKind::Operator => CycleTokenKind::Operator,
+            pattrns::CycleTokenKind::GroupOpen => CycleTokenKind::GroupOpen,
+            pattrns::CycleTokenKind::GroupClose => CycleTokenKind::GroupClose,
+            pattrns::CycleTokenKind::Separator => CycleTokenKind::Separator,
+        }
+    }
+}
+
+/// Byte range in a tokenized mini-notation string. The end is exclusive.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CycleRange {
+    start: u32,
+    end: u32,
+}
+
+impl From<std::ops::Range<usize>> for CycleRange {
+    fn from(range: std::ops::Range<usize>) -> Self {
+        Self {
+            start: range.start as u32,
+            end: range.end as u32,
+        }
+    }
+}
+
+/// C lang compatible representation of a rust `pattrns::CycleToken`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct CycleToken {
+    kind: CycleTokenKind,
+    range: CycleRange,
+}
+
+/// C lang compatible representation of a rust `Vec<CycleToken>` using a C Array.
+#[repr(C)]
+pub struct CycleTokens {
+    pub tokens_ptr: *const CycleToken,
+    pub tokens_len: u32,
+}
+
+impl From<&[pattrns::CycleToken]> for CycleTokens {
+    fn from(tokens: &[pattrns::CycleToken]) -> Self {
+        // create a raw vector of tokens and prevent the temp vector from
+        // being destroyed. we'll do so when dropping Self.
+        let mut tokens_vector = ManuallyDrop::new(
+            tokens
+                .iter()
+                .map(|token| CycleToken {
+                    kind: token.kind.into(),
+                    range: token.range.clone().into(),
+                })
+                .collect::<Vec<_>>(),
+        );
+        tokens_vector.shrink_to_fit(); // make capacity = len
+        let tokens_ptr = tokens_vector.as_ptr();
+        let tokens_len = tokens_vector.len() as u32;
+        Self {
+            tokens_ptr,
+            tokens_len,
+        }
+    }
+}
+
+impl Drop for CycleTokens {
+    fn drop(&mut self) {
+        if !self.tokens_ptr.is_null() {
+            unsafe {
+                drop(Vec::from_raw_parts(
+                    self.tokens_ptr.cast_mut(),
+                    self.tokens_len as usize,
+                    self.tokens_len as usize,
+                ));
+            }
+        }
+    }
+}
+
+/// C lang compatible representation of a rust `Result<CycleTokens>`.
+/// Errors carry the byte range of the syntax error in the tokenized string.
+/// Error strings must be released manually with `drop_error_string`.
+/// Values must be released manually with `drop_cycle_tokens`.
+#[repr(C)]
+pub enum CycleTokensResult {
+    Error(*const c_char, CycleRange),
+    Value(*mut CycleTokens),
+}
+
+#[no_mangle]
+/// Split the given mini-notation string into tokens, e.g. to highlight the notation in editors.
+/// Token ranges are byte ranges in the given UTF-8 string.
+/// The returned tokens result must be deleted via `drop_cycle_tokens` or `drop_error_string`.
+pub unsafe extern "C" fn tokenize_cycle(content: *const c_char) -> CycleTokensResult {
+    if content.is_null() {
+        return CycleTokensResult::Error(
+            new_raw_cstring("Trying to tokenize a null ptr"),
+            CycleRange::default(),
+        );
+    }
+    try_catch!(
+        |error| CycleTokensResult::Error(error, CycleRange::default()),
+        {
+            let content = CStr::from_ptr(content).to_string_lossy();
+            match pattrns::tokenize_cycle(&content) {
+                Ok(tokens) => CycleTokensResult::Value(Box::into_raw(Box::new(CycleTokens::from(
+                    tokens.as_slice(),
+                )))),
+                Err(err) => CycleTokensResult::Error(
+                    new_raw_cstring(&err.message),
+                    CycleRange::from(err.range),
+                ),
+            }
+        }
+    )
+}
+
+#[no_mangle]
+/// Drop array of cycle tokens, created via `tokenize_cycle`
+pub unsafe extern "C" fn drop_cycle_tokens(tokens: *mut CycleTokens) {
+    if !tokens.is_null() {
+        drop(Box::from_raw(tokens));
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parameter_values() {
+        let time_base = Timebase {
+            bpm: 120.0,
+            bpb: 4,
+            sample_rate: 44100,
+        };
+        let content = CString::new(
+            r#"
+            return pattern {
+              parameter = {
+                parameter.boolean("enabled", true),
+                parameter.integer("steps", 4, {1, 16}),
+                parameter.number("amount", 0.25),
+                parameter.enum("mode", "b", {"a", "b", "c"}),
+              },
+              event = "c4"
+            }
+            "#,
+        )
+        .unwrap();
+        let content_name = CString::new("test").unwrap();
+        unsafe {
+            let pattern = match new_pattern_from_string(
+                time_base,
+                ptr::null(),
+                content.as_ptr(),
+                content_name.as_ptr(),
+            ) {
+                PatternResult::Value(pattern) => pattern,
+                PatternResult::Error(err, _) => {
+                    panic!("{}", CStr::from_ptr(err).to_string_lossy())
+                }
+            };
+            let parameter_id = CString::new("steps").unwrap();
+            assert!(matches!(
+                set_pattern_parameter_value(pattern, parameter_id.as_ptr(), 8.0),
+                VoidResult::Ok(())
+            ));
+
+            let parameters = match pattern_parameters(pattern) {
+                ParameterSetResult::Value(parameters) => parameters,
+                ParameterSetResult::Error(err) => {
+                    panic!("{}", CStr::from_ptr(err).to_string_lossy())
+                }
+            };
+            let parameters_slice = std::slice::from_raw_parts(
+                (*parameters).parameters_ptr,
+                (*parameters).parameters_len as usize,
+            );
+            assert_eq!(parameters_slice.len(), 4);
+
+            // values match the full parameter set
+            let mut id_hashes = [0_u64; 4];
+            let mut values = [0_f64; 4];
+            let mut len = 4_u32;
+            assert!(matches!(
+                pattern_parameter_values(
+                    pattern,
+                    id_hashes.as_mut_ptr(),
+                    values.as_mut_ptr(),
+                    &mut len
+                ),
+                VoidResult::Ok(())
+            ));
+            assert_eq!(len, 4);
+            for (index, parameter) in parameters_slice.iter().enumerate() {
+                let id = CStr::from_ptr(parameter.id).to_string_lossy();
+                assert_eq!(id_hashes[index], parameter.id_hash, "{}", id);
+                assert_eq!(id_hashes[index], pattrns::Parameter::hash_id(&id), "{}", id);
+                assert_eq!(values[index], parameter.value, "{}", id);
+            }
+            assert_eq!(values, [1.0, 8.0, 0.25, 1.0]);
+
+            // arrays which are too small get the available values only
+            let mut values = [0_f64; 2];
+            let mut len = 2_u32;
+            assert!(matches!(
+                pattern_parameter_values(pattern, ptr::null_mut(), values.as_mut_ptr(), &mut len),
+                VoidResult::Ok(())
+            ));
+            assert_eq!(len, 4);
+            assert_eq!(values, [1.0, 8.0]);
+
+            drop_parameter_set(parameters);
+            drop_pattern(pattern);
+        }
+    }
+
+    #[test]
+    fn pattern_error_ranges() {
+        let content_name = CString::new("test").unwrap();
+        let new_pattern = |content: &str| {
+            let time_base = Timebase {
+                bpm: 120.0,
+                bpb: 4,
+                sample_rate: 44100,
+            };
+            let content = CString::new(content).unwrap();
+            unsafe {
+                new_pattern_from_string(
+                    time_base,
+                    ptr::null(),
+                    content.as_ptr(),
+                    content_name.as_ptr(),
+                )
+            }
+        };
+        unsafe {
+            // compile errors carry their range
+            match new_pattern("return pattern {\n  event = \"c4\"\n  unit = \"1/4\" }") {
+                PatternResult::Error(err, range) => {
+                    drop_error_string(err);
+                    assert_eq!(
+                        (
+                            range.start_line,
+                            range.start_column,
+                            range.end_line,
+                            range.end_column
+                        ),
+                        (3, 3, 3, 7)
+                    );
+                }
+                PatternResult::Value(_) => panic!("expected a compile error"),
+            }
+            // runtime errors have no range
+            match new_pattern("return 1") {
+                PatternResult::Error(err, range) => {
+                    drop_error_string(err);
+                    assert_eq!(range.start_line, 0);
+                    assert_eq!(range.end_column, 0);
+                }
+                PatternResult::Value(_) => panic!("expected a runtime error"),
+            }
+        }
+    }
+
+    #[test]
+    fn cycle_tokens() {
+        let content = CString::new("a*2 [b ~]").unwrap();
+        unsafe {
+            let tokens = match tokenize_cycle(content.as_ptr()) {
+                CycleTokensResult::Value(tokens) => tokens,
+                CycleTokensResult::Error(err, _) => {
+                    panic!("{}", CStr::from_ptr(err).to_string_lossy())
+                }
+            };
+            let tokens_slice =
+                std::slice::from_raw_parts((*tokens).tokens_ptr, (*tokens).tokens_len as usize);
+            assert_eq!(
+                tokens_slice
+                    .iter()
+                    .map(|token| (token.kind, token.range.start, token.range.end))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (CycleTokenKind::Value, 0, 1),
+                    (CycleTokenKind::Operator, 1, 2),
+                    (CycleTokenKind::Value, 2, 3),
+                    (CycleTokenKind::GroupOpen, 4, 5),
+                    (CycleTokenKind::Value, 5, 6),
+                    (CycleTokenKind::Rest, 7, 8),
+                    (CycleTokenKind::GroupClose, 8, 9),
+                ]
+            );
+            drop_cycle_tokens(tokens);
+
+            // syntax errors carry their range
+            let content = CString::new("a [b").unwrap();
+            match tokenize_cycle(content.as_ptr()) {
+                CycleTokensResult::Error(err, range) => {
+                    drop_error_string(err);
+                    assert_eq!(range, CycleRange { start: 4, end: 4 });
+                }
+                CycleTokensResult::Value(_) => panic!("expected a syntax error"),
+            }
+        }
+    }
+}
@@ -207,6 +207,12 @@ namespace pattrns
     return pattern_step_count_relay(pattern);
   }
 
+  CREATE_FUNCTION_RELAY(pattern_current_sample_time);
+  extern "C" UInt64Result pattern_current_sample_time(Pattern* pattern)
+  {
+    return pattern_current_sample_time_relay(pattern);
+  }
+
   CREATE_FUNCTION_RELAY(set_pattern_time_base);
   extern "C" VoidResult set_pattern_time_base(Pattern *pattern, Timebase time_base)
   {
@@ -0,0 +1,151 @@
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use notify::{RecursiveMode, Watcher};
+use simplelog::*;
+
+use pattrns::{osc::OscSink, prelude::*};
+
+// -------------------------------------------------------------------------------------------------
+
+// TODO: make this configurable with an cmd line arg
+const DEMO_PATH: &str = "./examples/assets";
+// TODO: make this configurable with an cmd line arg
+const OSC_TARGET: &str = "127.0.0.1:9000";
+
+// virtual sample rate of the sequence's time base
+const SAMPLE_RATE: u32 = 44100;
+
+// -------------------------------------------------------------------------------------------------
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // init logging
+    TermLogger::init(
+        log::STATIC_MAX_LEVEL,
+        ConfigBuilder::default().build(),
+        TerminalMode::Mixed,
+        ColorChoice::Auto,
+    )
+    .unwrap_or_else(|err| {
+        log::error!("init_logger error: {err:?}");
+    });
+
+    // fetch scripts from demo dir: the script's index is used as instrument/channel
+    log::info!("Searching for script files in path '{DEMO_PATH}'...");
+    struct PatternEntry {
+        instrument_id: InstrumentId,
+        script_path: PathBuf,
+    }
+    let mut entries = vec![];
+    for dir_entry in fs::read_dir(DEMO_PATH)?.flatten() {
+        let path = dir_entry.path();
+        if path.extension().is_some_and(|e| e == "lua") {
+            entries.push(PatternEntry {
+                instrument_id: InstrumentId::from(entries.len()),
+                script_path: path,
+            });
+        }
+    }
+
+    // set default time base config
+    let beat_time = BeatTimeBase {
+        beats_per_min: 124.0,
+        beats_per_bar: 4,
+        samples_per_sec: SAMPLE_RATE,
+    };
+
+    // create OSC sink
+    log::info!("Sending OSC messages to '{OSC_TARGET}'...");
+    let mut sink = OscSink::new(OSC_TARGET, beat_time)?;
+
+    // Watch for script changes, signaling in 'script_files_changed'
+    let script_files_changed = Arc::new(AtomicBool::new(false));
+
+    let mut watcher = notify::recommended_watcher({
+        let script_files_changed = script_files_changed.clone();
+        move |res: Result<notify::Event, notify::Error>| match res {
+            Ok(event) => {
+                if !event.kind.is_access() {
+                    log::info!("File change event: {event:?}");
+                    script_files_changed.store(true, Ordering::Relaxed);
+                }
+            }
+            Err(err) => log::error!("File watch error: {err}"),
+        }
+    })?;
+    watcher.watch(Path::new(DEMO_PATH), RecursiveMode::Recursive)?;
+
+    // stop on Control-C
+    let stop_running = Arc::new(AtomicBool::new(false));
+    ctrlc::set_handler({
+        let stop_running = stop_running.clone();
+        move || {
+            stop_running.store(true, Ordering::Relaxed);
+        }
+    })?;
+
+    // (re)run all scripts
+    let preload_time = beat_time.seconds_to_samples(0.1);
+    while !stop_running.load(Ordering::Relaxed) {
+        if script_files_changed.load(Ordering::Relaxed) {
+            script_files_changed.store(false, Ordering::Relaxed);
+            log::info!("Rebuilding all patterns...");
+        }
+
+        // build final phrase
+        let load = |instrument: Option<InstrumentId>, file_name: &Path| {
+            new_pattern_from_file(beat_time, instrument, file_name).unwrap_or_else(|err| {
+                log::warn!(
+                    "Script '{}' failed to compile:\n{}",
+                    file_name.display(),
+                    err
+                );
+                Rc::new(RefCell::new(BeatTimePattern::new(
+                    beat_time,
+                    BeatTimeStep::Beats(1.0),
+                )))
+            })
+        };
+        let phrase = Phrase::new(
+            beat_time,
+            entries
+                .iter()
+                .map(|e| load(Some(e.instrument_id), &e.script_path))
+                .collect(),
+            BeatTimeStep::Bar(4.0),
+        );
+
+        // wrap phrase into a sequence
+        let mut sequence = Sequence::new(beat_time, vec![phrase]);
+        // stop notes of the previous run: event times of the new sequence start at 0 again
+        sink.reset(0).unwrap_or_else(|err| {
+            log::warn!("Failed to send OSC message: {err}");
+        });
+
+        // run until we got a stop signal or on script file changes, sending events which are
+        // due within the preload time as OSC messages. Event times are relative to the start.
+        let start_time = Instant::now();
+        while !script_files_changed.load(Ordering::Relaxed) && !stop_running.load(Ordering::Relaxed)
+        {
+            let elapsed = beat_time.seconds_to_samples(start_time.elapsed().as_secs_f64());
+            sequence.consume_events_until_time(elapsed + preload_time, &mut |index, event| {
+                sink.send_event(index, &event).unwrap_or_else(|err| {
+                    log::warn!("Failed to send OSC message: {err}");
+                });
+            });
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    Ok(())
+}
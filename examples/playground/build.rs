@@ -29,13 +29,19 @@ fn main() {
             "_initialize_playground",
             "_shutdown_playground",
             "_start_playing",
+            "_start_playing_with_count_in",
             "_stop_playing",
             "_stop_playing_notes",
             "_midi_note_on",
             "_midi_note_off",
+            "_start_recording",
+            "_stop_recording",
+            "_set_recording_grid",
             "_set_volume",
+            "_set_reverb_send",
             "_set_bpm",
             "_set_instrument",
+            "_set_transpose_mode",
             "_set_parameter_value",
             "_update_script",
             "_load_sample",
@@ -44,7 +50,12 @@ fn main() {
             "_get_example_scripts",
             "_get_quickstart_scripts",
             "_get_script_error",
+            "_get_script_output",
             "_get_script_parameters",
+            "_tokenize_cycle",
+            "_get_cycle_operators",
+            "_export_project",
+            "_import_project",
         ];
         println!(
             "cargo::rustc-link-arg=-sEXPORTED_FUNCTIONS={}",
@@ -1,13 +1,25 @@
 #![allow(clippy::missing_safety_doc)]
 
 use std::{
-    cell::RefCell, collections::HashMap, ffi, fs, path::Path, rc::Rc, sync::Arc, time::Duration,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    ffi, fs,
+    path::Path,
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
 };
 
 use emscripten_rs_sys::{emscripten_request_animation_frame_loop, emscripten_run_script};
 use serde::ser::SerializeStruct;
 
-use pattrns::prelude::*;
+use pattrns::{
+    player::{effects, MixerId},
+    prelude::*,
+};
+
+mod recorder;
+use recorder::MidiRecorder;
 
 // -------------------------------------------------------------------------------------------------
 
@@ -109,6 +121,23 @@ impl From<&Rc<RefCell<pattrns::Parameter>>> for ScriptParameter {
     }
 }
 
+/// Serialized playground session, as exported and imported by the frontend.
+///
+/// Samples and the default instrument are referenced by name only: sample contents
+/// must be loaded separately before importing a project. Missing mixer values keep the
+/// current mixer state.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+struct Project {
+    script: String,
+    bpm: f32,
+    instrument: Option<String>,
+    samples: Vec<String>,
+    parameters: BTreeMap<String, f64>,
+    volume: Option<f32>,
+    reverb_send: Option<f32>,
+}
+
 /// Single pattern triggered by a MIDI note
 #[derive(Clone)]
 struct PlayingNote {
@@ -117,23 +146,80 @@ struct PlayingNote {
     sample_offset: SampleTime,
 }
 
+/// Create a new pattern from the given script content.
+/// Returns an empty fallback pattern and the error message on errors.
+fn new_script_pattern(
+    time_base: BeatTimeBase,
+    instrument_id: Option<usize>,
+    script_content: &str,
+) -> (Rc<RefCell<dyn Pattern>>, String) {
+    // create a new pattern from our script: scripts are user provided, so sandbox them
+    match new_pattern_from_string_sandboxed(
+        time_base,
+        instrument_id.map(InstrumentId::from),
+        script_content,
+        "[script]",
+        &SandboxConfig::new(),
+    ) {
+        Ok(pattern) => {
+            // return pattern as it is
+            (pattern, String::new())
+        }
+        Err(err) => {
+            // create an empty fallback pattern on errors
+            (
+                Rc::new(RefCell::new(BeatTimePattern::new(
+                    time_base,
+                    BeatTimeStep::Beats(1.0),
+                ))),
+                err.to_string(),
+            )
+        }
+    }
+}
+
+/// Apply the given parameter values to the pattern's parameters, clamped to their ranges.
+/// Values of parameters which don't exist in the pattern are ignored.
+fn apply_parameter_values(pattern: &Rc<RefCell<dyn Pattern>>, values: &HashMap<String, f64>) {
+    for (id, value) in values {
+        if let Some(parameter) = pattern
+            .borrow()
+            .parameters()
+            .iter()
+            .find(|p| p.borrow().id() == id)
+        {
+            let clamped_value = value.clamp(
+                *parameter.borrow().range().start(),
+                *parameter.borrow().range().end(),
+            );
+            parameter.borrow_mut().set_value(clamped_value);
+        }
+    }
+}
+
 /// The backend's global app state.
 struct Playground {
     playing: bool,
     player: SamplePlayer,
     sample_pool: Arc<SamplePool>,
     samples: Vec<SampleEntry>,
+    reverb_mixer_id: MixerId,
+    reverb_send: f32,
+    volume: f32,
     sequence: Option<Sequence>,
     pattern: Option<Rc<RefCell<dyn Pattern>>>,
     time_base: BeatTimeBase,
     time_base_changed: bool,
     instrument_id: Option<usize>,
+    transpose_mode: TransposeMode,
     script_content: String,
     script_changed: bool,
     script_parameters: Vec<ScriptParameter>,
     script_parameter_values: HashMap<String, f64>,
     script_error: String,
+    script_output: Vec<String>,
     playing_notes: Vec<PlayingNote>,
+    midi_recorder: MidiRecorder,
     output_start_sample_time: u64,
     emitted_sample_time: u64,
 }
@@ -172,6 +258,14 @@ impl Playground {
         player.set_sample_root_note(Note::C4);
         player.set_new_note_action(NewNoteAction::Off(Some(Duration::from_millis(350))));
 
+        // shared reverb bus, which samples can send to
+        let reverb_mixer_id = player.add_aux_bus(None)?;
+        player
+            .inner_mut()
+            .add_effect(effects::ReverbEffect::default(), Some(reverb_mixer_id))?;
+        let reverb_send = 0.0;
+        let volume = 1.0;
+
         // sequence & pattern
         let sequence = None;
         let pattern = None;
@@ -190,12 +284,15 @@ impl Playground {
         let script_parameters = Vec::new();
         let script_parameter_values = HashMap::new();
         let script_error = String::new();
+        let script_output = Vec::new();
 
         // MIDI note playback
         let playing_notes = Vec::new();
+        let midi_recorder = MidiRecorder::new();
 
         // default instrument
         let instrument_id = samples.first().map(|e| e.id);
+        let transpose_mode = TransposeMode::default();
 
         // playback time
         let output_start_sample_time = player.inner().output_sample_frame_position();
@@ -212,6 +309,9 @@ impl Playground {
             playing,
             sample_pool,
             samples,
+            reverb_mixer_id,
+            reverb_send,
+            volume,
             sequence,
             pattern,
             time_base,
@@ -221,8 +321,11 @@ impl Playground {
             script_parameters,
             script_parameter_values,
             script_error,
+            script_output,
             playing_notes,
+            midi_recorder,
             instrument_id,
+            transpose_mode,
             output_start_sample_time,
             emitted_sample_time,
         })
@@ -309,6 +412,30 @@ impl Playground {
         }
     }
 
+    /// Start playing after a count-in of the given number of bars, which clicks the
+    /// currently selected instrument on each beat.
+    pub fn start_playing_with_count_in(&mut self, bars: u32) {
+        if !self.playing {
+            // play the count-in and delay the play head by its duration
+            let preload_offset = self
+                .time_base
+                .seconds_to_samples(Self::PLAYBACK_PRELOAD_SECONDS);
+            let start_time = self.player.inner().output_sample_frame_position() + preload_offset;
+            self.player
+                .set_count_in_instrument(self.instrument_id.map(InstrumentId::from));
+            self.output_start_sample_time =
+                self.player
+                    .start_playing_with_count_in(&self.time_base, start_time, bars);
+            self.emitted_sample_time = 0;
+            // reset sequence
+            if let Some(sequence) = self.sequence.as_mut() {
+                sequence.reset();
+            }
+            // start playback
+            self.playing = true;
+        }
+    }
+
     /// Stops all currently playing audio sources and resets the sequence.
     pub fn stop_playing(&mut self) {
         let _ = self.player.stop_all_sources();
@@ -322,12 +449,51 @@ impl Playground {
 
     /// Set global playback volume.
     pub fn set_volume(&mut self, volume: f32) {
-        self.player.inner_mut().set_output_volume(volume);
+        self.volume = volume.max(0.0);
+        self.player.inner_mut().set_output_volume(self.volume);
+    }
+
+    /// Set the amount of all samples' signals that gets sent to the shared reverb bus.
+    pub fn set_reverb_send(&mut self, amount: f32) {
+        self.reverb_send = amount.clamp(0.0, 1.0);
+        for sample in &self.samples {
+            self.sample_pool.set_send(
+                InstrumentId::from(sample.id),
+                self.reverb_mixer_id,
+                self.reverb_send,
+            );
+        }
+    }
+
+    /// Start recording incoming MIDI notes.
+    pub fn start_recording(&mut self) {
+        let time = self.player.inner().output_sample_frame_position();
+        self.midi_recorder.start(time);
+    }
+
+    /// Stop recording incoming MIDI notes and return a script which plays back the quantized
+    /// recording, if any notes got recorded.
+    pub fn stop_recording(&mut self) -> Option<String> {
+        let time = self.player.inner().output_sample_frame_position();
+        self.midi_recorder.stop(time);
+        self.midi_recorder.to_script(&self.time_base)
+    }
+
+    /// Set the MIDI recording's quantization grid in steps per beat.
+    pub fn set_recording_grid(&mut self, steps_per_beat: u32) {
+        self.midi_recorder.set_steps_per_beat(steps_per_beat);
     }
 
     /// Handle incoming MIDI note on event
     pub fn handle_midi_note_on(&mut self, note: u8, velocity: u8) {
         assert!(note as usize <= Self::NUM_MIDI_NOTES);
+        if velocity == 0 {
+            // note-ons with zero velocity are note-offs by MIDI convention
+            self.handle_midi_note_off(note);
+            return;
+        }
+        let time = self.player.inner().output_sample_frame_position();
+        self.midi_recorder.note_on(note, velocity, time);
         if self.playing_notes.is_empty() || self.pattern_slot(note as usize).is_none() {
             // reset play head
             self.output_start_sample_time = self.player.inner().output_sample_frame_position();
@@ -359,17 +525,20 @@ impl Playground {
                 .pattern
                 .as_ref()
                 .expect("Expecting a valid pattern instance when notes are playing");
-            let new_pattern = self.new_pattern_instance(pattern, Some(new_note));
-            let pattern_slot = self
-                .pattern_slot(note as usize)
-                .expect("Missing MIDI pattern slot");
-            *pattern_slot = PatternSlot::Pattern(new_pattern);
+            let new_pattern_slots = self.new_note_pattern_slots(pattern, &[new_note]);
+            // replace the pattern slot, stopping notes of a still playing previous pattern
+            for (pattern_index, pattern_slot) in new_pattern_slots {
+                self.set_pattern_slot(pattern_index, pattern_slot)
+                    .expect("Missing MIDI pattern slot");
+            }
         }
     }
 
     /// Handle incoming MIDI note off event
     pub fn handle_midi_note_off(&mut self, note: u8) {
         assert!(note as usize <= Self::NUM_MIDI_NOTES);
+        let time = self.player.inner().output_sample_frame_position();
+        self.midi_recorder.note_off(note, time);
         // ony handle off events when we got an on event
         if let Some((playing_notes_index, _)) = self
             .playing_notes
@@ -405,6 +574,11 @@ impl Playground {
         self.script_changed = true;
     }
 
+    /// Sets how MIDI note transposed patterns handle notes which exit the valid note range.
+    pub fn set_transpose_mode(&mut self, mode: TransposeMode) {
+        self.transpose_mode = mode;
+    }
+
     /// Sets a script parameter value.
     pub fn set_parameter_value(&mut self, id: &str, value: f64) {
         self.script_parameter_values.insert(id.to_owned(), value);
@@ -428,6 +602,65 @@ impl Playground {
         }
     }
 
+    /// Serialize the current session state into a JSON project string.
+    pub fn export_project(&self) -> String {
+        let sample_name = |id: usize| {
+            self.samples
+                .iter()
+                .find(|s| s.id == id)
+                .map(|s| s.name.clone())
+        };
+        let project = Project {
+            script: self.script_content.clone(),
+            bpm: self.time_base.beats_per_min,
+            instrument: self.instrument_id.and_then(sample_name),
+            samples: self.samples.iter().map(|s| s.name.clone()).collect(),
+            parameters: self
+                .script_parameters
+                .iter()
+                .map(|p| {
+                    let parameter = p.0.borrow();
+                    (parameter.id().to_string(), parameter.value())
+                })
+                .collect(),
+            volume: Some(self.volume),
+            reverb_send: Some(self.reverb_send),
+        };
+        serde_json::to_string(&project).expect("Failed to serialize project")
+    }
+
+    /// Restore session state from a JSON project string, as created by `export_project`.
+    /// Sample references that are not available in the sample pool are skipped.
+    pub fn import_project(&mut self, json: &str) -> Result<(), String> {
+        let project = serde_json::from_str::<Project>(json).map_err(|err| err.to_string())?;
+        for sample_name in &project.samples {
+            if !self.samples.iter().any(|s| &s.name == sample_name) {
+                println!("Skipping unavailable project sample '{}'", sample_name);
+            }
+        }
+        if project.bpm > 0.0 {
+            self.set_bpm(project.bpm);
+        }
+        if let Some(volume) = project.volume {
+            self.set_volume(volume);
+        }
+        if let Some(reverb_send) = project.reverb_send {
+            self.set_reverb_send(reverb_send);
+        }
+        self.instrument_id = project.instrument.as_ref().and_then(|name| {
+            let sample = self.samples.iter().find(|s| &s.name == name);
+            if sample.is_none() {
+                println!("Skipping unavailable project instrument '{}'", name);
+            }
+            sample.map(|s| s.id)
+        });
+        // parameter values get applied to the new pattern in `rebuild_sequence`
+        self.script_parameter_values = project.parameters.into_iter().collect();
+        self.script_content = project.script;
+        self.script_changed = true;
+        Ok(())
+    }
+
     /// Load a sample from a raw file buffer and add it to the pool
     pub fn load_sample(&mut self, file_buffer: Vec<u8>, file_name: &str) -> Result<usize, String> {
         match self.sample_pool.load_sample_buffer(file_buffer, file_name) {
@@ -439,6 +672,8 @@ impl Playground {
                     .to_string_lossy()
                     .to_string();
                 self.samples.push(SampleEntry { name, id });
+                self.sample_pool
+                    .set_send(instrument_id, self.reverb_mixer_id, self.reverb_send);
                 Ok(id)
             }
             Err(err) => Err(err.to_string()),
@@ -523,6 +758,8 @@ impl Playground {
                     self.update_script_error(&err.to_string());
                     pattrns::bindings::clear_lua_callback_errors();
                 }
+                // handle debug output
+                self.update_script_output();
             }
             self.emitted_sample_time += samples_to_emit;
         }
@@ -530,11 +767,15 @@ impl Playground {
 
     // Rebuild sequence and pattern from actual script content
     fn rebuild_sequence(&mut self) {
-        // clear runtime errors
+        // clear runtime errors and warnings
         pattrns::bindings::clear_lua_callback_errors();
-        // build pattern and set compile errors and parameters
-        let (pattern, error) = self.new_pattern();
+        pattrns::bindings::clear_lua_callback_warnings();
+        // build pattern and set compile errors
+        let (pattern, error) =
+            new_script_pattern(self.time_base, self.instrument_id, &self.script_content);
         self.update_script_error(&error);
+        // restore previous parameter values in new pattern, then set parameters
+        apply_parameter_values(&pattern, &self.script_parameter_values);
         self.update_script_parameters(
             &pattern
                 .borrow()
@@ -543,30 +784,15 @@ impl Playground {
                 .map(ScriptParameter::from)
                 .collect::<Vec<_>>(),
         );
-        // restore previous parameter values in new pattern
-        for (id, value) in &self.script_parameter_values {
-            if let Some(parameter) = pattern
-                .borrow()
-                .parameters()
-                .iter()
-                .find(|p| p.borrow().id() == id)
-            {
-                let clamped_value = value.clamp(
-                    *parameter.borrow().range().start(),
-                    *parameter.borrow().range().end(),
-                );
-                parameter.borrow_mut().set_value(clamped_value);
-            }
-        }
         // build pattern slots
         let pattern_slots = {
             if !self.playing_notes.is_empty() {
                 // one pattern for each live played note
                 let mut slots = vec![PatternSlot::Stop; Self::NUM_MIDI_NOTES];
-                for playing_note in &self.playing_notes.clone() {
-                    slots[playing_note.note as usize] = PatternSlot::Pattern(
-                        self.new_pattern_instance(&pattern, Some(playing_note.clone())),
-                    );
+                for (pattern_index, pattern_slot) in
+                    self.new_note_pattern_slots(&pattern, &self.playing_notes)
+                {
+                    slots[pattern_index] = pattern_slot;
                 }
                 slots
             } else {
@@ -591,6 +817,14 @@ impl Playground {
         );
         self.sequence.replace(sequence);
         self.pattern.replace(pattern);
+        // reset debug output and fetch output from the script's top level
+        if !self.script_output.is_empty() {
+            self.script_output.clear();
+            unsafe {
+                call_frontend_notifier("on_script_output_changed");
+            }
+        }
+        self.update_script_output();
         // reset all update flags: we're fully up to date now.
         self.script_changed = false;
         self.time_base_changed = false;
@@ -614,6 +848,35 @@ impl Playground {
         }
     }
 
+    /// Fetch new debug output and warnings from the pattern and update the frontend if needed
+    fn update_script_output(&mut self) {
+        let mut lines = self
+            .pattern
+            .as_ref()
+            .and_then(|pattern| pattern.borrow().debug_output())
+            .map(|debug_output| debug_output.take())
+            .unwrap_or_default();
+        if pattrns::bindings::has_lua_callback_warnings().is_some() {
+            lines.extend(
+                pattrns::bindings::lua_callback_warnings()
+                    .into_iter()
+                    .map(|warning| format!("Warning: {}", warning)),
+            );
+            pattrns::bindings::clear_lua_callback_warnings();
+        }
+        if !lines.is_empty() {
+            self.script_output.extend(lines);
+            let excess_lines = self
+                .script_output
+                .len()
+                .saturating_sub(DebugOutput::MAX_LINES);
+            self.script_output.drain(..excess_lines);
+            unsafe {
+                call_frontend_notifier("on_script_output_changed");
+            }
+        }
+    }
+
     /// Update script parameters internally and in frontend if needed
     fn update_script_parameters(&mut self, parameters: &[ScriptParameter]) {
         let parameters_changed = self.script_parameters != parameters;
@@ -644,49 +907,53 @@ impl Playground {
         }
     }
 
-    /// Create a new pattern from the currently set script content.
-    fn new_pattern(&self) -> (Rc<RefCell<dyn Pattern>>, String) {
-        // create a new pattern from our script
-        match new_pattern_from_string(
-            self.time_base,
-            self.instrument_id.map(InstrumentId::from),
-            &self.script_content,
-            "[script]",
-        ) {
-            Ok(pattern) => {
-                // return pattern as it is
-                (pattern, String::new())
-            }
-            Err(err) => {
-                // create an empty fallback pattern on errors
-                (
-                    Rc::new(RefCell::new(BeatTimePattern::new(
-                        self.time_base,
-                        BeatTimeStep::Beats(1.0),
-                    ))),
-                    err.to_string(),
-                )
-            }
+    /// Replace a pattern slot by index, returning the previous slot. pattern_index is used as
+    /// MIDI note number.
+    fn set_pattern_slot(
+        &mut self,
+        pattern_index: usize,
+        pattern_slot: PatternSlot,
+    ) -> Option<PatternSlot> {
+        let sequence = self.sequence.as_mut()?;
+        let phrase = sequence
+            .phrases_mut()
+            .first_mut()
+            .expect("Failed to access phrase");
+        if pattern_index < phrase.pattern_slots().len() {
+            Some(phrase.set_pattern_slot(pattern_index, pattern_slot))
+        } else {
+            None
         }
     }
 
-    /// Create a new pattern instance clone for the given note from the passed pattern
-    /// for the given optional midi note for note transforms.
-    fn new_pattern_instance(
+    /// Create reset pattern duplicates of the passed pattern for the given live played MIDI
+    /// notes, with the notes' sample offsets and note transforms applied. Returns the MIDI
+    /// notes' pattern slot indices and their new pattern slots.
+    fn new_note_pattern_slots(
         &self,
         pattern: &Rc<RefCell<dyn Pattern>>,
-        midi_note: Option<PlayingNote>,
-    ) -> Rc<RefCell<dyn Pattern>> {
-        // create a new pattern clone
-        let pattern = pattern.borrow().duplicate();
-        // and apply sample offset and event transform
-        pattern
-            .borrow_mut()
-            .set_sample_offset(midi_note.as_ref().map(|n| n.sample_offset).unwrap_or(0));
-        pattern
-            .borrow_mut()
-            .set_event_transform(self.new_pattern_event_transform(midi_note));
-        pattern
+        midi_notes: &[PlayingNote],
+    ) -> Vec<(usize, PatternSlot)> {
+        let duplicates = pattern.borrow().clone_into_phrase(
+            midi_notes.len(),
+            self.time_base,
+            BeatTimeStep::Bar(4.0),
+        );
+        duplicates
+            .pattern_slots()
+            .iter()
+            .zip(midi_notes)
+            .map(|(pattern_slot, midi_note)| {
+                if let PatternSlot::Pattern(pattern) = pattern_slot {
+                    let mut pattern = pattern.borrow_mut();
+                    pattern.set_sample_offset(midi_note.sample_offset as f64);
+                    pattern.set_event_transform(
+                        self.new_pattern_event_transform(Some(midi_note.clone())),
+                    );
+                }
+                (midi_note.note as usize, pattern_slot.clone())
+            })
+            .collect()
     }
 
     /// Create a note event transform function which applies instrument and
@@ -695,42 +962,38 @@ impl Playground {
         &self,
         midi_note: Option<PlayingNote>,
     ) -> Option<EventTransform> {
-        let transforms: Vec<_> = [
-            // Instrument transform
-            self.instrument_id.map(InstrumentId::from).map(|id| {
-                Box::new(move |note: &mut NoteEvent| {
-                    if note.instrument.is_none() {
-                        note.instrument = Some(id)
-                    }
-                }) as Box<dyn Fn(&mut NoteEvent)>
-            }),
-            // Note transform
-            midi_note.map(|note| {
-                let offset = note.note as i32 - 48;
-                let volume = note.velocity as f32 / 127.0;
-                Box::new(move |note_event: &mut NoteEvent| {
-                    note_event.note = note_event.note.transposed(offset);
-                    note_event.volume *= volume;
-                }) as Box<dyn Fn(&mut NoteEvent)>
-            }),
-        ]
-        .into_iter()
-        .flatten()
-        .collect();
-
-        if !transforms.is_empty() {
-            Some(Rc::new(move |event: &mut Event| {
-                if let Event::NoteEvents(note_events) = event {
-                    note_events.iter_mut().flatten().for_each(|note_event| {
-                        transforms
-                            .iter()
-                            .for_each(|transform| transform(note_event))
-                    });
-                }
-            }))
-        } else {
-            None
-        }
+        compose_event_transforms(
+            [
+                // Instrument transform
+                self.instrument_id.map(InstrumentId::from).map(|id| {
+                    new_note_event_transform(move |note_event| {
+                        if let Some(note) = note_event {
+                            if note.instrument.is_none() {
+                                note.instrument = Some(id)
+                            }
+                        }
+                    })
+                }),
+                // Note transform
+                midi_note.map(|note| {
+                    let offset = note.note as i32 - 48;
+                    let volume = note.velocity as f32 / 127.0;
+                    let mode = self.transpose_mode;
+                    new_note_event_transform(move |note_event| {
+                        if let Some(note) = note_event {
+                            if let Some(transposed) = note.note.transposed_with_mode(offset, mode) {
+                                note.note = transposed;
+                                note.volume *= volume;
+                            } else {
+                                *note_event = None;
+                            }
+                        }
+                    })
+                }),
+            ]
+            .into_iter()
+            .flatten(),
+        )
     }
 }
 
@@ -798,6 +1061,12 @@ pub extern "C" fn start_playing() {
     with_playground_mut(|playground| playground.start_playing());
 }
 
+/// Start playback after a count-in of the given number of bars.
+#[no_mangle]
+pub extern "C" fn start_playing_with_count_in(bars: u32) {
+    with_playground_mut(|playground| playground.start_playing_with_count_in(bars));
+}
+
 /// Stop playback.
 #[no_mangle]
 pub extern "C" fn stop_playing() {
@@ -816,6 +1085,12 @@ pub extern "C" fn set_volume(volume: f32) {
     with_playground_mut(|playground| playground.set_volume(volume));
 }
 
+/// Set new reverb send amount of all samples.
+#[no_mangle]
+pub extern "C" fn set_reverb_send(amount: f32) {
+    with_playground_mut(|playground| playground.set_reverb_send(amount));
+}
+
 /// Handle note on event from the frontend
 #[no_mangle]
 pub extern "C" fn midi_note_on(note: u8, velocity: u8) {
@@ -828,6 +1103,26 @@ pub extern "C" fn midi_note_off(note: u8) {
     with_playground_mut(|playground| playground.handle_midi_note_off(note));
 }
 
+/// Start recording MIDI notes from the frontend.
+#[no_mangle]
+pub extern "C" fn start_recording() {
+    with_playground_mut(|playground| playground.start_recording());
+}
+
+/// Stop recording MIDI notes. Returns the recording as pattern script or an empty string,
+/// when nothing got recorded.
+#[no_mangle]
+pub unsafe extern "C" fn stop_recording() -> *const ffi::c_char {
+    let script = with_playground_mut(|playground| playground.stop_recording());
+    new_raw_cstring(&script.unwrap_or_default())
+}
+
+/// Set MIDI recording quantization grid in steps per beat.
+#[no_mangle]
+pub extern "C" fn set_recording_grid(steps_per_beat: u32) {
+    with_playground_mut(|playground| playground.set_recording_grid(steps_per_beat));
+}
+
 /// Update player's BPM.
 #[no_mangle]
 pub extern "C" fn set_bpm(bpm: ffi::c_int) {
@@ -840,6 +1135,18 @@ pub extern "C" fn set_instrument(id: ffi::c_int) {
     with_playground_mut(|playground| playground.set_instrument(id));
 }
 
+/// Update how MIDI note transposed patterns handle notes which exit the valid note range:
+/// 0 = clamp, 1 = wrap by octaves, 2 = drop.
+#[no_mangle]
+pub extern "C" fn set_transpose_mode(mode: ffi::c_int) {
+    let mode = match mode {
+        1 => TransposeMode::Wrap,
+        2 => TransposeMode::Drop,
+        _ => TransposeMode::Clamp,
+    };
+    with_playground_mut(|playground| playground.set_transpose_mode(mode));
+}
+
 /// Set a script parameter value.
 #[no_mangle]
 pub unsafe extern "C" fn set_parameter_value(id_ptr: *const ffi::c_char, value: f64) {
@@ -917,6 +1224,77 @@ pub unsafe extern "C" fn get_script_parameters() -> *const ffi::c_char {
     new_raw_cstring(&serde_json::to_string(&parameters).unwrap())
 }
 
+/// Tokenizes the given cycle mini-notation string for syntax highlighting.
+/// Returns a JSON object with `tokens` (kind and byte range) and an optional `error`.
+#[no_mangle]
+pub unsafe extern "C" fn tokenize_cycle(notation_ptr: *const ffi::c_char) -> *const ffi::c_char {
+    let notation = ffi::CStr::from_ptr(notation_ptr).to_string_lossy();
+    let json = match pattrns::tokenize_cycle(&notation) {
+        Ok(tokens) => serde_json::json!({
+            "tokens": tokens
+                .iter()
+                .map(|token| {
+                    serde_json::json!({
+                        "kind": format!("{:?}", token.kind),
+                        "start": token.range.start,
+                        "end": token.range.end
+                    })
+                })
+                .collect::<Vec<_>>(),
+        }),
+        Err(err) => serde_json::json!({
+            "tokens": [],
+            "error": {
+                "message": err.message,
+                "start": err.range.start,
+                "end": err.range.end
+            }
+        }),
+    };
+    new_raw_cstring(&json.to_string())
+}
+
+/// Returns all operators of the cycle mini-notation as JSON array, e.g. for autocompletion.
+/// Unlimited argument counts are serialized as `null`.
+#[no_mangle]
+pub extern "C" fn get_cycle_operators() -> *const ffi::c_char {
+    let operators = pattrns::cycle_operators()
+        .iter()
+        .map(|operator| {
+            serde_json::json!({
+                "syntax": operator.syntax,
+                "name": operator.name,
+                "kind": format!("{:?}", operator.kind),
+                "min_arguments": operator.min_arguments,
+                "max_arguments": (operator.max_arguments != usize::MAX)
+                    .then_some(operator.max_arguments),
+                "description": operator.description,
+                "example": operator.example
+            })
+        })
+        .collect::<Vec<_>>();
+    new_raw_cstring(&serde_json::Value::from(operators).to_string())
+}
+
+/// Returns the current session state as JSON project string.
+#[no_mangle]
+pub unsafe extern "C" fn export_project() -> *const ffi::c_char {
+    let json = with_playground(|playground| playground.export_project());
+    new_raw_cstring(&json)
+}
+
+/// Restores the session state from a JSON project string.
+/// Returns an error string on failure or null on success.
+#[no_mangle]
+pub unsafe extern "C" fn import_project(json_ptr: *const ffi::c_char) -> *const ffi::c_char {
+    let json = ffi::CStr::from_ptr(json_ptr).to_string_lossy().into_owned();
+    match with_playground_mut(|playground| Some(playground.import_project(&json))) {
+        Some(Err(err)) => new_raw_cstring(&err),
+        Some(Ok(())) => std::ptr::null(),
+        None => new_raw_cstring("Playground is not initialized"),
+    }
+}
+
 /// Returns actual script runtime errors, if any
 #[no_mangle]
 pub unsafe extern "C" fn get_script_error() -> *const ffi::c_char {
@@ -924,6 +1302,13 @@ pub unsafe extern "C" fn get_script_error() -> *const ffi::c_char {
     new_raw_cstring(&string)
 }
 
+/// Returns captured debug output of the actual script, if any
+#[no_mangle]
+pub unsafe extern "C" fn get_script_output() -> *const ffi::c_char {
+    let string = with_playground(|playground| playground.script_output.join("\n"));
+    new_raw_cstring(&string)
+}
+
 // -------------------------------------------------------------------------------------------------
 
 /// Call the given `window.$NOTIFIER` function in the frontend
@@ -933,3 +1318,64 @@ unsafe fn call_frontend_notifier(notifier_name: &str) {
     emscripten_run_script(ptr);
     free_cstring(ptr);
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn project_serialization() {
+        let project = Project {
+            script: "return pattern { event = \"c4\" }".to_string(),
+            bpm: 98.0,
+            instrument: Some("Kick".to_string()),
+            samples: vec!["Kick".to_string(), "Snare".to_string()],
+            parameters: BTreeMap::from([("volume".to_string(), 0.5)]),
+            volume: Some(0.8),
+            reverb_send: Some(0.25),
+        };
+        let json = serde_json::to_string(&project).unwrap();
+        assert_eq!(serde_json::from_str::<Project>(&json).unwrap(), project);
+        // missing fields fall back to defaults
+        assert_eq!(
+            serde_json::from_str::<Project>(r#"{ "script": "" }"#).unwrap(),
+            Project::default()
+        );
+    }
+
+    #[test]
+    fn project_playback() {
+        let json = r#"{
+            "script": "return pattern { parameter = { parameter.integer('key', 48, { 0, 127 }) }, event = function(context) return context.parameter.key end }",
+            "bpm": 60,
+            "parameters": { "key": 60, "missing": 1 }
+        }"#;
+        let project = serde_json::from_str::<Project>(json).unwrap();
+        // load the project's pattern and apply its parameter values
+        let time_base = BeatTimeBase {
+            beats_per_min: project.bpm,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let (pattern, error) = new_script_pattern(time_base, None, &project.script);
+        assert_eq!(error, "");
+        apply_parameter_values(&pattern, &project.parameters.into_iter().collect());
+        // play it
+        let events = (0..2)
+            .map(|_| {
+                let event = pattern.borrow_mut().run_until_time(u64::MAX).unwrap();
+                let note = match event.event {
+                    Some(Event::NoteEvents(notes)) => notes[0].as_ref().map(|n| n.note),
+                    _ => None,
+                };
+                (event.time, note)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![(0, Some(Note::from(60))), (44100, Some(Note::from(60)))]
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! Records live played MIDI notes and converts them into an editable pattern script.
+
+use pattrns::prelude::*;
+
+// -------------------------------------------------------------------------------------------------
+
+/// A single recorded MIDI note with its start and end sample times.
+#[derive(Clone, Debug, PartialEq)]
+struct RecordedNote {
+    note: u8,
+    velocity: u8,
+    start: SampleTime,
+    end: Option<SampleTime>,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A note which starts in a quantized step of a recording.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuantizedNote {
+    pub note: u8,
+    pub velocity: u8,
+    /// Length of the note in steps, always >= 1.
+    pub length: usize,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Captures incoming MIDI note on and offs with their timing, quantizes them to a configurable
+/// step grid and generates a cycle based pattern script from the recorded performance.
+#[derive(Clone, Debug)]
+pub struct MidiRecorder {
+    recording: bool,
+    start_time: SampleTime,
+    end_time: SampleTime,
+    steps_per_beat: u32,
+    notes: Vec<RecordedNote>,
+}
+
+impl MidiRecorder {
+    /// Default quantization grid: sixteenth notes.
+    const DEFAULT_STEPS_PER_BEAT: u32 = 4;
+
+    pub fn new() -> Self {
+        Self {
+            recording: false,
+            start_time: 0,
+            end_time: 0,
+            steps_per_beat: Self::DEFAULT_STEPS_PER_BEAT,
+            notes: Vec::new(),
+        }
+    }
+
+    /// true while recording notes.
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Quantization grid in steps per beat.
+    pub fn steps_per_beat(&self) -> u32 {
+        self.steps_per_beat
+    }
+    /// Set a new quantization grid in steps per beat.
+    pub fn set_steps_per_beat(&mut self, steps_per_beat: u32) {
+        self.steps_per_beat = steps_per_beat.max(1);
+    }
+
+    /// Start a new recording at the given sample time, discarding previously recorded notes.
+    pub fn start(&mut self, time: SampleTime) {
+        self.recording = true;
+        self.start_time = time;
+        self.end_time = time;
+        self.notes.clear();
+    }
+
+    /// Stop recording at the given sample time. Notes which are still held get stopped too.
+    pub fn stop(&mut self, time: SampleTime) {
+        if self.recording {
+            self.recording = false;
+            self.end_time = time.max(self.start_time);
+            for note in &mut self.notes {
+                note.end.get_or_insert(self.end_time);
+            }
+        }
+    }
+
+    /// Record a note on at the given sample time.
+    pub fn note_on(&mut self, note: u8, velocity: u8, time: SampleTime) {
+        if self.recording {
+            self.notes.push(RecordedNote {
+                note,
+                velocity,
+                start: time.max(self.start_time),
+                end: None,
+            });
+        }
+    }
+
+    /// Record a note off at the given sample time.
+    pub fn note_off(&mut self, note: u8, time: SampleTime) {
+        if self.recording {
+            if let Some(recorded_note) = self
+                .notes
+                .iter_mut()
+                .rev()
+                .find(|n| n.note == note && n.end.is_none())
+            {
+                recorded_note.end = Some(time.max(recorded_note.start));
+            }
+        }
+    }
+
+    /// Quantize the recorded notes to the recorder's grid with the given time base. Returns
+    /// all notes which start in each step. The step count is rounded up to whole bars.
+    pub fn quantized_steps(&self, time_base: &BeatTimeBase) -> Vec<Vec<QuantizedNote>> {
+        let step_samples = time_base.samples_per_beat() / self.steps_per_beat as f64;
+        let steps_per_bar = (time_base.beats_per_bar * self.steps_per_beat) as usize;
+        let to_step = |time: SampleTime| {
+            ((time.saturating_sub(self.start_time)) as f64 / step_samples).round() as usize
+        };
+        let end_step = self
+            .notes
+            .iter()
+            .map(|note| note.end.unwrap_or(self.end_time))
+            .fold(to_step(self.end_time), |end, time| end.max(to_step(time)));
+        let step_count = end_step.div_ceil(steps_per_bar).max(1) * steps_per_bar;
+        let mut steps = vec![Vec::new(); step_count];
+        for note in &self.notes {
+            // notes which got quantized past the end wrap around to the start
+            let start = to_step(note.start);
+            let end = to_step(note.end.unwrap_or(self.end_time));
+            let length = end.saturating_sub(start).max(1);
+            steps[start % step_count].push(QuantizedNote {
+                note: note.note,
+                velocity: note.velocity,
+                length,
+            });
+        }
+        steps
+    }
+
+    /// Generate a pattern script which plays back the quantized recording as cycle.
+    /// Returns None, when nothing got recorded.
+    pub fn to_script(&self, time_base: &BeatTimeBase) -> Option<String> {
+        if self.notes.is_empty() {
+            return None;
+        }
+        let steps = self.quantized_steps(time_base);
+        let steps_per_bar = (time_base.beats_per_bar * self.steps_per_beat) as usize;
+        let bars = steps.len() / steps_per_bar;
+        let mut cycle_steps = Vec::with_capacity(steps.len());
+        let mut hold_until = 0;
+        for (index, notes) in steps.iter().enumerate() {
+            if notes.is_empty() {
+                cycle_steps.push(if index < hold_until { "_" } else { "~" }.to_string());
+            } else {
+                let note_names = notes
+                    .iter()
+                    .map(|note| {
+                        let name = Note::from(note.note).to_string().to_lowercase();
+                        if note.velocity < 127 {
+                            format!("{}:v{:.2}", name, note.velocity as f32 / 127.0)
+                        } else {
+                            name
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                if note_names.len() == 1 {
+                    cycle_steps.push(note_names[0].clone());
+                } else {
+                    cycle_steps.push(format!("[{}]", note_names.join(", ")));
+                }
+                hold_until = index + notes.iter().map(|n| n.length).max().unwrap_or(1);
+            }
+        }
+        let bar_lines = cycle_steps
+            .chunks(steps_per_bar)
+            .map(|bar| format!("    {}", bar.join(" ")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some(format!(
+            "-- recorded performance, quantized to 1/{} beats\n\
+             return pattern {{\n  \
+               unit = \"bars\",\n  \
+               resolution = {},\n  \
+               event = cycle([[\n{}\n  ]])\n\
+             }}\n",
+            self.steps_per_beat, bars, bar_lines
+        ))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quantize() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // sixteenth steps are 5512.5 samples long
+        let mut recorder = MidiRecorder::new();
+        recorder.start(1000);
+        recorder.note_on(48, 127, 1000 + 200);
+        recorder.note_on(52, 64, 1000 + 5400);
+        recorder.note_off(48, 1000 + 10900);
+        recorder.note_off(52, 1000 + 11200);
+        recorder.note_on(55, 127, 1000 + 3 * 5512 + 2000);
+        recorder.stop(1000 + 16 * 5512);
+        let steps = recorder.quantized_steps(&time_base);
+        assert_eq!(steps.len(), 16);
+        assert_eq!(
+            steps[0],
+            vec![QuantizedNote {
+                note: 48,
+                velocity: 127,
+                length: 2
+            }]
+        );
+        assert_eq!(
+            steps[1],
+            vec![QuantizedNote {
+                note: 52,
+                velocity: 64,
+                length: 1
+            }]
+        );
+        assert!(steps[2].is_empty());
+        assert_eq!(
+            steps[3],
+            vec![QuantizedNote {
+                note: 55,
+                velocity: 127,
+                length: 13
+            }]
+        );
+        // coarser grids
+        recorder.set_steps_per_beat(2);
+        let steps = recorder.quantized_steps(&time_base);
+        assert_eq!(steps.len(), 8);
+        assert_eq!(steps[0].len(), 2);
+        assert!(steps[1].is_empty());
+        assert_eq!(steps[2].len(), 1);
+        // generated scripts use one cycle step per grid step
+        recorder.set_steps_per_beat(4);
+        let script = recorder.to_script(&time_base).unwrap();
+        let cycle = "c4 e4:v0.50 ~ g4 _ _ _ _ _ _ _ _ _ _ _ _";
+        assert!(script.contains(cycle));
+        assert!(Cycle::from(cycle).is_ok());
+    }
+}
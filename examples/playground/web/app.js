@@ -120,6 +120,13 @@ const backend = {
         return error;
     },
 
+    getScriptOutput: function () {
+        let stringPtr = this._playground.ccall('get_script_output', 'number', [])
+        const output = this._playground.UTF8ToString(stringPtr);
+        this._freeCString(stringPtr)
+        return output;
+    },
+
     getScriptParameters: function () {
         let stringPtr = this._playground.ccall('get_script_parameters', 'number', [])
         const json = this._playground.UTF8ToString(stringPtr);
@@ -128,6 +135,20 @@ const backend = {
         return parameters;
     },
 
+    tokenizeCycle: function (notation) {
+        const stringPtr = this._playground.ccall('tokenize_cycle', 'number', ['string'], [notation])
+        const json = this._playground.UTF8ToString(stringPtr);
+        this._freeCString(stringPtr)
+        return JSON.parse(json);
+    },
+
+    getCycleOperators: function () {
+        const stringPtr = this._playground.ccall('get_cycle_operators', 'number', [])
+        const json = this._playground.UTF8ToString(stringPtr);
+        this._freeCString(stringPtr)
+        return JSON.parse(json);
+    },
+
     isPlaying: function () {
         return this._isPlaying;
     },
@@ -137,6 +158,11 @@ const backend = {
         this._isPlaying = true;
     },
 
+    startPlayingWithCountIn: function (bars) {
+        this._playground.ccall("start_playing_with_count_in", "undefined", ["number"], [bars]);
+        this._isPlaying = true;
+    },
+
     stopPlaying: function () {
         this._playground.ccall("stop_playing");
         this._isPlaying = false;
@@ -146,6 +172,10 @@ const backend = {
         this._playground.ccall("set_volume", "undefined", ["number"], [volume]);
     },
 
+    setReverbSend: function (amount) {
+        this._playground.ccall("set_reverb_send", "undefined", ["number"], [amount]);
+    },
+
     stopPlayingNotes: function () {
         this._playground.ccall("stop_playing_notes");
     },
@@ -158,10 +188,29 @@ const backend = {
         this._playground.ccall("midi_note_off", 'undefined', ['number'], [note]);
     },
 
+    startRecording: function () {
+        this._playground.ccall("start_recording");
+    },
+
+    stopRecording: function () {
+        let stringPtr = this._playground.ccall('stop_recording', 'number', [])
+        const script = this._playground.UTF8ToString(stringPtr);
+        this._freeCString(stringPtr)
+        return script;
+    },
+
+    setRecordingGrid: function (stepsPerBeat) {
+        this._playground.ccall("set_recording_grid", 'undefined', ['number'], [stepsPerBeat]);
+    },
+
     updateInstrument: function (instrument) {
         this._playground.ccall("set_instrument", 'undefined', ['number'], [instrument]);
     },
 
+    updateTransposeMode: function (mode) {
+        this._playground.ccall("set_transpose_mode", 'undefined', ['number'], [mode]);
+    },
+
     updateBpm: function (bpm) {
         this._playground.ccall("set_bpm", 'undefined', ['number'], [bpm]);
     },
@@ -189,6 +238,23 @@ const backend = {
         this._playground.ccall('clear_samples', 'undefined', []);
     },
 
+    exportProject: function () {
+        const stringPtr = this._playground.ccall('export_project', 'number', [])
+        const json = this._playground.UTF8ToString(stringPtr);
+        this._freeCString(stringPtr)
+        return json;
+    },
+
+    importProject: function (json) {
+        const errorPtr = this._playground.ccall('import_project', 'number', ['string'], [json]);
+        if (errorPtr === 0) {
+            return null;
+        }
+        const error = this._playground.UTF8ToString(errorPtr);
+        this._freeCString(errorPtr)
+        return error;
+    },
+
     _freeCString: function (stringPtr) {
         this._playground.ccall('free_cstring', 'undefined', ['number'], [stringPtr])
     },
@@ -203,6 +269,7 @@ const app = {
     _changedHashFromUserEdit: false,
     _changedScriptFromHash: false,
     _midiEnabled: false,
+    _recording: false,
     _bpmInput: null,
 
     initialize: function () {
@@ -218,6 +285,7 @@ const app = {
         this._initSampleDropdown();
         this._initExampleScripts();
         this._initScriptErrorHandler();
+        this._initScriptOutputHandler();
         this._initScriptParameterHandler();
         this._initEditor();
     },
@@ -265,7 +333,13 @@ const app = {
                 backend.stopPlaying();
                 this.setStatus("Playback stopped.");
             } else {
-                backend.startPlaying();
+                const countInSelect = document.getElementById('countInSelect');
+                const countInBars = countInSelect ? parseInt(countInSelect.value) : 0;
+                if (countInBars > 0) {
+                    backend.startPlayingWithCountIn(countInBars);
+                } else {
+                    backend.startPlaying();
+                }
                 this.setStatus("Playing...");
             }
             this._togglePlayButton(backend.isPlaying());
@@ -290,7 +364,8 @@ const app = {
         // Set up control handlers
         const playButton = document.getElementById('playButton');
         const midiButton = document.getElementById('midiButton');
-        console.assert(playButton && midiButton);
+        const recordButton = document.getElementById('recordButton');
+        console.assert(playButton && midiButton && recordButton);
         
         playButton.addEventListener('click', () => this._togglePlayback());
         playButton.title = "Toggle Playback (Ctrl+Shift+Space)";
@@ -364,6 +439,15 @@ const app = {
             e.target.dispatchEvent(new Event('change', { 'bubbles': true }));
         });
 
+        const reverbSlider = document.getElementById('reverbSlider');
+        console.assert(reverbSlider);
+
+        backend.setReverbSend(parseInt(reverbSlider.value, 10) / 100.0);
+        reverbSlider.addEventListener('input', (e) => {
+            const amount = parseInt(e.target.value, 10) / 100.0;
+            backend.setReverbSend(amount);
+        });
+
         let midiAccess = null;
         let currentMidiNotes = new Set();
 
@@ -406,9 +490,33 @@ const app = {
                 });
         }
 
+        const startRecording = () => {
+            this._recording = true;
+            recordButton.classList.add("enabled");
+            backend.startRecording();
+            app.setStatus("Recording MIDI input...");
+        }
+
+        const stopRecording = () => {
+            this._recording = false;
+            recordButton.classList.remove("enabled");
+            const script = backend.stopRecording();
+            if (script) {
+                this._editor.setValue(script);
+                backend.updateScriptContent(script);
+                app.setStatus("Converted the recorded MIDI input into a new script");
+            } else {
+                app.setStatus("Recording stopped: no notes got recorded");
+            }
+        }
+
         const disableMidi = () => {
             this._midiEnabled = false;
             midiButton.classList.remove("enabled");
+            // Stop recording
+            if (this._recording) {
+                stopRecording();
+            }
             // Stop listening to MIDI input
             if (midiAccess) {
                 for (let input of midiAccess.inputs.values()) {
@@ -431,6 +539,7 @@ const app = {
                     // Disable play/stop buttons on success
                     this._togglePlayButton(false);
                     playButton.disabled = true;
+                    recordButton.disabled = false;
                 }).catch(err => {
                     const isError = true;
                     app.setStatus("Failed to access MIDI: " + err, isError);
@@ -439,6 +548,7 @@ const app = {
                 disableMidi().then(() => {
                     // Re-enable play/stop buttons
                     playButton.disabled = false;
+                    recordButton.disabled = true;
                 }).catch(err => {
                     const isError = true;
                     app.setStatus("Failed to release MIDI: " + err, isError);
@@ -446,6 +556,16 @@ const app = {
             }
         });
 
+        recordButton.title = "Record MIDI input into a new script (needs MIDI input)";
+        recordButton.disabled = true;
+        recordButton.addEventListener('click', () => {
+            if (!this._recording) {
+                startRecording();
+            } else {
+                stopRecording();
+            }
+        });
+
         const loadSampleButton = document.getElementById('loadSampleButton');
         const sampleFileInput = document.getElementById('sampleFileInput');
         const clearSamplesButton = document.getElementById('clearSamplesButton');
@@ -494,6 +614,79 @@ const app = {
             };
             reader.readAsArrayBuffer(file);
         });
+
+        const exportProjectButton = document.getElementById('exportProjectButton');
+        const importProjectButton = document.getElementById('importProjectButton');
+        const projectFileInput = document.getElementById('projectFileInput');
+        console.assert(exportProjectButton && importProjectButton && projectFileInput);
+
+        exportProjectButton.addEventListener('click', () => {
+            const json = backend.exportProject();
+            const link = document.createElement('a');
+            link.href = URL.createObjectURL(new Blob([json], { type: 'application/json' }));
+            link.download = 'pattrns-project.json';
+            link.click();
+            URL.revokeObjectURL(link.href);
+            this.setStatus('Exported project.');
+        });
+
+        importProjectButton.addEventListener('click', () => {
+            projectFileInput.value = null;
+            projectFileInput.click();
+        });
+
+        const importProject = (fileName, json) => {
+            const error = backend.importProject(json);
+            if (error) {
+                const isError = true;
+                this.setStatus(`Failed to import project '${fileName}': ${error}`, isError);
+                return;
+            }
+            // sync controls with the imported session state
+            const project = JSON.parse(json);
+            const samples = backend.getSamples();
+            if (project.bpm > 0) {
+                this._bpmInput.value = project.bpm;
+            }
+            if (project.volume != null) {
+                updateVolumeDisplay(project.volume);
+            }
+            if (project.reverb_send != null) {
+                reverbSlider.value = Math.round(project.reverb_send * 100);
+            }
+            const instrument = samples.find(sample => sample.name === project.instrument);
+            if (instrument) {
+                document.getElementById('sampleSelect').value = instrument.id;
+            }
+            this._changedScriptFromHash = true;
+            this._editor.setValue(project.script || '');
+            this._updateEditCount(0);
+            this._highlightSelectedExample(null);
+            this._updateHash();
+            const missingSamples = (project.samples || [])
+                .filter(name => !samples.some(sample => sample.name === name));
+            if (missingSamples.length > 0) {
+                this.setStatus(`Imported project '${fileName}'. ` +
+                    `Skipped unavailable samples: ${missingSamples.join(', ')}`);
+            } else {
+                this.setStatus(`Imported project '${fileName}'.`);
+            }
+        };
+
+        projectFileInput.addEventListener('change', (event) => {
+            const file = event.target.files[0];
+            if (!file) {
+                return;
+            }
+
+            let reader = new FileReader();
+            reader.onload = (e) => importProject(file.name, e.target.result);
+            reader.onerror = (e) => {
+                const isError = true;
+                this.setStatus(`Error reading file: ${e.target.error.message}`, isError);
+            };
+            reader.readAsText(file);
+        });
     },
 
     _selectInstrument: function (id) {
@@ -848,6 +1041,13 @@ const app = {
         }
     },
 
+    // install script debug output change handler
+    _initScriptOutputHandler: function () {
+        window.on_script_output_changed = () => {
+            this._updateScriptOutputUI();
+        }
+    },
+
     // install script parameter change handler
     _initScriptParameterHandler: function () {
         window.on_script_parameters_changed = () => {
@@ -856,6 +1056,25 @@ const app = {
         this._updateParametersUI();
     },
 
+    // update script debug output panel
+    _updateScriptOutputUI: function () {
+        const outputPane = document.getElementById('editor-output');
+        console.assert(outputPane);
+
+        const outputContent = document.getElementById('editor-output-content');
+        console.assert(outputContent);
+
+        const output = backend.getScriptOutput();
+        if (output) {
+            outputContent.textContent = output;
+            outputPane.style.display = 'flex';
+            outputContent.scrollTop = outputContent.scrollHeight;
+        } else {
+            outputContent.textContent = '';
+            outputPane.style.display = 'none';
+        }
+    },
+
     // update script error display in editor and error panel
     _updateScriptErrorsUI: function () {
         const errorPane = document.getElementById('editor-error');
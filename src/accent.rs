@@ -0,0 +1,142 @@
+//! Automatic accents for `Pattern` events.
+
+use crate::Event;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Boosts the volume of note events on every nth step of a [`Pattern`](crate::Pattern), e.g.
+/// to accent the first sixteenth of every beat.
+///
+/// Steps are counted from the pattern's start (after its offset), starting with 0, so the very
+/// first step always gets accented. All note events which start within an accented step get
+/// their volume multiplied by the accent's amount.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accent {
+    every: usize,
+    amount: f32,
+}
+
+impl Default for Accent {
+    fn default() -> Self {
+        Self::new(1, 1.0)
+    }
+}
+
+impl Accent {
+    /// Create a new accent which multiplies the volume of notes on every `every` step with the
+    /// given `amount` >= 0.
+    pub fn new(every: usize, amount: f32) -> Self {
+        let every = every.max(1);
+        let amount = if amount.is_finite() {
+            amount.max(0.0)
+        } else {
+            1.0
+        };
+        Self { every, amount }
+    }
+
+    /// Step interval of the accent.
+    pub fn every(&self) -> usize {
+        self.every
+    }
+
+    /// Volume factor which gets applied to accented notes.
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    /// Returns true when no note volume gets changed at all.
+    pub fn is_neutral(&self) -> bool {
+        self.amount == 1.0
+    }
+
+    /// Apply the accent to the given event, which starts at the given position in pattern steps.
+    pub(crate) fn apply(&self, event: &mut Event, step_position: f64) {
+        const EPSILON: f64 = 1e-6;
+        let step = (step_position + EPSILON).floor();
+        if step < 0.0 || !(step as u64).is_multiple_of(self.every as u64) {
+            return;
+        }
+        if let Event::NoteEvents(note_events) = event {
+            for note_event in note_events.iter_mut().flatten() {
+                note_event.volume *= self.amount;
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        event::{new_note, NoteEvent},
+        prelude::*,
+    };
+
+    #[test]
+    fn pattern_accent() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let note_volumes = |pattern: BeatTimePattern| {
+            pattern
+                .take(8)
+                .map(|event| match event.event {
+                    Some(Event::NoteEvents(notes)) => notes
+                        .iter()
+                        .flatten()
+                        .map(|note: &NoteEvent| note.volume)
+                        .collect::<Vec<_>>(),
+                    _ => vec![],
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // every 4th step gets boosted
+        let pattern = time_base
+            .every_nth_sixteenth(1.0)
+            .with_accent(Accent::new(4, 1.5))
+            .emit(new_note((Note::C4, None, 0.5)).to_emitter());
+        assert_eq!(
+            note_volumes(pattern),
+            vec![
+                vec![0.75],
+                vec![0.5],
+                vec![0.5],
+                vec![0.5],
+                vec![0.75],
+                vec![0.5],
+                vec![0.5],
+                vec![0.5]
+            ]
+        );
+
+        // accents apply on the straight grid, so swing does not shift them
+        let pattern = time_base
+            .every_nth_sixteenth(1.0)
+            .with_swing(Swing::new(0.5))
+            .with_accent(Accent::new(2, 2.0))
+            .emit(new_note((Note::C4, None, 0.25)).to_emitter());
+        assert_eq!(
+            note_volumes(pattern),
+            vec![
+                vec![0.5],
+                vec![0.25],
+                vec![0.5],
+                vec![0.25],
+                vec![0.5],
+                vec![0.25],
+                vec![0.5],
+                vec![0.25]
+            ]
+        );
+
+        // invalid values are clamped
+        assert_eq!(Accent::new(0, -1.0), Accent::new(1, 0.0));
+        assert!(Accent::new(4, f32::NAN).is_neutral());
+    }
+}
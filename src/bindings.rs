@@ -1,6 +1,6 @@
 //! Lua bindings for the entire crate.
 
-use std::{cell::RefCell, collections::HashSet, path::Path, rc::Rc};
+use std::{cell::RefCell, collections::HashSet, path::Path, rc::Rc, time::Duration};
 
 use rand::{Rng, SeedableRng};
 use rand_xoshiro::Xoshiro256PlusPlus;
@@ -12,7 +12,7 @@ use mlua::prelude::*;
 use self::{
     cycle::CycleUserData,
     note::NoteUserData,
-    parameter::ParameterUserData,
+    parameter::{ParameterChangeUserData, ParameterUserData},
     pattern::pattern_from_userdata,
     sequence::SequenceUserData,
     unwrap::{
@@ -22,11 +22,14 @@ use self::{
 };
 
 use crate::{
-    event::InstrumentId,
+    event::{new_note, InstrumentId},
     note::chord::Chord,
-    pattern::{beat_time::BeatTimePattern, second_time::SecondTimePattern, Pattern},
+    pattern::{
+        beat_time::BeatTimePattern, ms_time::MsTimePattern, second_time::SecondTimePattern, Pattern,
+    },
+    rhythm::euclidean::euclidean,
     time::BeatTimeBase,
-    Note, Parameter, Scale,
+    Cycle, DebugOutput, Note, Parameter, Scale,
 };
 
 // ---------------------------------------------------------------------------------------------
@@ -34,9 +37,12 @@ use crate::{
 // private binding impls
 mod callback;
 mod cycle;
+mod error;
 mod note;
 mod parameter;
 mod pattern;
+mod random;
+mod sandbox;
 mod scale;
 mod sequence;
 mod timeout;
@@ -44,13 +50,21 @@ mod unwrap;
 
 // public re-exports
 pub use callback::{
-    add_lua_callback_error, clear_lua_callback_errors, has_lua_callback_errors, lua_callback_errors,
+    add_lua_callback_error, add_lua_callback_warning, clear_lua_callback_errors,
+    clear_lua_callback_warnings, has_lua_callback_errors, has_lua_callback_warnings,
+    lua_callback_errors, lua_callback_warnings,
 };
+pub use error::{ErrorRange, PatternError};
+pub use mlua;
+pub use sandbox::SandboxConfig;
 
 // internal re-exports
 pub(crate) use callback::{ContextPlaybackState, LuaCallback};
 pub(crate) use timeout::LuaTimeoutHook;
-pub(crate) use unwrap::{gate_trigger_from_value, note_events_from_value, pulse_from_value};
+pub(crate) use unwrap::{
+    gate_hold_from_value, note_events_from_value, parameter_change_events_from_value,
+    pulse_from_value,
+};
 
 // ---------------------------------------------------------------------------------------------
 
@@ -64,6 +78,8 @@ pub(crate) struct LuaAppData {
     pub(crate) rand_rgn: Xoshiro256PlusPlus,
     /// Declared global variables for the strict checks.
     pub(crate) declared_globals: HashSet<Vec<u8>>,
+    /// Captured output of `print` calls, shared with all patterns created in this Lua instance.
+    pub(crate) debug_output: DebugOutput,
 }
 
 impl LuaAppData {
@@ -71,10 +87,12 @@ impl LuaAppData {
         let rand_seed = None;
         let rand_rgn = Xoshiro256PlusPlus::from_seed(rand::rng().random());
         let declared_globals = HashSet::new();
+        let debug_output = DebugOutput::new();
         Self {
             rand_seed,
             rand_rgn,
             declared_globals,
+            debug_output,
         }
     }
 }
@@ -85,6 +103,11 @@ impl LuaAppData {
 /// hook instance to limit duration of script calls.
 /// Use [`register_bindings`] to register the bindings for the newly created engine.
 pub(crate) fn new_engine() -> LuaResult<(Lua, LuaTimeoutHook)> {
+    new_engine_with_timeout(LuaTimeoutHook::DEFAULT_TIMEOUT)
+}
+
+/// Create a new raw lua engine like [`new_engine`], but with a custom script timeout.
+pub(crate) fn new_engine_with_timeout(timeout: Duration) -> LuaResult<(Lua, LuaTimeoutHook)> {
     // create a new lua instance with the allowed std libraries
     let lua = Lua::new_with(
         // Only basics: no OS, IO, PACKAGE, DEBUG, FFI!
@@ -93,7 +116,7 @@ pub(crate) fn new_engine() -> LuaResult<(Lua, LuaTimeoutHook)> {
     )
     .expect("Failed to create a new lua engine");
     // install a timeout hook
-    let timeout_hook = LuaTimeoutHook::new(&lua);
+    let timeout_hook = LuaTimeoutHook::new_with_timeout(&lua, timeout);
     // create new app data
     lua.set_app_data(LuaAppData::new());
     // return the lua instance and timeout manager
@@ -105,22 +128,29 @@ pub(crate) fn new_engine() -> LuaResult<(Lua, LuaTimeoutHook)> {
 /// Evaluate a lua script file which creates and returns a pattern.
 ///
 /// ### Errors
-/// Will return `Err` if `file_name` does not exist, failed to load or the lua file at the given
-/// path fails to evaulate to a valid pattern.
+/// Will return [`PatternError::Io`] if `file_name` does not exist or failed to load, and
+/// [`PatternError::CompileError`] or [`PatternError::RuntimeError`] if the lua file at the
+/// given path fails to evaluate to a valid pattern.
 pub fn new_pattern_from_file<P: AsRef<Path>>(
     time_base: BeatTimeBase,
     instrument: Option<InstrumentId>,
     file_path: P,
-) -> Result<Rc<RefCell<dyn Pattern>>, Box<dyn std::error::Error>> {
+) -> Result<Rc<RefCell<dyn Pattern>>, PatternError> {
+    // read script file
+    let file_path = file_path.as_ref();
+    let script = std::fs::read(file_path)?;
     // create a new engine and register bindings
-    let (mut lua, mut timeout_hook) =
-        new_engine().map_err(Into::<Box<dyn std::error::Error>>::into)?;
+    let (mut lua, mut timeout_hook) = new_engine()?;
     register_bindings(&mut lua, &timeout_hook, &time_base)?;
     // restart the timeout hook
     timeout_hook.reset();
     // compile and evaluate script
-    let chunk = lua.load(file_path.as_ref());
-    let result = chunk.eval::<LuaValue>()?;
+    let chunk = lua
+        .load(&script)
+        .set_name(format!("@{}", file_path.display()));
+    let result = chunk
+        .eval::<LuaValue>()
+        .map_err(|err| PatternError::from(err).with_source(&script))?;
     // convert result
     pattern_from_userdata(&lua, &timeout_hook, &result, &time_base, instrument).map_err(Into::into)
 }
@@ -128,26 +158,93 @@ pub fn new_pattern_from_file<P: AsRef<Path>>(
 /// Evaluate a Lua string expression which creates and returns a pattern.
 ///
 /// ### Errors
-/// Will return `Err` if the lua string contents fail to evaluate to a valid pattern.
+/// Will return [`PatternError::CompileError`] or [`PatternError::RuntimeError`] if the lua
+/// string contents fail to evaluate to a valid pattern.
 pub fn new_pattern_from_string(
     time_base: BeatTimeBase,
     instrument: Option<InstrumentId>,
     script: &str,
     script_name: &str,
-) -> Result<Rc<RefCell<dyn Pattern>>, Box<dyn std::error::Error>> {
+) -> Result<Rc<RefCell<dyn Pattern>>, PatternError> {
+    new_pattern_from_string_with_env(time_base, instrument, script, script_name, |_| Ok(()))
+}
+
+/// Evaluate a Lua string expression which creates and returns a pattern, allowing the host
+/// to register custom globals, such as functions or libraries, before the script runs.
+///
+/// The `register` function is called with the Lua engine after all pattrns bindings got
+/// registered. Globals that are set via `lua.globals().set(...)` are declared globals and
+/// thus can be accessed by the script, even when strict global checks are enabled.
+///
+/// NB: Registered functions are owned by the Lua engine, which lives as long as the pattern
+/// and all of its clones: anything captured by registered functions must thus be `'static`.
+/// Functions will be called from the thread that runs the pattern, so they should not block.
+///
+/// ### Errors
+/// Will return [`PatternError::RuntimeError`] if the register function fails, and
+/// [`PatternError::CompileError`] or [`PatternError::RuntimeError`] if the lua string
+/// contents fail to evaluate to a valid pattern.
+pub fn new_pattern_from_string_with_env<F>(
+    time_base: BeatTimeBase,
+    instrument: Option<InstrumentId>,
+    script: &str,
+    script_name: &str,
+    register: F,
+) -> Result<Rc<RefCell<dyn Pattern>>, PatternError>
+where
+    F: Fn(&Lua) -> LuaResult<()>,
+{
     // create a new engine and register bindings
-    let (mut lua, mut timeout_hook) =
-        new_engine().map_err(Into::<Box<dyn std::error::Error>>::into)?;
+    let (mut lua, mut timeout_hook) = new_engine()?;
     register_bindings(&mut lua, &timeout_hook, &time_base)?;
+    // register host bindings
+    register(&lua)?;
     // restart the timeout hook
     timeout_hook.reset();
     // compile and evaluate script
     let chunk = lua.load(script).set_name(script_name);
-    let result = chunk.eval::<LuaValue>()?;
+    let result = chunk
+        .eval::<LuaValue>()
+        .map_err(|err| PatternError::from(err).with_source(script))?;
     // convert result
     pattern_from_userdata(&lua, &timeout_hook, &result, &time_base, instrument).map_err(Into::into)
 }
 
+/// Evaluate a Lua string expression which creates and returns a pattern in a sandboxed Lua
+/// engine, which limits the script's execution time and memory usage and removes unsafe
+/// globals. Use this to run untrusted scripts, e.g. in a public playground.
+///
+/// Limits also apply when running the pattern's callbacks later on: errors in callbacks are
+/// reported as [`lua_callback_errors`], as usual.
+///
+/// ### Errors
+/// Will return [`PatternError::RuntimeError`] if the script exceeds the sandbox limits or if
+/// the Lua backend does not support memory limits, and [`PatternError::CompileError`] or
+/// [`PatternError::RuntimeError`] if the lua string contents fail to evaluate to a valid pattern.
+pub fn new_pattern_from_string_sandboxed(
+    time_base: BeatTimeBase,
+    instrument: Option<InstrumentId>,
+    script: &str,
+    script_name: &str,
+    config: &SandboxConfig,
+) -> Result<Rc<RefCell<dyn Pattern>>, PatternError> {
+    // create a new engine with the sandbox timeout and register bindings
+    let (mut lua, mut timeout_hook) = new_engine_with_timeout(config.timeout())?;
+    register_bindings(&mut lua, &timeout_hook, &time_base)?;
+    // restrict the environment
+    config.apply(&lua)?;
+    // restart the timeout hook
+    timeout_hook.reset();
+    // compile and evaluate script
+    let chunk = lua.load(script).set_name(script_name);
+    let result = chunk
+        .eval::<LuaValue>()
+        .map_err(|err| PatternError::from(config.map_error(err)).with_source(script))?;
+    // convert result
+    pattern_from_userdata(&lua, &timeout_hook, &result, &time_base, instrument)
+        .map_err(|err| config.map_error(err).into())
+}
+
 // -------------------------------------------------------------------------------------------------
 
 /// Register pattrns bindings to the given Lua engine.
@@ -160,6 +257,7 @@ pub(crate) fn register_bindings(
     register_global_bindings(lua, timeout_hook, time_base)?;
     register_parameter_bindings(lua)?;
     register_math_bindings(lua)?;
+    random::register_random_bindings(lua)?;
     register_table_bindings(lua)?;
     register_pulse_bindings(lua)?;
     Ok(())
@@ -172,6 +270,23 @@ fn register_global_bindings(
 ) -> LuaResult<()> {
     let globals = lua.globals();
 
+    // function print(...)
+    globals.raw_set(
+        "print",
+        lua.create_function(|lua, args: LuaMultiValue| -> LuaResult<()> {
+            let line = args
+                .iter()
+                .map(LuaValue::to_string)
+                .collect::<LuaResult<Vec<_>>>()?
+                .join("\t");
+            lua.app_data_ref::<LuaAppData>()
+                .expect("Failed to access Lua app data")
+                .debug_output
+                .push(line);
+            Ok(())
+        })?,
+    )?;
+
     // function scale(note, mode|intervals)
     globals.raw_set(
         "scale",
@@ -228,19 +343,47 @@ fn register_global_bindings(
         })?,
     )?;
 
-    // function note(args...)
+    // function note(args...), note.from_hz(hz, a4_hz?)
+    globals.raw_set("note", NoteUserData::create_library(lua)?)?;
+
+    // function note_off(voice?)
     globals.raw_set(
-        "note",
-        lua.create_function(|_lua, args: LuaMultiValue| -> LuaResult<NoteUserData> {
-            NoteUserData::from(args)
-        })?,
+        "note_off",
+        lua.create_function(
+            |_lua, voice: Option<LuaInteger>| -> LuaResult<NoteUserData> {
+                // limit voices to a sane range to avoid allocating huge note arrays
+                const MAX_VOICES: LuaInteger = 128;
+                let voice = voice.unwrap_or(1);
+                if !(1..=MAX_VOICES).contains(&voice) {
+                    return Err(bad_argument_error(
+                        "note_off",
+                        "voice",
+                        1,
+                        &format!("expecting a voice index in range [1 - {}]", MAX_VOICES),
+                    ));
+                }
+                let mut notes = vec![None; voice as usize - 1];
+                notes.push(new_note(Note::OFF));
+                Ok(NoteUserData { notes })
+            },
+        )?,
+    )?;
+
+    // function parameter_change(id, value)
+    globals.raw_set(
+        "parameter_change",
+        lua.create_function(
+            |_lua, (id, value): (LuaValue, LuaValue)| -> LuaResult<ParameterChangeUserData> {
+                ParameterChangeUserData::from(id, value)
+            },
+        )?,
     )?;
 
     // function note_number(note)
     globals.raw_set(
         "note_number",
-        lua.create_function(|_lua, value: LuaValue| -> LuaResult<LuaValue> {
-            let note_event = note_event_from_value(&value, None)?;
+        lua.create_function(|lua, value: LuaValue| -> LuaResult<LuaValue> {
+            let note_event = note_event_from_value(lua, &value, None)?;
             match note_event {
                 Some(note_event) => Ok(LuaValue::Integer(u8::from(note_event.note) as LuaInteger)),
                 _ => Ok(LuaValue::Integer(Note::EMPTY as u8 as LuaInteger)),
@@ -252,8 +395,8 @@ fn register_global_bindings(
     globals.raw_set(
         "chord",
         lua.create_function(
-            |_lua, (note, mode_or_intervals): (LuaValue, LuaValue)| -> LuaResult<NoteUserData> {
-                NoteUserData::from_chord(&note, &mode_or_intervals)
+            |lua, (note, mode_or_intervals): (LuaValue, LuaValue)| -> LuaResult<NoteUserData> {
+                NoteUserData::from_chord(lua, &note, &mode_or_intervals)
             },
         )?,
     )?;
@@ -266,26 +409,73 @@ fn register_global_bindings(
         })?,
     )?;
 
+    // function euclidean(pulses, steps, rotation?)
+    globals.raw_set(
+        "euclidean",
+        lua.create_function(
+            |lua,
+             (pulses, steps, rotation): (LuaInteger, LuaInteger, Option<LuaInteger>)|
+             -> LuaResult<LuaTable> {
+                // use the same limit as cycles do for their events
+                const MAX_STEPS: LuaInteger = Cycle::EVENT_LIMIT_DEFAULT as LuaInteger;
+                if !(0..=MAX_STEPS).contains(&pulses) {
+                    return Err(bad_argument_error(
+                        "euclidean",
+                        "pulses",
+                        1,
+                        &format!("expecting an integer in range [0 - {}]", MAX_STEPS),
+                    ));
+                }
+                if !(0..=MAX_STEPS).contains(&steps) {
+                    return Err(bad_argument_error(
+                        "euclidean",
+                        "steps",
+                        2,
+                        &format!("expecting an integer in range [0 - {}]", MAX_STEPS),
+                    ));
+                }
+                // wrap rotations into the step range, so they fit into the rhythm's offset
+                let rotation = {
+                    let value = rotation.unwrap_or(0);
+                    let wrapped = value.rem_euclid(steps.max(1));
+                    if wrapped != value {
+                        add_lua_callback_warning(
+                            "euclidean",
+                            &format!(
+                                "rotation {} is out of the step range: using {}",
+                                value, wrapped
+                            ),
+                        );
+                    }
+                    wrapped
+                };
+                lua.create_sequence_from(euclidean(pulses as u32, steps as u32, rotation as i32))
+            },
+        )?,
+    )?;
+
     // function sequence(args...)
     globals.raw_set(
         "sequence",
-        lua.create_function(|_lua, args: LuaMultiValue| -> LuaResult<SequenceUserData> {
-            SequenceUserData::from(args)
+        lua.create_function(|lua, args: LuaMultiValue| -> LuaResult<SequenceUserData> {
+            SequenceUserData::from(lua, args)
         })?,
     )?;
 
-    // function cycle(input)
+    // function cycle(input, bindings?)
     globals.raw_set(
         "cycle",
-        lua.create_function(|lua, arg: LuaString| -> LuaResult<CycleUserData> {
-            // NB: don't keep borrowing app_data_ref here
-            let rand_seed = {
-                lua.app_data_ref::<LuaAppData>()
-                    .expect("Failed to access Lua app data")
-                    .rand_seed
-            };
-            CycleUserData::from(arg, rand_seed)
-        })?,
+        lua.create_function(
+            |lua, (arg, bindings): (LuaString, Option<LuaTable>)| -> LuaResult<CycleUserData> {
+                // NB: don't keep borrowing app_data_ref here
+                let rand_seed = {
+                    lua.app_data_ref::<LuaAppData>()
+                        .expect("Failed to access Lua app data")
+                        .rand_seed
+                };
+                CycleUserData::from(arg, bindings, rand_seed)
+            },
+        )?,
     )?;
 
     // function pattern { args... }
@@ -296,7 +486,7 @@ fn register_global_bindings(
             let time_base = *time_base;
             move |lua, table: LuaTable| -> LuaResult<LuaValue> {
                 // error on unknown option keys
-                const RHYTHM_PROPERTIES: [&str; 8] = [
+                const RHYTHM_PROPERTIES: [&str; 13] = [
                     "unit",
                     "resolution",
                     "offset",
@@ -305,19 +495,23 @@ fn register_global_bindings(
                     "pulse",
                     "gate",
                     "event",
+                    "instrument",
+                    "seed",
+                    "swing",
+                    "swing_division",
+                    "accent",
                 ];
                 validate_table_properties(&table, &RHYTHM_PROPERTIES)?;
                 // check which time unit is specified
-                let second_time_unit = match table.get::<String>("unit") {
-                    Ok(unit) => matches!(unit.as_str(), "seconds" | "ms"),
-                    Err(_) => false,
-                };
-                if second_time_unit {
-                    SecondTimePattern::from_table(lua, &timeout_hook, &time_base, &table)?
-                        .into_lua(lua)
-                } else {
-                    BeatTimePattern::from_table(lua, &timeout_hook, &time_base, &table)?
-                        .into_lua(lua)
+                match table.get::<String>("unit").as_deref() {
+                    Ok("seconds") => {
+                        SecondTimePattern::from_table(lua, &timeout_hook, &time_base, &table)?
+                            .into_lua(lua)
+                    }
+                    Ok("ms") => MsTimePattern::from_table(lua, &timeout_hook, &time_base, &table)?
+                        .into_lua(lua),
+                    _ => BeatTimePattern::from_table(lua, &timeout_hook, &time_base, &table)?
+                        .into_lua(lua),
                 }
             }
         })?,
@@ -426,13 +620,30 @@ fn register_parameter_bindings(lua: &mut Lua) -> LuaResult<()> {
                         "ids can not be empty",
                     ));
                 }
-                let default = default.as_integer().ok_or_else(|| {
-                    bad_argument_error("integer", "default", 1, "expecting an integer value")
-                })? as i32;
+                // clamp integers to the parameter's value type range
+                let to_i32 = |value: LuaInteger, name: &str| {
+                    let clamped = value.clamp(i32::MIN as LuaInteger, i32::MAX as LuaInteger);
+                    if clamped != value {
+                        add_lua_callback_warning(
+                            "integer",
+                            &format!(
+                                "parameter {} {} is out of the supported range: using {}",
+                                name, value, clamped
+                            ),
+                        );
+                    }
+                    clamped as i32
+                };
+                let default = to_i32(
+                    default.as_integer().ok_or_else(|| {
+                        bad_argument_error("integer", "default", 1, "expecting an integer value")
+                    })?,
+                    "default",
+                );
                 let range = {
                     if let Some(range) = range {
-                        let start = range.get::<LuaInteger>(1)? as i32;
-                        let end = range.get::<LuaInteger>(2)? as i32;
+                        let start = to_i32(range.get::<LuaInteger>(1)?, "range start");
+                        let end = to_i32(range.get::<LuaInteger>(2)?, "range end");
                         start..=end
                     } else {
                         0..=100
@@ -523,53 +734,17 @@ fn register_parameter_bindings(lua: &mut Lua) -> LuaResult<()> {
     // function enum(id, default, values, name?, description?)
     parameter.raw_set(
         "enum",
-        lua.create_function(
-            |_lua,
-             (id, default, value_table, name, description): (
-                LuaValue,
-                LuaValue,
-                LuaTable,
-                LuaValue,
-                LuaValue,
-            )|
-             -> LuaResult<ParameterUserData> {
-                let id = string_from_value(&id, "enum", "id", 1)?;
-                if id.is_empty() {
-                    return Err(bad_argument_error("enum", "id", 1, "ids can not be empty"));
-                }
-                let default = string_from_value(&default, "enum", "default", 2)?;
-                let mut values = Vec::with_capacity(value_table.len()? as usize);
-                for value in value_table.sequence_values::<String>() {
-                    values.push(value?);
-                }
-                if !values.iter().any(|v| v.eq_ignore_ascii_case(&default)) {
-                    return Err(bad_argument_error(
-                        "enum",
-                        "values",
-                        2,
-                        "values must contain the default value",
-                    ));
-                }
-                if (1..values.len()).any(|i| {
-                    values[i..]
-                        .iter()
-                        .any(|v| v.eq_ignore_ascii_case(&values[i - 1]))
-                }) {
-                    return Err(bad_argument_error(
-                        "enum",
-                        "values",
-                        2,
-                        "values must not contain duplicate entries",
-                    ));
-                }
-                let name = optional_string_from_value(&name, "enum", "name", 3)?;
-                let description =
-                    optional_string_from_value(&description, "enum", "description", 4)?;
-                Ok(ParameterUserData {
-                    parameter: Parameter::with_enum(&id, &name, &description, values, default),
-                })
-            },
-        )?,
+        lua.create_function(|_lua, args| {
+            enum_parameter_from_args("enum", args, Parameter::with_enum)
+        })?,
+    )?;
+
+    // function enum_index(id, default, values, name?, description?)
+    parameter.raw_set(
+        "enum_index",
+        lua.create_function(|_lua, args| {
+            enum_parameter_from_args("enum_index", args, Parameter::with_enum_index)
+        })?,
     )?;
 
     lua.globals().raw_set("parameter", parameter)?;
@@ -577,6 +752,54 @@ fn register_parameter_bindings(lua: &mut Lua) -> LuaResult<()> {
     Ok(())
 }
 
+// shared implementation of the enum and enum_index parameter functions
+fn enum_parameter_from_args(
+    func: &str,
+    (id, default, value_table, name, description): (
+        LuaValue,
+        LuaValue,
+        LuaTable,
+        LuaValue,
+        LuaValue,
+    ),
+    new_parameter: fn(&str, &str, &str, Vec<String>, String) -> Parameter,
+) -> LuaResult<ParameterUserData> {
+    let id = string_from_value(&id, func, "id", 1)?;
+    if id.is_empty() {
+        return Err(bad_argument_error(func, "id", 1, "ids can not be empty"));
+    }
+    let default = string_from_value(&default, func, "default", 2)?;
+    let mut values = Vec::with_capacity(value_table.len()? as usize);
+    for value in value_table.sequence_values::<String>() {
+        values.push(value?);
+    }
+    if !values.iter().any(|v| v.eq_ignore_ascii_case(&default)) {
+        return Err(bad_argument_error(
+            func,
+            "values",
+            2,
+            "values must contain the default value",
+        ));
+    }
+    if (1..values.len()).any(|i| {
+        values[i..]
+            .iter()
+            .any(|v| v.eq_ignore_ascii_case(&values[i - 1]))
+    }) {
+        return Err(bad_argument_error(
+            func,
+            "values",
+            2,
+            "values must not contain duplicate entries",
+        ));
+    }
+    let name = optional_string_from_value(&name, func, "name", 3)?;
+    let description = optional_string_from_value(&description, func, "description", 4)?;
+    Ok(ParameterUserData {
+        parameter: new_parameter(&id, &name, &description, values, default),
+    })
+}
+
 fn register_math_bindings(lua: &mut Lua) -> LuaResult<()> {
     let math = lua.globals().get::<LuaTable>("math")?;
 
@@ -838,6 +1061,34 @@ mod test {
         // pulse.lua is present
         assert!(lua.load(r#"return pulse.new()"#).eval::<LuaTable>().is_ok());
 
+        // euclidean is present and shares the cycle's implementation
+        assert_eq!(
+            lua.load(r#"return euclidean(5, 8)"#)
+                .eval::<Vec<bool>>()
+                .unwrap(),
+            [true, false, true, true, false, true, true, false]
+        );
+        assert_eq!(
+            lua.load(r#"return euclidean(3, 8, 3)"#)
+                .eval::<Vec<bool>>()
+                .unwrap(),
+            euclidean(3, 8, 3)
+        );
+        assert!(lua
+            .load(r#"return euclidean(-1, 8)"#)
+            .eval::<LuaTable>()
+            .is_err());
+        assert!(lua
+            .load(r#"return euclidean(3, 0x7fffffff)"#)
+            .eval::<LuaTable>()
+            .is_err());
+        assert_eq!(
+            lua.load(r#"return euclidean(3, 8, math.mininteger)"#)
+                .eval::<Vec<bool>>()
+                .unwrap(),
+            euclidean(3, 8, 0)
+        );
+
         // math.randomstate is present
         assert!(lua
             .load(r#"return math.randomstate(123)(1, 10)"#)
@@ -906,4 +1157,79 @@ mod test {
         )?;
         Ok(())
     }
+
+    #[test]
+    fn create_pattern_with_env() -> Result<(), Box<dyn std::error::Error>> {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+
+        // host functions are callable from the script
+        let pattern = new_pattern_from_string_with_env(
+            time_base,
+            None,
+            r#"return pattern { unit = "1/4", event = { host_note() } }"#,
+            "[test host env]",
+            |lua| {
+                lua.globals()
+                    .set("host_note", lua.create_function(|_lua, ()| Ok("c4"))?)
+            },
+        )?;
+        let event = pattern.borrow_mut().next().and_then(|e| e.event);
+        assert_eq!(
+            event,
+            Some(crate::Event::NoteEvents(vec![crate::event::new_note(
+                crate::Note::C4
+            )]))
+        );
+
+        // errors in the register function are passed through
+        assert!(new_pattern_from_string_with_env(
+            time_base,
+            None,
+            r#"return pattern { event = "c4" }"#,
+            "[test host env error]",
+            |_lua| Err(LuaError::runtime("host failure")),
+        )
+        .is_err_and(|err| err.to_string().contains("host failure")));
+        Ok(())
+    }
+
+    #[test]
+    fn debug_output() -> Result<(), Box<dyn std::error::Error>> {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+
+        // print calls get captured per pattern
+        let pattern = new_pattern_from_string(
+            time_base,
+            None,
+            r#"
+            print("x")
+            return pattern {
+              event = function(context)
+                print("step", context.step, nil)
+                return "c4"
+              end
+            }
+            "#,
+            "[test debug output]",
+        )?;
+        let debug_output = pattern.borrow().debug_output().unwrap();
+        assert_eq!(debug_output.take(), vec!["x"]);
+        pattern.borrow_mut().next();
+        assert_eq!(debug_output.take(), vec!["step\t1\tnil"]);
+
+        // other patterns use their own output
+        let other_pattern =
+            new_pattern_from_string(time_base, None, r#"return pattern { }"#, "[test]")?;
+        other_pattern.borrow_mut().next();
+        assert!(other_pattern.borrow().debug_output().unwrap().is_empty());
+        Ok(())
+    }
 }
@@ -3,14 +3,20 @@ use std::{cell::RefCell, collections::HashMap, fmt::Debug, rc::Rc};
 use mlua::prelude::*;
 
 use lazy_static::lazy_static;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
 use std::sync::RwLock;
 
-use crate::{BeatTimeBase, Event, Parameter, ParameterSet, RhythmEvent};
+use crate::{
+    bindings::{unwrap::bad_argument_error, LuaAppData},
+    BeatTimeBase, Event, NoteEvent, Parameter, ParameterSet, RhythmEvent,
+};
 
 // -------------------------------------------------------------------------------------------------
 
 lazy_static! {
     static ref LUA_CALLBACK_ERRORS: RwLock<Vec<LuaError>> = Vec::new().into();
+    static ref LUA_CALLBACK_WARNINGS: RwLock<Vec<String>> = Vec::new().into();
 }
 
 /// Returns some error if there are any Lua callback errors, with the !first! error that happened.
@@ -61,6 +67,55 @@ pub fn add_lua_callback_error(name: &str, err: &LuaError) {
         .push(err.clone());
 }
 
+/// Returns some warning if there are any Lua warnings, with the !first! warning that happened.
+/// Warnings are non-fatal issues, such as invalid values which got clamped or ignored while
+/// compiling a pattern. Use `lua_callback_warnings` to get fetch all warnings since the warnings
+/// got cleared.
+///
+/// ### Panics
+/// Panics if accessing the global lua callback warning vector fails.
+pub fn has_lua_callback_warnings() -> Option<String> {
+    LUA_CALLBACK_WARNINGS
+        .read()
+        .expect("Failed to lock Lua callback warning vector")
+        .first()
+        .cloned()
+}
+
+/// Returns all Lua warnings, if any.
+///
+/// ### Panics
+/// Panics if accessing the global lua callback warning vector failed.
+pub fn lua_callback_warnings() -> Vec<String> {
+    LUA_CALLBACK_WARNINGS
+        .read()
+        .expect("Failed to lock Lua callback warning vector")
+        .clone()
+}
+
+/// Clears all Lua warnings.
+///
+/// ### Panics
+/// Panics if accessing the global lua callback warning vector failed.
+pub fn clear_lua_callback_warnings() {
+    LUA_CALLBACK_WARNINGS
+        .write()
+        .expect("Failed to lock Lua callback warning vector")
+        .clear();
+}
+
+/// Add/signal a new Lua warning for the given function name.
+///
+/// ### Panics
+/// Panics if accessing the global lua callback warning vector failed.
+pub fn add_lua_callback_warning(name: &str, message: &str) {
+    log::warn!("Lua function '{}': {}", name, message);
+    LUA_CALLBACK_WARNINGS
+        .write()
+        .expect("Failed to lock Lua callback warning vector")
+        .push(format!("{}: {}", name, message));
+}
+
 // -------------------------------------------------------------------------------------------------
 
 /// Playback state in LuaCallback context.
@@ -70,6 +125,10 @@ pub(crate) enum ContextPlaybackState {
 }
 
 impl ContextPlaybackState {
+    fn is_seeking(&self) -> bool {
+        matches!(self, Self::Seeking)
+    }
+
     fn into_bytes_string(self) -> &'static [u8] {
         match self {
             Self::Seeking => b"seeking",
@@ -103,7 +162,6 @@ pub(crate) struct LuaCallback {
     generator: Option<LuaFunction>,
     function: LuaFunction,
     initialized: bool,
-    #[allow(unused)]
     lua: Lua,
 }
 
@@ -136,8 +194,12 @@ impl LuaCallback {
     pub fn new(lua: &Lua, function: LuaFunction) -> LuaResult<Self> {
         // create a strong lua ref, to ensure the function stays valid
         let lua = lua.clone();
-        // create a new callback context
-        let context = lua.create_userdata(CallbackContext::new())?;
+        // create a new callback context, using the global random seed, if set
+        let rand_seed = lua
+            .app_data_ref::<LuaAppData>()
+            .expect("Failed to access Lua app data")
+            .rand_seed;
+        let context = lua.create_userdata(CallbackContext::new(rand_seed))?;
         // and memorize the function without calling it
         let environment = function.environment();
         let generator = None;
@@ -163,6 +225,11 @@ impl LuaCallback {
         }
     }
 
+    /// The Lua engine instance the callback runs in.
+    pub fn lua(&self) -> &Lua {
+        &self.lua
+    }
+
     /// Name of the inner function for errors. Usually will be an anonymous function.
     pub fn name(&self) -> String {
         self.function
@@ -177,6 +244,9 @@ impl LuaCallback {
         playback_state: ContextPlaybackState,
     ) -> LuaResult<()> {
         let values = &mut self.context.borrow_mut::<CallbackContext>()?.values;
+        let seeking = playback_state.is_seeking();
+        values.insert(b"seeking", seeking.into());
+        values.insert(b"running", (!seeking).into());
         values.insert(b"playback", playback_state.into_bytes_string().into());
         Ok(())
     }
@@ -204,6 +274,20 @@ impl LuaCallback {
         Ok(())
     }
 
+    /// Sets the pattern's smoothed output level for the callback context.
+    pub fn set_context_output_level(&mut self, level: f32) -> LuaResult<()> {
+        let values = &mut self.context.borrow_mut::<CallbackContext>()?.values;
+        values.insert(b"level", level.clamp(0.0, 1.0).into());
+        Ok(())
+    }
+
+    /// Sets the notes which currently are playing in the pattern's slot for the callback context.
+    pub fn set_context_playing_notes(&mut self, notes: &[NoteEvent]) -> LuaResult<()> {
+        let context = &mut self.context.borrow_mut::<CallbackContext>()?;
+        context.playing_notes = Rc::from(notes);
+        Ok(())
+    }
+
     /// Sets the event which triggered the pattern for the callback context.
     pub fn set_context_trigger_event(&mut self, event: &Event) -> LuaResult<()> {
         let trigger_context = &mut self
@@ -234,6 +318,92 @@ impl LuaCallback {
         Ok(())
     }
 
+    /// Sets the musical playback position context for the callback from the given position
+    /// in beats, using the given time base to calculate the position in bars.
+    pub fn set_context_playback_position(
+        &mut self,
+        beats: f64,
+        time_base: &BeatTimeBase,
+    ) -> LuaResult<()> {
+        let context = &mut self.context.borrow_mut::<CallbackContext>()?;
+        let bars = beats / time_base.beats_per_bar.max(1) as f64;
+        context.playback_position = Some(CallbackPlaybackPosition { beats, bars });
+        Ok(())
+    }
+
+    /// Sets the duration of a single pattern step context for the callback from the given step
+    /// length in beats, using the given time base to calculate the duration in samples and
+    /// seconds.
+    pub fn set_context_step_duration(
+        &mut self,
+        step_beats: f64,
+        time_base: &BeatTimeBase,
+    ) -> LuaResult<()> {
+        let context = &mut self.context.borrow_mut::<CallbackContext>()?;
+        let samples = step_beats * time_base.samples_per_beat();
+        let seconds = samples / time_base.samples_per_sec as f64;
+        context.step_duration = Some(CallbackStepDuration { samples, seconds });
+        Ok(())
+    }
+
+    /// Sets the seed of the context's random number generator for the callback and restarts
+    /// the generator with the new seed.
+    pub fn set_context_seed(&mut self, seed: u64) -> LuaResult<()> {
+        let context = &mut self.context.borrow_mut::<CallbackContext>()?;
+        context.rng = CallbackRng::new(Some(seed));
+        Ok(())
+    }
+
+    /// Returns true if there are functions scheduled via `context.schedule` which did not run yet.
+    pub fn has_scheduled_callbacks(&self) -> LuaResult<bool> {
+        let context = self.context.borrow::<CallbackContext>()?;
+        let has_scheduled_callbacks = !context.scheduled_callbacks.borrow().is_empty();
+        Ok(has_scheduled_callbacks)
+    }
+
+    /// Runs all functions scheduled via `context.schedule` which are due at the given playback
+    /// position in beats with the callback's context, and returns their results in order.
+    /// Due functions get removed from the schedule queue.
+    pub fn run_scheduled_callbacks(&mut self, beats: f64) -> LuaResult<Vec<LuaValue>> {
+        let due_functions = {
+            let context = self.context.borrow::<CallbackContext>()?;
+            let mut scheduled_callbacks = context.scheduled_callbacks.borrow_mut();
+            // NB: keep order of equally timed callbacks
+            scheduled_callbacks.sort_by(|a, b| a.beats.total_cmp(&b.beats));
+            let due_count = scheduled_callbacks.partition_point(|c| c.beats <= beats);
+            scheduled_callbacks
+                .drain(..due_count)
+                .map(|c| c.function)
+                .collect::<Vec<_>>()
+        };
+        due_functions
+            .into_iter()
+            .map(|function| function.call::<LuaValue>(&self.context))
+            .collect()
+    }
+
+    /// Removes all functions which got scheduled via `context.schedule`.
+    pub fn clear_scheduled_callbacks(&mut self) -> LuaResult<()> {
+        let context = self.context.borrow::<CallbackContext>()?;
+        context.scheduled_callbacks.borrow_mut().clear();
+        Ok(())
+    }
+
+    /// Restores the context's random number generator to its initial state, when it is seeded.
+    pub fn reset_context_rng(&mut self) -> LuaResult<()> {
+        let context = &mut self.context.borrow_mut::<CallbackContext>()?;
+        context.rng.reset();
+        Ok(())
+    }
+
+    /// Sets the pattern's fixed, flattened pulse values for the callback context.
+    /// None, when the pattern's pulse is generated dynamically.
+    pub fn set_context_pulses(&mut self, pulses: Option<&[f32]>) -> LuaResult<()> {
+        let context = &mut self.context.borrow_mut::<CallbackContext>()?;
+        context.pulses = pulses.map(Rc::from);
+        Ok(())
+    }
+
     /// Sets the step emitter context for the callback.
     pub fn set_context_step(&mut self, step: usize) -> LuaResult<()> {
         let values = &mut self.context.borrow_mut::<CallbackContext>()?.values;
@@ -379,23 +549,61 @@ impl LuaCallback {
 ///
 /// NB: CallbackTriggersContext and CallbackInputsContext are not LuaOwnedAnyUserData.
 /// A userdata ref would cause reference cycles that would prevent destroying the Lua instance...
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 struct CallbackContext {
     values: HashMap<&'static [u8], ContextValue>,
+    pulses: Option<Rc<[f32]>>,
+    playback_position: Option<CallbackPlaybackPosition>,
+    step_duration: Option<CallbackStepDuration>,
+    playing_notes: Rc<[NoteEvent]>,
+    rng: CallbackRng,
+    scheduled_callbacks: Rc<RefCell<Vec<ScheduledCallback>>>,
     trigger_context: CallbackTriggerContext,
     inputs_context: CallbackInputsContext,
 }
 
 impl CallbackContext {
-    fn new() -> Self {
+    fn new(rand_seed: Option<u64>) -> Self {
+        // output levels are optional: they only get fed back when running in a player
+        let values = HashMap::from([(b"level".as_slice(), ContextValue::from(0.0_f32))]);
+        let pulses = None;
+        let playback_position = None;
+        let step_duration = None;
+        // playing notes also only get fed back when running in a player
+        let playing_notes = Rc::from([]);
+        let rng = CallbackRng::new(rand_seed);
+        let scheduled_callbacks = Rc::new(RefCell::new(Vec::new()));
         Self {
-            values: HashMap::new(),
+            values,
+            pulses,
+            playback_position,
+            step_duration,
+            playing_notes,
+            rng,
+            scheduled_callbacks,
             trigger_context: CallbackTriggerContext::new(),
             inputs_context: CallbackInputsContext::new(),
         }
     }
 }
 
+impl Clone for CallbackContext {
+    fn clone(&self) -> Self {
+        // NB: scheduled callbacks are not shared with clones, but copied
+        Self {
+            values: self.values.clone(),
+            pulses: self.pulses.clone(),
+            playback_position: self.playback_position,
+            step_duration: self.step_duration,
+            playing_notes: Rc::clone(&self.playing_notes),
+            rng: self.rng.clone(),
+            scheduled_callbacks: Rc::new(RefCell::new(self.scheduled_callbacks.borrow().clone())),
+            trigger_context: self.trigger_context.clone(),
+            inputs_context: self.inputs_context.clone(),
+        }
+    }
+}
+
 impl LuaUserData for CallbackContext {
     fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
         fields.add_meta_field_with("__index", |lua| {
@@ -409,9 +617,61 @@ impl LuaUserData for CallbackContext {
                     lua.create_userdata(this.inputs_context.clone())?
                         .into_lua(lua)
                 }
+                // schedule function (medium overhead - creates a new function)
+                else if key == b"schedule" && this.playback_position.is_some() {
+                    let position = this.playback_position.map_or(0.0, |p| p.beats);
+                    let scheduled_callbacks = Rc::clone(&this.scheduled_callbacks);
+                    lua.create_function(
+                        move |_lua, (beats, function): (LuaNumber, LuaFunction)| {
+                            if !beats.is_finite() || beats < 0.0 {
+                                return Err(bad_argument_error(
+                                    "schedule",
+                                    "beats",
+                                    1,
+                                    "beats must be a finite number >= 0",
+                                ));
+                            }
+                            scheduled_callbacks.borrow_mut().push(ScheduledCallback {
+                                beats: position + beats,
+                                function,
+                            });
+                            Ok(())
+                        },
+                    )?
+                    .into_lua(lua)
+                }
+                // random number generator (small overhead - creates a new ref)
+                else if key == b"rng" {
+                    lua.create_userdata(this.rng.handle())?.into_lua(lua)
+                }
                 // trigger event values (also, medium overhead - creates copies)
                 else if key == b"trigger" {
                     this.trigger_context.clone().into_lua(lua)
+                }
+                // musical playback position (medium overhead - creates a new table)
+                else if key == b"playback_position" && this.playback_position.is_some() {
+                    this.playback_position.into_lua(lua)
+                }
+                // step duration in samples and seconds (medium overhead - creates a new table)
+                else if key == b"step_duration" && this.step_duration.is_some() {
+                    this.step_duration.into_lua(lua)
+                }
+                // playing notes of the pattern slot (medium overhead - creates new tables)
+                else if key == b"playing_notes" {
+                    let notes = lua.create_table()?;
+                    for note_event in this.playing_notes.iter() {
+                        notes.push(note_event.clone().into_lua(lua)?)?;
+                    }
+                    notes.into_lua(lua)
+                }
+                // fixed pulse values (rarely used, high overhead - creates a new table)
+                else if key == b"pulses" {
+                    if let Some(pulses) = &this.pulses {
+                        lua.create_sequence_from(pulses.iter().copied())?
+                            .into_lua(lua)
+                    } else {
+                        Ok(LuaValue::Nil)
+                    }
                 } else {
                     Err(mlua::Error::RuntimeError(format!(
                         "undefined field '{}' in context",
@@ -489,19 +749,172 @@ impl CallbackTriggerContext {
 
 impl IntoLua for CallbackTriggerContext {
     fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
-        if let Some(event) = self.event {
-            event.into_lua(lua)
-        } else {
-            Ok(LuaValue::Nil)
+        match self.event {
+            Some(Event::NoteEvents(note_events)) => {
+                // values of the first trigger note, for monophonic triggers
+                let table = match note_events.iter().flatten().next() {
+                    Some(note_event) => note_event.clone().into_lua(lua)?,
+                    None => LuaValue::Table(lua.create_table()?),
+                };
+                // and all trigger notes as list
+                let notes = lua.create_table()?;
+                for (index, note_event) in note_events.into_iter().enumerate() {
+                    if let Some(note_event) = note_event {
+                        notes.set(index + 1, note_event.into_lua(lua)?)?;
+                    } else {
+                        notes.set(index + 1, lua.create_table()?)?;
+                    }
+                }
+                if let LuaValue::Table(table) = &table {
+                    table.set("notes", notes)?;
+                }
+                Ok(table)
+            }
+            Some(event) => event.into_lua(lua),
+            None => Ok(LuaValue::Nil),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Memorizes the musical playback position of an emitter within a CallbackContext
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct CallbackPlaybackPosition {
+    beats: f64,
+    bars: f64,
+}
+
+impl IntoLua for CallbackPlaybackPosition {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table_with_capacity(0, 2)?;
+        table.raw_set("beats", self.beats)?;
+        table.raw_set("bars", self.bars)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A function which got scheduled via `context.schedule` to run at the given playback position.
+#[derive(Debug, Clone)]
+struct ScheduledCallback {
+    beats: f64,
+    function: LuaFunction,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Memorizes an optionally seeded random number generator within a CallbackContext.
+///
+/// Clones get a copy of the generator's current state, so cloned callbacks produce the same
+/// random numbers, but don't share their state.
+#[derive(Debug)]
+struct CallbackRng {
+    seed: Option<u64>,
+    rand: Rc<RefCell<Xoshiro256PlusPlus>>,
+}
+
+impl CallbackRng {
+    fn new(seed: Option<u64>) -> Self {
+        let rand = Rc::new(RefCell::new(match seed {
+            Some(seed) => Xoshiro256PlusPlus::seed_from_u64(seed),
+            None => Xoshiro256PlusPlus::from_seed(rand::rng().random()),
+        }));
+        Self { seed, rand }
+    }
+
+    fn reset(&mut self) {
+        if let Some(seed) = self.seed {
+            *self.rand.borrow_mut() = Xoshiro256PlusPlus::seed_from_u64(seed);
+        }
+    }
+
+    fn handle(&self) -> CallbackRngHandle {
+        CallbackRngHandle {
+            rand: Rc::clone(&self.rand),
+        }
+    }
+}
+
+impl Clone for CallbackRng {
+    fn clone(&self) -> Self {
+        Self {
+            seed: self.seed,
+            rand: Rc::new(RefCell::new(self.rand.borrow().clone())),
         }
     }
 }
 
+/// Lua userdata ref to a CallbackContext's random number generator.
+struct CallbackRngHandle {
+    rand: Rc<RefCell<Xoshiro256PlusPlus>>,
+}
+
+impl LuaUserData for CallbackRngHandle {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("number", |_lua, this, ()| -> LuaResult<LuaNumber> {
+            Ok(this.rand.borrow_mut().random::<LuaNumber>())
+        });
+
+        methods.add_method(
+            "integer",
+            |_lua, this, (min, max): (LuaInteger, LuaInteger)| -> LuaResult<LuaInteger> {
+                if max < min {
+                    return Err(bad_argument_error(
+                        "rng:integer",
+                        "max",
+                        2,
+                        "invalid interval: max must be >= min",
+                    ));
+                }
+                Ok(this.rand.borrow_mut().random_range(min..=max))
+            },
+        );
+
+        methods.add_method(
+            "choice",
+            |_lua, this, table: LuaTable| -> LuaResult<LuaValue> {
+                let len = table.raw_len();
+                if len == 0 {
+                    return Err(bad_argument_error(
+                        "rng:choice",
+                        "table",
+                        1,
+                        "table must not be empty",
+                    ));
+                }
+                let index = this.rand.borrow_mut().random_range(1..=len);
+                table.raw_get(index)
+            },
+        );
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Memorizes the duration of a single emitter step within a CallbackContext
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct CallbackStepDuration {
+    samples: f64,
+    seconds: f64,
+}
+
+impl IntoLua for CallbackStepDuration {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table_with_capacity(0, 2)?;
+        table.raw_set("samples", self.samples)?;
+        table.raw_set("seconds", self.seconds)?;
+        Ok(LuaValue::Table(table))
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 
 /// A to lua convertible value within a CallbackContext
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum ContextValue {
+    Boolean(bool),
     Number(LuaNumber),
     String(&'static [u8]),
 }
@@ -509,6 +922,7 @@ enum ContextValue {
 impl IntoLua for &ContextValue {
     fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
         match *self {
+            ContextValue::Boolean(bool) => Ok(LuaValue::Boolean(bool)),
             ContextValue::Number(num) => Ok(LuaValue::Number(num)),
             ContextValue::String(str) => Ok(LuaValue::String(lua.create_string(str)?)),
         }
@@ -521,6 +935,12 @@ impl From<&'static [u8]> for ContextValue {
     }
 }
 
+impl From<bool> for ContextValue {
+    fn from(val: bool) -> Self {
+        ContextValue::Boolean(val)
+    }
+}
+
 macro_rules! context_value_from_number_impl {
     ($type:ty) => {
         impl From<$type> for ContextValue {
@@ -552,6 +972,7 @@ mod test {
         pattern::{
             beat_time::BeatTimePattern, second_time::SecondTimePattern, Pattern, PatternEvent,
         },
+        SampleTime,
     };
 
     fn new_test_engine(
@@ -613,22 +1034,26 @@ mod test {
                     PatternEvent {
                         event: Some(Event::NoteEvents(vec![Some((Note::C4).into())])),
                         time: 0,
-                        duration: 44100
+                        duration: 44100,
+                        note_duration: None
                     },
                     PatternEvent {
                         time: 44100,
                         event: None,
-                        duration: 44100
+                        duration: 44100,
+                        note_duration: None
                     },
                     PatternEvent {
                         time: 88200,
                         event: Some(Event::NoteEvents(vec![Some((Note::Ds4).into())])),
-                        duration: 44100
+                        duration: 44100,
+                        note_duration: None
                     },
                     PatternEvent {
                         time: 132300,
                         event: Some(Event::NoteEvents(vec![Some((Note::G4).into())])),
-                        duration: 44100
+                        duration: 44100,
+                        note_duration: None
                     }
                 ]
             );
@@ -636,6 +1061,319 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn playback_position() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // record playback positions of all event calls in eighth note steps
+        let pattern = lua
+            .load(
+                r#"
+                positions = {}
+                return pattern {
+                    unit = "1/8",
+                    pulse = {1, 0, 1, 1},
+                    event = function(init_context)
+                      return function(context)
+                        local position = context.playback_position
+                        table.insert(positions, {position.beats, position.bars})
+                        return "c4"
+                      end
+                    end
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+        let recorded_positions = || -> LuaResult<Vec<(f64, f64)>> {
+            let positions = lua.globals().get::<LuaTable>("positions")?;
+            let positions = positions
+                .sequence_values::<Vec<f64>>()
+                .map(|position| position.map(|position| (position[0], position[1])))
+                .collect::<LuaResult<Vec<_>>>()?;
+            lua.globals().set("positions", lua.create_table()?)?;
+            Ok(positions)
+        };
+
+        let mut pattern = pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+        let expected = vec![
+            (0.0, 0.0),
+            (1.0, 0.25),
+            (1.5, 0.375),
+            (2.0, 0.5),
+            (3.0, 0.75),
+            (3.5, 0.875),
+        ];
+        for _ in 0..8 {
+            pattern.next();
+        }
+        assert_eq!(recorded_positions()?, expected);
+
+        // positions restart from 0 after a reset
+        pattern.reset();
+        for _ in 0..3 {
+            pattern.next();
+        }
+        assert_eq!(recorded_positions()?, expected[..2]);
+
+        // seeking keeps positions in sync with regular runs
+        pattern.reset();
+        pattern.advance_until_time(3 * 11025);
+        for _ in 0..5 {
+            pattern.next();
+        }
+        assert_eq!(recorded_positions()?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn step_duration() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // record step durations of all event calls in eighth note steps
+        let pattern = lua
+            .load(
+                r#"
+                step_durations = {}
+                return pattern {
+                    unit = "1/8",
+                    event = function(context)
+                      local step_duration = context.step_duration
+                      table.insert(step_durations, {step_duration.samples, step_duration.seconds})
+                      return "c4"
+                    end
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+        let recorded_step_duration = || -> LuaResult<(f64, f64)> {
+            let step_durations = lua.globals().get::<LuaTable>("step_durations")?;
+            let step_duration = step_durations.get::<Vec<f64>>(step_durations.raw_len())?;
+            Ok((step_duration[0], step_duration[1]))
+        };
+
+        let mut pattern = pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+        pattern.next();
+        assert_eq!(pattern.step_length(), 11025.0);
+        assert_eq!(recorded_step_duration()?, (pattern.step_length(), 0.25));
+
+        // step durations follow tempo changes
+        pattern.set_time_base(&BeatTimeBase {
+            beats_per_min: 60.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        });
+        pattern.next();
+        assert_eq!(pattern.step_length(), 22050.0);
+        assert_eq!(recorded_step_duration()?, (pattern.step_length(), 0.5));
+        Ok(())
+    }
+
+    #[test]
+    fn rng() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        let new_pattern = |seed: &str| -> LuaResult<BeatTimePattern> {
+            lua.load(format!(
+                r#"
+                return pattern {{
+                    {seed}
+                    event = function(context)
+                      local key = context.rng:integer(48, 60)
+                      local volume = context.rng:number()
+                      local instrument = context.rng:choice({{1, 2, 3}})
+                      return {{ key = key, volume = volume, instrument = instrument }}
+                    end
+                }}
+            "#
+            ))
+            .eval::<LuaValue>()?
+            .as_userdata()
+            .unwrap()
+            .take::<BeatTimePattern>()
+        };
+        fn run_events(pattern: &mut (dyn Pattern + 'static)) -> Vec<Option<Event>> {
+            (0..8)
+                .map(|_| pattern.next().and_then(|event| event.event))
+                .collect()
+        }
+
+        // seeded patterns produce the same events
+        let mut pattern = new_pattern("seed = 1234,")?;
+        let events = run_events(&mut pattern);
+        assert_eq!(events, run_events(&mut new_pattern("seed = 1234,")?));
+        assert_ne!(events, run_events(&mut new_pattern("seed = 5678,")?));
+
+        // reset restores the seeded state
+        pattern.reset();
+        assert_eq!(events, run_events(&mut pattern));
+
+        // duplicates with different triggers produce the same events
+        pattern.reset();
+        let duplicate = pattern.duplicate();
+        pattern.set_trigger_event(&Event::NoteEvents(vec![Some(Note::C4.into())]));
+        duplicate
+            .as_ref()
+            .borrow_mut()
+            .set_trigger_event(&Event::NoteEvents(vec![Some(Note::G5.into())]));
+        assert_eq!(events, run_events(&mut pattern));
+        assert_eq!(events, run_events(&mut *duplicate.as_ref().borrow_mut()));
+
+        // pulse and gate functions get seeded too
+        let new_gated_pattern = |seed: &str| -> LuaResult<BeatTimePattern> {
+            lua.load(format!(
+                r#"
+                return pattern {{
+                    {seed}
+                    pulse = function(context)
+                      return context.rng:number() > 0.25 and 1 or 0
+                    end,
+                    gate = function(context)
+                      return context.rng:number() > 0.5
+                    end,
+                    event = "c4"
+                }}
+            "#
+            ))
+            .eval::<LuaValue>()?
+            .as_userdata()
+            .unwrap()
+            .take::<BeatTimePattern>()
+        };
+        fn run_event_times(pattern: &mut (dyn Pattern + 'static)) -> Vec<SampleTime> {
+            (0..32)
+                .filter_map(|_| pattern.next())
+                .filter(|event| event.event.is_some())
+                .map(|event| event.time)
+                .collect()
+        }
+        let mut pattern = new_gated_pattern("seed = 1234,")?;
+        let event_times = run_event_times(&mut pattern);
+        assert_eq!(
+            event_times,
+            run_event_times(&mut new_gated_pattern("seed = 1234,")?)
+        );
+        assert_ne!(
+            event_times,
+            run_event_times(&mut new_gated_pattern("seed = 5678,")?)
+        );
+        pattern.reset();
+        assert_eq!(event_times, run_event_times(&mut pattern));
+
+        // negative seeds are distinct seeds
+        let event_times = run_event_times(&mut new_gated_pattern("seed = -1,")?);
+        assert_eq!(
+            event_times,
+            run_event_times(&mut new_gated_pattern("seed = -1.0,")?)
+        );
+        assert_ne!(
+            event_times,
+            run_event_times(&mut new_gated_pattern("seed = -2,")?)
+        );
+        assert_ne!(
+            event_times,
+            run_event_times(&mut new_gated_pattern("seed = 0,")?)
+        );
+
+        // invalid arguments
+        assert!(lua
+            .load(r#"pattern { seed = "abc", event = "c4" }"#)
+            .eval::<LuaValue>()
+            .is_err());
+        assert!(lua
+            .load(r#"pattern { seed = 1e300, event = "c4" }"#)
+            .eval::<LuaValue>()
+            .is_err());
+        let mut invalid_pattern = lua
+            .load(r#"pattern { event = function(context) return context.rng:choice({}) end }"#)
+            .eval::<LuaValue>()?
+            .as_userdata()
+            .unwrap()
+            .take::<BeatTimePattern>()?;
+        clear_lua_callback_errors();
+        invalid_pattern.next();
+        assert!(has_lua_callback_errors().is_some());
+        clear_lua_callback_errors();
+        Ok(())
+    }
+
+    #[test]
+    fn schedule() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // schedule a response two beats after each note
+        let pattern = lua
+            .load(
+                r#"
+                return pattern {
+                    unit = "beats",
+                    pulse = {1, 0, 0, 0},
+                    event = function(context)
+                      context.schedule(2, function(context)
+                        return "e4"
+                      end)
+                      return "c4"
+                    end
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+        let mut pattern = pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+        let run_events = |pattern: &mut BeatTimePattern, count: usize| {
+            (0..count)
+                .map(|_| match pattern.next().and_then(|event| event.event) {
+                    Some(Event::NoteEvents(notes)) => {
+                        notes.iter().flatten().map(|n| n.note).collect()
+                    }
+                    _ => vec![],
+                })
+                .collect::<Vec<_>>()
+        };
+        let expected = vec![
+            vec![Note::C4],
+            vec![],
+            vec![Note::E4],
+            vec![],
+            vec![Note::C4],
+            vec![],
+            vec![Note::E4],
+            vec![],
+        ];
+        assert_eq!(run_events(&mut pattern, 8), expected);
+
+        // reset forgets pending scheduled functions
+        run_events(&mut pattern, 1);
+        pattern.reset();
+        assert_eq!(run_events(&mut pattern, 8), expected);
+
+        // invalid schedule arguments
+        assert!(lua
+            .load(
+                r#"pattern { event = function(context)
+                  context.schedule(-1, function() end)
+                end }"#
+            )
+            .eval::<LuaValue>()?
+            .as_userdata()
+            .unwrap()
+            .take::<BeatTimePattern>()
+            .map(|mut pattern| {
+                clear_lua_callback_errors();
+                pattern.next();
+                has_lua_callback_errors().is_some()
+            })?);
+        clear_lua_callback_errors();
+        Ok(())
+    }
+
     #[test]
     fn callback_clones() -> LuaResult<()> {
         let (lua, _) = new_test_engine(120.0, 4, 44100)?;
@@ -671,6 +1409,8 @@ mod test {
             volume: 0.5,
             panning: 0.0,
             delay: 0.25,
+            speed: None,
+            ratchet: None,
         })]);
         pattern.set_trigger_event(&trigger_event);
 
@@ -680,6 +1420,8 @@ mod test {
             volume: 1.0,
             panning: -1.0,
             delay: 0.5,
+            speed: None,
+            ratchet: None,
         })]);
         pattern2.set_trigger_event(&trigger_event2);
 
@@ -691,6 +1433,7 @@ mod test {
                 time: 0,
                 event: Some(trigger_event),
                 duration: 22050,
+                note_duration: None,
             })
         );
 
@@ -701,8 +1444,116 @@ mod test {
                 time: 0,
                 event: Some(trigger_event2),
                 duration: 22050,
+                note_duration: None,
             })
         );
         Ok(())
     }
+
+    #[test]
+    fn trigger_note_values() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // create a beat_time pattern which emits notes from the trigger note values
+        let pattern = lua
+            .load(
+                r#"
+                return pattern {
+                    unit = "1/4",
+                    event = function(context)
+                      assert(context.trigger.notes[2].key == context.trigger.key)
+                      return {
+                        key = context.trigger.key,
+                        volume = context.trigger.volume / 2,
+                        panning = -context.trigger.panning,
+                        instrument = #context.trigger.notes
+                      }
+                    end
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+        let mut pattern = pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+
+        pattern.set_trigger_event(&Event::NoteEvents(vec![
+            None,
+            Some(NoteEvent {
+                note: Note::A4,
+                instrument: None,
+                volume: 0.5,
+                panning: 0.25,
+                delay: 0.0,
+                speed: None,
+                ratchet: None,
+            }),
+            Some(Note::C5.into()),
+        ]));
+        let event = pattern.next().and_then(|event| event.event);
+        assert_eq!(
+            event,
+            Some(Event::NoteEvents(vec![Some(NoteEvent {
+                note: Note::A4,
+                instrument: Some(InstrumentId::from(3)),
+                volume: 0.25,
+                panning: -0.25,
+                delay: 0.0,
+                speed: None,
+                ratchet: None,
+            })]))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn warnings() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // NB: warnings are global and tests run in parallel: only check for our own warnings
+        let has_warning = |pattern: &str| {
+            lua_callback_warnings()
+                .iter()
+                .any(|warning| warning.contains(pattern))
+        };
+
+        // fractional seeds get truncated
+        lua.load(r#"pattern { seed = 1.5, event = "c4" }"#)
+            .eval::<LuaValue>()?;
+        assert!(has_warning("seed 1.5 is not an integer value: using 1"));
+        // integer parameter ranges get clamped
+        lua.load(r#"parameter.integer("int", 0, { -1, 8589934592 })"#)
+            .eval::<LuaValue>()?;
+        assert!(has_warning(
+            "parameter range end 8589934592 is out of the supported range: using 2147483647"
+        ));
+        // out of range euclidean rotations get wrapped
+        lua.load(r#"euclidean(3, 8, 10)"#).eval::<LuaValue>()?;
+        assert!(has_warning("rotation 10 is out of the step range: using 2"));
+        // step instruments which do not line up get truncated
+        lua.load(
+            r#"
+            local events = {}
+            for i = 1, 1023 do events[i] = "c4" end
+            return pattern { event = events, instrument = { 1, 2 } }
+            "#,
+        )
+        .eval::<LuaValue>()?;
+        assert!(has_warning(
+            "instruments do not line up with the event steps within 1024 steps: using 1024 steps"
+        ));
+        assert!(has_lua_callback_warnings().is_some());
+
+        // warnings are not errors
+        assert!(!lua_callback_errors()
+            .iter()
+            .any(|err| err.to_string().contains("8589934592")));
+
+        add_lua_callback_warning("test", "cleared warning");
+        assert!(has_warning("test: cleared warning"));
+        clear_lua_callback_warnings();
+        assert!(!has_warning("test: cleared warning"));
+        Ok(())
+    }
 }
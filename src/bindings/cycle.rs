@@ -15,8 +15,27 @@ pub struct CycleUserData {
 }
 
 impl CycleUserData {
-    pub fn from(arg: LuaString, seed: Option<u64>) -> LuaResult<Self> {
-        let mut cycle = Cycle::from(&arg.to_string_lossy()).map_err(LuaError::runtime)?;
+    pub fn from(arg: LuaString, bindings: Option<LuaTable>, seed: Option<u64>) -> LuaResult<Self> {
+        let mut named_cycles = Vec::new();
+        if let Some(bindings) = bindings {
+            for pair in bindings.pairs::<LuaString, LuaString>() {
+                let (name, input) = pair?;
+                let cycle = Cycle::from(&input.to_string_lossy()).map_err(|err| {
+                    LuaError::runtime(format!(
+                        "invalid cycle binding '{}': {}",
+                        name.to_string_lossy(),
+                        err
+                    ))
+                })?;
+                named_cycles.push((name.to_string_lossy(), cycle));
+            }
+        }
+        let named_cycles = named_cycles
+            .iter()
+            .map(|(name, cycle)| (name.as_str(), cycle.clone()))
+            .collect::<Vec<_>>();
+        let mut cycle = Cycle::from_with_bindings(&arg.to_string_lossy(), &named_cycles)
+            .map_err(LuaError::runtime)?;
         if let Some(seed) = seed {
             cycle = cycle.with_seed(seed);
         }
@@ -32,7 +51,7 @@ impl CycleUserData {
 
 impl LuaUserData for CycleUserData {
     fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
-        methods.add_method_mut("map", |_lua, this, value: LuaValue| match value {
+        methods.add_method_mut("map", |lua, this, value: LuaValue| match value {
             LuaValue::Function(func) => {
                 let cycle = this.cycle.clone();
                 let mappings = Vec::new();
@@ -47,7 +66,7 @@ impl LuaUserData for CycleUserData {
                 let cycle = this.cycle.clone();
                 let mut mappings = Vec::new();
                 for (k, v) in table.pairs::<LuaValue, LuaValue>().flatten() {
-                    mappings.push((k.to_string()?, note_events_from_value(&v, None)?));
+                    mappings.push((k.to_string()?, note_events_from_value(lua, &v, None)?));
                 }
                 let mapping_function = None;
                 Ok(CycleUserData {
@@ -76,6 +95,8 @@ impl LuaUserData for CycleUserData {
 mod test {
     use std::collections::HashMap;
 
+    use num_rational::Rational32 as Fraction;
+
     use super::*;
 
     use crate::{
@@ -116,6 +137,19 @@ mod test {
         assert!(evaluate_cycle_userdata(&lua, r#"cycle("[<")"#).is_err());
         assert!(evaluate_cycle_userdata(&lua, r#"cycle("[c4 e6]")"#).is_ok());
 
+        assert!(evaluate_cycle_userdata(&lua, r#"cycle("$a $b", { a = "c4 e4" })"#).is_err());
+        assert!(evaluate_cycle_userdata(&lua, r#"cycle("$a", { a = "[c4" })"#).is_err());
+        let mut cycle =
+            evaluate_cycle_userdata(&lua, r#"cycle("$a $a $b", { a = "c4 e4", b = "<g4 a4>" })"#)?
+                .cycle;
+        let mut expected = Cycle::from("[c4 e4] [c4 e4] <g4 a4>").map_err(LuaError::runtime)?;
+        for _ in 0..2 {
+            assert_eq!(
+                cycle.generate().map_err(LuaError::runtime)?,
+                expected.generate().map_err(LuaError::runtime)?
+            );
+        }
+
         Ok(())
     }
 
@@ -154,6 +188,23 @@ mod test {
             ])
         );
 
+        // signal names are no reserved names in mappings
+        let mapped_cycle = evaluate_cycle_userdata(
+            &lua,
+            r#"cycle("sine rand"):map({sine = "c4", rand = "e4"})"#,
+        )?;
+        let mut event_iter =
+            CycleEmitter::new(mapped_cycle.cycle).with_mappings(&mapped_cycle.mappings);
+        assert_eq!(
+            event_iter
+                .run(RhythmEvent::default(), true)
+                .map(|events| events.into_iter().map(|e| e.event).collect::<Vec<_>>()),
+            Some(vec![
+                Event::NoteEvents(vec![new_note(Note::C4)]),
+                Event::NoteEvents(vec![new_note(Note::E4)])
+            ])
+        );
+
         // check note properties
         let mapped_cycle = evaluate_cycle_userdata(&lua, r#"cycle("a:1:v0.1:p-1.0:d0.3")"#)?;
         let mut event_iter =
@@ -206,6 +257,53 @@ mod test {
             ])
         );
 
+        // check parameter applications
+        let mapped_cycle = evaluate_cycle_userdata(&lua, r#"cycle("[a a] # pan saw")"#)?;
+        let mut event_iter =
+            CycleEmitter::new(mapped_cycle.cycle).with_mappings(&mapped_cycle.mappings);
+        assert_eq!(
+            event_iter
+                .run(RhythmEvent::default(), true)
+                .map(|events| events.into_iter().map(|e| e.event).collect::<Vec<_>>()),
+            Some(vec![
+                Event::NoteEvents(vec![new_note((Note::A4, None, 1.0, 0.25, 0.0))]),
+                Event::NoteEvents(vec![new_note((Note::A4, None, 1.0, 0.75, 0.0))])
+            ])
+        );
+
+        // check ratchets
+        let mapped_cycle = evaluate_cycle_userdata(
+            &lua,
+            r#"cycle("a c4"):map({ a = { key = "e4", ratchet = 2 } })"#,
+        )?;
+        let mut event_iter =
+            CycleEmitter::new(mapped_cycle.cycle).with_mappings(&mapped_cycle.mappings);
+        assert_eq!(
+            event_iter.run(RhythmEvent::default(), true).map(|events| {
+                events
+                    .into_iter()
+                    .map(|e| (e.start, e.length, e.event))
+                    .collect::<Vec<_>>()
+            }),
+            Some(vec![
+                (
+                    Fraction::ZERO,
+                    Fraction::new(1, 2),
+                    Event::NoteEvents(vec![new_note(Note::E4)])
+                ),
+                (
+                    Fraction::new(1, 4),
+                    Fraction::new(1, 4),
+                    Event::NoteEvents(vec![new_note(Note::E4)])
+                ),
+                (
+                    Fraction::new(1, 2),
+                    Fraction::new(1, 2),
+                    Event::NoteEvents(vec![new_note(Note::C4)])
+                ),
+            ])
+        );
+
         Ok(())
     }
 
@@ -0,0 +1,428 @@
+//! Errors of the pattern script API.
+
+use std::fmt;
+
+use mlua::prelude::*;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Error type of the script based pattern constructors, such as
+/// [`new_pattern_from_string`](super::new_pattern_from_string).
+///
+/// Display impls print the raw Lua error messages, so existing error parsers, which e.g.
+/// look up the error line in the message, continue to work.
+#[derive(Debug)]
+pub enum PatternError {
+    /// The script file could not be read.
+    Io(std::io::Error),
+    /// The script failed to compile, e.g. because of syntax errors.
+    CompileError {
+        message: String,
+        /// Line in the script which caused the error, if known.
+        line: Option<usize>,
+        /// Source range of the token which caused the error, if known.
+        range: Option<ErrorRange>,
+    },
+    /// The script failed to run, or did not evaluate to a valid pattern.
+    RuntimeError {
+        message: String,
+        /// Line in the script which caused the error, if known.
+        line: Option<usize>,
+    },
+}
+
+/// Source code range of a [`PatternError`], e.g. to highlight syntax errors in editors.
+///
+/// Lines and columns are 1-based and count characters, not bytes. The end column is exclusive,
+/// so errors at the end of the script have an empty range.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ErrorRange {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl PatternError {
+    /// Line in the script which caused the error, if known.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Self::Io(_) => None,
+            Self::CompileError { line, .. } | Self::RuntimeError { line, .. } => *line,
+        }
+    }
+
+    /// Source range in the script which caused the error, if known.
+    /// Only available for compile errors.
+    pub fn range(&self) -> Option<ErrorRange> {
+        match self {
+            Self::CompileError { range, .. } => *range,
+            Self::Io(_) | Self::RuntimeError { .. } => None,
+        }
+    }
+
+    /// Resolve the source range of compile errors from the given script source.
+    pub(crate) fn with_source<S: AsRef<[u8]>>(self, source: S) -> Self {
+        match self {
+            Self::CompileError {
+                message,
+                line,
+                range: None,
+            } => {
+                let source = String::from_utf8_lossy(source.as_ref());
+                let range = line.and_then(|line| Self::parse_range(&message, line, &source));
+                Self::CompileError {
+                    message,
+                    line,
+                    range,
+                }
+            }
+            _ => self,
+        }
+    }
+
+    /// Parse the source range of the token in Lua syntax error messages, which look like
+    /// `[string "name"]:LINE: message near 'TOKEN'` or `... got 'TOKEN'` in Luau. When the
+    /// token can't be located, the range spans the whole line.
+    fn parse_range(message: &str, line: usize, source: &str) -> Option<ErrorRange> {
+        let line_text = source.lines().nth(line.checked_sub(1)?)?;
+        let column = |byte_index: usize| line_text[..byte_index].chars().count() + 1;
+        let token = [" near ", " got "]
+            .iter()
+            .filter_map(|prefix| message.rfind(prefix).map(|i| &message[i + prefix.len()..]))
+            .next()
+            .map(|token| token.lines().next().unwrap_or(token).trim())
+            .map(|token| {
+                token
+                    .strip_prefix('\'')
+                    .and_then(|token| token.strip_suffix('\''))
+                    .unwrap_or(token)
+            });
+        match token {
+            Some("<eof>") => {
+                let end = column(line_text.len());
+                Some(ErrorRange {
+                    start_line: line,
+                    start_column: end,
+                    end_line: line,
+                    end_column: end,
+                })
+            }
+            Some(token) => {
+                // NB: messages only name the token, so ambiguous tokens span the whole line
+                let (start, end) = match Self::find_token(line_text, token).as_slice() {
+                    [start] => (*start, start + token.len()),
+                    _ => Self::line_extent(line_text),
+                };
+                Some(ErrorRange {
+                    start_line: line,
+                    start_column: column(start),
+                    end_line: line,
+                    end_column: column(end),
+                })
+            }
+            None => {
+                let (start, end) = Self::line_extent(line_text);
+                Some(ErrorRange {
+                    start_line: line,
+                    start_column: column(start),
+                    end_line: line,
+                    end_column: column(end),
+                })
+            }
+        }
+    }
+
+    /// Byte indices of all occurrences of the given token in the given line. Word tokens only
+    /// match whole words, so e.g. a `x` token does not match the `x` in `max`.
+    fn find_token(line_text: &str, token: &str) -> Vec<usize> {
+        if token.is_empty() {
+            return vec![];
+        }
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let is_word = token.chars().all(is_word_char);
+        line_text
+            .match_indices(token)
+            .map(|(start, _)| start)
+            .filter(|start| {
+                !is_word
+                    || (!line_text[..*start].ends_with(is_word_char)
+                        && !line_text[start + token.len()..].starts_with(is_word_char))
+            })
+            .collect()
+    }
+
+    /// Byte range of the given line's content without leading and trailing whitespace.
+    fn line_extent(line_text: &str) -> (usize, usize) {
+        let trimmed = line_text.trim_start();
+        let start = line_text.len() - trimmed.len();
+        (start, start + trimmed.trim_end().len())
+    }
+
+    /// Parse the script line number from Lua error messages, which look like
+    /// `[string "name"]:LINE: message` or `path:LINE: message`.
+    fn parse_line(message: &str) -> Option<usize> {
+        message.match_indices(':').find_map(|(index, _)| {
+            let rest = &message[index + 1..];
+            let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+            if digits > 0 && rest[digits..].starts_with(':') {
+                rest[..digits].parse().ok()
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read script: {}", err),
+            Self::CompileError { message, .. } | Self::RuntimeError { message, .. } => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatternError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::CompileError { .. } | Self::RuntimeError { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PatternError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<LuaError> for PatternError {
+    fn from(err: LuaError) -> Self {
+        let message = err.to_string();
+        let line = Self::parse_line(&message);
+        match err {
+            LuaError::SyntaxError { .. } => Self::CompileError {
+                message,
+                line,
+                range: None,
+            },
+            _ => Self::RuntimeError { message, line },
+        }
+    }
+}
+
+/// Converts pattern errors back to Lua errors, so callers which previously propagated the
+/// `LuaError`s of the pattern constructors with `?` continue to work.
+impl From<PatternError> for LuaError {
+    fn from(err: PatternError) -> Self {
+        match err {
+            PatternError::Io(err) => LuaError::external(err),
+            PatternError::CompileError { message, .. } => LuaError::SyntaxError {
+                message,
+                incomplete_input: false,
+            },
+            PatternError::RuntimeError { message, .. } => LuaError::RuntimeError(message),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        bindings::{new_pattern_from_file, new_pattern_from_string},
+        BeatTimeBase,
+    };
+
+    #[test]
+    fn parse_line() {
+        assert_eq!(
+            PatternError::parse_line(r#"[string "test.lua"]:12: attempt to call a nil value"#),
+            Some(12)
+        );
+        assert_eq!(
+            PatternError::parse_line("syntax error: scripts/test.lua:3: unexpected symbol"),
+            Some(3)
+        );
+        assert_eq!(
+            PatternError::parse_line("script must return a pattern"),
+            None
+        );
+        assert_eq!(PatternError::parse_line("time: 12:30"), None);
+    }
+
+    #[test]
+    fn parse_range() {
+        let range = |start_line, start_column, end_line, end_column| {
+            Some(ErrorRange {
+                start_line,
+                start_column,
+                end_line,
+                end_column,
+            })
+        };
+        let source = "return pattern {\n  event = \"c4\"\n  unit = \"1/4\" }";
+        // lua tokens
+        assert_eq!(
+            PatternError::parse_range(
+                r#"[string "test.lua"]:3: '}' expected (to close '{' at line 1) near 'unit'"#,
+                3,
+                source
+            ),
+            range(3, 3, 3, 7)
+        );
+        // luau tokens
+        assert_eq!(
+            PatternError::parse_range(
+                r#"test.lua:3: Expected '}' (to close '{' at line 1), got 'unit'"#,
+                3,
+                source
+            ),
+            range(3, 3, 3, 7)
+        );
+        // end of file
+        assert_eq!(
+            PatternError::parse_range(
+                r#"[string "test.lua"]:1: '}' expected near '<eof>'"#,
+                1,
+                "return pattern {"
+            ),
+            range(1, 17, 1, 17)
+        );
+        assert_eq!(
+            PatternError::parse_range(
+                r#"[string "test.lua"]:1: unexpected symbol near <eof>"#,
+                1,
+                "return pattern {"
+            ),
+            range(1, 17, 1, 17)
+        );
+        // unknown tokens span the whole line
+        assert_eq!(
+            PatternError::parse_range(
+                r#"[string "test.lua"]:2: unexpected symbol near 'x'"#,
+                2,
+                source
+            ),
+            range(2, 3, 2, 15)
+        );
+        assert_eq!(
+            PatternError::parse_range(r#"[string "test.lua"]:2: oops"#, 2, source),
+            range(2, 3, 2, 15)
+        );
+        // columns count characters
+        assert_eq!(
+            PatternError::parse_range(
+                r#"[string "test.lua"]:1: unexpected symbol near 'x'"#,
+                1,
+                "-- ö\nlocal ä = x",
+            ),
+            range(1, 1, 1, 5)
+        );
+        assert_eq!(
+            PatternError::parse_range(
+                r#"[string "test.lua"]:2: unexpected symbol near 'x'"#,
+                2,
+                "-- ö\nlocal ä = x",
+            ),
+            range(2, 11, 2, 12)
+        );
+        // tokens match whole words only
+        assert_eq!(
+            PatternError::parse_range(
+                r#"[string "test.lua"]:1: unexpected symbol near 'x'"#,
+                1,
+                "local max = x",
+            ),
+            range(1, 13, 1, 14)
+        );
+        // ambiguous tokens span the whole line
+        assert_eq!(
+            PatternError::parse_range(
+                r#"[string "test.lua"]:1: unexpected symbol near 'x'"#,
+                1,
+                "  local x = x y",
+            ),
+            range(1, 3, 1, 16)
+        );
+        // invalid lines
+        assert_eq!(
+            PatternError::parse_range(r#"[string "test.lua"]:4: oops"#, 4, source),
+            None
+        );
+    }
+
+    #[test]
+    fn variants() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let new_pattern =
+            |script: &str| new_pattern_from_string(time_base, None, script, "test.lua").map(|_| ());
+        assert!(new_pattern("return pattern { event = \"c4\" }").is_ok());
+        // missing files
+        assert!(matches!(
+            new_pattern_from_file(time_base, None, "does/not/exist.lua").map(|_| ()),
+            Err(PatternError::Io(_))
+        ));
+        // syntax errors
+        let err = new_pattern("\nreturn pattern {").unwrap_err();
+        assert!(matches!(err, PatternError::CompileError { .. }));
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(
+            err.range(),
+            Some(ErrorRange {
+                start_line: 2,
+                start_column: 17,
+                end_line: 2,
+                end_column: 17
+            })
+        );
+        let err =
+            new_pattern("return pattern {\n  event = \"c4\"\n  unit = \"1/4\" }").unwrap_err();
+        assert_eq!(err.line(), Some(3));
+        assert_eq!(
+            err.range(),
+            Some(ErrorRange {
+                start_line: 3,
+                start_column: 3,
+                end_line: 3,
+                end_column: 7
+            })
+        );
+        // runtime errors with location
+        let err = new_pattern("local x = nil\nreturn x.y").unwrap_err();
+        assert!(matches!(
+            err,
+            PatternError::RuntimeError { line: Some(2), .. }
+        ));
+        assert!(err.to_string().contains("[string \"test.lua\"]:2:"));
+        assert_eq!(err.range(), None);
+        // invalid return values
+        assert!(matches!(
+            new_pattern("return 1"),
+            Err(PatternError::RuntimeError { line: None, .. })
+        ));
+        // conversions back to lua errors
+        let err = new_pattern("\nreturn pattern {").unwrap_err();
+        let message = err.to_string();
+        assert!(matches!(
+            LuaError::from(err),
+            LuaError::SyntaxError { message: lua_message, .. } if lua_message == message
+        ));
+        let err = new_pattern("local x = nil\nreturn x.y").unwrap_err();
+        let message = err.to_string();
+        assert!(matches!(
+            LuaError::from(err),
+            LuaError::RuntimeError(lua_message) if lua_message == message
+        ));
+    }
+}
@@ -21,7 +21,7 @@ pub struct NoteUserData {
 }
 
 impl NoteUserData {
-    pub fn from(args: LuaMultiValue) -> LuaResult<Self> {
+    pub fn from(lua: &Lua, args: LuaMultiValue) -> LuaResult<Self> {
         // a single value, probably a sequence
         if args.len() == 1 {
             let arg = args
@@ -34,34 +34,73 @@ impl NoteUserData {
                 let mut notes = vec![];
                 for (index, arg) in sequence.iter().enumerate() {
                     // flatten sequence events into a single array
-                    notes.append(&mut note_events_from_value(arg, Some(index))?);
+                    notes.append(&mut note_events_from_value(lua, arg, Some(index))?);
                 }
                 Ok(NoteUserData { notes })
             } else {
                 Ok(NoteUserData {
-                    notes: note_events_from_value(&arg, None)?,
+                    notes: note_events_from_value(lua, &arg, None)?,
                 })
             }
         // multiple values, maybe of different type
         } else {
             let mut notes = vec![];
             for (index, arg) in args.iter().enumerate() {
-                notes.append(&mut note_events_from_value(arg, Some(index))?);
+                notes.append(&mut note_events_from_value(lua, arg, Some(index))?);
             }
             Ok(NoteUserData { notes })
         }
     }
 
-    pub fn from_chord(note: &LuaValue, mode_or_intervals: &LuaValue) -> LuaResult<Self> {
+    /// Create the global `note` library: a callable table which creates notes from note values
+    /// and also provides functions to create notes from other sources, such as frequencies.
+    pub fn create_library(lua: &Lua) -> LuaResult<LuaTable> {
+        let library = lua.create_table()?;
+        // function note.from_hz(hz, a4_hz?)
+        library.raw_set(
+            "from_hz",
+            lua.create_function(
+                |_lua, (hz, a4_hz): (f32, Option<f32>)| -> LuaResult<NoteUserData> {
+                    let a4_hz = a4_hz_argument("from_hz", a4_hz, 2)?;
+                    if !(hz.is_finite() && hz > 0.0) {
+                        return Err(bad_argument_error(
+                            "from_hz",
+                            "hz",
+                            1,
+                            "expecting a frequency > 0",
+                        ));
+                    }
+                    let note = Note::from_frequency(hz, a4_hz);
+                    Ok(NoteUserData {
+                        notes: vec![Some(NoteEvent::from(note))],
+                    })
+                },
+            )?,
+        )?;
+        // function note(args...)
+        let metatable = lua.create_table()?;
+        metatable.raw_set(
+            "__call",
+            lua.create_function(
+                |lua, (_library, args): (LuaValue, LuaMultiValue)| -> LuaResult<NoteUserData> {
+                    NoteUserData::from(lua, args)
+                },
+            )?,
+        )?;
+        library.set_metatable(Some(metatable))?;
+        Ok(library)
+    }
+
+    pub fn from_chord(lua: &Lua, note: &LuaValue, mode_or_intervals: &LuaValue) -> LuaResult<Self> {
         if let Some(mode) = mode_or_intervals.as_string() {
-            let notes = chord_events_from_mode(note, &mode.to_string_lossy())?;
+            let notes = chord_events_from_mode(lua, note, &mode.to_string_lossy())?;
             Ok(Self { notes })
         } else if let Some(table) = mode_or_intervals.as_table() {
             let intervals = table
                 .clone()
                 .sequence_values::<i32>()
                 .collect::<LuaResult<Vec<i32>>>()?;
-            let notes = chord_events_from_intervals(note, &intervals)?;
+            let notes = chord_events_from_intervals(lua, note, &intervals)?;
             Ok(Self { notes })
         } else {
             Err(bad_argument_error(
@@ -90,6 +129,19 @@ impl LuaUserData for NoteUserData {
     }
 
     fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("to_hz", |_lua, this, a4_hz: Option<f32>| {
+            let a4_hz = a4_hz_argument("to_hz", a4_hz, 1)?;
+            Ok(this
+                .notes
+                .iter()
+                .map(|note| {
+                    note.as_ref()
+                        .and_then(|note| note.note.to_frequency(a4_hz))
+                        .map_or(LuaValue::Nil, |hz| LuaValue::Number(hz as LuaNumber))
+                })
+                .collect::<LuaMultiValue>())
+        });
+
         methods.add_function(
             "transpose",
             |lua, (ud, value): (LuaAnyUserData, LuaValue)| {
@@ -214,12 +266,29 @@ impl LuaUserData for NoteUserData {
     }
 }
 
+// ---------------------------------------------------------------------------------------------
+
+// Validate an optional A4 reference frequency argument of note frequency conversion functions.
+fn a4_hz_argument(func_name: &'static str, a4_hz: Option<f32>, arg_index: usize) -> LuaResult<f32> {
+    let a4_hz = a4_hz.unwrap_or(Note::DEFAULT_A4_FREQUENCY);
+    if a4_hz.is_finite() && a4_hz > 0.0 {
+        Ok(a4_hz)
+    } else {
+        Err(bad_argument_error(
+            func_name,
+            "a4_hz",
+            arg_index,
+            "expecting a reference frequency > 0",
+        ))
+    }
+}
+
 // --------------------------------------------------------------------------------------------------
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{bindings::*, event::new_note};
+    use crate::{bindings::*, event::new_note, Event};
 
     fn new_test_engine() -> LuaResult<(Lua, LuaTimeoutHook)> {
         let (mut lua, mut timeout_hook) = new_engine()?;
@@ -365,6 +434,35 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn note_off() -> LuaResult<()> {
+        let (lua, _) = new_test_engine()?;
+
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note_off()"#)?.notes,
+            vec![new_note(Note::OFF)]
+        );
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note_off(3)"#)?.notes,
+            vec![None, None, new_note(Note::OFF)]
+        );
+        assert!(evaluate_note_userdata(&lua, r#"note_off(0)"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note_off(129)"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note_off("x")"#).is_err());
+
+        // note-offs can be used in note arrays
+        let value = lua.load(r#"{"c4", note_off(2)}"#).eval::<LuaValue>()?;
+        let notes = note_events_from_value(&lua, &value, None)?;
+        assert_eq!(notes, vec![new_note(Note::C4), None, new_note(Note::OFF)]);
+
+        // and display as "==="
+        assert_eq!(
+            Event::NoteEvents(notes).to_string(false),
+            "C4 1.00 0.00 0.00 | --- | ==="
+        );
+        Ok(())
+    }
+
     #[test]
     fn note_chord() -> LuaResult<()> {
         let (lua, _) = new_test_engine()?;
@@ -412,6 +510,26 @@ mod test {
             ]
         );
 
+        // Note chord voicings
+        assert!(evaluate_note_userdata(&lua, r#"note("c4'maj'x")"#).is_err());
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note("c4'maj'i v0.2")"#)?.notes,
+            vec![
+                new_note(("e4", None, 0.2)),
+                new_note(("g4", None, 0.2)),
+                new_note(("c5", None, 0.2)),
+            ]
+        );
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"chord("c4", "maj7'd2")"#)?.notes,
+            vec![
+                new_note("g3"),
+                new_note("c4"),
+                new_note("e4"),
+                new_note("b4"),
+            ]
+        );
+
         Ok(())
     }
 
@@ -570,6 +688,43 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn note_speed() -> LuaResult<()> {
+        let (lua, _) = new_test_engine()?;
+
+        // speed
+        assert!(evaluate_note_userdata(&lua, r#"note({key = "c4", speed = 0})"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note({key = "c4", speed = -1})"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note({key = "c4", speed = "1"})"#).is_err());
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note({key = "c4"})"#)?.notes,
+            vec![new_note("c4")]
+        );
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note({key = 48, volume = 0.5, speed = 2})"#)?.notes,
+            vec![Some(NoteEvent {
+                speed: Some(2.0),
+                ..("c4", None, 0.5).into()
+            })]
+        );
+        // speed is carried along with note transforms
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note({key = "c4", speed = 0.5}):transpose({12})"#)?
+                .notes,
+            vec![Some(NoteEvent {
+                speed: Some(0.5),
+                .."c5".into()
+            })]
+        );
+        assert_eq!(
+            lua.load(r#"note({key = "c4", speed = 0.5}).notes[1].speed"#)
+                .eval::<LuaNumber>()?,
+            0.5
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn note_numbers() -> LuaResult<()> {
         let (lua, _) = new_test_engine()?;
@@ -588,6 +743,92 @@ mod test {
         assert_eq!(evaluate_number(&lua, r#"note_number(nil)"#)?, 0xFE);
         assert_eq!(evaluate_number(&lua, r#"note_number("off")"#)?, 0xFF);
 
+        // Note frequencies
+        assert_eq!(lua.load(r#"note("a4"):to_hz()"#).eval::<f32>()?, 440.0);
+        assert_eq!(lua.load(r#"note(69):to_hz()"#).eval::<f32>()?, 880.0);
+        assert_eq!(
+            lua.load(r#"note({ key = "a3" }):to_hz(432)"#)
+                .eval::<f32>()?,
+            216.0
+        );
+        assert_eq!(
+            lua.load(r#"note("a3", "off", "a4", "---"):to_hz()"#)
+                .eval::<(f32, LuaValue, f32, LuaValue)>()?,
+            (220.0, LuaValue::Nil, 440.0, LuaValue::Nil)
+        );
+        assert!(lua.load(r#"note("a4"):to_hz(0)"#).exec().is_err());
+        assert!(lua
+            .load(r#"note("off"):to_hz()"#)
+            .eval::<LuaValue>()?
+            .is_nil());
+        let note_from_hz = |expression: &str| -> LuaResult<Vec<Option<NoteEvent>>> {
+            Ok(lua
+                .load(expression)
+                .eval::<LuaAnyUserData>()?
+                .borrow::<NoteUserData>()?
+                .notes
+                .clone())
+        };
+        assert_eq!(
+            note_from_hz(r#"note.from_hz(440)"#)?,
+            vec![Some(Note::A4.into())]
+        );
+        assert_eq!(
+            note_from_hz(r#"note.from_hz(262)"#)?,
+            vec![Some(Note::C4.into())]
+        );
+        assert_eq!(
+            note_from_hz(r#"note.from_hz(216, 432)"#)?,
+            vec![Some(Note::A3.into())]
+        );
+        assert_eq!(
+            note_from_hz(r#"note.from_hz(1)"#)?,
+            vec![Some(Note::C0.into())]
+        );
+        assert_eq!(
+            note_from_hz(r#"note.from_hz(1e6)"#)?,
+            vec![Some(Note::G10.into())]
+        );
+        assert!(note_from_hz(r#"note.from_hz(0)"#).is_err());
+        assert!(note_from_hz(r#"note.from_hz(440, -1)"#).is_err());
+        // note library still is callable
+        assert_eq!(note_from_hz(r#"note("c4")"#)?, vec![Some(Note::C4.into())]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn note_random_instruments() -> LuaResult<()> {
+        let (lua, _) = new_test_engine()?;
+
+        assert!(evaluate_note_userdata(&lua, r#"note({ key = "c4", instrument = {} })"#).is_err());
+        assert!(
+            evaluate_note_userdata(&lua, r#"note({ key = "c4", instrument = { "a" } })"#).is_err()
+        );
+        assert!(
+            evaluate_note_userdata(&lua, r#"note({ key = "c4", instrument = { -1 } })"#).is_err()
+        );
+
+        let random_instruments = |seed: u64| -> LuaResult<Vec<Option<InstrumentId>>> {
+            lua.load(format!("math.randomseed({})", seed)).exec()?;
+            let mut instruments = Vec::new();
+            for _ in 0..32 {
+                let note_event = evaluate_note_userdata(
+                    &lua,
+                    r#"note({ key = "c4", instrument = { 1, 2, 3 } })"#,
+                )?;
+                instruments.push(note_event.notes[0].as_ref().unwrap().instrument);
+            }
+            Ok(instruments)
+        };
+        let instruments = random_instruments(1234)?;
+        assert!(instruments
+            .iter()
+            .all(|i| [1, 2, 3].map(|i| Some(InstrumentId::from(i))).contains(i)));
+        assert!(instruments.iter().any(|i| *i != instruments[0]));
+        assert_eq!(random_instruments(1234)?, instruments);
+        assert_ne!(random_instruments(4321)?, instruments);
+
         Ok(())
     }
 }
@@ -1,6 +1,8 @@
 use mlua::prelude::*;
 
-use crate::Parameter;
+use super::unwrap::bad_argument_error;
+
+use crate::{Parameter, ParameterChangeEvent, ParameterId};
 
 // ---------------------------------------------------------------------------------------------
 
@@ -14,10 +16,74 @@ impl LuaUserData for ParameterUserData {}
 
 // ---------------------------------------------------------------------------------------------
 
+/// Lua Userdata impl for a ParameterChangeEvent, as returned by `event` functions.
+#[derive(Clone, Debug)]
+pub(crate) struct ParameterChangeUserData {
+    pub(crate) change: ParameterChangeEvent,
+    // original string id, when the parameter id is a hashed string id
+    id_string: Option<String>,
+}
+
+impl ParameterChangeUserData {
+    pub fn from(id: LuaValue, value: LuaValue) -> LuaResult<Self> {
+        // integer ids are passed as they are, string ids as hashes, see `Parameter::id_hash`.
+        // positive Lua integers are always smaller than `Parameter::HASHED_ID_BIT`.
+        let (parameter, id_string) = match id {
+            LuaValue::Integer(id) if id >= 0 => (ParameterId::from(id as u64), None),
+            LuaValue::String(id) => {
+                let id = id.to_string_lossy();
+                (ParameterId::from(Parameter::hash_id(&id)), Some(id))
+            }
+            _ => {
+                return Err(bad_argument_error(
+                    "parameter_change",
+                    "id",
+                    1,
+                    "expecting a parameter id string or a positive integer id",
+                ))
+            }
+        };
+        let value = match value {
+            LuaValue::Integer(value) => value as f32,
+            LuaValue::Number(value) => value as f32,
+            _ => {
+                return Err(bad_argument_error(
+                    "parameter_change",
+                    "value",
+                    2,
+                    "expecting a number value",
+                ))
+            }
+        };
+        let change = ParameterChangeEvent {
+            parameter: Some(parameter),
+            value,
+        };
+        Ok(Self { change, id_string })
+    }
+}
+
+impl LuaUserData for ParameterChangeUserData {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("parameter", |lua, this| {
+            if let Some(id) = &this.id_string {
+                id.as_str().into_lua(lua)
+            } else {
+                this.change.parameter.map(u64::from).into_lua(lua)
+            }
+        });
+        fields.add_field_method_get("value", |_lua, this| Ok(this.change.value as f64));
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+
 #[cfg(test)]
 mod test {
+    use std::rc::Rc;
+
     use super::*;
-    use crate::bindings::*;
+    use crate::{bindings::*, event::new_parameter_change, Event, NoteEvent};
 
     fn new_test_engine() -> LuaResult<Lua> {
         // create a new engine and register bindings
@@ -150,4 +216,193 @@ mod test {
             .is_ok());
         Ok(())
     }
+
+    #[test]
+    fn enum_index() -> LuaResult<()> {
+        let lua = new_test_engine()?;
+
+        assert!(lua
+            .load(r#"parameter.enum_index("name", "c", {"a", "b"})"#) // default not in values
+            .eval::<LuaValue>()
+            .is_err());
+
+        // context values are one-based indices of the selected values
+        let pattern = lua
+            .load(
+                r#"
+                local notes = { "c4", "d4", "e4" }
+                return pattern {
+                  parameter = { parameter.enum_index("mode", "b", {"a", "b", "c"}) },
+                  event = function(context)
+                    return notes[context.parameter.mode]
+                  end
+                }
+                "#,
+            )
+            .eval::<LuaValue>()?;
+        let mut pattern = pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+        let parameter = Rc::clone(&pattern.parameters()[0]);
+        assert!(parameter.borrow().is_enum_index());
+        assert_eq!(parameter.borrow().string_value(), "b");
+
+        let mut next_note = || match pattern.next().and_then(|e| e.event) {
+            Some(Event::NoteEvents(notes)) => notes[0].as_ref().map(|n| n.note),
+            _ => None,
+        };
+        assert_eq!(next_note(), Some(Note::D4));
+        parameter.borrow_mut().set_value(2.0);
+        assert_eq!(next_note(), Some(Note::E4));
+        Ok(())
+    }
+
+    #[test]
+    fn parameter_changes() -> LuaResult<()> {
+        let lua = new_test_engine()?;
+
+        assert!(lua
+            .load(r#"parameter_change(-1, 0.5)"#) // invalid id
+            .eval::<LuaValue>()
+            .is_err());
+        assert!(lua
+            .load(r#"parameter_change("cutoff", "0.5")"#) // invalid value
+            .eval::<LuaValue>()
+            .is_err());
+        assert_eq!(
+            lua.load(r#"parameter_change(2, 0.5).parameter"#)
+                .eval::<LuaInteger>()?,
+            2
+        );
+        assert_eq!(
+            lua.load(r#"parameter_change("cutoff", 0.5).parameter"#)
+                .eval::<String>()?,
+            "cutoff"
+        );
+        assert_eq!(
+            lua.load(r#"parameter_change(2, 0.5).value"#)
+                .eval::<LuaNumber>()?,
+            0.5
+        );
+        // hashed string ids never collide with integer ids
+        let cutoff = ParameterId::from(Parameter::hash_id("cutoff"));
+        assert!(u64::from(cutoff) >= Parameter::HASHED_ID_BIT);
+        let change =
+            ParameterChangeUserData::from(LuaValue::Integer(i64::MAX), LuaValue::Number(0.5))?;
+        assert_eq!(
+            change.change.parameter,
+            Some(ParameterId::from(i64::MAX as u64))
+        );
+        assert!((i64::MAX as u64) < Parameter::HASHED_ID_BIT);
+
+        // event functions can emit parameter changes along with notes
+        let pattern = lua
+            .load(
+                r#"
+                return pattern {
+                  event = function(context)
+                    if context.step == 1 then
+                      return parameter_change("cutoff", 0.5)
+                    end
+                    context.schedule(0, function(context)
+                      return parameter_change(7, context.step / 4)
+                    end)
+                    return "c4"
+                  end
+                }
+                "#,
+            )
+            .eval::<LuaValue>()?;
+        let mut pattern = pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+        let events = pattern
+            .by_ref()
+            .take(4)
+            .map(|e| (e.time, e.event))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    0,
+                    Some(Event::ParameterChangeEvent(new_parameter_change(
+                        cutoff, 0.5
+                    )))
+                ),
+                (
+                    22050,
+                    Some(Event::NoteEvents(vec![Some(NoteEvent::from(Note::C4))]))
+                ),
+                // scheduled parameter changes are emitted before the pulse's notes
+                (
+                    44100,
+                    Some(Event::ParameterChangeEvent(new_parameter_change(
+                        ParameterId::from(7),
+                        0.75
+                    )))
+                ),
+                (
+                    44100,
+                    Some(Event::NoteEvents(vec![Some(NoteEvent::from(Note::C4))]))
+                ),
+            ]
+        );
+
+        // parameter changes can be mixed with notes in tables, keeping the notes' voices
+        let pattern = lua
+            .load(
+                r#"
+                return pattern {
+                  event = function(context)
+                    if context.step == 1 then
+                      return { "c4", parameter_change(7, 0.25), "e4" }
+                    end
+                    return { parameter_change(7, 0.5), "c4", parameter_change(8, 1), "e4" }
+                  end
+                }
+                "#,
+            )
+            .eval::<LuaValue>()?;
+        let mut pattern = pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+        let events = pattern
+            .by_ref()
+            .take(5)
+            .map(|e| e.event)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                Some(Event::ParameterChangeEvent(new_parameter_change(
+                    ParameterId::from(7),
+                    0.25
+                ))),
+                Some(Event::NoteEvents(vec![
+                    Some(NoteEvent::from(Note::C4)),
+                    None,
+                    Some(NoteEvent::from(Note::E4))
+                ])),
+                Some(Event::ParameterChangeEvent(new_parameter_change(
+                    ParameterId::from(7),
+                    0.5
+                ))),
+                Some(Event::ParameterChangeEvent(new_parameter_change(
+                    ParameterId::from(8),
+                    1.0
+                ))),
+                Some(Event::NoteEvents(vec![
+                    None,
+                    Some(NoteEvent::from(Note::C4)),
+                    None,
+                    Some(NoteEvent::from(Note::E4))
+                ])),
+            ]
+        );
+        Ok(())
+    }
 }
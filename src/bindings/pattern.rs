@@ -3,20 +3,36 @@ use std::{cell::RefCell, rc::Rc};
 use mlua::prelude::*;
 
 use crate::{
-    bindings::{cycle::CycleUserData, unwrap::emitter_from_value, LuaTimeoutHook},
+    bindings::{
+        cycle::CycleUserData,
+        unwrap::{
+            accent_from_value, bad_argument_error, emitter_from_value, gate_from_value,
+            parameters_from_value, pulse_values_from_value, rhythm_from_value,
+            rhythm_repeat_count_from_value, seed_from_value, step_instruments_from_value,
+            swing_division_from_value,
+        },
+        LuaAppData, LuaTimeoutHook,
+    },
     event::InstrumentId,
-    pattern::{beat_time::BeatTimePattern, second_time::SecondTimePattern, Pattern},
-    BeatTimeBase,
+    pattern::{
+        beat_time::BeatTimePattern,
+        generic::{GenericPattern, GenericPatternTimeStep},
+        ms_time::MsTimePattern,
+        second_time::SecondTimePattern,
+        Pattern,
+    },
+    BeatTimeBase, Swing,
 };
 
 // ---------------------------------------------------------------------------------------------
 
 mod beat_time;
+mod ms_time;
 mod second_time;
 
 // ---------------------------------------------------------------------------------------------
 
-// unwrap a BeatTimePattern or SecondTimePattern from the given LuaValue,
+// unwrap a BeatTimePattern, SecondTimePattern or MsTimePattern from the given LuaValue,
 // which is expected to be a user data
 pub(crate) fn pattern_from_userdata(
     lua: &Lua,
@@ -25,27 +41,52 @@ pub(crate) fn pattern_from_userdata(
     time_base: &BeatTimeBase,
     instrument: Option<InstrumentId>,
 ) -> LuaResult<Rc<RefCell<dyn Pattern>>> {
+    // patterns capture the output of all print calls in the Lua instance
+    let debug_output = lua
+        .app_data_ref::<LuaAppData>()
+        .expect("Failed to access Lua app data")
+        .debug_output
+        .clone();
     if let Some(user_data) = value.as_userdata() {
         if user_data.is::<BeatTimePattern>() {
             // NB: take instead of cloning: pattern userdata has no other usage than being defined
             Ok(Rc::new(RefCell::new(
                 user_data
                     .take::<BeatTimePattern>()?
-                    .with_instrument(instrument),
+                    .with_instrument(instrument)
+                    .with_debug_output(debug_output),
             )))
         } else if user_data.is::<SecondTimePattern>() {
             Ok(Rc::new(RefCell::new(
                 // NB: take instead of cloning: pattern userdata has no other usage than being defined
                 user_data
                     .take::<SecondTimePattern>()?
-                    .with_instrument(instrument),
+                    .with_instrument(instrument)
+                    .with_debug_output(debug_output),
+            )))
+        } else if user_data.is::<MsTimePattern>() {
+            Ok(Rc::new(RefCell::new(
+                // NB: take instead of cloning: pattern userdata has no other usage than being defined
+                user_data
+                    .take::<MsTimePattern>()?
+                    .with_instrument(instrument)
+                    .with_debug_output(debug_output),
             )))
         } else if user_data.is::<CycleUserData>() {
             // create a default pattern from the given cycle
             Ok(Rc::new(RefCell::new(
                 BeatTimePattern::new(*time_base, crate::BeatTimeStep::Bar(1.0))
                     .with_instrument(instrument)
-                    .trigger_dyn(emitter_from_value(lua, timeout_hook, value, time_base)?),
+                    .with_debug_output(debug_output)
+                    .trigger_dyn(emitter_from_value(
+                        lua,
+                        timeout_hook,
+                        value,
+                        time_base,
+                        None,
+                        None,
+                        None,
+                    )?),
             )))
         } else {
             Err(LuaError::FromLuaConversionError {
@@ -66,6 +107,94 @@ pub(crate) fn pattern_from_userdata(
     }
 }
 
+// apply all time unit independent properties of a `pattern{}` table to the given pattern
+fn pattern_with_table_properties<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep>(
+    lua: &Lua,
+    timeout_hook: &LuaTimeoutHook,
+    time_base: &BeatTimeBase,
+    table: &LuaTable,
+    mut pattern: GenericPattern<Step, Offset>,
+) -> LuaResult<GenericPattern<Step, Offset>> {
+    // parameter
+    if table.contains_key("parameter")? {
+        let value = table.get::<LuaTable>("parameter")?;
+        let parameters = parameters_from_value(lua, &value)?;
+        pattern = pattern.with_parameters(parameters);
+    }
+    // seed
+    let mut seed = None;
+    if table.contains_key("seed")? {
+        let value = table.get::<LuaValue>("seed")?;
+        seed = Some(seed_from_value(&value)?);
+    }
+    // pulse
+    let mut pulses = None;
+    if table.contains_key("pulse")? {
+        let value = table.get::<LuaValue>("pulse")?;
+        pulses = pulse_values_from_value(&value)?;
+        let rhythm = rhythm_from_value(lua, timeout_hook, &value, time_base, seed)?;
+        pattern = pattern.with_rhythm_dyn(rhythm);
+    }
+    // gate
+    if table.contains_key("gate")? {
+        let value = table.get::<LuaValue>("gate")?;
+        let gate = gate_from_value(lua, timeout_hook, &value, time_base, seed)?;
+        pattern = pattern.with_gate_dyn(gate);
+    }
+    // repeat
+    if table.contains_key("repeats")? {
+        let value = table.get::<LuaValue>("repeats")?;
+        let repeat = rhythm_repeat_count_from_value(&value)?;
+        pattern = pattern.with_repeat(repeat);
+    }
+    // swing
+    if table.contains_key("swing")? {
+        let swing = table.get::<f32>("swing")?;
+        if (0.0..=1.0).contains(&swing) {
+            // map straight - triplet feel to swing amounts in step lengths
+            pattern = pattern.with_swing(Swing::new(swing / 3.0));
+        } else {
+            return Err(bad_argument_error(
+                "pattern",
+                "swing",
+                1,
+                "swing must be in range [0 - 1]",
+            ));
+        }
+    }
+    // swing division
+    if table.contains_key("swing_division")? {
+        let value = table.get::<LuaValue>("swing_division")?;
+        pattern = pattern.with_swing_division(swing_division_from_value(&value)?);
+    }
+    // accent
+    if table.contains_key("accent")? {
+        let value = table.get::<LuaValue>("accent")?;
+        pattern = pattern.with_accent(accent_from_value(&value)?);
+    }
+    // instrument
+    let mut instruments = None;
+    if table.contains_key("instrument")? {
+        let value = table.get::<LuaValue>("instrument")?;
+        instruments = Some(step_instruments_from_value(lua, &value)?);
+    }
+    // event
+    if table.contains_key("event")? {
+        let value = table.get::<LuaValue>("event")?;
+        let emitter = emitter_from_value(
+            lua,
+            timeout_hook,
+            &value,
+            time_base,
+            pulses.as_deref(),
+            instruments.as_deref(),
+            seed,
+        )?;
+        pattern = pattern.trigger_dyn(emitter);
+    }
+    Ok(pattern)
+}
+
 // --------------------------------------------------------------------------------------------------
 
 #[cfg(test)]
@@ -74,8 +203,11 @@ mod test {
         bindings::*,
         event::{Event, NoteEvent},
         note::Note,
-        pattern::{beat_time::BeatTimePattern, second_time::SecondTimePattern, PatternEvent},
-        time::BeatTimeStep,
+        pattern::{
+            beat_time::BeatTimePattern, ms_time::MsTimePattern, second_time::SecondTimePattern,
+            PatternEvent,
+        },
+        time::{BeatTimeStep, MsTimeStep},
         RhythmEvent,
     };
 
@@ -158,14 +290,245 @@ mod test {
                     note: Note::C6,
                     volume: 1.0,
                     panning: 0.0,
-                    delay: 0.0
+                    delay: 0.0,
+                    speed: None,
+                    ratchet: None
                 })])),
-                duration: 11025
+                duration: 11025,
+                note_duration: None
             })
         );
         Ok(())
     }
 
+    #[test]
+    fn beat_time_chords() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // nested tables in event arrays are chords
+        let beat_time_pattern = lua
+            .load(
+                r#"
+                pattern {
+                    event = { {"c4", "e4", "g4"}, "d4" }
+                }
+            "#,
+            )
+            .eval::<LuaValue>()
+            .unwrap();
+        let mut beat_time_pattern = beat_time_pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+
+        let events = beat_time_pattern
+            .by_ref()
+            .take(2)
+            .map(|e| e.event)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                Some(Event::NoteEvents(vec![
+                    Some(NoteEvent::from(Note::C4)),
+                    Some(NoteEvent::from(Note::E4)),
+                    Some(NoteEvent::from(Note::G4)),
+                ])),
+                Some(Event::NoteEvents(vec![
+                    Some(NoteEvent::from(Note::D4)),
+                    Some(NoteEvent::from(Note::OFF)),
+                    Some(NoteEvent::from(Note::OFF)),
+                ])),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn beat_time_instruments() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        let note_instruments = |script: &str| -> LuaResult<Vec<Vec<Option<usize>>>> {
+            let pattern = lua.load(script).eval::<LuaValue>()?;
+            let mut pattern = pattern
+                .as_userdata()
+                .unwrap()
+                .borrow_mut::<BeatTimePattern>()?;
+            Ok(pattern
+                .by_ref()
+                .take(4)
+                .map(|e| match e.event {
+                    Some(Event::NoteEvents(notes)) => notes
+                        .iter()
+                        .flatten()
+                        .filter(|n| n.note.is_note_on())
+                        .map(|n| n.instrument.map(usize::from))
+                        .collect(),
+                    _ => vec![],
+                })
+                .collect())
+        };
+
+        // alternating instruments per step
+        assert_eq!(
+            note_instruments(r#"pattern { event = {"c4", "d4"}, instrument = {1, 2} }"#)?,
+            vec![vec![Some(1)], vec![Some(2)], vec![Some(1)], vec![Some(2)]]
+        );
+        // instruments and events of different lengths
+        assert_eq!(
+            note_instruments(r#"pattern { event = "c4", instrument = {1, 2} }"#)?,
+            vec![vec![Some(1)], vec![Some(2)], vec![Some(1)], vec![Some(2)]]
+        );
+        assert_eq!(
+            note_instruments(r#"pattern { event = {"c4", "d4", "e4"}, instrument = {1, 2} }"#)?,
+            vec![vec![Some(1)], vec![Some(2)], vec![Some(1)], vec![Some(2)]]
+        );
+        // inline instruments and chords
+        assert_eq!(
+            note_instruments(
+                r#"pattern { event = {"c4 #5", {key = "d4", instrument = 6}, "e4'maj"}, instrument = 1 }"#
+            )?,
+            vec![
+                vec![Some(5)],
+                vec![Some(6)],
+                vec![Some(1), Some(1), Some(1)],
+                vec![Some(5)]
+            ]
+        );
+        // instruments can only be applied to fixed events
+        assert!(note_instruments(
+            r#"pattern { event = function(context) return "c4" end, instrument = 1 }"#
+        )
+        .is_err());
+        assert!(note_instruments(r#"pattern { event = "c4", instrument = {} }"#).is_err());
+        assert!(note_instruments(r#"pattern { event = "c4", instrument = -1 }"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn beat_time_swing() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        let note_times = |script: &str| -> LuaResult<Vec<Vec<u64>>> {
+            let pattern = lua.load(script).eval::<LuaValue>()?;
+            let mut pattern = pattern
+                .as_userdata()
+                .unwrap()
+                .borrow_mut::<BeatTimePattern>()?;
+            Ok(pattern
+                .by_ref()
+                .take(4)
+                .map(|e| match &e.event {
+                    Some(Event::NoteEvents(notes)) => notes
+                        .iter()
+                        .flatten()
+                        .map(|n| e.note_event_time(n))
+                        .collect(),
+                    _ => vec![],
+                })
+                .collect())
+        };
+
+        // straight
+        assert_eq!(
+            note_times(r#"pattern { unit = "1/8", event = "c4", swing = 0 }"#)?,
+            vec![vec![0], vec![11025], vec![22050], vec![33075]]
+        );
+        // triplet feel: offbeats get delayed by a third of a step
+        assert_eq!(
+            note_times(r#"pattern { unit = "1/8", event = "c4", swing = 1 }"#)?,
+            vec![vec![0], vec![11025 + 3675], vec![22050], vec![33075 + 3675]]
+        );
+        // composes with note delays
+        assert_eq!(
+            note_times(r#"pattern { unit = "1/8", event = {"c4 d0.25", "c4 d0.25"}, swing = 1 }"#)?,
+            vec![
+                vec![2756],
+                vec![11025 + 2756 + 3675],
+                vec![22050 + 2756],
+                vec![33075 + 2756 + 3675]
+            ]
+        );
+        // step length is not affected
+        let pattern = lua
+            .load(r#"pattern { unit = "1/8", event = "c4", swing = 0.5 }"#)
+            .eval::<LuaValue>()?;
+        let pattern = pattern.as_userdata().unwrap().borrow::<BeatTimePattern>()?;
+        assert_eq!(pattern.step_length(), 11025.0);
+        // swing division: swings eighths in a pattern which runs at 1/16
+        assert_eq!(
+            note_times(
+                r#"pattern { unit = "1/16", event = "c4", swing = 1, swing_division = "1/8" }"#
+            )?,
+            vec![vec![0], vec![5512], vec![11025 + 3675], vec![16537]]
+        );
+        assert!(
+            note_times(r#"pattern { event = "c4", swing = 1, swing_division = "1/7" }"#).is_err()
+        );
+        // invalid swing amounts
+        assert!(note_times(r#"pattern { event = "c4", swing = -0.5 }"#).is_err());
+        assert!(note_times(r#"pattern { event = "c4", swing = 1.5 }"#).is_err());
+        assert!(note_times(r#"pattern { event = "c4", swing = "a lot" }"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn beat_time_accent() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        let note_volumes = |script: &str| -> LuaResult<Vec<f32>> {
+            let pattern = lua.load(script).eval::<LuaValue>()?;
+            let mut pattern = pattern
+                .as_userdata()
+                .unwrap()
+                .borrow_mut::<BeatTimePattern>()?;
+            Ok(pattern
+                .by_ref()
+                .take(8)
+                .flat_map(|e| match e.event {
+                    Some(Event::NoteEvents(notes)) => {
+                        notes.into_iter().flatten().map(|n| n.volume).collect()
+                    }
+                    _ => vec![],
+                })
+                .collect())
+        };
+
+        // every 4th step's volume is boosted by the amount
+        assert_eq!(
+            note_volumes(
+                r#"pattern { unit = "1/16", event = "c4 v0.5", accent = { every = 4, amount = 1.5 } }"#
+            )?,
+            vec![0.75, 0.5, 0.5, 0.5, 0.75, 0.5, 0.5, 0.5]
+        );
+        // composes with swing and volumes from callbacks
+        assert_eq!(
+            note_volumes(
+                r#"pattern {
+                  unit = "1/16", swing = 1, accent = { every = 2, amount = 2 },
+                  event = function(context) return { key = "c4", volume = 0.25 } end
+                }"#
+            )?,
+            vec![0.5, 0.25, 0.5, 0.25, 0.5, 0.25, 0.5, 0.25]
+        );
+        // invalid accents
+        assert!(note_volumes(r#"pattern { event = "c4", accent = 4 }"#).is_err());
+        assert!(
+            note_volumes(r#"pattern { event = "c4", accent = { every = 0, amount = 1 } }"#)
+                .is_err()
+        );
+        assert!(
+            note_volumes(r#"pattern { event = "c4", accent = { every = 1.5, amount = 1 } }"#)
+                .is_err()
+        );
+        assert!(
+            note_volumes(r#"pattern { event = "c4", accent = { every = 4, amount = -1 } }"#)
+                .is_err()
+        );
+        assert!(note_volumes(r#"pattern { event = "c4", accent = { every = 4 } }"#).is_err());
+        Ok(())
+    }
+
     #[test]
     fn beat_time_callbacks() -> LuaResult<()> {
         let (lua, _) = new_test_engine(120.0, 4, 44100)?;
@@ -176,6 +539,8 @@ mod test {
             volume: 0.5,
             panning: 0.0,
             delay: 0.25,
+            speed: None,
+            ratchet: None,
         })]);
 
         // BeatTimePattern function Context
@@ -235,6 +600,7 @@ mod test {
                       local step = 1 
                       local function validate_context(context) 
                         assert(context.playback == "running")
+                        assert(context.running and not context.seeking)
                         assert(context.beats_per_min == 120)
                         assert(context.beats_per_bar == 4)
                         assert(context.samples_per_sec == 44100)
@@ -277,9 +643,12 @@ mod test {
                     note: Note::C4,
                     volume: 1.0,
                     panning: 0.0,
-                    delay: 0.0
+                    delay: 0.0,
+                    speed: None,
+                    ratchet: None
                 })])),
                 duration: 11025,
+                note_duration: None,
             })
         );
 
@@ -291,6 +660,430 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn beat_time_output_level() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // duck notes with the pattern's output level
+        let beat_time_pattern = lua
+            .load(
+                r#"
+                return pattern {
+                    unit = "1/4",
+                    event = function(context)
+                      return { key = "c4", volume = 1.0 - context.level }
+                    end
+                }
+            "#,
+            )
+            .eval::<LuaValue>()
+            .unwrap();
+
+        let mut beat_time_pattern = beat_time_pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+
+        let volume = |event: Option<PatternEvent>| match event.and_then(|e| e.event) {
+            Some(Event::NoteEvents(notes)) => notes[0].as_ref().map(|n| n.volume),
+            _ => None,
+        };
+        // without a player, the level is zero
+        assert_eq!(volume(beat_time_pattern.next()), Some(1.0));
+        // loud notes got played: callbacks see the fed back level
+        beat_time_pattern.set_output_level(0.75);
+        assert_eq!(volume(beat_time_pattern.next()), Some(0.25));
+        // levels are clamped
+        beat_time_pattern.set_output_level(2.0);
+        assert_eq!(volume(beat_time_pattern.next()), Some(0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn beat_time_ratchets() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        assert!(lua
+            .load(r#"note({ key = "c4", ratchet = 0 })"#)
+            .eval::<LuaValue>()
+            .is_err());
+        assert!(lua
+            .load(r#"note({ key = "c4", ratchet = 1.5 })"#)
+            .eval::<LuaValue>()
+            .is_err());
+
+        // ratchets retrigger notes within a step, starting at the note's delay
+        let beat_time_pattern = lua
+            .load(
+                r#"
+                return pattern {
+                    unit = "1/4",
+                    event = function(context)
+                      if context.step == 1 then
+                        return { key = "c4", ratchet = 3 }
+                      end
+                      return { "e4", { key = "g4", delay = 0.25, ratchet = 2 } }
+                    end
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+
+        let mut beat_time_pattern = beat_time_pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+
+        let notes = |event: PatternEvent| match &event.event {
+            Some(Event::NoteEvents(notes)) => notes
+                .iter()
+                .flatten()
+                .map(|n| {
+                    assert_eq!(n.ratchet, None);
+                    (event.note_event_time(n), n.note)
+                })
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        };
+        let events = beat_time_pattern
+            .by_ref()
+            .take(5)
+            .flat_map(notes)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                (0, Note::C4),
+                (7350, Note::C4),
+                (14700, Note::C4),
+                (22050, Note::E4),
+                (27562, Note::G4),
+                (35831, Note::G4),
+            ]
+        );
+        // the next step starts after the last ratchet
+        assert_eq!(beat_time_pattern.next().map(|e| e.time), Some(44100),);
+        Ok(())
+    }
+
+    #[test]
+    fn beat_time_playing_notes() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // harmonize with the slot's playing notes
+        let beat_time_pattern = lua
+            .load(
+                r#"
+                return pattern {
+                    unit = "1/4",
+                    event = function(context)
+                      local notes = context.playing_notes
+                      if #notes == 0 then
+                        return "c4"
+                      end
+                      return note_number(notes[#notes].key) + 4
+                    end
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+
+        let mut beat_time_pattern = beat_time_pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+
+        let note = |event: Option<PatternEvent>| match event.and_then(|e| e.event) {
+            Some(Event::NoteEvents(notes)) => notes[0].as_ref().map(|n| n.note),
+            _ => None,
+        };
+        // without a player, no notes are playing
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::C4));
+        // the triggered note got played: callbacks see the fed back playing notes
+        beat_time_pattern.set_playing_notes(&[NoteEvent::from(Note::C4)]);
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::E4));
+        beat_time_pattern
+            .set_playing_notes(&[NoteEvent::from(Note::C4), NoteEvent::from(Note::E4)]);
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::Gs4));
+        // stopped notes are no longer visible
+        beat_time_pattern.set_playing_notes(&[]);
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::C4));
+        Ok(())
+    }
+
+    #[test]
+    fn beat_time_pulses() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // fixed pulses are accessible in events as flattened array
+        let beat_time_pattern = lua
+            .load(
+                r#"
+                return pattern {
+                    unit = "1/4",
+                    pulse = {1, 0, {1, 0.5}, true},
+                    event = function(context)
+                      local pulses = context.pulses
+                      assert(#pulses == 5)
+                      assert(pulses[1] == 1 and pulses[2] == 0)
+                      assert(pulses[3] == 1 and pulses[4] == 0.5 and pulses[5] == 1)
+                      return pulses[context.pulse_step] == 1 and "c4" or "e4"
+                    end
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+
+        let mut beat_time_pattern = beat_time_pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+
+        let note = |event: Option<PatternEvent>| match event.and_then(|e| e.event) {
+            Some(Event::NoteEvents(notes)) => notes[0].as_ref().map(|n| n.note),
+            _ => None,
+        };
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::C4));
+        assert_eq!(note(beat_time_pattern.next()), None);
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::C4));
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::E4));
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::C4));
+
+        // dynamic pulses have no pulse array
+        let beat_time_pattern = lua
+            .load(
+                r#"
+                return pattern {
+                    pulse = function(_context) return 1 end,
+                    event = function(context)
+                      return context.pulses == nil and "e4" or "c4"
+                    end
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+
+        let mut beat_time_pattern = beat_time_pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::E4));
+        Ok(())
+    }
+
+    #[test]
+    fn beat_time_gate_hold() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // hold gate values hold notes for the given number of pulses
+        let beat_time_pattern = lua
+            .load(
+                r#"
+                return pattern {
+                    unit = "1/4",
+                    pulse = {1, 1, 0, 0, 1},
+                    gate = function(context)
+                      if context.pulse_step == 1 then
+                        return { hold = 3 }
+                      end
+                      return context.pulse_value > 0 and context.pulse_step ~= 2
+                    end,
+                    event = {"c4", "e4"}
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+
+        let mut beat_time_pattern = beat_time_pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+
+        let note = |event: PatternEvent| match event.event {
+            Some(Event::NoteEvents(notes)) => (event.time, notes[0].as_ref().map(|n| n.note)),
+            _ => (event.time, None),
+        };
+        let events = beat_time_pattern
+            .by_ref()
+            .take(6)
+            .map(note)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                (0, Some(Note::C4)),
+                (22050, None),
+                (44100, None),
+                (66150, Some(Note::OFF)),
+                (88200, Some(Note::E4)),
+                (110250, Some(Note::C4)),
+            ]
+        );
+
+        let new_pattern = |gate: &str, pulse: &str| -> LuaResult<BeatTimePattern> {
+            lua.load(format!(
+                r#"
+                return pattern {{
+                    unit = "1/4",
+                    pulse = {pulse},
+                    repeats = false,
+                    gate = function(context)
+                      {gate}
+                    end,
+                    event = {{"c4", "e4"}}
+                }}
+                "#
+            ))
+            .eval::<LuaValue>()?
+            .as_userdata()
+            .unwrap()
+            .take::<BeatTimePattern>()
+        };
+        let run = |pattern: BeatTimePattern| pattern.map(note).collect::<Vec<_>>();
+
+        // plain numbers trigger, but don't hold
+        assert_eq!(
+            run(new_pattern("return context.pulse_value * 2", "{1, 0, 1}")?),
+            vec![(0, Some(Note::C4)), (22050, None), (44100, Some(Note::E4))]
+        );
+
+        // new notes replace held notes: they get stopped by the player's new note action
+        assert_eq!(
+            run(new_pattern(
+                "return context.pulse_step == 1 and { hold = 3 } or true",
+                "{1, 1, 1, 1}"
+            )?),
+            vec![
+                (0, Some(Note::C4)),
+                (22050, Some(Note::E4)),
+                (44100, Some(Note::C4)),
+                (66150, Some(Note::E4)),
+            ]
+        );
+
+        // notes which still are held at the end of the rhythm get stopped
+        assert_eq!(
+            run(new_pattern(
+                "return context.pulse_value > 0 and { hold = 4 } or false",
+                "{1, 0}"
+            )?),
+            vec![(0, Some(Note::C4)), (22050, None), (44100, Some(Note::OFF))]
+        );
+
+        // invalid hold values are errors
+        clear_lua_callback_errors();
+        assert_eq!(
+            run(new_pattern("return { hold = 0 }", "{1}")?),
+            vec![(0, None)]
+        );
+        assert!(has_lua_callback_errors().is_some());
+        clear_lua_callback_errors();
+        Ok(())
+    }
+
+    #[test]
+    fn beat_time_gate_note_duration() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // held notes carry their musical length, which differs from the step length
+        let beat_time_pattern = lua
+            .load(
+                r#"
+                return pattern {
+                    unit = "1/8",
+                    pulse = {1, 0, 0, 1},
+                    gate = function(context)
+                      if context.pulse_step == 1 then
+                        return { hold = 3 }
+                      end
+                      return context.pulse_value > 0
+                    end,
+                    event = {"c4", "e4"}
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+
+        let mut beat_time_pattern = beat_time_pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+
+        let durations = |event: PatternEvent| {
+            let note_duration = event.note_duration();
+            let note = match event.event {
+                Some(Event::NoteEvents(notes)) => notes[0].as_ref().map(|n| n.note),
+                _ => None,
+            };
+            (event.time, note, event.duration, note_duration)
+        };
+        let events = beat_time_pattern
+            .by_ref()
+            .take(5)
+            .map(durations)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                (0, Some(Note::C4), 11025, Some(33075)),
+                (11025, None, 11025, None),
+                (22050, None, 11025, None),
+                (33075, Some(Note::OFF), 11025, None),
+                (33075, Some(Note::E4), 11025, None),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn beat_time_seek_to_step() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(120.0, 4, 44100)?;
+
+        // seeking advances stateful generators
+        let beat_time_pattern = lua
+            .load(
+                r#"
+                seeking_steps = 0
+                return pattern {
+                    unit = "1/4",
+                    event = function(_init_context)
+                      local notes, step = {"c4", "d4", "e4", "f4"}, 0
+                      return function(context)
+                        assert(context.seeking == (context.playback == "seeking"))
+                        assert(context.running == (context.playback == "running"))
+                        if context.seeking then
+                          seeking_steps = seeking_steps + 1
+                        end
+                        step = step + 1
+                        return notes[(step - 1) % #notes + 1]
+                      end
+                    end
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+
+        let mut beat_time_pattern = beat_time_pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<BeatTimePattern>()?;
+
+        let note = |event: Option<PatternEvent>| match event.and_then(|e| e.event) {
+            Some(Event::NoteEvents(notes)) => notes[0].as_ref().map(|n| n.note),
+            _ => None,
+        };
+        let seeking_steps = || lua.globals().get::<LuaInteger>("seeking_steps");
+        beat_time_pattern.seek_to_step(2);
+        // pulses which end before the seek target get skipped in seeking mode
+        assert_eq!(seeking_steps()?, 1);
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::E4));
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::F4));
+        beat_time_pattern.seek_to_step(1);
+        assert_eq!(seeking_steps()?, 1);
+        assert_eq!(note(beat_time_pattern.next()), Some(Note::D4));
+        Ok(())
+    }
+
     #[test]
     fn second_time() -> LuaResult<()> {
         let (lua, _) = new_test_engine(130.0, 8, 48000)?;
@@ -354,9 +1147,11 @@ mod test {
             volume: 0.25,
             panning: 0.5,
             delay: 0.75,
+            speed: None,
+            ratchet: None,
         })]);
 
-        // SecondTimePattern function Context
+        // MsTimePattern function Context
         let second_time_rhythm = lua
             .load(
                 r#"
@@ -376,6 +1171,70 @@ mod test {
             )
             .eval::<LuaValue>()
             .unwrap();
+        let second_time_pattern = second_time_rhythm
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<MsTimePattern>();
+        assert!(second_time_pattern.is_ok());
+
+        let mut second_time_pattern = second_time_pattern.unwrap();
+        second_time_pattern.set_trigger_event(&trigger_event);
+
+        let event = second_time_pattern.next();
+        assert_eq!(
+            event,
+            Some(PatternEvent {
+                time: 0,
+                event: Some(Event::NoteEvents(vec![Some(NoteEvent {
+                    instrument: None,
+                    note: Note::C4,
+                    volume: 1.0,
+                    panning: 0.0,
+                    delay: 0.0,
+                    speed: None,
+                    ratchet: None
+                })],),),
+                duration: 48,
+                note_duration: None
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn second_time_seconds_callbacks() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(130.0, 8, 48000)?;
+
+        let trigger_event = Event::NoteEvents(vec![Some(NoteEvent {
+            note: Note::C4,
+            instrument: None,
+            volume: 0.25,
+            panning: 0.5,
+            delay: 0.75,
+            speed: None,
+            ratchet: None,
+        })]);
+
+        // SecondTimePattern function Context
+        let second_time_rhythm = lua
+            .load(
+                r#"
+                return pattern {
+                    unit = "seconds",
+                    pulse = function(context)
+                      return 1
+                    end,
+                    gate = function(context)
+                      return true
+                    end,
+                    event = function(context)
+                      return "c4"
+                    end
+                }
+            "#,
+            )
+            .eval::<LuaValue>()
+            .unwrap();
         let second_time_pattern = second_time_rhythm
             .as_userdata()
             .unwrap()
@@ -395,11 +1254,97 @@ mod test {
                     note: Note::C4,
                     volume: 1.0,
                     panning: 0.0,
-                    delay: 0.0
+                    delay: 0.0,
+                    speed: None,
+                    ratchet: None
                 })],),),
-                duration: 48
+                duration: 48000,
+                note_duration: None
             })
         );
         Ok(())
     }
+
+    #[test]
+    fn table_properties() -> LuaResult<()> {
+        let (lua, timeout_hook) = new_test_engine(120.0, 4, 48000)?;
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 48000,
+        };
+
+        // unit independent properties get applied to all pattern types
+        for unit in ["beats", "seconds", "ms"] {
+            let value = lua
+                .load(format!(
+                    r#"
+                    pattern {{
+                        unit = "{unit}",
+                        pulse = {{1, 0, 1}},
+                        repeats = 0,
+                        instrument = {{1, 2}},
+                        event = {{"c4", "d4"}}
+                    }}
+                "#
+                ))
+                .eval::<LuaValue>()?;
+            let pattern =
+                super::pattern_from_userdata(&lua, &timeout_hook, &value, &time_base, None)?;
+            let mut pattern = pattern.borrow_mut();
+            let notes = (&mut *pattern)
+                .filter_map(|event| match event.event {
+                    Some(Event::NoteEvents(notes)) => notes[0]
+                        .as_ref()
+                        .map(|note| (note.note, note.instrument.map(usize::from))),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                notes,
+                vec![(Note::C4, Some(1)), (Note::D4, Some(2))],
+                "unexpected events for unit '{unit}'"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn ms_time() -> LuaResult<()> {
+        let (lua, _) = new_test_engine(130.0, 8, 48000)?;
+
+        // MsTimePattern
+        let ms_time_pattern = lua
+            .load(
+                r#"
+                pattern {
+                    unit = "ms",
+                    resolution = 250,
+                    offset = 2,
+                    event = {"c5", "d5"}
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+        let mut ms_time_pattern = ms_time_pattern
+            .as_userdata()
+            .unwrap()
+            .borrow_mut::<MsTimePattern>()?;
+        assert_eq!(ms_time_pattern.step(), MsTimeStep(250.0));
+        assert_eq!(ms_time_pattern.offset(), MsTimeStep(500.0));
+        assert_eq!(ms_time_pattern.step_length(), 12000.0);
+        let events = ms_time_pattern
+            .by_ref()
+            .take(2)
+            .map(|event| (event.time, event.duration))
+            .collect::<Vec<_>>();
+        assert_eq!(events, vec![(24000, 12000), (36000, 12000)]);
+
+        // invalid units
+        assert!(lua
+            .load(r#"pattern { unit = "us", event = "c4" }"#)
+            .eval::<LuaValue>()
+            .is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,65 @@
+use mlua::prelude::*;
+
+use super::{
+    super::{unwrap::bad_argument_error, LuaTimeoutHook},
+    pattern_with_table_properties,
+};
+
+use crate::prelude::*;
+
+// -------------------------------------------------------------------------------------------------
+
+impl LuaUserData for MsTimePattern {
+    // MsTimePattern is only passed through ATM
+}
+
+impl MsTimePattern {
+    // create a MsTimePattern from the given Lua table value
+    pub(crate) fn from_table(
+        lua: &Lua,
+        timeout_hook: &LuaTimeoutHook,
+        time_base: &BeatTimeBase,
+        table: &LuaTable,
+    ) -> LuaResult<MsTimePattern> {
+        // resolution
+        let mut resolution = 1.0;
+        if table.contains_key("resolution")? {
+            resolution = table.get::<f64>("resolution")?;
+            if resolution <= 0.0 {
+                return Err(bad_argument_error(
+                    "rhythm",
+                    "resolution",
+                    1,
+                    "resolution must be > 0",
+                ));
+            }
+        }
+        // unit
+        if table.contains_key("unit")? {
+            let unit = table.get::<String>("unit")?;
+            match unit.as_str() {
+                "ms" => (),
+                _ => return Err(bad_argument_error("pattern", "unit", 1, 
+                "expected one of 'ms|seconds' or 'bars|beats' or '1/1|1/2|1/4|1/8|1/16|1/32|1/64"))
+            }
+        }
+        // create a new MsTimePattern with the given time base and step
+        let mut pattern = MsTimePattern::new(*time_base, MsTimeStep(resolution));
+        // offset
+        if table.contains_key("offset")? {
+            let offset = table.get::<f64>("offset")?;
+            if offset >= 0.0 {
+                pattern = pattern.with_offset(MsTimeStep(offset * resolution));
+            } else {
+                return Err(bad_argument_error(
+                    "pattern",
+                    "offset",
+                    1,
+                    "offset must be a number >= 0",
+                ));
+            }
+        }
+        // all other properties
+        pattern_with_table_properties(lua, timeout_hook, time_base, table, pattern)
+    }
+}
@@ -1,11 +1,8 @@
 use mlua::prelude::*;
 
-use super::super::{
-    unwrap::{
-        bad_argument_error, emitter_from_value, gate_from_value, parameters_from_value,
-        rhythm_from_value, rhythm_repeat_count_from_value,
-    },
-    LuaTimeoutHook,
+use super::{
+    super::{unwrap::bad_argument_error, LuaTimeoutHook},
+    pattern_with_table_properties,
 };
 
 use crate::prelude::*;
@@ -42,7 +39,6 @@ impl SecondTimePattern {
             let unit = table.get::<String>("unit")?;
             match unit.as_str() {
                 "seconds" => (),
-                "ms" => resolution /= 1000.0,
                 _ => return Err(bad_argument_error("pattern", "unit", 1, 
                 "expected one of 'ms|seconds' or 'bars|beats' or '1/1|1/2|1/4|1/8|1/16|1/32|1/64"))
             }
@@ -63,36 +59,7 @@ impl SecondTimePattern {
                 ));
             }
         }
-        // parameter
-        if table.contains_key("parameter")? {
-            let value = table.get::<LuaTable>("parameter")?;
-            let parameters = parameters_from_value(lua, &value)?;
-            pattern = pattern.with_parameters(parameters);
-        }
-        // pulse
-        if table.contains_key("pulse")? {
-            let value = table.get::<LuaValue>("pulse")?;
-            let rhythm = rhythm_from_value(lua, timeout_hook, &value, time_base)?;
-            pattern = pattern.with_rhythm_dyn(rhythm);
-        }
-        // gate
-        if table.contains_key("gate")? {
-            let value = table.get::<LuaValue>("gate")?;
-            let gate = gate_from_value(lua, timeout_hook, &value, time_base)?;
-            pattern = pattern.with_gate_dyn(gate);
-        }
-        // repeat
-        if table.contains_key("repeats")? {
-            let value = table.get::<LuaValue>("repeats")?;
-            let repeat = rhythm_repeat_count_from_value(&value)?;
-            pattern = pattern.with_repeat(repeat);
-        }
-        // event
-        if table.contains_key("event")? {
-            let value: LuaValue = table.get::<LuaValue>("event")?;
-            let emitter = emitter_from_value(lua, timeout_hook, &value, time_base)?;
-            pattern = pattern.trigger_dyn(emitter);
-        }
-        Ok(pattern)
+        // all other properties
+        pattern_with_table_properties(lua, timeout_hook, time_base, table, pattern)
     }
 }
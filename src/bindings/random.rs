@@ -0,0 +1,217 @@
+use std::f64::consts::PI;
+
+use mlua::prelude::*;
+use rand::Rng;
+
+use super::{unwrap::bad_argument_error, LuaAppData};
+
+// ---------------------------------------------------------------------------------------------
+
+/// Register the `random` library, which generates non uniform distributed random numbers
+/// with the Lua engine's global, seeded random number generator.
+pub(crate) fn register_random_bindings(lua: &mut Lua) -> LuaResult<()> {
+    let random = lua.create_table()?;
+
+    // function random.gauss(mean?, stddev?)
+    random.raw_set(
+        "gauss",
+        lua.create_function(|lua, args: (Option<LuaNumber>, Option<LuaNumber>)| {
+            let mean = args.0.unwrap_or(0.0);
+            let stddev = args.1.unwrap_or(1.0);
+            if !stddev.is_finite() || stddev < 0.0 {
+                return Err(bad_argument_error(
+                    "random.gauss",
+                    "stddev",
+                    2,
+                    "standard deviation must be a finite number >= 0",
+                ));
+            }
+            let rand = &mut lua
+                .app_data_mut::<LuaAppData>()
+                .expect("Failed to access Lua app data")
+                .rand_rgn;
+            Ok(gauss(rand, mean, stddev))
+        })?,
+    )?;
+
+    // function random.exp(lambda?)
+    random.raw_set(
+        "exp",
+        lua.create_function(|lua, lambda: Option<LuaNumber>| -> LuaResult<LuaNumber> {
+            let lambda = lambda.unwrap_or(1.0);
+            if !lambda.is_finite() || lambda <= 0.0 {
+                return Err(bad_argument_error(
+                    "random.exp",
+                    "lambda",
+                    1,
+                    "rate must be a finite number > 0",
+                ));
+            }
+            let rand = &mut lua
+                .app_data_mut::<LuaAppData>()
+                .expect("Failed to access Lua app data")
+                .rand_rgn;
+            Ok(exponential(rand, lambda))
+        })?,
+    )?;
+
+    // function random.perlin(x)
+    random.raw_set(
+        "perlin",
+        lua.create_function(|_lua, x: LuaNumber| -> LuaResult<LuaNumber> { Ok(perlin(x)) })?,
+    )?;
+
+    lua.globals().raw_set("random", random)
+}
+
+// ---------------------------------------------------------------------------------------------
+
+/// Normal distributed random number with the given mean and standard deviation,
+/// generated via the Box-Muller transform.
+pub(crate) fn gauss<R: Rng>(rand: &mut R, mean: f64, stddev: f64) -> f64 {
+    // avoid ln(0) by using the range (0, 1]
+    let u1 = 1.0 - rand.random::<f64>();
+    let u2 = rand.random::<f64>();
+    let z = (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos();
+    mean + z * stddev
+}
+
+/// Exponential distributed random number with the given rate (lambda), generated via
+/// inverse transform sampling. The distribution's mean is `1 / lambda`.
+pub(crate) fn exponential<R: Rng>(rand: &mut R, lambda: f64) -> f64 {
+    // avoid ln(0) by using the range (0, 1]
+    let u = 1.0 - rand.random::<f64>();
+    -u.ln() / lambda
+}
+
+/// One dimensional Perlin gradient noise at the given position in range \[-1 - 1\].
+///
+/// Noise values smoothly change with the position and are 0 at integer positions. The noise
+/// is deterministic: it does not depend on any random seed.
+pub(crate) fn perlin(x: f64) -> f64 {
+    // pseudo random gradient in range [-1, 1] for the given lattice point
+    fn gradient(index: i64) -> f64 {
+        // splitmix64 finalizer
+        let mut hash = (index as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        hash ^= hash >> 31;
+        (hash >> 11) as f64 / (1_u64 << 53) as f64 * 2.0 - 1.0
+    }
+    // quintic fade curve: 6t^5 - 15t^4 + 10t^3
+    fn fade(t: f64) -> f64 {
+        t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+    }
+    if !x.is_finite() {
+        return 0.0;
+    }
+    let x0 = x.floor();
+    let t = x - x0;
+    let index = x0 as i64;
+    let n0 = gradient(index) * t;
+    let n1 = gradient(index.wrapping_add(1)) * (t - 1.0);
+    // the 1D noise amplitude is at most 0.5: scale it to [-1, 1]
+    let noise = 2.0 * (n0 + fade(t) * (n1 - n0));
+    noise.clamp(-1.0, 1.0)
+}
+
+// ---------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::*;
+    use crate::{
+        bindings::{new_engine, register_bindings},
+        BeatTimeBase,
+    };
+
+    fn mean_and_stddev(values: &[f64]) -> (f64, f64) {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        (mean, variance.sqrt())
+    }
+
+    #[test]
+    fn distributions() {
+        let mut rand = Xoshiro256PlusPlus::seed_from_u64(0x1234);
+        // gauss
+        let values = (0..10000)
+            .map(|_| gauss(&mut rand, 2.0, 0.5))
+            .collect::<Vec<_>>();
+        let (mean, stddev) = mean_and_stddev(&values);
+        assert!((mean - 2.0).abs() < 0.02, "gauss mean: {}", mean);
+        assert!((stddev - 0.5).abs() < 0.02, "gauss stddev: {}", stddev);
+        let within_one_stddev = values.iter().filter(|v| (*v - 2.0).abs() < 0.5).count();
+        assert!((6600..7000).contains(&within_one_stddev));
+        // exp
+        let values = (0..10000)
+            .map(|_| exponential(&mut rand, 4.0))
+            .collect::<Vec<_>>();
+        let (mean, stddev) = mean_and_stddev(&values);
+        assert!(values.iter().all(|v| *v >= 0.0));
+        assert!((mean - 0.25).abs() < 0.01, "exp mean: {}", mean);
+        assert!((stddev - 0.25).abs() < 0.01, "exp stddev: {}", stddev);
+        // perlin
+        let values = (0..10000)
+            .map(|i| perlin(i as f64 * 0.0123 - 50.0))
+            .collect::<Vec<_>>();
+        assert!(values.iter().all(|v| (-1.0..=1.0).contains(v)));
+        let (mean, _) = mean_and_stddev(&values);
+        assert!(mean.abs() < 0.1, "perlin mean: {}", mean);
+        assert!(values.windows(2).all(|w| (w[1] - w[0]).abs() < 0.05));
+        assert_eq!(perlin(3.0), 0.0);
+        assert_eq!(perlin(-7.0), 0.0);
+        assert_eq!(perlin(1.25), perlin(1.25));
+        assert_ne!(perlin(1.25), perlin(2.25));
+    }
+
+    #[test]
+    fn bindings() -> LuaResult<()> {
+        let (mut lua, mut timeout_hook) = new_engine()?;
+        register_bindings(
+            &mut lua,
+            &timeout_hook,
+            &BeatTimeBase {
+                beats_per_min: 120.0,
+                beats_per_bar: 4,
+                samples_per_sec: 44000,
+            },
+        )?;
+        timeout_hook.reset();
+
+        // seeded sequences repeat
+        let sequence = r#"
+            math.randomseed(1234)
+            local values = {}
+            for i = 1, 8 do
+              values[#values + 1] = random.gauss(0, 2)
+              values[#values + 1] = random.exp(0.5)
+            end
+            return values
+        "#;
+        let values1 = lua.load(sequence).eval::<Vec<LuaNumber>>()?;
+        let values2 = lua.load(sequence).eval::<Vec<LuaNumber>>()?;
+        assert_eq!(values1, values2);
+        assert!(values1.iter().skip(1).step_by(2).all(|v| *v >= 0.0));
+
+        // default arguments
+        assert!(lua
+            .load("return random.gauss()")
+            .eval::<LuaNumber>()
+            .is_ok());
+        assert!(lua.load("return random.exp()").eval::<LuaNumber>().is_ok());
+        assert_eq!(
+            lua.load("return random.perlin(2.5)").eval::<LuaNumber>()?,
+            perlin(2.5)
+        );
+
+        // invalid arguments
+        assert!(lua.load("return random.gauss(0, -1)").exec().is_err());
+        assert!(lua.load("return random.exp(0)").exec().is_err());
+        assert!(lua.load("return random.perlin('x')").exec().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+use std::time::Duration;
+
+use mlua::prelude::*;
+
+use super::LuaTimeoutHook;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Execution limits and environment restrictions for untrusted pattern scripts.
+///
+/// See [`new_pattern_from_string_sandboxed`](super::new_pattern_from_string_sandboxed).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SandboxConfig {
+    timeout: Duration,
+    memory_limit: Option<usize>,
+    removed_globals: Vec<String>,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            timeout: LuaTimeoutHook::DEFAULT_TIMEOUT,
+            memory_limit: Some(Self::DEFAULT_MEMORY_LIMIT),
+            removed_globals: Self::DEFAULT_REMOVED_GLOBALS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl SandboxConfig {
+    /// Default memory limit of a sandboxed Lua engine in bytes.
+    pub const DEFAULT_MEMORY_LIMIT: usize = 32 * 1024 * 1024;
+
+    /// Globals which get removed by default: functions which load code or interact with
+    /// the Lua engine's environments or garbage collector.
+    pub const DEFAULT_REMOVED_GLOBALS: [&'static str; 10] = [
+        "load",
+        "loadstring",
+        "loadfile",
+        "dofile",
+        "require",
+        "module",
+        "collectgarbage",
+        "getfenv",
+        "setfenv",
+        "newproxy",
+    ];
+
+    /// Create a new sandbox config with the default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return a new config with the given max duration of a single script or callback run.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Return a new config with the given memory limit in bytes. None disables the limit.
+    pub fn with_memory_limit(self, memory_limit: Option<usize>) -> Self {
+        Self {
+            memory_limit,
+            ..self
+        }
+    }
+
+    /// Return a new config which removes the given globals from the script's environment.
+    pub fn with_removed_globals<S: Into<String>, I: IntoIterator<Item = S>>(
+        self,
+        removed_globals: I,
+    ) -> Self {
+        let removed_globals = removed_globals.into_iter().map(Into::into).collect();
+        Self {
+            removed_globals,
+            ..self
+        }
+    }
+
+    /// Max duration of a single script or callback run.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Memory limit of the Lua engine in bytes, if any.
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    /// Globals which get removed from the script's environment.
+    pub fn removed_globals(&self) -> &[String] {
+        &self.removed_globals
+    }
+
+    /// Apply memory limits and remove globals in the given Lua engine.
+    pub(crate) fn apply(&self, lua: &Lua) -> LuaResult<()> {
+        let globals = lua.globals();
+        for name in &self.removed_globals {
+            globals.raw_set(name.as_str(), LuaValue::Nil)?;
+        }
+        if let Some(memory_limit) = self.memory_limit {
+            lua.set_memory_limit(memory_limit)?;
+        }
+        Ok(())
+    }
+
+    /// Replace raw Lua memory errors with a more descriptive error message.
+    pub(crate) fn map_error(&self, err: LuaError) -> LuaError {
+        let is_memory_error = err.chain().any(|err| {
+            matches!(
+                err.downcast_ref::<LuaError>(),
+                Some(LuaError::MemoryError(_))
+            )
+        });
+        match self.memory_limit {
+            Some(memory_limit) if is_memory_error => LuaError::RuntimeError(format!(
+                "Script memory limit exceeded. The script tried to use more than {} KB of memory.\n\
+                Please avoid creating huge tables or strings in your script.",
+                memory_limit / 1024
+            )),
+            _ => err,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        bindings::{new_pattern_from_string_sandboxed, PatternError},
+        BeatTimeBase,
+    };
+
+    const TIME_BASE: BeatTimeBase = BeatTimeBase {
+        beats_per_min: 120.0,
+        beats_per_bar: 4,
+        samples_per_sec: 44100,
+    };
+
+    #[test]
+    fn normal_scripts() -> Result<(), Box<dyn std::error::Error>> {
+        let pattern = new_pattern_from_string_sandboxed(
+            TIME_BASE,
+            None,
+            r#"
+            local notes = {}
+            for i = 1, 100 do notes[i] = "c4" end
+            return pattern { unit = "1/4", event = function(context)
+              return notes[context.step % #notes + 1]
+            end }
+            "#,
+            "[test sandbox]",
+            &SandboxConfig::new(),
+        )?;
+        assert!(pattern
+            .borrow_mut()
+            .next()
+            .is_some_and(|e| e.event.is_some()));
+        Ok(())
+    }
+
+    #[test]
+    fn removed_globals() {
+        let config = SandboxConfig::new();
+        assert!(new_pattern_from_string_sandboxed(
+            TIME_BASE,
+            None,
+            r#"return load("return pattern {}")()"#,
+            "[test sandbox]",
+            &config,
+        )
+        .is_err());
+        let config = config.with_removed_globals(["cycle"]);
+        assert!(new_pattern_from_string_sandboxed(
+            TIME_BASE,
+            None,
+            r#"return cycle("c4")"#,
+            "[test sandbox]",
+            &config,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn memory_limit() {
+        let allocation_bomb = r#"
+            local t = {}
+            for i = 1, 100000000 do t[i] = string.rep("x", 64) .. i end
+            return pattern { }
+        "#;
+        let config = SandboxConfig::new()
+            .with_memory_limit(Some(4 * 1024 * 1024))
+            .with_timeout(Duration::from_secs(10));
+        let result = new_pattern_from_string_sandboxed(
+            TIME_BASE,
+            None,
+            allocation_bomb,
+            "[test sandbox]",
+            &config,
+        );
+        assert!(
+            matches!(
+                &result,
+                Err(PatternError::RuntimeError { message, .. })
+                    if message.contains("memory limit exceeded")
+            ),
+            "unexpected result: {:?}",
+            result.err()
+        );
+
+        // allocations in callbacks get aborted too
+        let pattern = new_pattern_from_string_sandboxed(
+            TIME_BASE,
+            None,
+            r#"
+            local t = {}
+            return pattern { event = function(context)
+              for i = 1, 100000000 do t[#t + 1] = string.rep("x", 64) .. i end
+              return "c4"
+            end }
+            "#,
+            "[test sandbox]",
+            &config,
+        )
+        .expect("Failed to create pattern");
+        let event = pattern.borrow_mut().next();
+        assert!(event.is_none_or(|e| e.event.is_none()));
+    }
+
+    #[test]
+    fn timeout() {
+        let result = new_pattern_from_string_sandboxed(
+            TIME_BASE,
+            None,
+            r#"while true do end"#,
+            "[test sandbox]",
+            &SandboxConfig::new().with_timeout(Duration::from_millis(50)),
+        );
+        assert!(result.is_err_and(|err| err.to_string().contains("Script timeout")));
+    }
+}
@@ -111,10 +111,10 @@ impl LuaUserData for Scale {
 
         methods.add_method(
             "fit",
-            |_lua, this, args: LuaMultiValue| -> LuaResult<LuaMultiValue> {
+            |lua, this, args: LuaMultiValue| -> LuaResult<LuaMultiValue> {
                 let mut ret = LuaMultiValue::new();
                 for (arg_index, arg) in args.iter().enumerate() {
-                    if let Some(note_event) = note_event_from_value(arg, Some(arg_index))? {
+                    if let Some(note_event) = note_event_from_value(lua, arg, Some(arg_index))? {
                         let fit_note = this.transpose(note_event.note, 0);
                         ret.push_back(LuaValue::Integer(u8::from(fit_note) as LuaInteger));
                     } else {
@@ -124,6 +124,23 @@ impl LuaUserData for Scale {
                 Ok(ret)
             },
         );
+
+        methods.add_method(
+            "quantize",
+            |lua, this, args: LuaMultiValue| -> LuaResult<LuaMultiValue> {
+                let mut ret = LuaMultiValue::new();
+                for (arg_index, arg) in args.iter().enumerate() {
+                    match note_event_from_value(lua, arg, Some(arg_index))? {
+                        Some(note_event) if note_event.note.is_note_on() => {
+                            let note = this.quantize(note_event.note);
+                            ret.push_back(LuaValue::Integer(u8::from(note) as LuaInteger));
+                        }
+                        _ => ret.push_back(LuaValue::Nil),
+                    }
+                }
+                Ok(ret)
+            },
+        );
     }
 }
 
@@ -329,4 +346,43 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn scale_quantize() -> LuaResult<()> {
+        let lua = new_test_engine()?;
+
+        assert_eq!(
+            lua.load(
+                r#"local cmin = scale("c", "minor")
+                return cmin:quantize("d#4", "e4", 54, { key = "b3" })"#
+            )
+            .eval::<LuaMultiValue>()?
+            .iter()
+            .map(|v| v.as_i32().unwrap())
+            .collect::<Vec<i32>>(),
+            vec![51, 51, 53, 46]
+        );
+        assert!(lua
+            .load(r#"return scale("c", "minor"):quantize("off")"#)
+            .eval::<LuaValue>()?
+            .is_nil());
+        assert!(lua
+            .load(r#"return scale("c", "minor"):quantize("x")"#)
+            .exec()
+            .is_err());
+        // melodies from scale notes
+        assert_eq!(
+            lua.load(
+                r#"local cmaj = scale("c4", "major")
+                return cmaj.notes[3], cmaj:quantize(cmaj.notes[3] + 1)"#
+            )
+            .eval::<LuaMultiValue>()?
+            .iter()
+            .map(|v| v.as_i32().unwrap())
+            .collect::<Vec<i32>>(),
+            vec![52, 53]
+        );
+
+        Ok(())
+    }
 }
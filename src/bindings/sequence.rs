@@ -20,7 +20,7 @@ pub struct SequenceUserData {
 }
 
 impl SequenceUserData {
-    pub fn from(args: LuaMultiValue) -> LuaResult<Self> {
+    pub fn from(lua: &Lua, args: LuaMultiValue) -> LuaResult<Self> {
         // a single value, probably a sequence array
         if args.len() == 1 {
             let arg = args
@@ -33,19 +33,19 @@ impl SequenceUserData {
                 let mut notes = vec![];
                 for (index, arg) in sequence.iter().enumerate() {
                     // add each sequence item as separate sequence event
-                    notes.push(note_events_from_value(arg, Some(index))?);
+                    notes.push(note_events_from_value(lua, arg, Some(index))?);
                 }
                 Ok(SequenceUserData { notes })
             } else {
                 Ok(SequenceUserData {
-                    notes: vec![note_events_from_value(&arg, None)?],
+                    notes: vec![note_events_from_value(lua, &arg, None)?],
                 })
             }
         // multiple values, maybe of different type
         } else {
             let mut notes = vec![];
             for (index, arg) in args.iter().enumerate() {
-                notes.push(note_events_from_value(arg, Some(index))?);
+                notes.push(note_events_from_value(lua, arg, Some(index))?);
             }
             Ok(SequenceUserData { notes })
         }
@@ -24,11 +24,7 @@ pub(crate) struct LuaTimeoutHook {
 impl LuaTimeoutHook {
     // default number of ms a script may run before a timeout error is fired.
     // assumes scripts are running in a real-time alike context.
-    const DEFAULT_TIMEOUT: Duration = Duration::from_millis(200);
-
-    pub(crate) fn new(lua: &Lua) -> Self {
-        Self::new_with_timeout(lua, Self::DEFAULT_TIMEOUT)
-    }
+    pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_millis(200);
 
     pub(crate) fn new_with_timeout(lua: &Lua, timeout: Duration) -> Self {
         let active = Rc::new(RefCell::new(1));
@@ -3,11 +3,16 @@
 use std::{cell::RefCell, ops::RangeBounds, rc::Rc, sync::Arc};
 
 use mlua::prelude::*;
+use rand::Rng;
 
 use crate::{
     bindings::{
-        callback::LuaCallback, cycle::CycleUserData, note::NoteUserData,
-        parameter::ParameterUserData, sequence::SequenceUserData, LuaTimeoutHook,
+        callback::{add_lua_callback_warning, LuaCallback},
+        cycle::CycleUserData,
+        note::NoteUserData,
+        parameter::{ParameterChangeUserData, ParameterUserData},
+        sequence::SequenceUserData,
+        LuaAppData, LuaTimeoutHook,
     },
     prelude::*,
 };
@@ -97,6 +102,12 @@ impl IntoLua for NoteEvent {
         table.set("volume", self.volume as f64)?;
         table.set("panning", self.panning as f64)?;
         table.set("delay", self.delay as f64)?;
+        if let Some(speed) = self.speed {
+            table.set("speed", speed as f64)?;
+        }
+        if let Some(ratchet) = self.ratchet {
+            table.set("ratchet", ratchet)?;
+        }
         Ok(LuaValue::Table(table))
     }
 }
@@ -107,7 +118,7 @@ impl IntoLua for ParameterChangeEvent {
     fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
         let table = lua.create_table()?;
         if let Some(id) = self.parameter {
-            table.set("parameter", usize::from(id).into_lua(lua)?)?;
+            table.set("parameter", u64::from(id).into_lua(lua)?)?;
         }
         table.set("value", self.value as f64)?;
         Ok(LuaValue::Table(table))
@@ -344,7 +355,10 @@ where
     }
 }
 
-pub(crate) fn instrument_value_from_table(table: &LuaTable) -> LuaResult<Option<InstrumentId>> {
+pub(crate) fn instrument_value_from_table(
+    lua: &Lua,
+    table: &LuaTable,
+) -> LuaResult<Option<InstrumentId>> {
     let value = table.get::<LuaValue>("instrument")?;
     if value.is_nil() {
         Ok(None)
@@ -357,11 +371,37 @@ pub(crate) fn instrument_value_from_table(table: &LuaTable) -> LuaResult<Option<
                 value
             )))
         }
+    } else if let Some(instruments) = value.as_table() {
+        // { 1, 2, 3 }: pick a random instrument with the global random number generator
+        let instruments = instruments
+            .clone()
+            .sequence_values::<LuaValue>()
+            .collect::<LuaResult<Vec<_>>>()?;
+        if instruments.is_empty() {
+            return Err(LuaError::RuntimeError(
+                "'instrument' property array must not be empty".to_string(),
+            ));
+        }
+        let index = {
+            let mut app_data = lua
+                .app_data_mut::<LuaAppData>()
+                .expect("Failed to access Lua app data");
+            app_data.rand_rgn.random_range(0..instruments.len())
+        };
+        match instruments[index].as_integer() {
+            Some(value) if value >= 0 => Ok(Some(InstrumentId::from(value as usize))),
+            _ => Err(LuaError::RuntimeError(format!(
+                "'instrument' property array values must be integers >= 0 but got '{:?}'",
+                instruments[index]
+            ))),
+        }
     } else {
         Err(LuaError::FromLuaConversionError {
             from: value.type_name(),
             to: "number".to_string(),
-            message: Some("'instrument' property must be an integer".to_string()),
+            message: Some(
+                "'instrument' property must be an integer or an array of integers".to_string(),
+            ),
         })
     }
 }
@@ -378,6 +418,40 @@ pub(crate) fn delay_value_from_table(table: &LuaTable) -> LuaResult<f32> {
     float_value_from_table(table, "delay", 0.0..1.0, 0.0)
 }
 
+pub(crate) fn speed_value_from_table(table: &LuaTable) -> LuaResult<Option<f32>> {
+    if table.get::<LuaValue>("speed")?.is_nil() {
+        Ok(None)
+    } else {
+        let speed = float_value_from_table(table, "speed", 0.0..=f32::MAX, 1.0)?;
+        if speed > 0.0 {
+            Ok(Some(speed))
+        } else {
+            Err(LuaError::RuntimeError(format!(
+                "speed property must be > 0 but is '{}'",
+                speed
+            )))
+        }
+    }
+}
+
+pub(crate) fn ratchet_value_from_table(table: &LuaTable) -> LuaResult<Option<u32>> {
+    match table.get::<LuaValue>("ratchet")? {
+        LuaValue::Nil => Ok(None),
+        LuaValue::Integer(ratchet)
+            if (1..=NoteEvent::MAX_RATCHET as LuaInteger).contains(&ratchet) =>
+        {
+            Ok(Some(ratchet as u32))
+        }
+        value => Err(LuaError::RuntimeError(format!(
+            "ratchet property must be an integer in range [1 - {}] but is '{}'",
+            NoteEvent::MAX_RATCHET,
+            value
+                .to_string()
+                .unwrap_or_else(|_| value.type_name().to_string())
+        ))),
+    }
+}
+
 fn float_value_from_string<Range>(
     str: &str,
     name: &'static str,
@@ -534,7 +608,10 @@ pub(crate) fn note_event_from_string(str: &str) -> LuaResult<Option<NoteEvent>>
     }
 }
 
-pub(crate) fn note_event_from_table_map(table: &LuaTable) -> LuaResult<Option<NoteEvent>> {
+pub(crate) fn note_event_from_table_map(
+    lua: &Lua,
+    table: &LuaTable,
+) -> LuaResult<Option<NoteEvent>> {
     if table.is_empty() {
         return Ok(None);
     }
@@ -544,25 +621,37 @@ pub(crate) fn note_event_from_table_map(table: &LuaTable) -> LuaResult<Option<No
             "missing 'key' property in note table".to_string(),
         ))
     } else {
-        let instrument = instrument_value_from_table(table)?;
+        let instrument = instrument_value_from_table(lua, table)?;
         let volume = volume_value_from_table(table)?;
         let panning = panning_value_from_table(table)?;
         let delay = delay_value_from_table(table)?;
-        // { key = 60, [volume = 1.0, panning = 0.0, delay = 0.0] }
+        let speed = speed_value_from_table(table)?;
+        let ratchet = ratchet_value_from_table(table)?;
+        // { key = 60, [volume = 1.0, panning = 0.0, delay = 0.0, speed = 1.0, ratchet = 1] }
         if let Some(note_value) = key.as_i32() {
-            Ok(new_note((
-                Note::from(note_value as u8),
-                instrument,
-                volume,
-                panning,
-                delay,
-            )))
+            Ok(Some(NoteEvent {
+                speed,
+                ratchet,
+                ..(
+                    Note::from(note_value as u8),
+                    instrument,
+                    volume,
+                    panning,
+                    delay,
+                )
+                    .into()
+            }))
         }
-        // { key = "C4", [instrument = 1, volume = 1.0, panning = 0.0, delay = 0.0] }
+        // { key = "C4", [instrument = 1, volume = 1.0, panning = 0.0, delay = 0.0, speed = 1.0,
+        //   ratchet = 1] }
         else if let Some(note_str) = key.as_string().map(|s| s.to_string_lossy()) {
             let note = Note::try_from(&*note_str)
                 .map_err(|err| LuaError::RuntimeError(err.to_string()))?;
-            Ok(new_note((note, instrument, volume, panning, delay)))
+            Ok(Some(NoteEvent {
+                speed,
+                ratchet,
+                ..(note, instrument, volume, panning, delay).into()
+            }))
         } else {
             Err(LuaError::FromLuaConversionError {
                 from: key.type_name(),
@@ -574,6 +663,7 @@ pub(crate) fn note_event_from_table_map(table: &LuaTable) -> LuaResult<Option<No
 }
 
 pub(crate) fn note_event_from_value(
+    lua: &Lua,
     arg: &LuaValue,
     arg_index: Option<usize>,
 ) -> LuaResult<Option<NoteEvent>> {
@@ -581,7 +671,7 @@ pub(crate) fn note_event_from_value(
         LuaValue::Nil => Ok(None),
         LuaValue::Integer(note_value) => note_event_from_number(*note_value),
         LuaValue::String(str) => note_event_from_string(&str.to_string_lossy()),
-        LuaValue::Table(table) => note_event_from_table_map(table),
+        LuaValue::Table(table) => note_event_from_table_map(lua, table),
         _ => Err(LuaError::FromLuaConversionError {
             from: arg.type_name(),
             to: "note".to_string(),
@@ -594,7 +684,60 @@ pub(crate) fn note_event_from_value(
     }
 }
 
+/// Split `parameter_change` userdata from the given value: either a single parameter change
+/// or a sequence table which mixes parameter changes with note values.
+/// Returns the parameter change events and the remaining note value, if there is any.
+/// Parameter changes in note tables become empty note slots, so the voice indices of all
+/// notes in the table stay stable.
+pub(crate) fn parameter_change_events_from_value(
+    lua: &Lua,
+    arg: LuaValue,
+) -> LuaResult<(Vec<ParameterChangeEvent>, Option<LuaValue>)> {
+    fn parameter_change(arg: &LuaValue) -> LuaResult<Option<ParameterChangeEvent>> {
+        match arg {
+            LuaValue::UserData(userdata) if userdata.is::<ParameterChangeUserData>() => Ok(Some(
+                userdata.borrow::<ParameterChangeUserData>()?.change.clone(),
+            )),
+            _ => Ok(None),
+        }
+    }
+    match arg {
+        LuaValue::UserData(_) => {
+            if let Some(change) = parameter_change(&arg)? {
+                Ok((vec![change], None))
+            } else {
+                Ok((vec![], Some(arg)))
+            }
+        }
+        LuaValue::Table(ref table) => {
+            let mut changes = Vec::new();
+            let mut notes = Vec::new();
+            let mut has_notes = false;
+            for value in table.sequence_values::<LuaValue>() {
+                let value = value?;
+                if let Some(change) = parameter_change(&value)? {
+                    changes.push(change);
+                    notes.push(LuaValue::String(lua.create_string("")?));
+                } else {
+                    has_notes = true;
+                    notes.push(value);
+                }
+            }
+            if changes.is_empty() {
+                Ok((changes, Some(arg)))
+            } else if !has_notes {
+                Ok((changes, None))
+            } else {
+                let notes = LuaValue::Table(lua.create_sequence_from(notes)?);
+                Ok((changes, Some(notes)))
+            }
+        }
+        _ => Ok((vec![], Some(arg))),
+    }
+}
+
 pub(crate) fn note_events_from_value(
+    lua: &Lua,
     arg: &LuaValue,
     arg_index: Option<usize>,
 ) -> LuaResult<Vec<Option<NoteEvent>>> {
@@ -633,12 +776,12 @@ pub(crate) fn note_events_from_value(
                 let mut note_events = vec![];
                 for (arg_index, arg) in sequence.iter().enumerate() {
                     // flatten sequence events into a single array
-                    note_events.append(&mut note_events_from_value(arg, Some(arg_index))?);
+                    note_events.append(&mut note_events_from_value(lua, arg, Some(arg_index))?);
                 }
                 Ok(note_events)
             // { key = xxx } map
             } else {
-                Ok(vec![note_event_from_value(arg, arg_index)?])
+                Ok(vec![note_event_from_value(lua, arg, arg_index)?])
             }
         }
         LuaValue::String(str) => {
@@ -650,7 +793,7 @@ pub(crate) fn note_events_from_value(
                 Ok(vec![note_event_from_string(&str)?])
             }
         }
-        _ => Ok(vec![note_event_from_value(arg, arg_index)?]),
+        _ => Ok(vec![note_event_from_value(lua, arg, arg_index)?]),
     }
 }
 
@@ -697,10 +840,11 @@ pub(crate) fn chord_events_from_string(chord_string: &str) -> LuaResult<Vec<Opti
 }
 
 pub(crate) fn chord_events_from_mode(
+    lua: &Lua,
     note: &LuaValue,
     mode: &str,
 ) -> LuaResult<Vec<Option<NoteEvent>>> {
-    let note_event = note_event_from_value(note, Some(1))?;
+    let note_event = note_event_from_value(lua, note, Some(1))?;
     if let Some(note_event) = note_event {
         let chord = Chord::try_from((note_event.note, mode))
             .map_err(|err| LuaError::RuntimeError(err.to_string()))?;
@@ -724,10 +868,11 @@ pub(crate) fn chord_events_from_mode(
 }
 
 pub(crate) fn chord_events_from_intervals(
+    lua: &Lua,
     note: &LuaValue,
     intervals: &[i32],
 ) -> LuaResult<Vec<Option<NoteEvent>>> {
-    let note_event = note_event_from_value(note, Some(1))?;
+    let note_event = note_event_from_value(lua, note, Some(1))?;
     if let Some(note_event) = note_event {
         let chord = Chord::try_from((note_event.note, intervals))
             .map_err(|err| LuaError::RuntimeError(err.to_string()))?;
@@ -810,6 +955,94 @@ pub(crate) fn rhythm_repeat_count_from_value(value: &LuaValue) -> LuaResult<Opti
     }
 }
 
+pub(crate) fn seed_from_value(value: &LuaValue) -> LuaResult<u64> {
+    // NB: map negative seeds to distinct seeds instead of clamping them to 0
+    if let Some(seed) = value.as_integer() {
+        Ok(seed as u64)
+    } else if let Some(seed) = value
+        .as_number()
+        .filter(|seed| (i64::MIN as f64..=i64::MAX as f64).contains(&seed.trunc()))
+    {
+        if seed.fract() != 0.0 {
+            add_lua_callback_warning(
+                "pattern",
+                &format!(
+                    "seed {} is not an integer value: using {}",
+                    seed,
+                    seed.trunc()
+                ),
+            );
+        }
+        Ok(seed.trunc() as i64 as u64)
+    } else {
+        Err(LuaError::FromLuaConversionError {
+            from: value.type_name(),
+            to: "seed".to_string(),
+            message: Some("must be an integer value".to_string()),
+        })
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub(crate) fn swing_division_from_value(value: &LuaValue) -> LuaResult<BeatTimeStep> {
+    let division = value.as_string().map(|str| str.to_string_lossy());
+    match division.as_deref() {
+        Some("bars") => Ok(BeatTimeStep::Bar(1.0)),
+        Some("1/1") => Ok(BeatTimeStep::Whole(1.0)),
+        Some("1/2") => Ok(BeatTimeStep::Half(1.0)),
+        Some("beats" | "1/4") => Ok(BeatTimeStep::Beats(1.0)),
+        Some("1/8") => Ok(BeatTimeStep::Eighth(1.0)),
+        Some("1/16") => Ok(BeatTimeStep::Sixteenth(1.0)),
+        Some("1/32") => Ok(BeatTimeStep::ThirtySecond(1.0)),
+        Some("1/64") => Ok(BeatTimeStep::SixtyFourth(1.0)),
+        _ => Err(bad_argument_error(
+            "pattern",
+            "swing_division",
+            1,
+            "expected one of 'bars|beats' or '1/1|1/2|1/4|1/8|1/16|1/32|1/64'",
+        )),
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub(crate) fn accent_from_value(value: &LuaValue) -> LuaResult<Accent> {
+    let table = value.as_table().ok_or_else(|| {
+        bad_argument_error(
+            "pattern",
+            "accent",
+            1,
+            "expected a table with 'every' and 'amount' keys",
+        )
+    })?;
+    let every = match table.get::<LuaValue>("every")? {
+        LuaValue::Integer(every) if every >= 1 => every as usize,
+        LuaValue::Number(every) if every >= 1.0 && every.fract() == 0.0 => every as usize,
+        _ => {
+            return Err(bad_argument_error(
+                "pattern",
+                "accent",
+                1,
+                "accent 'every' must be an integer >= 1",
+            ))
+        }
+    };
+    let amount = match table.get::<LuaValue>("amount")? {
+        LuaValue::Integer(amount) if amount >= 0 => amount as f32,
+        LuaValue::Number(amount) if amount >= 0.0 && amount.is_finite() => amount as f32,
+        _ => {
+            return Err(bad_argument_error(
+                "pattern",
+                "accent",
+                1,
+                "accent 'amount' must be a number >= 0",
+            ))
+        }
+    };
+    Ok(Accent::new(every, amount))
+}
+
 // -------------------------------------------------------------------------------------------------
 
 pub fn gate_trigger_from_value(value: &LuaValue) -> LuaResult<bool> {
@@ -842,6 +1075,27 @@ pub fn gate_trigger_from_value(value: &LuaValue) -> LuaResult<bool> {
     }
 }
 
+/// Boolean-ish gate trigger values or `{ hold = pulses }` tables, which trigger and hold the
+/// triggered notes for the given number of pulses.
+pub fn gate_hold_from_value(value: &LuaValue) -> LuaResult<(bool, Option<usize>)> {
+    match value {
+        LuaValue::Table(table) => match table.get::<LuaValue>("hold")? {
+            LuaValue::Integer(pulses) if pulses >= 1 => {
+                Ok((true, Some(usize::try_from(pulses).unwrap_or(usize::MAX))))
+            }
+            LuaValue::Number(pulses) if pulses >= 1.0 && pulses.fract() == 0.0 => {
+                Ok((true, Some(pulses.min(usize::MAX as f64) as usize)))
+            }
+            _ => Err(LuaError::FromLuaConversionError {
+                from: "table",
+                to: "gate value".to_string(),
+                message: Some("Invalid gate hold value: expecting an integer >= 1".to_string()),
+            }),
+        },
+        _ => Ok((gate_trigger_from_value(value)?, None)),
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 
 pub(crate) fn parameters_from_value(_lua: &Lua, value: &LuaTable) -> LuaResult<ParameterSet> {
@@ -886,10 +1140,15 @@ pub(crate) fn rhythm_from_value(
     timeout_hook: &LuaTimeoutHook,
     value: &LuaValue,
     time_base: &BeatTimeBase,
+    seed: Option<u64>,
 ) -> LuaResult<Box<dyn Rhythm>> {
     match value {
         LuaValue::Function(func) => {
-            let callback = LuaCallback::new(lua, func.clone())?;
+            let mut callback = LuaCallback::new(lua, func.clone())?;
+            if let Some(seed) = seed {
+                // use a different seed than the gate and event functions
+                callback.set_context_seed(seed.wrapping_add(1))?;
+            }
             let rhythm = ScriptedRhythm::new(timeout_hook, callback, time_base)?;
             Ok(Box::new(rhythm))
         }
@@ -911,15 +1170,36 @@ pub(crate) fn rhythm_from_value(
 
 // -------------------------------------------------------------------------------------------------
 
+pub(crate) fn pulse_values_from_value(value: &LuaValue) -> LuaResult<Option<Vec<f32>>> {
+    match value {
+        LuaValue::Table(table) => {
+            let mut values = Vec::new();
+            for result in table.clone().sequence_values::<LuaValue>() {
+                let pulse = pulse_from_value(&result?)?;
+                values.extend(pulse.to_rhythm_events().iter().map(|event| event.value));
+            }
+            Ok(Some(values))
+        }
+        _ => Ok(None),
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 pub(crate) fn gate_from_value(
     lua: &Lua,
     timeout_hook: &LuaTimeoutHook,
     value: &LuaValue,
     time_base: &BeatTimeBase,
+    seed: Option<u64>,
 ) -> LuaResult<Box<dyn Gate>> {
     match value {
         LuaValue::Function(func) => {
-            let callback = LuaCallback::new(lua, func.clone())?;
+            let mut callback = LuaCallback::new(lua, func.clone())?;
+            if let Some(seed) = seed {
+                // use a different seed than the pulse and event functions
+                callback.set_context_seed(seed.wrapping_add(2))?;
+            }
             let gate = ScriptedGate::new(timeout_hook, callback, time_base)?;
             Ok(Box::new(gate))
         }
@@ -933,21 +1213,67 @@ pub(crate) fn gate_from_value(
 
 // -------------------------------------------------------------------------------------------------
 
+pub(crate) fn step_instruments_from_value(
+    lua: &Lua,
+    value: &LuaValue,
+) -> LuaResult<Vec<InstrumentId>> {
+    let instruments = integer_array_from_value(lua, value.clone(), 1, "instrument", 0..=i32::MAX)?;
+    if instruments.is_empty() {
+        return Err(bad_argument_error(
+            "pattern",
+            "instrument",
+            1,
+            "instrument array must not be empty",
+        ));
+    }
+    Ok(instruments
+        .into_iter()
+        .map(|instrument| InstrumentId::from(instrument as usize))
+        .collect())
+}
+
+// -------------------------------------------------------------------------------------------------
+
 pub(crate) fn emitter_from_value(
     lua: &Lua,
     timeout_hook: &LuaTimeoutHook,
     value: &LuaValue,
     time_base: &BeatTimeBase,
+    pulses: Option<&[f32]>,
+    instruments: Option<&[InstrumentId]>,
+    seed: Option<u64>,
 ) -> LuaResult<Box<dyn Emitter>> {
+    // per step instruments can only be applied to fixed events
+    let fixed_emitter = |steps: Vec<Vec<Option<NoteEvent>>>| -> Box<dyn Emitter> {
+        match instruments {
+            Some(instruments) => {
+                Box::new(with_step_instruments(steps, instruments).to_sequence_emitter())
+            }
+            None => Box::new(steps.to_sequence_emitter()),
+        }
+    };
+    let ensure_no_instruments = || {
+        if instruments.is_some() {
+            Err(bad_argument_error(
+                "pattern",
+                "instrument",
+                1,
+                "instruments can only be applied to fixed events, not to functions or cycles",
+            ))
+        } else {
+            Ok(())
+        }
+    };
     match value {
         LuaValue::UserData(userdata) => {
             if userdata.is::<NoteUserData>() {
                 let note = userdata.borrow::<NoteUserData>()?.clone();
-                Ok(Box::new(note.notes.to_emitter()))
+                Ok(fixed_emitter(vec![note.notes]))
             } else if userdata.is::<SequenceUserData>() {
                 let sequence = userdata.borrow::<SequenceUserData>()?.clone();
-                Ok(Box::new(sequence.notes.to_sequence_emitter()))
+                Ok(fixed_emitter(sequence.notes))
             } else if userdata.is::<CycleUserData>() {
+                ensure_no_instruments()?;
                 // NB: take instead of cloning: cycle userdata has no other usage than being defined
                 let userdata = userdata.take::<CycleUserData>()?;
                 let cycle = userdata.cycle;
@@ -974,7 +1300,12 @@ pub(crate) fn emitter_from_value(
             }
         }
         LuaValue::Function(function) => {
-            let callback = LuaCallback::new(lua, function.clone())?;
+            ensure_no_instruments()?;
+            let mut callback = LuaCallback::new(lua, function.clone())?;
+            callback.set_context_pulses(pulses)?;
+            if let Some(seed) = seed {
+                callback.set_context_seed(seed)?;
+            }
             let emitter = ScriptedEmitter::new(timeout_hook, callback, time_base)?;
             Ok(Box::new(emitter))
         }
@@ -983,21 +1314,64 @@ pub(crate) fn emitter_from_value(
             if let Some(sequence) = sequence_from_table(table) {
                 let mut note_event_sequence = vec![];
                 for (arg_index, arg) in sequence.iter().enumerate() {
-                    note_event_sequence.push(note_events_from_value(arg, Some(arg_index))?);
+                    note_event_sequence.push(note_events_from_value(lua, arg, Some(arg_index))?);
                 }
-                let iter = note_event_sequence.to_sequence_emitter();
-                Ok(Box::new(iter))
+                Ok(fixed_emitter(note_event_sequence))
             }
             // convert table to a single note event
             else {
-                let emitter = note_event_from_value(value, None)?.to_emitter();
-                Ok(Box::new(emitter))
+                let note_event = note_event_from_value(lua, value, None)?;
+                Ok(fixed_emitter(vec![vec![note_event]]))
             }
         }
         _ => {
             // try converting a note number or note/chord string to an emitter.
-            let emitter = note_events_from_value(value, None)?.to_emitter();
-            Ok(Box::new(emitter))
+            let note_events = note_events_from_value(lua, value, None)?;
+            Ok(fixed_emitter(vec![note_events]))
         }
     }
 }
+
+/// Repeat the given event steps until they line up with the given instruments and assign
+/// the instruments to all notes which have no instrument set yet.
+fn with_step_instruments(
+    steps: Vec<Vec<Option<NoteEvent>>>,
+    instruments: &[InstrumentId],
+) -> Vec<Vec<Option<NoteEvent>>> {
+    const MAX_STEPS: usize = 1024;
+    if steps.is_empty() || instruments.is_empty() {
+        return steps;
+    }
+    let gcd = |mut a: usize, mut b: usize| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    let lcm = steps.len() / gcd(steps.len(), instruments.len()) * instruments.len();
+    let step_count = lcm.min(MAX_STEPS.max(steps.len()));
+    if step_count != lcm {
+        add_lua_callback_warning(
+            "pattern",
+            &format!(
+                "instruments do not line up with the event steps within {} steps: using {} steps",
+                MAX_STEPS, step_count
+            ),
+        );
+    }
+    (0..step_count)
+        .map(|index| {
+            let instrument = instruments[index % instruments.len()];
+            steps[index % steps.len()]
+                .iter()
+                .cloned()
+                .map(|note_event| {
+                    note_event.map(|mut note_event| {
+                        note_event.instrument = note_event.instrument.or(Some(instrument));
+                        note_event
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
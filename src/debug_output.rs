@@ -0,0 +1,82 @@
+//! Captured debug output of `Pattern`s.
+
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+// -------------------------------------------------------------------------------------------------
+
+/// A shared, bounded buffer which collects debug output lines of a [`Pattern`](crate::Pattern),
+/// such as `print` calls in Lua scripts.
+///
+/// Clones share the same buffer, so pattern instances which got created from the same script
+/// also share their debug output. When the buffer is full, the oldest lines get dropped.
+#[derive(Clone, Debug, Default)]
+pub struct DebugOutput {
+    lines: Rc<RefCell<VecDeque<String>>>,
+}
+
+impl DebugOutput {
+    /// Max number of lines which are kept in the buffer.
+    pub const MAX_LINES: usize = 256;
+
+    /// Create a new, empty debug output buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a new line, dropping the oldest line when the buffer is full.
+    pub fn push<S: Into<String>>(&self, line: S) {
+        let mut lines = self.lines.borrow_mut();
+        if lines.len() >= Self::MAX_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line.into());
+    }
+
+    /// Returns true when there are no buffered lines.
+    pub fn is_empty(&self) -> bool {
+        self.lines.borrow().is_empty()
+    }
+
+    /// Copy of all buffered lines, oldest lines first.
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.borrow().iter().cloned().collect()
+    }
+
+    /// Remove and return all buffered lines, oldest lines first.
+    pub fn take(&self) -> Vec<String> {
+        self.lines.borrow_mut().drain(..).collect()
+    }
+
+    /// Remove all buffered lines.
+    pub fn clear(&self) {
+        self.lines.borrow_mut().clear();
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lines() {
+        let output = DebugOutput::new();
+        assert!(output.is_empty());
+        output.push("a");
+        // clones share the buffer
+        output.clone().push("b");
+        assert_eq!(output.lines(), vec!["a", "b"]);
+        assert_eq!(output.take(), vec!["a", "b"]);
+        assert!(output.is_empty());
+        // buffer size is bounded
+        for index in 0..DebugOutput::MAX_LINES + 2 {
+            output.push(index.to_string());
+        }
+        let lines = output.lines();
+        assert_eq!(lines.len(), DebugOutput::MAX_LINES);
+        assert_eq!(lines.first().map(String::as_str), Some("2"));
+        output.clear();
+        assert!(output.is_empty());
+    }
+}
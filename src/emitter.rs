@@ -2,7 +2,7 @@
 
 use std::fmt::Debug;
 
-use crate::{BeatTimeBase, Event, ParameterSet, RhythmEvent};
+use crate::{BeatTimeBase, Event, NoteEvent, ParameterSet, RhythmEvent};
 
 type Fraction = num_rational::Rational32;
 
@@ -51,6 +51,55 @@ impl EmitterEvent {
             event,
         }
     }
+
+    /// Split ratcheted notes of the event into evenly spaced retriggers within the event's
+    /// start and length. The first hit plays at the note's delay within the event itself,
+    /// all following hits are returned as separate events, which end at the event's end.
+    /// Returned events are ordered by their start times and carry no more ratchets.
+    pub fn split_ratchets(mut self) -> Vec<Self> {
+        let Event::NoteEvents(note_events) = &mut self.event else {
+            return vec![self];
+        };
+        let voice_count = note_events.len();
+        let mut retriggers = Vec::new();
+        for (voice_index, note_event) in note_events.iter_mut().enumerate() {
+            let Some(note_event) = note_event else {
+                continue;
+            };
+            let ratchet = note_event
+                .ratchet
+                .take()
+                .unwrap_or(1)
+                .min(NoteEvent::MAX_RATCHET);
+            if ratchet <= 1 || !note_event.note.is_note_on() {
+                continue;
+            }
+            // quantize delays to keep fractions small
+            const DELAY_RESOLUTION: i32 = 960;
+            let delay = Fraction::new(
+                (note_event.delay.clamp(0.0, 1.0) * DELAY_RESOLUTION as f32).round() as i32,
+                DELAY_RESOLUTION,
+            );
+            let hit_length = (Fraction::ONE - delay) / ratchet as i32 * self.length;
+            for hit in 1..ratchet as i32 {
+                let mut note_events = vec![None; voice_count];
+                note_events[voice_index] = Some(NoteEvent {
+                    delay: 0.0,
+                    ..note_event.clone()
+                });
+                retriggers.push(Self::new_with_fraction(
+                    Event::NoteEvents(note_events),
+                    self.start + delay * self.length + hit_length * hit,
+                    hit_length,
+                ));
+            }
+        }
+        let mut events = Vec::with_capacity(1 + retriggers.len());
+        events.push(self);
+        events.append(&mut retriggers);
+        events.sort_by_key(|event| event.start);
+        events
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -58,6 +107,43 @@ impl EmitterEvent {
 /// A clonable, resettable [`Event`] iterator.
 ///
 /// Used by [`Pattern`](crate::Pattern) to generate events from pulse rhythms.
+///
+/// Implement this trait to generate custom events in Rust, and plug it into a pattern via
+/// `BeatTimePattern::with_emitter`, `SecondTimePattern::with_emitter` or the pattern's `emit`
+/// and `trigger_dyn` builder functions. The pattern calls `run` for each pulse of its rhythm
+/// and passes time base, parameter and trigger changes via the `set_` functions.
+///
+/// ```rust
+/// use pattrns::{prelude::*, RhythmEvent};
+///
+/// #[derive(Clone, Debug)]
+/// struct FixedNoteEmitter(Note);
+///
+/// impl Emitter for FixedNoteEmitter {
+///     fn set_time_base(&mut self, _time_base: &BeatTimeBase) {}
+///     fn set_trigger_event(&mut self, _event: &Event) {}
+///     fn set_parameters(&mut self, _parameters: ParameterSet) {}
+///     fn run(&mut self, _pulse: RhythmEvent, emit_event: bool) -> Option<Vec<EmitterEvent>> {
+///         emit_event.then(|| vec![EmitterEvent::new(Event::NoteEvents(vec![Some(self.0.into())]))])
+///     }
+///     fn duplicate(&self) -> Box<dyn Emitter> {
+///         Box::new(self.clone())
+///     }
+///     fn reset(&mut self) {}
+/// }
+///
+/// let time_base = BeatTimeBase {
+///     beats_per_min: 120.0,
+///     beats_per_bar: 4,
+///     samples_per_sec: 44100,
+/// };
+/// let mut pattern = BeatTimePattern::with_emitter(
+///     time_base,
+///     BeatTimeStep::Beats(1.0),
+///     Box::new(FixedNoteEmitter(Note::C4)),
+/// );
+/// assert!(pattern.next().is_some());
+/// ```
 pub trait Emitter: Debug {
     /// Update the iterator's internal beat time base with the new time base.
     fn set_time_base(&mut self, time_base: &BeatTimeBase);
@@ -68,6 +154,18 @@ pub trait Emitter: Debug {
     /// Set or update optional parameter map for callbacks.
     fn set_parameters(&mut self, parameters: ParameterSet);
 
+    /// Set or update the pattern's current, smoothed output level for callbacks.
+    /// Ignored by default.
+    fn set_output_level(&mut self, _level: f32) {}
+
+    /// Set or update the notes which currently are playing in the pattern's slot for callbacks.
+    /// Ignored by default.
+    fn set_playing_notes(&mut self, _notes: &[NoteEvent]) {}
+
+    /// Set or update the length of a single pattern step in beats, e.g. to convert pulse step
+    /// times to musical positions. Ignored by default.
+    fn set_step_length(&mut self, _step_beats: f64) {}
+
     /// Move iterator with the given rhythm event pulse value forward.
     /// `pulse` contains the current value and timing information for the current step in the pattern.
     /// `emit_event` indicates whether the iterator should trigger the next event in the sequence as
@@ -20,6 +20,7 @@ impl TryFrom<&CycleValue> for Vec<Option<NoteEvent>> {
             CycleValue::Hold => Ok(vec![None]),
             CycleValue::Rest => Ok(vec![new_note(Note::OFF)]),
             CycleValue::Float(_f) => Ok(vec![None]),
+            CycleValue::Signal(_s) => Ok(vec![None]),
             CycleValue::Integer(i) => Ok(vec![new_note(Note::from((*i).clamp(0, 0x7f) as u8))]),
             CycleValue::Pitch(p) => Ok(vec![new_note(Note::from(p.midi_note()))]),
             CycleValue::Chord(p, m) => {
@@ -222,10 +223,14 @@ impl CycleNoteEvents {
                     merged_note_events.append(note_events);
                 }
             }
-            // convert padded, merged note events to a timed 'Event'
+            // convert padded, merged note events to timed 'Event's with split ratchets
             let event = Event::NoteEvents(merged_note_events);
-            event_iter_items.push(EmitterEvent::new_with_fraction(event, start_time, length));
+            event_iter_items.append(
+                &mut EmitterEvent::new_with_fraction(event, start_time, length).split_ratchets(),
+            );
         }
+        // retriggers of ratchets may overlap following events
+        event_iter_items.sort_by_key(|event| event.start);
         event_iter_items
     }
 }
@@ -110,7 +110,7 @@ impl Emitter for FixedEmitter {
         }
         let event = self.events[self.event_index].clone();
         self.event_index = (self.event_index + 1) % self.events.len();
-        Some(vec![EmitterEvent::new(event)])
+        Some(EmitterEvent::new(event).split_ratchets())
     }
 
     fn advance(&mut self, _pulse: RhythmEvent, emit_event: bool) {
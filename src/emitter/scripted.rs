@@ -1,7 +1,10 @@
 use mlua::prelude::LuaResult;
 
 use crate::{
-    bindings::{note_events_from_value, ContextPlaybackState, LuaCallback, LuaTimeoutHook},
+    bindings::{
+        note_events_from_value, parameter_change_events_from_value, ContextPlaybackState,
+        LuaCallback, LuaTimeoutHook,
+    },
     emitter::fixed::FixedEmitter,
     BeatTimeBase, Emitter, EmitterEvent, Event, NoteEvent, ParameterSet, RhythmEvent,
 };
@@ -14,6 +17,8 @@ pub struct ScriptedEmitter {
     timeout_hook: LuaTimeoutHook,
     callback: LuaCallback,
     note_event_state: Vec<Option<NoteEvent>>,
+    time_base: BeatTimeBase,
+    step_beats: f64,
     pulse_step: usize,
     pulse_time_step: f64,
     step: usize,
@@ -44,17 +49,31 @@ impl ScriptedEmitter {
             pulse_time_step,
             step,
         )?;
+        let time_base = *time_base;
+        let step_beats = 1.0;
+        callback.set_context_playback_position(pulse_time_step * step_beats, &time_base)?;
+        callback.set_context_step_duration(step_beats, &time_base)?;
         Ok(Self {
             timeout_hook,
             callback,
             note_event_state,
+            time_base,
+            step_beats,
             pulse_step,
             pulse_time_step,
             step,
         })
     }
 
-    fn run(&mut self, pulse: RhythmEvent) -> LuaResult<Option<Vec<EmitterEvent>>> {
+    fn run(
+        &mut self,
+        pulse: RhythmEvent,
+        emit_event: bool,
+    ) -> LuaResult<Option<Vec<EmitterEvent>>> {
+        let has_scheduled_callbacks = self.callback.has_scheduled_callbacks()?;
+        if !emit_event && !has_scheduled_callbacks {
+            return Ok(None);
+        }
         // reset timeout
         self.timeout_hook.reset();
         // update function context
@@ -64,17 +83,56 @@ impl ScriptedEmitter {
         self.callback
             .set_context_pulse_step(self.pulse_step, self.pulse_time_step)?;
         self.callback.set_context_step(self.step)?;
-        // invoke callback and evaluate the result
-        let events = note_events_from_value(&self.callback.call()?, None)?;
-        // normalize event
-        let mut event = Event::NoteEvents(events);
-        FixedEmitter::normalize_event(&mut event, &mut self.note_event_state);
-        // return as EmitterEvent
-        Ok(Some(vec![EmitterEvent::new(event)]))
+        self.set_context_playback_position()?;
+        // invoke due scheduled functions, before the callback can schedule new ones
+        let scheduled_results = if has_scheduled_callbacks {
+            let beats = self.pulse_time_step * self.step_beats;
+            self.callback.run_scheduled_callbacks(beats)?
+        } else {
+            Vec::new()
+        };
+        // invoke callback and evaluate the result, appending results of scheduled functions
+        let mut results = Vec::with_capacity(1 + scheduled_results.len());
+        if emit_event {
+            results.push(self.callback.call()?);
+        }
+        results.extend(scheduled_results);
+        let mut events = Vec::new();
+        let mut parameter_change_events = Vec::new();
+        for result in results {
+            let (changes, notes) = parameter_change_events_from_value(self.callback.lua(), result)?;
+            parameter_change_events.extend(
+                changes
+                    .into_iter()
+                    .map(|change| EmitterEvent::new(Event::ParameterChangeEvent(change))),
+            );
+            if let Some(notes) = notes {
+                events.append(&mut note_events_from_value(
+                    self.callback.lua(),
+                    &notes,
+                    None,
+                )?);
+            }
+        }
+        if !emit_event && events.is_empty() && parameter_change_events.is_empty() {
+            return Ok(None);
+        }
+        // emit parameter changes as they are, before the notes
+        let mut emitter_events = parameter_change_events;
+        if !events.is_empty() || emitter_events.is_empty() {
+            // normalize note event
+            let mut event = Event::NoteEvents(events);
+            FixedEmitter::normalize_event(&mut event, &mut self.note_event_state);
+            emitter_events.append(&mut EmitterEvent::new(event).split_ratchets());
+        }
+        // return as EmitterEvents
+        Ok(Some(emitter_events))
     }
 
-    fn advance(&mut self, pulse: RhythmEvent) -> LuaResult<()> {
-        if self.callback.is_stateful().unwrap_or(true) {
+    fn advance(&mut self, pulse: RhythmEvent, emit_event: bool) -> LuaResult<()> {
+        let has_scheduled_callbacks = self.callback.has_scheduled_callbacks()?;
+        let is_stateful = self.callback.is_stateful().unwrap_or(true);
+        if (emit_event && is_stateful) || has_scheduled_callbacks {
             // reset timeout
             self.timeout_hook.reset();
             // update function context
@@ -84,13 +142,27 @@ impl ScriptedEmitter {
             self.callback
                 .set_context_pulse_step(self.pulse_step, self.pulse_time_step)?;
             self.callback.set_context_step(self.step)?;
+            self.set_context_playback_position()?;
+            // invoke due scheduled functions and ignore their results
+            if has_scheduled_callbacks {
+                let beats = self.pulse_time_step * self.step_beats;
+                self.callback.run_scheduled_callbacks(beats)?;
+            }
             // invoke callback and ignore the result
-            self.callback.call()?;
+            if emit_event && is_stateful {
+                self.callback.call()?;
+            }
             Ok(())
         } else {
             Ok(())
         }
     }
+
+    fn set_context_playback_position(&mut self) -> LuaResult<()> {
+        let beats = self.pulse_time_step * self.step_beats;
+        self.callback
+            .set_context_playback_position(beats, &self.time_base)
+    }
 }
 
 impl Clone for ScriptedEmitter {
@@ -99,6 +171,8 @@ impl Clone for ScriptedEmitter {
             timeout_hook: self.timeout_hook.clone(),
             callback: self.callback.clone(),
             note_event_state: self.note_event_state.clone(),
+            time_base: self.time_base,
+            step_beats: self.step_beats,
             pulse_step: self.pulse_step,
             pulse_time_step: self.pulse_time_step,
             step: self.step,
@@ -111,9 +185,26 @@ impl Emitter for ScriptedEmitter {
         // reset timeout
         self.timeout_hook.reset();
         // update function context with the new time base
+        self.time_base = *time_base;
         if let Err(err) = self.callback.set_context_time_base(time_base) {
             self.callback.handle_error(&err);
         }
+        if let Err(err) = self
+            .callback
+            .set_context_step_duration(self.step_beats, &self.time_base)
+        {
+            self.callback.handle_error(&err);
+        }
+    }
+
+    fn set_step_length(&mut self, step_beats: f64) {
+        self.step_beats = step_beats;
+        if let Err(err) = self
+            .callback
+            .set_context_step_duration(self.step_beats, &self.time_base)
+        {
+            self.callback.handle_error(&err);
+        }
     }
 
     fn set_trigger_event(&mut self, event: &Event) {
@@ -134,33 +225,47 @@ impl Emitter for ScriptedEmitter {
         }
     }
 
+    fn set_output_level(&mut self, level: f32) {
+        // reset timeout
+        self.timeout_hook.reset();
+        // update function context with the new level
+        if let Err(err) = self.callback.set_context_output_level(level) {
+            self.callback.handle_error(&err);
+        }
+    }
+
+    fn set_playing_notes(&mut self, notes: &[NoteEvent]) {
+        // reset timeout
+        self.timeout_hook.reset();
+        // update function context with the new notes
+        if let Err(err) = self.callback.set_context_playing_notes(notes) {
+            self.callback.handle_error(&err);
+        }
+    }
+
     fn run(&mut self, pulse: RhythmEvent, emit_event: bool) -> Option<Vec<EmitterEvent>> {
-        // generate a new event and move or only update pulse counters
+        // generate new events, if needed, and move pulse counters
+        let event = match self.run(pulse, emit_event) {
+            Ok(event) => event,
+            Err(err) => {
+                self.callback.handle_error(&err);
+                None
+            }
+        };
         if emit_event {
-            let event = match self.run(pulse) {
-                Ok(event) => event,
-                Err(err) => {
-                    self.callback.handle_error(&err);
-                    None
-                }
-            };
             self.step += 1;
-            self.pulse_step += 1;
-            self.pulse_time_step += pulse.step_time;
-            event
-        } else {
-            self.pulse_step += 1;
-            self.pulse_time_step += pulse.step_time;
-            None
         }
+        self.pulse_step += 1;
+        self.pulse_time_step += pulse.step_time;
+        event
     }
 
     fn advance(&mut self, pulse: RhythmEvent, emit_event: bool) {
-        // generate a new event and move or only update pulse counters
+        // generate a new event, if needed, and move pulse counters
+        if let Err(err) = self.advance(pulse, emit_event) {
+            self.callback.handle_error(&err);
+        }
         if emit_event {
-            if let Err(err) = self.advance(pulse) {
-                self.callback.handle_error(&err);
-            }
             self.step += 1;
         }
         self.pulse_step += 1;
@@ -188,6 +293,17 @@ impl Emitter for ScriptedEmitter {
         {
             self.callback.handle_error(&err);
         }
+        if let Err(err) = self.set_context_playback_position() {
+            self.callback.handle_error(&err);
+        }
+        // restore random number generator
+        if let Err(err) = self.callback.reset_context_rng() {
+            self.callback.handle_error(&err);
+        }
+        // forget scheduled functions
+        if let Err(err) = self.callback.clear_scheduled_callbacks() {
+            self.callback.handle_error(&err);
+        }
         // restore function
         if let Err(err) = self.callback.reset() {
             self.callback.handle_error(&err);
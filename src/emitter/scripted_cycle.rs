@@ -100,7 +100,7 @@ impl ScriptedCycleEmitter {
                 )?;
                 // call mapping function
                 let result = mapping_callback.call_with_arg(event.string())?;
-                note_events_from_value(&result, None)?
+                note_events_from_value(mapping_callback.lua(), &result, None)?
             } else if let Some(note_events) = self.mappings.get(event.string()) {
                 // apply custom note mapping
                 note_events.clone()
@@ -286,6 +286,28 @@ impl Emitter for ScriptedCycleEmitter {
         }
     }
 
+    fn set_output_level(&mut self, level: f32) {
+        if let Some(timeout_hook) = &mut self.timeout_hook {
+            timeout_hook.reset();
+        }
+        if let Some(callback) = &mut self.mapping_callback {
+            if let Err(err) = callback.set_context_output_level(level) {
+                callback.handle_error(&err);
+            }
+        }
+    }
+
+    fn set_playing_notes(&mut self, notes: &[NoteEvent]) {
+        if let Some(timeout_hook) = &mut self.timeout_hook {
+            timeout_hook.reset();
+        }
+        if let Some(callback) = &mut self.mapping_callback {
+            if let Err(err) = callback.set_context_playing_notes(notes) {
+                callback.handle_error(&err);
+            }
+        }
+    }
+
     fn run(&mut self, _pulse: RhythmEvent, emit_event: bool) -> Option<Vec<EmitterEvent>> {
         if emit_event {
             Some(self.generate())
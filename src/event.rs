@@ -35,7 +35,7 @@ pub struct InstrumentId(usize);
     PartialEq,
     Hash,
 )]
-pub struct ParameterId(usize);
+pub struct ParameterId(u64);
 
 // -------------------------------------------------------------------------------------------------
 
@@ -44,14 +44,44 @@ pub struct ParameterId(usize);
 pub struct NoteEvent {
     pub note: Note,
     pub instrument: Option<InstrumentId>,
-    pub volume: f32,  // [0 - INF]
-    pub panning: f32, // [-1 - 1]
-    pub delay: f32,   // [0 - 1]
+    pub volume: f32,          // [0 - INF]
+    pub panning: f32,         // [-1 - 1]
+    pub delay: f32,           // [0 - 1]
+    pub speed: Option<f32>,   // [0 - INF]
+    pub ratchet: Option<u32>, // [1 - MAX_RATCHET]
 }
 
 impl NoteEvent {
+    /// Max supported number of ratchets of a single note.
+    pub const MAX_RATCHET: u32 = 64;
+
+    /// Set an optional fixed playback speed, which overrides the speed derived from the note.
+    /// When set, the note no longer is key tracked, but still carries its note value.
+    #[must_use]
+    pub fn with_speed<S: Into<Option<f32>>>(self, speed: S) -> Self {
+        Self {
+            speed: speed.into(),
+            ..self
+        }
+    }
+
+    /// Set an optional number of evenly spaced retriggers of the note within its step, in range
+    /// \[1 - [`Self::MAX_RATCHET`]\]. Emitters split ratcheted notes into separate events, so
+    /// emitted notes no longer carry ratchets.
+    #[must_use]
+    pub fn with_ratchet<R: Into<Option<u32>>>(self, ratchet: R) -> Self {
+        Self {
+            ratchet: ratchet
+                .into()
+                .map(|ratchet| ratchet.clamp(1, Self::MAX_RATCHET)),
+            ..self
+        }
+    }
+
     pub fn to_string(&self, show_instruments: bool) -> String {
-        if show_instruments {
+        if self.note.is_note_off() {
+            "===".to_string()
+        } else if show_instruments {
             format!(
                 "{} {} {:.2} {:.2} {:.2}",
                 self.note,
@@ -86,6 +116,8 @@ where
             volume: 1.0,
             panning: 0.0,
             delay: 0.0,
+            speed: None,
+            ratchet: None,
         }
     }
 }
@@ -104,6 +136,8 @@ where
             volume: 1.0,
             panning: 0.0,
             delay: 0.0,
+            speed: None,
+            ratchet: None,
         }
     }
 }
@@ -123,6 +157,8 @@ where
             volume,
             panning: 0.0,
             delay: 0.0,
+            speed: None,
+            ratchet: None,
         }
     }
 }
@@ -143,6 +179,8 @@ where
             volume,
             panning,
             delay: 0.0,
+            speed: None,
+            ratchet: None,
         }
     }
 }
@@ -164,6 +202,8 @@ where
             volume,
             panning,
             delay,
+            speed: None,
+            ratchet: None,
         }
     }
 }
@@ -305,4 +345,126 @@ impl Display for Event {
 // -------------------------------------------------------------------------------------------------
 
 /// A refcounted function which transforms emitted [`Event`] contents.
+///
+/// Use [`compose_event_transforms`] to stack multiple transforms and
+/// [`new_note_event_transform`] to create transforms which only modify note events.
 pub type EventTransform = Rc<dyn Fn(&mut Event)>;
+
+/// Create an [`EventTransform`] which applies the given function to all note slots of
+/// note events. Parameter change events are passed through. Set a note slot to `None` to
+/// drop the note.
+pub fn new_note_event_transform<F>(transform: F) -> EventTransform
+where
+    F: Fn(&mut Option<NoteEvent>) + 'static,
+{
+    Rc::new(move |event: &mut Event| {
+        if let Event::NoteEvents(note_events) = event {
+            note_events.iter_mut().for_each(&transform);
+        }
+    })
+}
+
+/// Compose the given event transforms into a single [`EventTransform`], which applies all
+/// transforms in the given order, so each transform sees the result of the previous one.
+/// Returns `None` when no transforms are given.
+pub fn compose_event_transforms<I>(transforms: I) -> Option<EventTransform>
+where
+    I: IntoIterator<Item = EventTransform>,
+{
+    let mut transforms = transforms.into_iter().collect::<Vec<_>>();
+    match transforms.len() {
+        0 => None,
+        1 => transforms.pop(),
+        _ => Some(Rc::new(move |event: &mut Event| {
+            transforms.iter().for_each(|transform| transform(event))
+        })),
+    }
+}
+
+/// A refcounted predicate which decides if an emitted [`NoteEvent`] should be kept (`true`)
+/// or dropped (`false`), e.g. to isolate or mute specific instruments or note ranges.
+pub type NoteFilter = Rc<dyn Fn(&NoteEvent) -> bool>;
+
+/// Apply the given note filter to the given event, replacing dropped notes with `None`, so
+/// the voice layout of the remaining notes stays untouched.
+pub(crate) fn apply_note_filter(filter: &NoteFilter, event: &mut Event) {
+    if let Event::NoteEvents(note_events) = event {
+        for note_event in note_events.iter_mut() {
+            if note_event.as_ref().is_some_and(|note| !filter(note)) {
+                *note_event = None;
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn composed_event_transforms() {
+        assert!(compose_event_transforms([]).is_none());
+
+        // transpose, then scale volume, then drop notes above C5
+        let transform = compose_event_transforms([
+            new_note_event_transform(|note_event| {
+                if let Some(note) = note_event {
+                    note.note = note.note.transposed(12);
+                }
+            }),
+            new_note_event_transform(|note_event| {
+                if let Some(note) = note_event {
+                    note.volume *= 0.5;
+                }
+            }),
+            new_note_event_transform(|note_event| {
+                if note_event.as_ref().is_some_and(|note| note.note > Note::C5) {
+                    *note_event = None;
+                }
+            }),
+        ])
+        .unwrap();
+
+        let mut event = Event::NoteEvents(vec![
+            new_note(Note::C4),
+            None,
+            new_note((Note::E4, None, 0.5)),
+        ]);
+        transform(&mut event);
+        assert_eq!(
+            event,
+            Event::NoteEvents(vec![new_note((Note::C5, None, 0.5)), None, None])
+        );
+
+        // transforms are applied in order: the second transform sees the first one's result
+        let transform = compose_event_transforms([
+            new_note_event_transform(|note_event| {
+                if let Some(note) = note_event {
+                    note.volume = 0.25;
+                }
+            }),
+            new_note_event_transform(|note_event| {
+                if let Some(note) = note_event {
+                    note.note = note.note.transposed((note.volume * 16.0) as i32);
+                }
+            }),
+        ])
+        .unwrap();
+        let mut event = Event::NoteEvents(vec![new_note(Note::C4)]);
+        transform(&mut event);
+        assert_eq!(
+            event,
+            Event::NoteEvents(vec![new_note((Note::E4, None, 0.25))])
+        );
+
+        // parameter changes are passed through
+        let mut event = Event::ParameterChangeEvent(new_parameter_change(None, 0.5));
+        transform(&mut event);
+        assert_eq!(
+            event,
+            Event::ParameterChangeEvent(new_parameter_change(None, 0.5))
+        );
+    }
+}
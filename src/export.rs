@@ -0,0 +1,450 @@
+//! Export rendered [`Sequence`]s to other formats, e.g. to import them into a DAW.
+
+use std::collections::HashMap;
+
+use crate::{
+    phrase::PatternIndex, Event, ExactSampleTime, ParameterId, PatternEvent, SampleTime, Sequence,
+    TimeSignature,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Ticks per quarter note of exported MIDI files.
+pub const MIDI_TICKS_PER_BEAT: u16 = 960;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Render the given sequence from its start until the given sample time into a standard MIDI
+/// file (format 0) and return the file's bytes.
+///
+/// Tempo is taken from the sequence's [`BeatTimeBase`](crate::BeatTimeBase), using
+/// [`MIDI_TICKS_PER_BEAT`] ticks per beat. The given time signature is written into the file,
+/// e.g. the one the player uses for its count-in. When None, the time base's default
+/// x/4 time signature is used. Note events are written as note-on/note-off
+/// pairs: note delays are applied to the note-on times and volumes become velocities. Instrument
+/// ids are used as MIDI channels (modulo 16), notes without instrument use channel 0. Notes with
+/// a known [`note_duration`](PatternEvent::note_duration) get stopped after their duration, all
+/// other notes by note-offs or at the end of the file. New notes in the same pattern slot voice
+/// always stop the voice's previous note, also when it has a known duration.
+///
+/// Parameter change events are written as control changes on channel 0 when their parameter
+/// is present in the given parameter id → CC number map, else they are ignored.
+///
+/// NB: This resets the sequence, so it should not be used with sequences which are currently
+/// playing back.
+pub fn sequence_to_midi(
+    sequence: &mut Sequence,
+    duration: SampleTime,
+    time_signature: Option<TimeSignature>,
+    parameter_ccs: &[(ParameterId, u8)],
+) -> Vec<u8> {
+    let time_base = *sequence.time_base();
+    let time_signature = time_signature.unwrap_or_else(|| time_base.time_signature());
+    let samples_per_beat = time_base.samples_per_beat();
+    let to_ticks = |time: SampleTime| -> u64 {
+        if samples_per_beat > 0.0 {
+            (time as ExactSampleTime / samples_per_beat * MIDI_TICKS_PER_BEAT as ExactSampleTime)
+                .round() as u64
+        } else {
+            0
+        }
+    };
+
+    let end_ticks = to_ticks(duration);
+    let events = sequence.render_from(0, duration);
+    sequence.reset();
+
+    let mut writer = MidiTrackWriter::new();
+    // (pattern_index, voice_index) -> (channel, key, optional note-off ticks)
+    let mut playing_notes = HashMap::<(PatternIndex, usize), (u8, u8, Option<u64>)>::new();
+    for (pattern_index, pattern_event) in events {
+        let PatternEvent { time, event, .. } = &pattern_event;
+        match event {
+            Some(Event::NoteEvents(note_events)) => {
+                for (voice_index, note_event) in note_events.iter().enumerate() {
+                    let Some(note_event) = note_event else {
+                        continue;
+                    };
+                    if !note_event.note.is_note_on() && !note_event.note.is_note_off() {
+                        continue;
+                    }
+                    let ticks = to_ticks(pattern_event.note_event_time(note_event));
+                    // stop the voice's previous note, unless it already stopped
+                    if let Some((channel, key, off_ticks)) =
+                        playing_notes.remove(&(pattern_index, voice_index))
+                    {
+                        writer.note_off(off_ticks.map_or(ticks, |t| t.min(ticks)), channel, key);
+                    }
+                    if note_event.note.is_note_on() {
+                        let channel = note_event
+                            .instrument
+                            .map_or(0, |instrument| (usize::from(instrument) % 16) as u8);
+                        let key = (note_event.note as u8).min(127);
+                        let velocity =
+                            ((note_event.volume.clamp(0.0, 1.0) * 127.0).round() as u8).max(1);
+                        writer.note_on(ticks, channel, key, velocity);
+                        // notes with a known length get stopped after their length, or by
+                        // new notes in the same voice which retrigger before that
+                        let off_ticks = pattern_event.note_duration().map(|note_duration| {
+                            to_ticks(*time + note_duration)
+                                .clamp(ticks + 1, end_ticks.max(ticks + 1))
+                        });
+                        playing_notes
+                            .insert((pattern_index, voice_index), (channel, key, off_ticks));
+                    }
+                }
+            }
+            Some(Event::ParameterChangeEvent(change)) => {
+                let controller = change.parameter.and_then(|parameter| {
+                    parameter_ccs
+                        .iter()
+                        .find(|(id, _)| *id == parameter)
+                        .map(|(_, cc)| *cc)
+                });
+                if let Some(controller) = controller {
+                    let value = (change.value.clamp(0.0, 1.0) * 127.0).round() as u8;
+                    writer.control_change(to_ticks(*time), 0, controller.min(127), value);
+                }
+            }
+            None => (),
+        }
+    }
+    // stop all still playing notes at the end
+    let mut playing_notes = playing_notes.into_iter().collect::<Vec<_>>();
+    playing_notes.sort_by_key(|(voice, _)| *voice);
+    for (_, (channel, key, off_ticks)) in playing_notes {
+        writer.note_off(off_ticks.unwrap_or(end_ticks), channel, key);
+    }
+
+    // write header and track chunks
+    let micros_per_beat = if time_base.beats_per_min > 0.0 {
+        (60_000_000.0 / time_base.beats_per_min as f64).round() as u32
+    } else {
+        500_000
+    };
+    let track = writer.finish(micros_per_beat, &time_signature, end_ticks);
+
+    let mut bytes = Vec::with_capacity(14 + 8 + track.len());
+    bytes.extend_from_slice(b"MThd");
+    bytes.extend_from_slice(&6_u32.to_be_bytes());
+    bytes.extend_from_slice(&0_u16.to_be_bytes()); // format 0
+    bytes.extend_from_slice(&1_u16.to_be_bytes()); // one track
+    bytes.extend_from_slice(&MIDI_TICKS_PER_BEAT.to_be_bytes());
+    bytes.extend_from_slice(b"MTrk");
+    bytes.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&track);
+    bytes
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Collects timed MIDI messages and writes them as MIDI track chunk data.
+struct MidiTrackWriter {
+    // (ticks, message bytes)
+    messages: Vec<(u64, Vec<u8>)>,
+}
+
+impl MidiTrackWriter {
+    fn new() -> Self {
+        let messages = Vec::new();
+        Self { messages }
+    }
+
+    fn note_on(&mut self, ticks: u64, channel: u8, key: u8, velocity: u8) {
+        self.messages
+            .push((ticks, vec![0x90 | (channel & 0x0F), key, velocity]));
+    }
+
+    fn note_off(&mut self, ticks: u64, channel: u8, key: u8) {
+        self.messages
+            .push((ticks, vec![0x80 | (channel & 0x0F), key, 0]));
+    }
+
+    fn control_change(&mut self, ticks: u64, channel: u8, controller: u8, value: u8) {
+        self.messages
+            .push((ticks, vec![0xB0 | (channel & 0x0F), controller, value]));
+    }
+
+    /// Write tempo and time signature meta events, followed by all messages and the
+    /// end of track meta event.
+    fn finish(
+        mut self,
+        micros_per_beat: u32,
+        time_signature: &TimeSignature,
+        end_ticks: u64,
+    ) -> Vec<u8> {
+        // sort by time, keeping the order of messages at the same time, but note-offs first
+        self.messages
+            .sort_by_key(|(ticks, message)| (*ticks, message[0] & 0xF0 != 0x80));
+
+        let mut track = Vec::new();
+        // tempo
+        track.push(0);
+        track.extend_from_slice(&[0xFF, 0x51, 0x03]);
+        track.extend_from_slice(&micros_per_beat.to_be_bytes()[1..]);
+        // time signature: numerator, denominator as power of two, MIDI clocks per metronome
+        // click (one click per felt beat, 24 clocks per quarter), 8 32ths per quarter
+        let numerator = time_signature.numerator().clamp(1, 255) as u8;
+        let denominator = time_signature.denominator().trailing_zeros() as u8;
+        let clocks_per_click = (time_signature.beat_length() * 24.0)
+            .round()
+            .clamp(1.0, 255.0) as u8;
+        track.push(0);
+        track.extend_from_slice(&[
+            0xFF,
+            0x58,
+            0x04,
+            numerator,
+            denominator,
+            clocks_per_click,
+            8,
+        ]);
+        // messages
+        let mut last_ticks = 0;
+        for (ticks, message) in &self.messages {
+            write_variable_length(&mut track, ticks - last_ticks);
+            track.extend_from_slice(message);
+            last_ticks = *ticks;
+        }
+        // end of track
+        write_variable_length(&mut track, end_ticks.saturating_sub(last_ticks));
+        track.extend_from_slice(&[0xFF, 0x2F, 0x00]);
+        track
+    }
+}
+
+/// Write a MIDI variable length quantity.
+fn write_variable_length(bytes: &mut Vec<u8>, value: u64) {
+    let value = value.min(0x0FFF_FFFF);
+    let mut buffer = [0_u8; 4];
+    let mut count = 0;
+    let mut remaining = value;
+    loop {
+        buffer[count] = (remaining & 0x7F) as u8;
+        count += 1;
+        remaining >>= 7;
+        if remaining == 0 {
+            break;
+        }
+    }
+    for index in (0..count).rev() {
+        let continuation = if index > 0 { 0x80 } else { 0 };
+        bytes.push(buffer[index] | continuation);
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{prelude::*, PatternSlot, Phrase, RhythmEvent};
+
+    /// Parse the messages of a format 0 MIDI file into (ticks, message bytes) tuples.
+    fn parse_midi(bytes: &[u8]) -> (u16, Vec<(u64, Vec<u8>)>) {
+        assert_eq!(&bytes[0..4], b"MThd");
+        assert_eq!(u16::from_be_bytes([bytes[8], bytes[9]]), 0);
+        assert_eq!(u16::from_be_bytes([bytes[10], bytes[11]]), 1);
+        let ticks_per_beat = u16::from_be_bytes([bytes[12], bytes[13]]);
+        assert_eq!(&bytes[14..18], b"MTrk");
+        let track_len = u32::from_be_bytes([bytes[18], bytes[19], bytes[20], bytes[21]]) as usize;
+        let track = &bytes[22..];
+        assert_eq!(track.len(), track_len);
+
+        let mut messages = Vec::new();
+        let mut ticks = 0;
+        let mut pos = 0;
+        while pos < track.len() {
+            let mut delta = 0_u64;
+            loop {
+                let byte = track[pos];
+                pos += 1;
+                delta = (delta << 7) | (byte & 0x7F) as u64;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            ticks += delta;
+            let len = if track[pos] == 0xFF {
+                3 + track[pos + 2] as usize
+            } else {
+                3
+            };
+            messages.push((ticks, track[pos..pos + len].to_vec()));
+            pos += len;
+        }
+        (ticks_per_beat, messages)
+    }
+
+    #[test]
+    fn midi_export() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let notes = time_base.every_nth_beat(1.0).emit(
+            vec![
+                new_note((Note::C4, InstrumentId::from(1), 0.5)),
+                new_note((Note::E4, None, 1.0, 0.0, 0.5)),
+                new_note(Note::OFF),
+            ]
+            .to_sequence_emitter(),
+        );
+        let parameters = time_base
+            .every_nth_bar(1.0)
+            .emit(new_parameter_change_emitter(ParameterId::from(2), 1.0));
+        let phrase = Phrase::new(
+            time_base,
+            vec![PatternSlot::from(notes), PatternSlot::from(parameters)],
+            BeatTimeStep::Bar(1.0),
+        );
+        let mut sequence = Sequence::new(time_base, vec![phrase]);
+
+        let bytes = sequence_to_midi(&mut sequence, 88200, None, &[(ParameterId::from(2), 7)]);
+        let (ticks_per_beat, messages) = parse_midi(&bytes);
+        assert_eq!(ticks_per_beat, MIDI_TICKS_PER_BEAT);
+        assert_eq!(
+            messages,
+            vec![
+                // 120 bpm, 4/4
+                (0, vec![0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20]),
+                (0, vec![0xFF, 0x58, 0x04, 4, 2, 24, 8]),
+                (0, vec![0x91, 48, 64]),
+                (0, vec![0xB0, 7, 127]),
+                // delayed note-on stops the previous note
+                (1440, vec![0x81, 48, 0]),
+                (1440, vec![0x90, 52, 127]),
+                (1920, vec![0x80, 52, 0]),
+                (2880, vec![0x91, 48, 64]),
+                (3840, vec![0x81, 48, 0]),
+                (3840, vec![0xFF, 0x2F, 0x00]),
+            ]
+        );
+    }
+
+    #[test]
+    fn midi_export_time_signature() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let notes = time_base
+            .every_nth_beat(1.0)
+            .emit(new_note_emitter(Note::C4));
+        let phrase = Phrase::new(
+            time_base,
+            vec![PatternSlot::from(notes)],
+            BeatTimeStep::Bar(1.0),
+        );
+        let mut sequence = Sequence::new(time_base, vec![phrase]);
+
+        for (time_signature, expected) in [
+            (TimeSignature::new(3, 4).unwrap(), [3, 2, 24, 8]),
+            (TimeSignature::new(6, 8).unwrap(), [6, 3, 36, 8]),
+            (TimeSignature::new(7, 8).unwrap(), [7, 3, 12, 8]),
+        ] {
+            let bytes = sequence_to_midi(&mut sequence, 44100, Some(time_signature), &[]);
+            let (_, messages) = parse_midi(&bytes);
+            assert_eq!(
+                messages[1].1,
+                [&[0xFF, 0x58, 0x04][..], &expected[..]].concat(),
+                "time signature {}",
+                time_signature
+            );
+        }
+    }
+
+    /// Holds the notes of the first pulse for the given number of pulses.
+    #[derive(Debug, Clone)]
+    struct FirstPulseHoldGate {
+        pulses: usize,
+        first_pulse: bool,
+    }
+
+    impl Gate for FirstPulseHoldGate {
+        fn set_time_base(&mut self, _time_base: &BeatTimeBase) {}
+        fn set_trigger_event(&mut self, _event: &Event) {}
+        fn set_parameters(&mut self, _parameters: ParameterSet) {}
+        fn run(&mut self, _pulse: &RhythmEvent) -> bool {
+            self.first_pulse = !self.first_pulse && self.pulses > 0;
+            true
+        }
+        fn hold_length(&self) -> Option<usize> {
+            self.first_pulse.then_some(self.pulses)
+        }
+        fn duplicate(&self) -> Box<dyn Gate> {
+            Box::new(self.clone())
+        }
+        fn reset(&mut self) {
+            self.first_pulse = false;
+        }
+    }
+
+    #[test]
+    fn midi_export_held_notes() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let notes = time_base
+            .every_nth_beat(1.0)
+            .with_gate(FirstPulseHoldGate {
+                pulses: 3,
+                first_pulse: false,
+            })
+            .emit(
+                vec![
+                    new_note(Note::C4),
+                    new_note(Note::C4),
+                    new_note(Note::C4),
+                    new_note(Note::E4),
+                ]
+                .to_sequence_emitter(),
+            );
+        let phrase = Phrase::new(
+            time_base,
+            vec![PatternSlot::from(notes)],
+            BeatTimeStep::Bar(1.0),
+        );
+        let mut sequence = Sequence::new(time_base, vec![phrase]);
+
+        let bytes = sequence_to_midi(&mut sequence, 88200, None, &[]);
+        let (_, messages) = parse_midi(&bytes);
+        assert_eq!(
+            messages,
+            vec![
+                (0, vec![0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20]),
+                (0, vec![0xFF, 0x58, 0x04, 4, 2, 24, 8]),
+                (0, vec![0x90, 48, 127]),
+                // retriggers stop held notes before their hold length ends
+                (960, vec![0x80, 48, 0]),
+                (960, vec![0x90, 48, 127]),
+                (1920, vec![0x80, 48, 0]),
+                (1920, vec![0x90, 48, 127]),
+                // the hold's note-off does not cut the retriggered note
+                (2880, vec![0x80, 48, 0]),
+                (2880, vec![0x90, 52, 127]),
+                (3840, vec![0x80, 52, 0]),
+                (3840, vec![0xFF, 0x2F, 0x00]),
+            ]
+        );
+    }
+
+    #[test]
+    fn variable_length() {
+        for (value, expected) in [
+            (0_u64, vec![0x00]),
+            (0x7F, vec![0x7F]),
+            (0x80, vec![0x81, 0x00]),
+            (0x2000, vec![0xC0, 0x00]),
+            (0x0FFF_FFFF, vec![0xFF, 0xFF, 0xFF, 0x7F]),
+        ] {
+            let mut bytes = Vec::new();
+            write_variable_length(&mut bytes, value);
+            assert_eq!(bytes, expected);
+        }
+    }
+}
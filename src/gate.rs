@@ -2,7 +2,7 @@
 
 use std::fmt::Debug;
 
-use crate::{BeatTimeBase, Event, ParameterSet, RhythmEvent};
+use crate::{BeatTimeBase, Event, NoteEvent, ParameterSet, RhythmEvent};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -25,9 +25,24 @@ pub trait Gate: Debug {
     /// Set or update optional, parameter map for callbacks.
     fn set_parameters(&mut self, parameters: ParameterSet);
 
+    /// Set or update the pattern's current, smoothed output level for callbacks.
+    /// Ignored by default.
+    fn set_output_level(&mut self, _level: f32) {}
+
+    /// Set or update the notes which currently are playing in the pattern's slot for callbacks.
+    /// Ignored by default.
+    fn set_playing_notes(&mut self, _notes: &[NoteEvent]) {}
+
     /// Returns if the event should be triggered.
     fn run(&mut self, pulse: &RhythmEvent) -> bool;
 
+    /// Number of pulses the notes, which got triggered by the last `run` call, should be held.
+    /// Held notes get stopped with note-offs after the given number of pulses. When None,
+    /// the default, notes play until the next notes get triggered.
+    fn hold_length(&self) -> Option<usize> {
+        None
+    }
+
     /// Create a new cloned instance of this gate. This actualy is a clone(), wrapped into
     /// a `Box<dyn Gate>`, but called 'duplicate' to avoid conflicts with possible
     /// Clone impls.
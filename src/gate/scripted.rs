@@ -1,8 +1,8 @@
 use mlua::prelude::LuaResult;
 
 use crate::{
-    bindings::{gate_trigger_from_value, LuaCallback, LuaTimeoutHook},
-    BeatTimeBase, Event, Gate, ParameterSet, RhythmEvent,
+    bindings::{gate_hold_from_value, LuaCallback, LuaTimeoutHook},
+    BeatTimeBase, Event, Gate, NoteEvent, ParameterSet, RhythmEvent,
 };
 
 // -------------------------------------------------------------------------------------------------
@@ -14,6 +14,7 @@ pub struct ScriptedGate {
     callback: LuaCallback,
     pulse_step: usize,
     pulse_time_step: f64,
+    hold_length: Option<usize>,
 }
 
 impl ScriptedGate {
@@ -33,16 +34,18 @@ impl ScriptedGate {
         };
         let pulse_step = 0;
         let pulse_time_step = 0.0;
+        let hold_length = None;
         callback.set_gate_context(time_base, pulse, pulse_step, pulse_time_step)?;
         Ok(Self {
             timeout_hook,
             callback,
             pulse_step,
             pulse_time_step,
+            hold_length,
         })
     }
 
-    fn next_gate_trigger_value(&mut self, pulse: &RhythmEvent) -> LuaResult<bool> {
+    fn next_gate_trigger_value(&mut self, pulse: &RhythmEvent) -> LuaResult<(bool, Option<usize>)> {
         // reset timeout
         self.timeout_hook.reset();
         // update context
@@ -50,7 +53,7 @@ impl ScriptedGate {
         self.callback
             .set_context_pulse_step(self.pulse_step, self.pulse_time_step)?;
         // invoke callback and evaluate the result
-        gate_trigger_from_value(&self.callback.call()?)
+        gate_hold_from_value(&self.callback.call()?)
     }
 }
 
@@ -61,6 +64,7 @@ impl Clone for ScriptedGate {
             callback: self.callback.clone(),
             pulse_step: self.pulse_step,
             pulse_time_step: self.pulse_time_step,
+            hold_length: self.hold_length,
         }
     }
 }
@@ -93,15 +97,34 @@ impl Gate for ScriptedGate {
         }
     }
 
+    fn set_output_level(&mut self, level: f32) {
+        // reset timeout
+        self.timeout_hook.reset();
+        // update function context with the new level
+        if let Err(err) = self.callback.set_context_output_level(level) {
+            self.callback.handle_error(&err);
+        }
+    }
+
+    fn set_playing_notes(&mut self, notes: &[NoteEvent]) {
+        // reset timeout
+        self.timeout_hook.reset();
+        // update function context with the new notes
+        if let Err(err) = self.callback.set_context_playing_notes(notes) {
+            self.callback.handle_error(&err);
+        }
+    }
+
     fn run(&mut self, pulse: &RhythmEvent) -> bool {
         // call function with context and evaluate the result
-        let result = match self.next_gate_trigger_value(pulse) {
+        let (result, hold_length) = match self.next_gate_trigger_value(pulse) {
             Err(err) => {
                 self.callback.handle_error(&err);
-                false
+                (false, None)
             }
             Ok(value) => value,
         };
+        self.hold_length = hold_length;
         // move step for the next iter call
         self.pulse_step += 1;
         self.pulse_time_step += pulse.step_time;
@@ -109,6 +132,10 @@ impl Gate for ScriptedGate {
         result
     }
 
+    fn hold_length(&self) -> Option<usize> {
+        self.hold_length
+    }
+
     fn duplicate(&self) -> Box<dyn Gate> {
         Box::new(self.clone())
     }
@@ -119,6 +146,7 @@ impl Gate for ScriptedGate {
         // reset step counter
         self.pulse_step = 0;
         self.pulse_time_step = 0.0;
+        self.hold_length = None;
         // update step in context
         if let Err(err) = self
             .callback
@@ -126,6 +154,10 @@ impl Gate for ScriptedGate {
         {
             self.callback.handle_error(&err);
         }
+        // restore random number generator
+        if let Err(err) = self.callback.reset_context_rng() {
+            self.callback.handle_error(&err);
+        }
         // reset function
         if let Err(err) = self.callback.reset() {
             self.callback.handle_error(&err);
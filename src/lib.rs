@@ -33,6 +33,8 @@ compile_error!(
 // -------------------------------------------------------------------------------------------------
 
 // Internal mods
+mod accent;
+mod debug_output;
 mod emitter;
 mod event;
 mod gate;
@@ -43,27 +45,37 @@ mod phrase;
 mod pulse;
 mod rhythm;
 mod sequence;
+mod swing;
 mod tidal;
 mod time;
 
 // Re-Exported basic Traits and Types
 pub use crate::{
+    accent::Accent,
+    debug_output::DebugOutput,
     emitter::{Emitter, EmitterEvent},
-    event::{Event, EventTransform, InstrumentId, NoteEvent, ParameterChangeEvent, ParameterId},
+    event::{
+        Event, EventTransform, InstrumentId, NoteEvent, NoteFilter, ParameterChangeEvent,
+        ParameterId,
+    },
     gate::Gate,
-    note::{chord::Chord, scale::Scale, Note},
+    note::{chord::Chord, scale::Scale, Note, TransposeMode},
     parameter::{Parameter, ParameterSet, ParameterType},
-    pattern::{Pattern, PatternEvent},
+    pattern::{Pattern, PatternEvent, PatternWarning},
     phrase::{PatternSlot, Phrase},
     pulse::Pulse,
-    rhythm::{Rhythm, RhythmEvent},
+    rhythm::{euclidean::euclidean, Rhythm, RhythmEvent},
     sequence::Sequence,
+    swing::Swing,
     tidal::{
-        Cycle, Event as CycleEvent, Span as CycleSpan, Target as CycleTarget, Value as CycleValue,
+        operators as cycle_operators, tokenize as tokenize_cycle, Cycle, Event as CycleEvent,
+        OperatorInfo as CycleOperator, OperatorKind as CycleOperatorKind, Signal as CycleSignal,
+        Span as CycleSpan, SyntaxError as CycleSyntaxError, Target as CycleTarget,
+        Token as CycleToken, TokenKind as CycleTokenKind, Value as CycleValue,
     },
     time::{
         BeatTimeBase, BeatTimeStep, ExactSampleTime, SampleTime, SampleTimeBase, SampleTimeDisplay,
-        SecondTimeBase,
+        SampleTimeRounding, SecondTimeBase, TimeSignature,
     },
 };
 
@@ -96,6 +108,9 @@ pub mod emitters {
 // Public modules
 #[cfg(feature = "scripting")]
 pub mod bindings;
+pub mod export;
+#[cfg(feature = "osc")]
+pub mod osc;
 #[cfg(feature = "player")]
 pub mod player;
 
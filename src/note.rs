@@ -162,6 +162,33 @@ pub enum Note {
 }
 
 impl Note {
+    /// Default reference frequency of the note A4 in Hz.
+    pub const DEFAULT_A4_FREQUENCY: f32 = 440.0;
+
+    /// Get the equal tempered frequency of the note in Hz, using the given frequency of A4 as
+    /// reference (usually [`Self::DEFAULT_A4_FREQUENCY`]).
+    /// Returns None when the note is not a note on value.
+    pub fn to_frequency(&self, a4_hz: f32) -> Option<f32> {
+        if !self.is_note_on() {
+            return None;
+        }
+        let semitones = *self as i32 - Note::A4 as i32;
+        Some(a4_hz * 2.0_f32.powf(semitones as f32 / 12.0))
+    }
+
+    /// Create a note from the given frequency in Hz, using the given frequency of A4 as reference.
+    /// The frequency gets rounded to the nearest equal tempered note. Frequencies which exit the
+    /// valid MIDI note range, including zero, negative or NaN frequencies, get clamped to it.
+    pub fn from_frequency(hz: f32, a4_hz: f32) -> Self {
+        let semitones = 12.0 * (hz / a4_hz).log2();
+        let note = (Note::A4 as i32 as f32 + semitones).round();
+        if note.is_nan() {
+            Note::C0
+        } else {
+            Note::from(note.clamp(0.0, 127.0) as u8)
+        }
+    }
+
     /// returns if this note value is a note-on.
     pub fn is_note_on(&self) -> bool {
         *self != Note::OFF && *self != Note::EMPTY
@@ -184,16 +211,58 @@ impl Note {
         *self as u8 / 12
     }
 
-    /// return a new transposed note with the given offset.
+    /// return a new transposed note with the given offset. Notes which exit the valid MIDI
+    /// note range get clamped to the range. See [`Self::transposed_with_mode`] for other modes.
     #[must_use]
     pub fn transposed(&self, offset: i32) -> Self {
+        self.transposed_with_mode(offset, TransposeMode::Clamp)
+            .expect("Clamped notes should never get dropped")
+    }
+
+    /// return a new transposed note with the given offset, using the given mode to handle
+    /// notes which exit the valid MIDI note range. Returns None when the note got dropped.
+    /// Note-offs and empty notes are never transposed.
+    #[must_use]
+    pub fn transposed_with_mode(&self, offset: i32, mode: TransposeMode) -> Option<Self> {
+        const RANGE: std::ops::RangeInclusive<i32> = 0..=0x7f;
         match self {
-            Self::OFF | Self::EMPTY => *self,
-            _ => Note::from((*self as i32 + offset).clamp(0, 0x7f) as u8),
+            Self::OFF | Self::EMPTY => Some(*self),
+            _ => {
+                let mut note = (*self as i32).saturating_add(offset);
+                if !RANGE.contains(&note) {
+                    match mode {
+                        TransposeMode::Clamp => note = note.clamp(*RANGE.start(), *RANGE.end()),
+                        TransposeMode::Wrap => {
+                            // move by octaves into the range, keeping the note's key
+                            if note < *RANGE.start() {
+                                note += (*RANGE.start() - note + 11) / 12 * 12;
+                            } else {
+                                note -= (note - *RANGE.end() + 11) / 12 * 12;
+                            }
+                        }
+                        TransposeMode::Drop => return None,
+                    }
+                }
+                Some(Note::from(note as u8))
+            }
         }
     }
 }
 
+// -------------------------------------------------------------------------------------------------
+
+/// Handling of notes which get transposed out of the valid MIDI note range \[0 - 127\].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum TransposeMode {
+    /// Clamp notes to the lowest or highest valid note.
+    #[default]
+    Clamp,
+    /// Move notes by octaves into the valid range, so they keep their key.
+    Wrap,
+    /// Drop notes which exit the valid range.
+    Drop,
+}
+
 impl TryFrom<&str> for Note {
     type Error = String;
 
@@ -389,7 +458,7 @@ impl Display for Note {
 
 #[cfg(test)]
 mod test {
-    use super::Note;
+    use super::{Note, TransposeMode};
 
     #[test]
     fn note_number_conversion() {
@@ -405,6 +474,77 @@ mod test {
         assert_eq!(u8::from(Note::OFF), 0xFF);
     }
 
+    #[test]
+    fn transpose_modes() {
+        // notes within the range are transposed in all modes
+        for mode in [
+            TransposeMode::Clamp,
+            TransposeMode::Wrap,
+            TransposeMode::Drop,
+        ] {
+            assert_eq!(Note::C4.transposed_with_mode(12, mode), Some(Note::C5));
+            assert_eq!(Note::C4.transposed_with_mode(-48, mode), Some(Note::C0));
+            assert_eq!(Note::C4.transposed_with_mode(79, mode), Some(Note::G10));
+            assert_eq!(Note::OFF.transposed_with_mode(200, mode), Some(Note::OFF));
+            assert_eq!(
+                Note::EMPTY.transposed_with_mode(-200, mode),
+                Some(Note::EMPTY)
+            );
+        }
+        // clamp
+        let mode = TransposeMode::Clamp;
+        assert_eq!(Note::C4.transposed_with_mode(80, mode), Some(Note::G10));
+        assert_eq!(Note::C4.transposed_with_mode(-49, mode), Some(Note::C0));
+        assert_eq!(
+            Note::C4.transposed_with_mode(i32::MAX, mode),
+            Some(Note::G10)
+        );
+        assert_eq!(Note::C4.transposed(-100), Note::C0);
+        // wrap
+        let mode = TransposeMode::Wrap;
+        assert_eq!(Note::C4.transposed_with_mode(80, mode), Some(Note::Gs9));
+        assert_eq!(Note::C4.transposed_with_mode(91, mode), Some(Note::G10));
+        assert_eq!(Note::C4.transposed_with_mode(92, mode), Some(Note::Gs9));
+        assert_eq!(Note::C4.transposed_with_mode(-49, mode), Some(Note::B0));
+        assert_eq!(Note::C4.transposed_with_mode(-60, mode), Some(Note::C0));
+        assert_eq!(Note::C4.transposed_with_mode(-61, mode), Some(Note::B0));
+        // drop
+        let mode = TransposeMode::Drop;
+        assert_eq!(Note::C4.transposed_with_mode(80, mode), None);
+        assert_eq!(Note::C4.transposed_with_mode(-49, mode), None);
+    }
+
+    #[test]
+    fn note_frequencies() {
+        let a4 = Note::DEFAULT_A4_FREQUENCY;
+        assert_eq!(Note::A4.to_frequency(a4), Some(440.0));
+        assert_eq!(Note::A5.to_frequency(a4), Some(880.0));
+        assert_eq!(Note::A3.to_frequency(432.0), Some(216.0));
+        assert!((Note::C4.to_frequency(a4).unwrap() - 261.6256).abs() < 1e-3);
+        assert_eq!(Note::OFF.to_frequency(a4), None);
+        assert_eq!(Note::EMPTY.to_frequency(a4), None);
+
+        assert_eq!(Note::from_frequency(440.0, a4), Note::A4);
+        assert_eq!(Note::from_frequency(432.0, 432.0), Note::A4);
+        assert_eq!(Note::from_frequency(261.0, a4), Note::C4);
+        assert_eq!(Note::from_frequency(269.0, a4), Note::C4);
+        assert_eq!(Note::from_frequency(270.0, a4), Note::Cs4);
+        for note in 0..=127_u8 {
+            let note = Note::from(note);
+            assert_eq!(
+                Note::from_frequency(note.to_frequency(a4).unwrap(), a4),
+                note
+            );
+        }
+        // out of range
+        assert_eq!(Note::from_frequency(1.0, a4), Note::C0);
+        assert_eq!(Note::from_frequency(0.0, a4), Note::C0);
+        assert_eq!(Note::from_frequency(-100.0, a4), Note::C0);
+        assert_eq!(Note::from_frequency(f32::NAN, a4), Note::C0);
+        assert_eq!(Note::from_frequency(100000.0, a4), Note::G10);
+        assert_eq!(Note::from_frequency(f32::INFINITY, a4), Note::G10);
+    }
+
     #[test]
     fn note_serialization() {
         assert_eq!(Note::C4.to_string(), "C4");
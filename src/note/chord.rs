@@ -180,6 +180,15 @@ lazy_static! {
 // --------------------------------------------------------------------------------------------------
 
 /// Note vector, created from a root [`Note`] and intervals.
+///
+/// Chord voicings can be changed via inversions, drop voicings and open voicings. In chord
+/// strings, voicings are appended to the chord mode, separated by `'` characters:
+/// - `i`, `ii`, `iii` or `i<N>`: inversion, moves the lowest note(s) up an octave.
+/// - `d<N>`: drop-N voicing, moves the N-th highest note down an octave, e.g. `d2`, `d3`.
+/// - `o` or `o<N>`: open voicing, moves every second note up by one or N octaves.
+///
+/// e.g. `"c4'maj7'i"` is a first inversion major seventh chord and `"c4'maj7'd2"` its drop-2
+/// voicing. Voicings get applied in the given order.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Chord {
     note: Note,
@@ -232,12 +241,12 @@ impl Chord {
     }
 
     /// Try converting the given string to a note and mode string tuple.
-    /// mode must be one of `Chord::names()`
+    /// mode must be one of `Chord::names()`, optionally followed by voicings.
     pub fn from_mode_string<N: Into<Note>>((note, mode): (N, &str)) -> Result<Self, String> {
         Self::try_from((note, mode))
     }
 
-    /// Root note.
+    /// Root note. For chords with drop voicings, this is the chord's lowest note.
     pub fn note(&self) -> Note {
         self.note
     }
@@ -246,27 +255,131 @@ impl Chord {
     pub fn intervals(&self) -> &[u8] {
         &self.intervals
     }
+
+    /// Return a new chord with the given inversion: moves the lowest note up an octave,
+    /// `inversion` times.
+    #[must_use]
+    pub fn with_inversion(self, inversion: usize) -> Self {
+        self.with_voiced_notes(|notes| {
+            // every chord size inversions move the whole chord up an octave
+            let octave_offset = Self::octave_offset(inversion / notes.len());
+            for _ in 0..inversion % notes.len() {
+                notes.sort();
+                notes[0] += 12;
+            }
+            for note in notes.iter_mut() {
+                *note += octave_offset;
+            }
+        })
+    }
+
+    /// Return a new chord with a drop voicing: moves the `drop`-th highest note down an
+    /// octave, e.g. 2 for drop-2 or 3 for drop-3 voicings. Out of range values are ignored.
+    #[must_use]
+    pub fn with_drop(self, drop: usize) -> Self {
+        self.with_voiced_notes(|notes| {
+            if (1..=notes.len()).contains(&drop) {
+                notes.sort();
+                let index = notes.len() - drop;
+                notes[index] -= 12;
+            }
+        })
+    }
+
+    /// Return a new chord with an open voicing: moves every second note up by the given
+    /// number of octaves.
+    #[must_use]
+    pub fn with_open_voicing(self, octaves: usize) -> Self {
+        self.with_voiced_notes(|notes| {
+            notes.sort();
+            for note in notes.iter_mut().skip(1).step_by(2) {
+                *note += Self::octave_offset(octaves);
+            }
+        })
+    }
+
+    /// Note offset of the given number of octaves. Offsets larger than the valid note range
+    /// are limited to the note range, as resulting notes get clamped anyway.
+    fn octave_offset(octaves: usize) -> i32 {
+        const MAX_OCTAVES: usize = 0x80 / 12 + 1;
+        12 * octaves.min(MAX_OCTAVES) as i32
+    }
+
+    /// Return a new chord with the given voicing string applied, e.g. `"i"` or `"d2"`.
+    fn with_voicing(self, voicing: &str) -> Result<Self, String> {
+        let invalid_voicing = || {
+            format!(
+                "invalid chord voicing '{}', valid voicings are: \
+                'i', 'ii', 'iii' or 'i<N>' (inversion), 'd<N>' (drop) or 'o', 'o<N>' (open)",
+                voicing
+            )
+        };
+        let number = |str: &str, default: usize| {
+            if str.is_empty() {
+                Ok(default)
+            } else {
+                str.parse::<usize>().map_err(|_| invalid_voicing())
+            }
+        };
+        if !voicing.is_empty() && voicing.chars().all(|c| c == 'i') {
+            Ok(self.with_inversion(voicing.len()))
+        } else if let Some(inversion) = voicing.strip_prefix('i') {
+            Ok(self.with_inversion(number(inversion, 1)?))
+        } else if let Some(drop) = voicing.strip_prefix('d') {
+            Ok(self.with_drop(number(drop, 2)?))
+        } else if let Some(octaves) = voicing.strip_prefix('o') {
+            Ok(self.with_open_voicing(number(octaves, 1)?))
+        } else {
+            Err(invalid_voicing())
+        }
+    }
+
+    /// Apply the given voicing function to the chord's notes and rebase the intervals.
+    fn with_voiced_notes<F: FnOnce(&mut Vec<i32>)>(self, voice: F) -> Self {
+        if self.intervals.is_empty() || !self.note.is_note_on() {
+            return self;
+        }
+        let mut notes = self
+            .intervals
+            .iter()
+            .map(|i| self.note as i32 + *i as i32)
+            .collect::<Vec<_>>();
+        voice(&mut notes);
+        notes.sort();
+        // keep notes in a valid range, when shifting them below the lowest note
+        let mut base = notes[0];
+        while base < 0 {
+            base += 12;
+        }
+        let offset = base - notes[0];
+        let intervals = notes
+            .iter()
+            .map(|n| (n + offset - base).clamp(0, u8::MAX as i32) as u8)
+            .collect();
+        Self {
+            note: Note::from(base.clamp(0, 0x7f) as u8),
+            intervals,
+        }
+    }
 }
 
 impl TryFrom<&str> for Chord {
     type Error = String;
 
     fn try_from(s: &str) -> Result<Self, String> {
-        let mut splits = s.split('\'');
-        if let Some(note_part) = splits.next() {
-            if let Some(chord_part) = splits.next() {
-                if splits.next().is_some() {
-                    return Err(
-                        "invalid chord string (found more than one ' character)".to_string()
-                    );
-                }
-                let note = Note::try_from(note_part)?;
-                let intervals = CHORD_TABLE.get(chord_part).ok_or(format!(
-                    "invalid chord mode, valid modes are: {}",
-                    Chord::names().join(",")
-                ))?;
-                return Ok(Self::new(note, intervals.clone()));
+        if let Some((note_part, chord_part)) = s.split_once('\'') {
+            let note = Note::try_from(note_part)?;
+            let mut splits = chord_part.split('\'');
+            let mode = splits.next().unwrap_or_default();
+            let intervals = CHORD_TABLE.get(mode).ok_or(format!(
+                "invalid chord mode, valid modes are: {}",
+                Chord::names().join(",")
+            ))?;
+            let mut chord = Self::new(note, intervals.clone());
+            for voicing in splits {
+                chord = chord.with_voicing(voicing)?;
             }
+            return Ok(chord);
         }
         Err("invalid chord string: \
           expecting a note and chord mode, separated by a ' character e.g. \"c4'maj\""
@@ -281,11 +394,17 @@ where
     type Error = String;
 
     fn try_from((note, mode): (N, &str)) -> Result<Self, String> {
+        let mut splits = mode.split('\'');
+        let mode = splits.next().unwrap_or_default();
         let intervals = CHORD_TABLE.get(mode).ok_or(format!(
             "Invalid chord mode, valid chords are: {}",
             Chord::names().join(",")
         ))?;
-        Ok(Self::new(note, intervals.clone()))
+        let mut chord = Self::new(note, intervals.clone());
+        for voicing in splits {
+            chord = chord.with_voicing(voicing)?;
+        }
+        Ok(chord)
     }
 }
 
@@ -380,4 +499,85 @@ mod test {
         );
         Ok(())
     }
+
+    #[test]
+    fn chord_voicings() -> Result<(), String> {
+        let notes = |chord: Chord| {
+            chord
+                .intervals()
+                .iter()
+                .map(|i| chord.note().transposed(*i as i32))
+                .collect::<Vec<_>>()
+        };
+        let maj7 = Chord::try_from((Note::C4, "maj7"))?;
+        assert_eq!(
+            notes(maj7.clone()),
+            [Note::C4, Note::E4, Note::G4, Note::B4]
+        );
+        // inversions move the lowest notes up an octave
+        assert_eq!(
+            notes(maj7.clone().with_inversion(1)),
+            [Note::E4, Note::G4, Note::B4, Note::C5]
+        );
+        assert_eq!(
+            notes(maj7.clone().with_inversion(2)),
+            [Note::G4, Note::B4, Note::C5, Note::E5]
+        );
+        assert_eq!(
+            notes(maj7.clone().with_inversion(4)),
+            [Note::C5, Note::E5, Note::G5, Note::B5]
+        );
+        // drop voicings move the n-th highest note down an octave
+        assert_eq!(
+            notes(maj7.clone().with_drop(2)),
+            [Note::G3, Note::C4, Note::E4, Note::B4]
+        );
+        assert_eq!(
+            notes(maj7.clone().with_drop(3)),
+            [Note::E3, Note::C4, Note::G4, Note::B4]
+        );
+        assert_eq!(maj7.clone().with_drop(5), maj7);
+        // open voicings move every second note up
+        assert_eq!(
+            notes(maj7.clone().with_open_voicing(1)),
+            [Note::C4, Note::G4, Note::E5, Note::B5]
+        );
+        // voicing strings
+        assert_eq!(
+            Chord::try_from("c4'maj7'i")?,
+            maj7.clone().with_inversion(1)
+        );
+        assert_eq!(
+            Chord::try_from((Note::C4, "maj7'ii"))?,
+            maj7.clone().with_inversion(2)
+        );
+        assert_eq!(
+            Chord::try_from("c4'maj7'i3")?,
+            maj7.clone().with_inversion(3)
+        );
+        assert_eq!(Chord::try_from("c4'maj7'd2")?, maj7.clone().with_drop(2));
+        assert_eq!(
+            Chord::try_from("c4'maj7'i'o2")?,
+            maj7.clone().with_inversion(1).with_open_voicing(2)
+        );
+        assert!(Chord::try_from("c4'maj7'x").is_err());
+        assert!(Chord::try_from("c4'maj7'd").is_ok());
+        assert!(Chord::try_from("c4'maj7'").is_err());
+        // notes stay in range
+        let low_chord = Chord::try_from((Note::C0, "maj'd1"))?;
+        assert!(low_chord.intervals().iter().all(|i| *i < 12));
+        // huge voicings don't hang or overflow
+        assert_eq!(
+            maj7.clone().with_inversion(usize::MAX),
+            maj7.clone()
+                .with_inversion(usize::MAX % 4)
+                .with_inversion(4 * 11)
+        );
+        assert!(maj7
+            .clone()
+            .with_open_voicing(usize::MAX)
+            .note()
+            .is_note_on());
+        Ok(())
+    }
 }
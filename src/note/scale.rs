@@ -444,6 +444,34 @@ impl Scale {
             .collect()
     }
 
+    /// Quantize the given note to the nearest note in the scale. When the note is exactly between
+    /// two scale notes, the lower note is picked. Note-offs and empty notes are passed through.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use pattrns::{Note, Scale};
+    /// let cmin = Scale::try_from((Note::C4, "minor")).unwrap();
+    /// assert_eq!(cmin.quantize(Note::E4), Note::Ds4);
+    /// assert_eq!(cmin.quantize(Note::E4.transposed(12)), Note::Ds5);
+    /// ```
+    pub fn quantize(&self, note: Note) -> Note {
+        if !note.is_note_on() {
+            return note;
+        }
+        let note = note as i32;
+        let lower = self.quantize_note(note);
+        let upper = (note..=note + 12)
+            .find(|n| self.mode.degrees[self.transposed_note_to_step(*n)] != 0)
+            .unwrap_or(lower);
+        let quantized = if (upper - note < note - lower && upper <= 0x7F) || lower < 0 {
+            upper
+        } else {
+            lower
+        };
+        Note::from(quantized.clamp(0, 0x7F) as u8)
+    }
+
     /// Transpose the given note into this scale, using the most strict strictness level.
     pub fn transpose(&self, note: Note, offset: i32) -> Note {
         self.transpose_with_strictness(note, offset, TransposeStrictness::ForceAllNotes)
@@ -665,6 +693,34 @@ mod test {
         );
     }
 
+    #[test]
+    fn quantize() -> Result<(), String> {
+        let cmin = Scale::new(Note::C4, Mode::try_from("minor")?);
+        // in-scale notes are kept
+        for note in cmin.notes() {
+            assert_eq!(cmin.quantize(note), note);
+        }
+        // out-of-scale notes move to the nearest scale note, ties move down
+        assert_eq!(cmin.quantize(Note::Cs4), Note::C4);
+        assert_eq!(cmin.quantize(Note::E4), Note::Ds4);
+        assert_eq!(cmin.quantize(Note::Fs4), Note::F4);
+        assert_eq!(cmin.quantize(Note::B4), Note::As4);
+        // works in all octaves and with transposed notes
+        assert_eq!(cmin.quantize(Note::E4.transposed(-24)), Note::Ds2);
+        assert_eq!(cmin.quantize(Note::C4.transposed(200)), Note::G10);
+        // pentatonic scales with larger gaps
+        let pentatonic = Scale::new(Note::C4, Mode::try_from("pentatonic major")?);
+        assert_eq!(pentatonic.quantize(Note::F4), Note::E4);
+        assert_eq!(pentatonic.quantize(Note::Gs4), Note::G4);
+        assert_eq!(pentatonic.quantize(Note::As4), Note::A4);
+        assert_eq!(pentatonic.quantize(Note::B4), Note::C5);
+        assert_eq!(pentatonic.quantize(Note::B0), Note::C1);
+        // note-offs and empty notes are passed through
+        assert_eq!(cmin.quantize(Note::OFF), Note::OFF);
+        assert_eq!(cmin.quantize(Note::EMPTY), Note::EMPTY);
+        Ok(())
+    }
+
     #[test]
     fn chord() -> Result<(), String> {
         let scale = Scale::new(Note::C4, Mode::try_from("major")?);
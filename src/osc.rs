@@ -0,0 +1,353 @@
+//! Forward [`PatternEvent`]s to other software via OSC (Open Sound Control) over UDP.
+
+use std::{
+    collections::HashMap,
+    io,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+};
+
+use crate::{
+    phrase::PatternIndex, Event, InstrumentId, Note, PatternEvent, SampleTime, SampleTimeBase,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// A single OSC message argument.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OscArgument {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+}
+
+impl OscArgument {
+    fn type_tag(&self) -> char {
+        match self {
+            Self::Int(_) => 'i',
+            Self::Long(_) => 'h',
+            Self::Float(_) => 'f',
+            Self::Double(_) => 'd',
+            Self::String(_) => 's',
+        }
+    }
+}
+
+/// Encode an OSC message with the given address pattern and arguments into a UDP packet.
+pub fn encode_osc_message(address: &str, arguments: &[OscArgument]) -> Vec<u8> {
+    fn write_string(buffer: &mut Vec<u8>, string: &str) {
+        // null terminated and padded to a multiple of 4 bytes
+        buffer.extend_from_slice(string.as_bytes());
+        buffer.resize((buffer.len() + 4) & !3, 0);
+    }
+    let mut buffer = Vec::with_capacity(64);
+    write_string(&mut buffer, address);
+    let type_tags = std::iter::once(',')
+        .chain(arguments.iter().map(OscArgument::type_tag))
+        .collect::<String>();
+    write_string(&mut buffer, &type_tags);
+    for argument in arguments {
+        match argument {
+            OscArgument::Int(value) => buffer.extend_from_slice(&value.to_be_bytes()),
+            OscArgument::Long(value) => buffer.extend_from_slice(&value.to_be_bytes()),
+            OscArgument::Float(value) => buffer.extend_from_slice(&value.to_be_bytes()),
+            OscArgument::Double(value) => buffer.extend_from_slice(&value.to_be_bytes()),
+            OscArgument::String(value) => write_string(&mut buffer, value),
+        }
+    }
+    buffer
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Sends [`PatternEvent`]s as OSC messages via UDP to the given target address.
+///
+/// Note events are sent as `/note` messages with the arguments `time` (double, the event's
+/// sample time in seconds, including note delays), `note` (int, with C4 = 48), `velocity`
+/// (float, the note's volume) and `channel` (int, the note's instrument id or -1). Note-offs are
+/// sent as `/note` messages of the stopped note with a zero velocity.
+///
+/// Parameter change events are sent as `/param` messages with the arguments `time` (double),
+/// `id` (int64, the bits of the 64-bit parameter id or -1) and `value` (float).
+///
+/// ```rust,no_run
+/// use pattrns::{osc::OscSink, prelude::*};
+///
+/// let time_base = BeatTimeBase {
+///     beats_per_min: 120.0,
+///     beats_per_bar: 4,
+///     samples_per_sec: 44100,
+/// };
+/// let pattern = time_base.every_nth_beat(1.0).emit(new_note_emitter("c4"));
+/// let phrase = Phrase::new(time_base, vec![pattern], BeatTimeStep::Bar(1.0));
+/// let mut sequence = Sequence::new(time_base, vec![phrase]);
+///
+/// let mut sink = OscSink::new("127.0.0.1:9000", time_base).unwrap();
+/// sequence.consume_events_until_time(44100, &mut |pattern_index, event| {
+///     sink.send_event(pattern_index, &event).unwrap_or_else(|err| {
+///         log::warn!("Failed to send OSC message: {err}");
+///     });
+/// });
+/// ```
+#[derive(Debug)]
+pub struct OscSink {
+    socket: UdpSocket,
+    target: SocketAddr,
+    time_base: Box<dyn SampleTimeBase>,
+    playing_notes: HashMap<(PatternIndex, usize), (Note, Option<InstrumentId>)>,
+}
+
+impl OscSink {
+    /// Create a new sink which sends messages to the given target address, converting event
+    /// sample times to seconds with the given time base.
+    ///
+    /// ### Errors
+    /// Returns an error if the target address is invalid or no UDP socket can be bound.
+    pub fn new<A: ToSocketAddrs, T: SampleTimeBase + 'static>(
+        target: A,
+        time_base: T,
+    ) -> io::Result<Self> {
+        let target = target.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Invalid OSC target address")
+        })?;
+        let local_address = if target.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local_address)?;
+        let time_base = Box::new(time_base);
+        let playing_notes = HashMap::new();
+        Ok(Self {
+            socket,
+            target,
+            time_base,
+            playing_notes,
+        })
+    }
+
+    /// The address messages get sent to.
+    pub fn target(&self) -> SocketAddr {
+        self.target
+    }
+
+    /// Convert the given pattern event to OSC messages and send them.
+    ///
+    /// ### Errors
+    /// Returns an error if sending a message failed.
+    pub fn send_event(
+        &mut self,
+        pattern_index: PatternIndex,
+        event: &PatternEvent,
+    ) -> io::Result<()> {
+        for message in self.event_messages(pattern_index, event) {
+            self.socket.send_to(&message, self.target)?;
+        }
+        Ok(())
+    }
+
+    /// Stop all playing notes at the given sample time and forget them, e.g. when restarting
+    /// the sequence.
+    ///
+    /// ### Errors
+    /// Returns an error if sending a note-off message failed. Playing notes get forgotten anyway.
+    pub fn reset(&mut self, time: SampleTime) -> io::Result<()> {
+        let seconds = self.time_base.samples_to_seconds(time);
+        let mut result = Ok(());
+        for (_, (note, instrument)) in self.playing_notes.drain() {
+            let message = Self::note_message(OscArgument::Double(seconds), note, 0.0, instrument);
+            if let Err(err) = self.socket.send_to(&message, self.target) {
+                result = Err(err);
+            }
+        }
+        result
+    }
+
+    /// Convert the given pattern event to encoded OSC messages.
+    fn event_messages(
+        &mut self,
+        pattern_index: PatternIndex,
+        event: &PatternEvent,
+    ) -> Vec<Vec<u8>> {
+        let time_base = &self.time_base;
+        let seconds = |time: SampleTime| OscArgument::Double(time_base.samples_to_seconds(time));
+        let mut messages = Vec::new();
+        match &event.event {
+            Some(Event::NoteEvents(note_events)) => {
+                for (voice_index, note_event) in note_events.iter().enumerate() {
+                    let note_event = match note_event {
+                        Some(note_event) => note_event,
+                        None => continue,
+                    };
+                    let time = seconds(event.note_event_time(note_event));
+                    let voice = (pattern_index, voice_index);
+                    // stop playing notes on note-offs and new notes in the same voice
+                    if note_event.note.is_note_off() || note_event.note.is_note_on() {
+                        if let Some((note, instrument)) = self.playing_notes.remove(&voice) {
+                            messages.push(Self::note_message(time.clone(), note, 0.0, instrument));
+                        }
+                    }
+                    if note_event.note.is_note_on() {
+                        messages.push(Self::note_message(
+                            time,
+                            note_event.note,
+                            note_event.volume,
+                            note_event.instrument,
+                        ));
+                        self.playing_notes
+                            .insert(voice, (note_event.note, note_event.instrument));
+                    }
+                }
+            }
+            Some(Event::ParameterChangeEvent(change)) => {
+                // 64-bit ids, including hashed ids, are sent as they are as int64 bits
+                let id = change.parameter.map_or(-1, |id| u64::from(id) as i64);
+                messages.push(encode_osc_message(
+                    "/param",
+                    &[
+                        seconds(event.time),
+                        OscArgument::Long(id),
+                        OscArgument::Float(change.value),
+                    ],
+                ));
+            }
+            None => (),
+        }
+        messages
+    }
+
+    fn note_message(
+        time: OscArgument,
+        note: Note,
+        velocity: f32,
+        instrument: Option<InstrumentId>,
+    ) -> Vec<u8> {
+        let channel = instrument.map_or(-1, |id| usize::from(id).try_into().unwrap_or(i32::MAX));
+        encode_osc_message(
+            "/note",
+            &[
+                time,
+                OscArgument::Int(note as i32),
+                OscArgument::Float(velocity.max(0.0)),
+                OscArgument::Int(channel),
+            ],
+        )
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{prelude::*, ParameterChangeEvent};
+
+    #[test]
+    fn encode_messages() {
+        // address and type tags get null terminated and padded to 4 bytes
+        assert_eq!(
+            encode_osc_message("/note", &[OscArgument::Int(48), OscArgument::Float(0.5)]),
+            [
+                b"/note\0\0\0".as_slice(),
+                b",if\0",
+                &48_i32.to_be_bytes(),
+                &0.5_f32.to_be_bytes()
+            ]
+            .concat()
+        );
+        assert_eq!(
+            encode_osc_message("/abc", &[OscArgument::String("test".to_string())]),
+            [b"/abc\0\0\0\0".as_slice(), b",s\0\0", b"test\0\0\0\0"].concat()
+        );
+    }
+
+    #[test]
+    fn send_events() -> io::Result<()> {
+        let receiver = UdpSocket::bind("127.0.0.1:0")?;
+        receiver.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let mut sink = OscSink::new(receiver.local_addr()?, time_base)?;
+
+        let receive = || -> io::Result<Vec<u8>> {
+            let mut buffer = [0; 1024];
+            let len = receiver.recv(&mut buffer)?;
+            Ok(buffer[..len].to_vec())
+        };
+        let note_message = |time: f64, note: Note, velocity: f32, channel: i32| {
+            encode_osc_message(
+                "/note",
+                &[
+                    OscArgument::Double(time),
+                    OscArgument::Int(note as i32),
+                    OscArgument::Float(velocity),
+                    OscArgument::Int(channel),
+                ],
+            )
+        };
+
+        // note-ons
+        let event = PatternEvent {
+            time: 44100,
+            event: Some(Event::NoteEvents(vec![new_note((
+                Note::C4,
+                InstrumentId::from(2),
+                0.5,
+            ))])),
+            duration: 0,
+            note_duration: None,
+        };
+        sink.send_event(0, &event)?;
+        assert_eq!(receive()?, note_message(1.0, Note::C4, 0.5, 2));
+        // note-offs stop the playing note
+        let event = PatternEvent {
+            time: 88200,
+            event: Some(Event::NoteEvents(vec![new_note(Note::OFF)])),
+            duration: 0,
+            note_duration: None,
+        };
+        sink.send_event(0, &event)?;
+        assert_eq!(receive()?, note_message(2.0, Note::C4, 0.0, 2));
+        // resets stop all playing notes
+        let event = PatternEvent {
+            time: 0,
+            event: Some(Event::NoteEvents(vec![new_note(Note::D4)])),
+            duration: 0,
+            note_duration: None,
+        };
+        sink.send_event(1, &event)?;
+        assert_eq!(receive()?, note_message(0.0, Note::D4, 1.0, -1));
+        sink.reset(44100)?;
+        assert_eq!(receive()?, note_message(1.0, Note::D4, 0.0, -1));
+        sink.reset(44100)?;
+        // parameter changes
+        let event = PatternEvent {
+            time: 22050,
+            event: Some(Event::ParameterChangeEvent(ParameterChangeEvent {
+                parameter: Some(ParameterId::from(7)),
+                value: 0.25,
+            })),
+            duration: 0,
+            note_duration: None,
+        };
+        sink.send_event(0, &event)?;
+        assert_eq!(
+            receive()?,
+            encode_osc_message(
+                "/param",
+                &[
+                    OscArgument::Double(0.5),
+                    OscArgument::Long(7),
+                    OscArgument::Float(0.25)
+                ]
+            )
+        );
+        Ok(())
+    }
+}
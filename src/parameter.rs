@@ -37,6 +37,11 @@ pub struct Parameter {
     default: f64,
     value: f64,
     value_strings: Vec<String>,
+    enum_index: bool,
+    smoothing_time: f64,
+    target_value: f64,
+    ramp_rate: f64,
+    smoothing_clock: f64,
 }
 
 impl Parameter {
@@ -62,6 +67,11 @@ impl Parameter {
         };
         let value = default;
         let value_strings = vec![];
+        let enum_index = false;
+        let smoothing_time = 0.0;
+        let target_value = value;
+        let ramp_rate = 0.0;
+        let smoothing_clock = 0.0;
         Self {
             id,
             name,
@@ -71,6 +81,11 @@ impl Parameter {
             default,
             value,
             value_strings,
+            enum_index,
+            smoothing_time,
+            target_value,
+            ramp_rate,
+            smoothing_clock,
         }
     }
 
@@ -101,6 +116,11 @@ impl Parameter {
         let default = default as f64;
         let value = default;
         let value_strings = vec![];
+        let enum_index = false;
+        let smoothing_time = 0.0;
+        let target_value = value;
+        let ramp_rate = 0.0;
+        let smoothing_clock = 0.0;
         Self {
             id,
             name,
@@ -110,6 +130,11 @@ impl Parameter {
             default,
             value,
             value_strings,
+            enum_index,
+            smoothing_time,
+            target_value,
+            ramp_rate,
+            smoothing_clock,
         }
     }
 
@@ -138,6 +163,11 @@ impl Parameter {
         let parameter_type = ParameterType::Float;
         let value = default;
         let value_strings = vec![];
+        let enum_index = false;
+        let smoothing_time = 0.0;
+        let target_value = value;
+        let ramp_rate = 0.0;
+        let smoothing_clock = 0.0;
         Self {
             id,
             name,
@@ -147,6 +177,11 @@ impl Parameter {
             default,
             value,
             value_strings,
+            enum_index,
+            smoothing_time,
+            target_value,
+            ramp_rate,
+            smoothing_clock,
         }
     }
 
@@ -183,6 +218,11 @@ impl Parameter {
             .unwrap_or(0) as f64;
         let value = default;
         let value_strings = values;
+        let enum_index = false;
+        let smoothing_time = 0.0;
+        let target_value = value;
+        let ramp_rate = 0.0;
+        let smoothing_clock = 0.0;
         Self {
             id,
             name,
@@ -192,6 +232,29 @@ impl Parameter {
             default,
             value,
             value_strings,
+            enum_index,
+            smoothing_time,
+            target_value,
+            ramp_rate,
+            smoothing_clock,
+        }
+    }
+
+    /// Create a new enum parameter, which passes the one-based index of the selected value
+    /// instead of the value string to callback contexts, e.g. to use it as array index.
+    ///
+    /// ### Panics
+    /// Panics if the default value is not in the specified values set.
+    pub fn with_enum_index(
+        id: &str,
+        name: &str,
+        description: &str,
+        values: Vec<String>,
+        default: String,
+    ) -> Self {
+        Self {
+            enum_index: true,
+            ..Self::with_enum(id, name, description, values, default)
         }
     }
 
@@ -200,6 +263,28 @@ impl Parameter {
         &self.id
     }
 
+    /// Bit which is set in all hashed parameter ids, see [`Self::hash_id`].
+    pub const HASHED_ID_BIT: u64 = 1 << 63;
+
+    /// Stable 64-bit FNV-1a hash of the parameter's id, e.g. to identify parameters in hosts
+    /// without passing around id strings.
+    pub fn id_hash(&self) -> u64 {
+        Self::hash_id(&self.id)
+    }
+
+    /// Stable 64-bit FNV-1a hash of the given parameter id, as returned by [`Self::id_hash`].
+    ///
+    /// The hash's highest bit is always set, so hashed ids never collide with plain integer
+    /// parameter ids, which must be smaller than [`Self::HASHED_ID_BIT`].
+    pub fn hash_id(id: &str) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+        let hash = id.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+        hash | Self::HASHED_ID_BIT
+    }
+
     /// Optional name of the parameter, as displayed to the user. Falls back to id, when unspecified.
     pub fn name(&self) -> &str {
         &self.name
@@ -225,12 +310,18 @@ impl Parameter {
         &self.value_strings
     }
 
+    /// True for enum parameters which pass the selected value's index to callback contexts.
+    pub fn is_enum_index(&self) -> bool {
+        self.enum_index
+    }
+
     /// Default value to reset the parameter.
     pub fn default(&self) -> f64 {
         self.default
     }
 
-    /// Actual parameter value in range.
+    /// Actual parameter value in range. When smoothing is enabled, this is the current,
+    /// possibly still ramping value and not the last set target value.
     pub fn value(&self) -> f64 {
         self.value
     }
@@ -241,27 +332,132 @@ impl Parameter {
     /// Panics if the passed value exceeds the specified range
     pub fn set_value(&mut self, value: f64) {
         assert!(self.range.contains(&value), "Invalid value");
-        self.value = value;
+        self.set_target_value(value);
+    }
+
+    /// Last set parameter value, which the actual value ramps towards when smoothing is enabled.
+    pub fn target_value(&self) -> f64 {
+        self.target_value
+    }
+
+    /// Time in seconds it takes for the actual value to ramp to newly set values. By default 0,
+    /// which applies new values immediately.
+    pub fn smoothing_time(&self) -> f64 {
+        self.smoothing_time
+    }
+
+    /// Set a new smoothing time in seconds, to avoid abrupt value jumps when values get changed
+    /// during playback. Smoothing only applies to float parameters: other parameter types always
+    /// apply new values immediately. Values < 0 are clamped to 0, which disables smoothing.
+    ///
+    /// Smoothed values get advanced by patterns while they are running, see
+    /// [`Self::advance_smoothing_until`].
+    pub fn set_smoothing_time(&mut self, seconds: f64) {
+        self.smoothing_time = seconds.max(0.0);
+        if self.smoothing_time == 0.0 {
+            self.value = self.target_value;
+        }
+    }
+
+    /// Move the actual value towards the target value by the given time in seconds, when
+    /// smoothing is enabled and the value did not yet reach its target.
+    pub fn advance_smoothing(&mut self, seconds: f64) {
+        if self.value != self.target_value && seconds > 0.0 {
+            let distance = self.target_value - self.value;
+            let delta = self.ramp_rate * seconds;
+            if delta >= distance.abs() {
+                self.value = self.target_value;
+            } else {
+                self.value += delta.copysign(distance);
+            }
+        }
+    }
+
+    /// Advance smoothed values up to the given absolute playback time in seconds.
+    ///
+    /// Parameters may be shared by multiple pattern instances, e.g. duplicated patterns
+    /// which play in parallel. Each instance reports its playback time here, but only the
+    /// time which has not yet been applied by some other instance moves the value.
+    pub fn advance_smoothing_until(&mut self, seconds: f64) {
+        if seconds > self.smoothing_clock {
+            self.advance_smoothing(seconds - self.smoothing_clock);
+            self.smoothing_clock = seconds;
+        }
+    }
+
+    /// Reset the playback time of [`Self::advance_smoothing_until`], e.g. when patterns
+    /// restart playback from the beginning.
+    pub fn reset_smoothing_clock(&mut self) {
+        self.smoothing_clock = 0.0;
+    }
+
+    /// Actual parameter value, normalized to range \[0 - 1\] within the parameter's range.
+    pub fn normalized_value(&self) -> f64 {
+        let (start, end) = (*self.range.start(), *self.range.end());
+        if end > start {
+            ((self.value - start) / (end - start)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
     }
 
-    /// Reset the value to the default value.
+    /// Set a new parameter value from a normalized value in range \[0 - 1\], which gets mapped
+    /// to the parameter's range. Non float parameter values are rounded to the nearest valid value.
+    ///
+    /// ### Panics
+    /// Panics if the passed value is not in range \[0 - 1\]
+    pub fn set_normalized_value(&mut self, value: f64) {
+        assert!((0.0..=1.0).contains(&value), "Invalid normalized value");
+        let (start, end) = (*self.range.start(), *self.range.end());
+        let value = start + value * (end - start);
+        self.set_target_value(
+            match self.parameter_type {
+                ParameterType::Float => value,
+                ParameterType::Boolean | ParameterType::Integer | ParameterType::Enum => {
+                    value.round()
+                }
+            }
+            .clamp(start, end),
+        );
+    }
+
+    /// Reset the value to the default value. Resets are applied immediately, without smoothing.
     pub fn reset(&mut self) {
-        self.value = self.default
+        self.value = self.default;
+        self.target_value = self.default;
+    }
+
+    /// Apply a new target value, ramping the actual value towards it when smoothing is enabled.
+    fn set_target_value(&mut self, value: f64) {
+        self.target_value = value;
+        if self.smoothing_time > 0.0 && self.parameter_type == ParameterType::Float {
+            self.ramp_rate = (self.target_value - self.value).abs() / self.smoothing_time;
+        } else {
+            self.value = value;
+        }
     }
 
     /// String representation of the value, depending on the parameter type.
     pub fn string_value(&self) -> String {
+        self.value_to_string(self.value)
+    }
+
+    /// Human-readable string representation of the given value, depending on the parameter type:
+    /// "On" or "Off" for booleans, rounded numbers for integers, numbers with two decimal places
+    /// for floats and the value string for enums. Values out of range are clamped to the range.
+    pub fn value_to_string(&self, value: f64) -> String {
+        let value = value.clamp(*self.range.start(), *self.range.end());
         match self.parameter_type {
             ParameterType::Boolean => {
-                if self.value > 0.5 {
+                if value > 0.5 {
                     "On".to_string()
                 } else {
                     "Off".to_string()
                 }
             }
-            ParameterType::Float => self.value.to_string(),
-            ParameterType::Integer => (self.value.round() as i64).to_string(),
-            ParameterType::Enum => self.value_strings[self.value.round() as usize].clone(),
+            ParameterType::Float => format!("{:.2}", value),
+            ParameterType::Integer => (value.round() as i64).to_string(),
+            ParameterType::Enum => self.value_strings[value.round() as usize].clone(),
         }
     }
 
@@ -278,9 +474,14 @@ impl Parameter {
             }
             ParameterType::Float => self.value.into_lua(lua),
             ParameterType::Integer => (self.value.round() as LuaInteger).into_lua(lua),
-            ParameterType::Enum => self.value_strings[self.value.round() as usize]
-                .clone()
-                .into_lua(lua),
+            ParameterType::Enum => {
+                let index = self.value.round() as usize;
+                if self.enum_index {
+                    (index as LuaInteger + 1).into_lua(lua)
+                } else {
+                    self.value_strings[index].clone().into_lua(lua)
+                }
+            }
         }
     }
 }
@@ -293,7 +494,194 @@ impl PartialEq for Parameter {
             && self.parameter_type == other.parameter_type
             && self.range == other.range
             && self.default == other.default
-            // SKIP value
+            // SKIP value and smoothing state
             && self.value_strings == other.value_strings
+            && self.enum_index == other.enum_index
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn id_hashes() {
+        // well known FNV-1a test vectors
+        assert_eq!(
+            Parameter::with_boolean("", "", "", false).id_hash(),
+            0xcbf2_9ce4_8422_2325
+        );
+        assert_eq!(
+            Parameter::with_boolean("a", "", "", false).id_hash(),
+            0xaf63_dc4c_8601_ec8c
+        );
+        assert_eq!(
+            Parameter::with_boolean("foobar", "", "", false).id_hash(),
+            0x8594_4171_f739_67e8
+        );
+        // hashes never collide with plain integer ids
+        for id in ["", "a", "cutoff", "resonance"] {
+            assert!(Parameter::hash_id(id) >= Parameter::HASHED_ID_BIT);
+        }
+        // hashes only depend on the id
+        assert_eq!(
+            Parameter::with_float("cutoff", "Cutoff", "", 0.0..=1.0, 0.5).id_hash(),
+            Parameter::with_integer("cutoff", "", "", 0..=127, 64).id_hash()
+        );
+    }
+
+    #[test]
+    fn normalized_values() {
+        let mut float = Parameter::with_float("float", "", "", -2.0..=6.0, 0.0);
+        assert_eq!(float.normalized_value(), 0.25);
+        float.set_normalized_value(0.0);
+        assert_eq!(float.value(), -2.0);
+        float.set_normalized_value(1.0);
+        assert_eq!(float.value(), 6.0);
+        assert_eq!(float.normalized_value(), 1.0);
+        float.set_normalized_value(0.5);
+        assert_eq!(float.value(), 2.0);
+
+        let mut integer = Parameter::with_integer("int", "", "", 1..=5, 1);
+        assert_eq!(integer.normalized_value(), 0.0);
+        integer.set_normalized_value(1.0);
+        assert_eq!(integer.value(), 5.0);
+        integer.set_normalized_value(0.3);
+        assert_eq!(integer.value(), 2.0);
+
+        let mut boolean = Parameter::with_boolean("bool", "", "", true);
+        assert_eq!(boolean.normalized_value(), 1.0);
+        boolean.set_normalized_value(0.0);
+        assert_eq!(boolean.value(), 0.0);
+
+        let mut enumeration = Parameter::with_enum(
+            "enum",
+            "",
+            "",
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            "a".to_string(),
+        );
+        enumeration.set_normalized_value(1.0);
+        assert_eq!(enumeration.string_value(), "c");
+        assert_eq!(enumeration.normalized_value(), 1.0);
+
+        let single = Parameter::with_integer("single", "", "", 1..=1, 1);
+        assert_eq!(single.normalized_value(), 0.0);
+    }
+
+    #[test]
+    fn value_strings() {
+        let boolean = Parameter::with_boolean("bool", "", "", true);
+        assert_eq!(boolean.string_value(), "On");
+        assert_eq!(boolean.value_to_string(0.0), "Off");
+        assert_eq!(boolean.value_to_string(1.0), "On");
+
+        let float = Parameter::with_float("float", "", "", -1.0..=1.0, 0.1 + 0.2);
+        assert_eq!(float.string_value(), "0.30");
+        assert_eq!(float.value_to_string(-0.126), "-0.13");
+        assert_eq!(float.value_to_string(5.0), "1.00");
+
+        let integer = Parameter::with_integer("int", "", "", 1..=200, 120);
+        assert_eq!(integer.string_value(), "120");
+        assert_eq!(integer.value_to_string(2.6), "3");
+        assert_eq!(integer.value_to_string(0.0), "1");
+
+        let enumeration = Parameter::with_enum(
+            "enum",
+            "",
+            "",
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            "b".to_string(),
+        );
+        assert_eq!(enumeration.string_value(), "b");
+        assert_eq!(enumeration.value_to_string(0.0), "a");
+        assert_eq!(enumeration.value_to_string(10.0), "c");
+    }
+
+    #[test]
+    fn smoothing() {
+        let mut float = Parameter::with_float("float", "", "", 0.0..=2.0, 0.0);
+        // by default values jump
+        float.set_value(2.0);
+        assert_eq!(float.value(), 2.0);
+        float.reset();
+
+        // values ramp towards the target while advancing
+        float.set_smoothing_time(1.0);
+        float.set_value(2.0);
+        assert_eq!(float.value(), 0.0);
+        assert_eq!(float.target_value(), 2.0);
+        let mut values = Vec::new();
+        for _ in 0..5 {
+            float.advance_smoothing(0.25);
+            values.push(float.value());
+        }
+        assert_eq!(values, vec![0.5, 1.0, 1.5, 2.0, 2.0]);
+        // new targets ramp from the current value
+        float.set_normalized_value(0.5);
+        float.advance_smoothing(0.5);
+        assert_eq!(float.value(), 1.5);
+        // disabling smoothing applies the target
+        float.set_smoothing_time(0.0);
+        assert_eq!(float.value(), 1.0);
+
+        // non float parameters don't smooth
+        let mut integer = Parameter::with_integer("int", "", "", 1..=5, 1);
+        integer.set_smoothing_time(1.0);
+        integer.set_value(5.0);
+        assert_eq!(integer.value(), 5.0);
+    }
+
+    #[test]
+    fn pattern_smoothing() {
+        use crate::prelude::*;
+
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let parameter = Rc::new(RefCell::new(Parameter::with_float(
+            "float",
+            "",
+            "",
+            0.0..=1.0,
+            0.0,
+        )));
+        parameter.borrow_mut().set_smoothing_time(2.0);
+        let mut pattern = time_base
+            .every_nth_beat(1.0)
+            .with_parameters(vec![Rc::clone(&parameter)])
+            .emit(new_note_emitter(Note::C4));
+        parameter.borrow_mut().set_value(1.0);
+        // steps are half a second long: successive steps see a ramp
+        let mut values = Vec::new();
+        for _ in 0..6 {
+            values.push(parameter.borrow().value());
+            pattern.next();
+        }
+        assert_eq!(values, vec![0.0, 0.25, 0.5, 0.75, 1.0, 1.0]);
+
+        // duplicated patterns share the parameter, but advance it only once
+        pattern.reset();
+        parameter.borrow_mut().reset();
+        let mut duplicate = pattern.clone();
+        parameter.borrow_mut().set_value(1.0);
+        let mut values = Vec::new();
+        for _ in 0..3 {
+            values.push(parameter.borrow().value());
+            pattern.next();
+            duplicate.next();
+        }
+        assert_eq!(values, vec![0.0, 0.25, 0.5]);
+
+        // seeking advances smoothing too
+        pattern.reset();
+        parameter.borrow_mut().reset();
+        parameter.borrow_mut().set_value(1.0);
+        pattern.advance_until_time(time_base.samples_per_sec as SampleTime);
+        assert_eq!(parameter.borrow().value(), 0.5);
     }
 }
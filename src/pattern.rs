@@ -1,15 +1,26 @@
 //! Emit `Event`s via an `Emitter` with a given time base on a rhythmical pattern
 //! defined as `Rhythm`.
 
-use std::{cell::RefCell, fmt::Debug, rc::Rc};
+use std::{
+    cell::RefCell,
+    fmt::{Debug, Display},
+    rc::Rc,
+};
 
-use crate::{BeatTimeBase, Event, EventTransform, ExactSampleTime, Parameter, SampleTime};
+use crate::{
+    time::to_sample_time, BeatTimeBase, BeatTimeStep, DebugOutput, Event, EventTransform,
+    ExactSampleTime, NoteEvent, NoteFilter, Parameter, PatternSlot, Phrase, SampleTime,
+    SampleTimeRounding,
+};
 
 // -------------------------------------------------------------------------------------------------
 
 pub(crate) mod generic;
+pub(crate) mod renoise;
+pub(crate) mod timeline;
 
 pub mod beat_time;
+pub mod ms_time;
 pub mod second_time;
 
 // -------------------------------------------------------------------------------------------------
@@ -19,7 +30,10 @@ pub mod second_time;
 pub struct PatternEvent {
     pub time: SampleTime,
     pub event: Option<Event>,
+    /// Length of the event's step in samples. Note delays are relative to this duration.
     pub duration: SampleTime,
+    /// Musical length of the event's notes in samples, see [`Self::note_duration`].
+    pub(crate) note_duration: Option<SampleTime>,
 }
 
 impl PatternEvent {
@@ -32,6 +46,56 @@ impl PatternEvent {
             ..self
         }
     }
+
+    /// Set an optional musical length of the event's notes in samples, relative to the event's
+    /// time. See [`Self::note_duration`].
+    #[must_use]
+    pub fn with_note_duration<D: Into<Option<SampleTime>>>(self, note_duration: D) -> Self {
+        Self {
+            note_duration: note_duration.into(),
+            ..self
+        }
+    }
+
+    /// Musical length of the event's notes in samples, relative to the event's time, when
+    /// known. This is set for notes which are held for a fixed length, e.g. by a pattern gate,
+    /// and may differ from the step `duration`. When `None`, notes play until they get stopped
+    /// by a note-off or a new note in the same voice.
+    ///
+    /// NB: A new note in the same voice also stops notes with a known duration before their
+    /// duration ends.
+    pub fn note_duration(&self) -> Option<SampleTime> {
+        self.note_duration
+    }
+
+    /// Sample time of the given note event, which is part of this pattern event.
+    /// This is the pattern event's time plus the note's delay, which is relative to
+    /// the pattern event's duration.
+    pub fn note_event_time(&self, note_event: &NoteEvent) -> SampleTime {
+        let delay = note_event.delay.clamp(0.0, 1.0) as ExactSampleTime;
+        self.time + to_sample_time(delay * self.duration as ExactSampleTime)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Pattern configurations which make a [`Pattern`] produce no events.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PatternWarning {
+    /// The pattern's step length is zero, so the pattern can't advance in time.
+    /// Such patterns stop playing right away.
+    ZeroStepLength,
+    /// The pattern's rhythm did not trigger any events over a full cycle.
+    NoEvents,
+}
+
+impl Display for PatternWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ZeroStepLength => write!(f, "pattern has a zero step length"),
+            Self::NoEvents => write!(f, "pattern produced no events over a full cycle"),
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -51,6 +115,8 @@ pub trait Pattern: Debug {
     /// Get the pattern's internal beat time base.
     fn time_base(&self) -> &BeatTimeBase;
     /// Update the pattern beat time bases with a new time base (e.g. on tempo changes).
+    /// Implementations should keep the musical phase of the current playback position, so
+    /// tempo or sample rate changes don't shift already scheduled steps.
     fn set_time_base(&mut self, time_base: &BeatTimeBase);
 
     /// Length in *samples* of a *single time step* in the pattern's rhythm.
@@ -58,10 +124,22 @@ pub trait Pattern: Debug {
     /// Number of *steps* in the rhythm's pattern (cycle step count).
     /// A pattern's rhythm repeats after `self.step_count() * self.step_length()` samples.
     fn step_count(&self) -> usize;
+    /// Offset in *samples* of the pattern's first step, relative to the pattern's sample offset,
+    /// e.g. when the pattern got created with a step offset. By default 0.
+    fn step_offset(&self) -> ExactSampleTime {
+        0.0
+    }
 
     /// Shared access to the pattern's parameter set, if any. Parameter sets do not change
     /// after construction, but their values may.
     fn parameters(&self) -> &[Rc<RefCell<Parameter>>];
+    /// Set all parameter values back to their declared default values. Unlike `reset`, this
+    /// does not affect the pattern's playback state.
+    fn reset_parameters_to_defaults(&mut self) {
+        for parameter in self.parameters() {
+            parameter.borrow_mut().reset();
+        }
+    }
 
     /// Set the event which triggered, started the pattern, *before* running the pattern.
     /// Rhythm, Gate or Emitter impls may use this to dynamically change their behavior.
@@ -72,11 +150,53 @@ pub trait Pattern: Debug {
     /// times but only event values.
     fn set_event_transform(&mut self, transform: Option<EventTransform>);
 
+    /// Set an optional output filter for the pattern, which drops all emitted notes for which
+    /// the filter returns false. The filter is applied after the event transform, right before
+    /// events get emitted. Dropped notes are replaced with `None`, so voices of the remaining
+    /// notes stay in place. Note that note-offs are filtered as well.
+    fn set_output_filter(&mut self, filter: Option<NoteFilter>);
+
+    /// Captured debug output of the pattern, such as `print` calls in scripted patterns, if any.
+    fn debug_output(&self) -> Option<DebugOutput>;
+
+    /// Current configuration warning of the pattern, if any. Warnings get detected while
+    /// running the pattern and are cleared as soon as the pattern emits events again.
+    fn warning(&self) -> Option<PatternWarning>;
+
+    /// Global velocity scale factor, which gets applied to all emitted note volumes.
+    fn velocity_scale(&self) -> f32;
+    /// Set a new global velocity scale factor, which gets multiplied with the volumes of all
+    /// emitted note events, *before* applying the event transform. Negative scales are clamped
+    /// to 0. The scale is not affected by pattern resets.
+    fn set_velocity_scale(&mut self, scale: f32);
+
+    /// Rounding mode which is used to convert exact event times to sample times.
+    fn sample_rounding(&self) -> SampleTimeRounding;
+    /// Set a new rounding mode for event times. Exact event times are tracked internally, so
+    /// this only affects the positions of single events. By default event times are floored.
+    fn set_sample_rounding(&mut self, rounding: SampleTimeRounding);
+
+    /// Set the current, smoothed output level of the pattern's played notes in range \[0 - 1\].
+    /// Players feed back levels with a latency of one processing block, so this is a best-effort
+    /// estimate only. Rhythm, Gate or Emitter impls may use this to react to the output.
+    /// Ignored by default.
+    fn set_output_level(&mut self, _level: f32) {}
+
+    /// Set the notes which currently are playing in the pattern's slot, ordered by voice.
+    /// Like output levels, players feed back playing notes with a latency of one processing
+    /// block. Rhythm, Gate or Emitter impls may use this to react to or harmonize with them.
+    /// Ignored by default.
+    fn set_playing_notes(&mut self, _notes: &[NoteEvent]) {}
+
     /// Custom sample offset value which is applied to all emitted events.
-    fn sample_offset(&self) -> SampleTime;
+    fn sample_offset(&self) -> ExactSampleTime;
     /// Set a new custom sample offset value. This may be used by a Sequencer to chain or offset
-    /// multiple pattern's in time.
-    fn set_sample_offset(&mut self, sample_offset: SampleTime);
+    /// multiple pattern's in time. Offsets are exact sample times, so chained patterns can start
+    /// at fractional sample positions without accumulating rounding errors.
+    ///
+    /// NB: Offsets were `SampleTime` values in earlier versions: Convert integer offsets with
+    /// `as ExactSampleTime`.
+    fn set_sample_offset(&mut self, sample_offset: ExactSampleTime);
 
     /// Sample time event iterator: Generates a single event by first running the pattern's rhythm
     /// to generate a new pulse. If the pulse's sample time is smaller than the given sample time
@@ -84,6 +204,14 @@ pub trait Pattern: Debug {
     /// Returns `None` when no event is due or when pattern playback finished.
     fn run_until_time(&mut self, sample_time: SampleTime) -> Option<PatternEvent>;
 
+    /// Sample time the pattern last got run or advanced to via `run_until_time` or
+    /// `advance_until_time`. This keeps advancing in rests, where no events are emitted,
+    /// so it can be used to sync visuals to the pattern's transport position.
+    /// Patterns which don't track their transport position return 0 by default.
+    fn current_sample_time(&self) -> SampleTime {
+        0
+    }
+
     /// Skip all events until the given target time is reached.
     ///
     /// This calls `run_until_time` by default, until the target time is reached and
@@ -94,12 +222,138 @@ pub trait Pattern: Debug {
         }
     }
 
+    /// Run the pattern on the given tick of an external clock, e.g. a MIDI clock with 24 ticks
+    /// per beat, instead of on sample times, to slave the pattern to an external master clock.
+    /// Returns the next event which is due before the following tick, so call this repeatedly
+    /// until it returns `None` for each received tick.
+    ///
+    /// Ticks are counted from the pattern's start and define the pattern's musical position,
+    /// so the pattern follows the clock, also when the clock's tempo differs from the tempo of
+    /// the pattern's time base. Emitted event times and durations are derived from the clock's
+    /// tick rate instead: `samples_per_tick` is the duration of a single clock tick in samples,
+    /// e.g. as measured from the incoming clock.
+    fn run_on_tick(
+        &mut self,
+        tick: u64,
+        ticks_per_beat: u32,
+        samples_per_tick: f64,
+    ) -> Option<PatternEvent> {
+        let sample_time = self.tick_to_sample_time(tick + 1, ticks_per_beat);
+        let event = self.run_until_time(sample_time)?;
+        // convert event times from the pattern's to the clock's tempo
+        let pattern_samples_per_tick =
+            self.time_base().samples_per_beat() / ticks_per_beat.max(1) as f64;
+        if pattern_samples_per_tick <= 0.0 {
+            return Some(event);
+        }
+        let scale = samples_per_tick.max(0.0) / pattern_samples_per_tick;
+        // pattern times already got rounded, so round them to the nearest clock time
+        let to_clock_time = |time: SampleTime| {
+            SampleTimeRounding::Nearest.to_sample_time(time as ExactSampleTime * scale)
+        };
+        Some(PatternEvent {
+            time: to_clock_time(event.time),
+            duration: to_clock_time(event.duration),
+            note_duration: event.note_duration.map(to_clock_time),
+            ..event
+        })
+    }
+
+    /// Skip all events until the given tick of an external clock is reached, e.g. when the
+    /// external clock jumps to a new position. See `run_on_tick`.
+    fn advance_tick(&mut self, tick: u64, ticks_per_beat: u32) {
+        let sample_time = self.tick_to_sample_time(tick, ticks_per_beat);
+        self.advance_until_time(sample_time);
+    }
+
+    /// Convert the given tick of an external clock with the given number of ticks per beat
+    /// to a sample time in the pattern's time base, using the pattern's sample rounding mode.
+    fn tick_to_sample_time(&self, tick: u64, ticks_per_beat: u32) -> SampleTime {
+        let samples_per_tick = self.time_base().samples_per_beat() / ticks_per_beat.max(1) as f64;
+        self.sample_rounding()
+            .to_sample_time(tick as ExactSampleTime * samples_per_tick)
+    }
+
     /// Create a new cloned instance of this pattern. This actually is a clone(), wrapped into
     /// a `Box<dyn Pattern>`, called 'duplicate' to avoid conflicts with possible Clone impls.
     fn duplicate(&self) -> Rc<RefCell<dyn Pattern>>;
 
     /// Resets/rewinds the pattern to its initial state.
     fn reset(&mut self);
+
+    /// Restart the pattern at the given sample time, e.g. for seamless restarts of live loops.
+    ///
+    /// Unlike [`reset`](Self::reset), which rewinds the pattern to sample time 0, the pattern
+    /// keeps its timeline: the pattern's step counter starts again at the given time, so players
+    /// can keep running the pattern without having to stop notes which are still ringing.
+    fn reset_preserving_sound(&mut self, sample_time: SampleTime) {
+        self.reset();
+        self.set_sample_offset(sample_time as ExactSampleTime);
+    }
+
+    /// Rewind the pattern and advance it to the start of the given step index, counted from 0,
+    /// e.g. to restart a loop at a specific step. All events of the previous steps get skipped
+    /// via `advance_until_time`, so the states of stateful generators stay in sync.
+    ///
+    /// The pattern's sample offset is kept, so the following events continue at the step's
+    /// sample time: `sample_offset + step_offset + step * step_length`.
+    fn seek_to_step(&mut self, step: usize) {
+        let sample_offset = self.sample_offset();
+        self.reset();
+        self.set_sample_offset(sample_offset);
+        let step_time =
+            sample_offset + self.step_offset() + step as ExactSampleTime * self.step_length();
+        self.advance_until_time(self.sample_rounding().to_sample_time(step_time));
+    }
+
+    /// Create a new [`Phrase`] with the given number of pattern slots, which each play a reset
+    /// duplicate of this pattern, e.g. to layer or trigger the same pattern multiple times.
+    ///
+    /// Duplicates are independent pattern instances: running one slot does not affect the
+    /// playback state of the others. All duplicates use the given time base.
+    fn clone_into_phrase(
+        &self,
+        count: usize,
+        time_base: BeatTimeBase,
+        length: BeatTimeStep,
+    ) -> Phrase {
+        let pattern_slots = (0..count)
+            .map(|_| {
+                let pattern = self.duplicate();
+                {
+                    let mut pattern = pattern.borrow_mut();
+                    pattern.set_time_base(&time_base);
+                    pattern.reset();
+                }
+                PatternSlot::Pattern(pattern)
+            })
+            .collect::<Vec<_>>();
+        Phrase::new(time_base, pattern_slots, length)
+    }
+
+    /// Render the given number of pattern cycles into a static Renoise instrument phrase
+    /// and return it as XML string. A cycle is the pattern's rhythm step count, where each
+    /// step becomes a phrase line.
+    ///
+    /// The phrase is rendered from a reset duplicate of the pattern, so this pattern's
+    /// playback state is not affected.
+    fn export_renoise_phrase(&self, cycles: usize) -> String {
+        let pattern = self.duplicate();
+        let mut pattern = pattern.borrow_mut();
+        renoise::export_phrase(&mut *pattern, cycles)
+    }
+
+    /// Render the given number of pattern cycles into a JSON array of all emitted note and
+    /// parameter change events, with their times in samples and beats, e.g. to visualize or
+    /// analyze the pattern in external tools. A cycle is the pattern's rhythm step count.
+    ///
+    /// The timeline is rendered from a reset duplicate of the pattern, so this pattern's
+    /// playback state is not affected.
+    fn export_timeline_json(&self, cycles: usize) -> String {
+        let pattern = self.duplicate();
+        let mut pattern = pattern.borrow_mut();
+        timeline::export_timeline(&mut *pattern, cycles)
+    }
 }
 
 /// Standard iterator impl for [`Pattern`].
@@ -56,3 +56,406 @@ impl BeatTimeBase {
     generate_step_funcs!(half, BeatTimeStep::Half);
     generate_step_funcs!(bar, BeatTimeStep::Bar);
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::{
+        emitter::fixed::{ToFixedEmitter, ToFixedEmitterSequence},
+        event::{new_note, Event, InstrumentId},
+        rhythm::fixed::ToFixedRhythm,
+        BeatTimeStep, Emitter, EmitterEvent, Note, Parameter, ParameterSet, Pattern, PatternEvent,
+        PatternWarning, RhythmEvent, SampleTime,
+    };
+
+    fn note_volumes(event: Option<PatternEvent>) -> Vec<f32> {
+        match event.and_then(|event| event.event) {
+            Some(Event::NoteEvents(notes)) => notes.iter().flatten().map(|n| n.volume).collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn velocity_scale() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let mut pattern = time_base
+            .every_nth_beat(1.0)
+            .with_instrument(InstrumentId::from(1))
+            .emit(
+                vec![
+                    new_note((Note::C4, None, 1.0)),
+                    new_note((Note::E4, None, 0.5)),
+                ]
+                .to_emitter(),
+            );
+        assert_eq!(pattern.velocity_scale(), 1.0);
+        assert_eq!(note_volumes(pattern.next()), vec![1.0, 0.5]);
+        pattern.set_velocity_scale(0.5);
+        assert_eq!(note_volumes(pattern.next()), vec![0.5, 0.25]);
+        // scale is applied before event transforms
+        pattern.set_event_transform(Some(Rc::new(|event: &mut Event| {
+            if let Event::NoteEvents(notes) = event {
+                for note in notes.iter_mut().flatten() {
+                    note.volume = note.volume.min(0.4);
+                }
+            }
+        })));
+        assert_eq!(note_volumes(pattern.next()), vec![0.4, 0.25]);
+        // scale survives resets
+        pattern.set_event_transform(None);
+        pattern.reset();
+        assert_eq!(pattern.velocity_scale(), 0.5);
+        assert_eq!(note_volumes(pattern.next()), vec![0.5, 0.25]);
+        // negative scales are clamped
+        pattern.set_velocity_scale(-1.0);
+        assert_eq!(pattern.velocity_scale(), 0.0);
+        assert_eq!(note_volumes(pattern.next()), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn reset_parameters_to_defaults() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let parameters: ParameterSet = vec![
+            Rc::new(RefCell::new(Parameter::with_boolean("b", "B", "", true))),
+            Rc::new(RefCell::new(Parameter::with_integer(
+                "i",
+                "I",
+                "",
+                0..=10,
+                2,
+            ))),
+            Rc::new(RefCell::new(Parameter::with_float(
+                "f",
+                "F",
+                "",
+                0.0..=1.0,
+                0.5,
+            ))),
+        ];
+        let mut pattern = time_base
+            .every_nth_beat(1.0)
+            .with_parameters(parameters)
+            .emit(vec![new_note(Note::C4)].to_emitter());
+        let values = |pattern: &BeatTimePattern| {
+            pattern
+                .parameters()
+                .iter()
+                .map(|p| p.borrow().value())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(&pattern), vec![1.0, 2.0, 0.5]);
+        for (parameter, value) in pattern.parameters().iter().zip([0.0, 7.0, 0.9]) {
+            parameter.borrow_mut().set_value(value);
+        }
+        pattern.next();
+        assert_eq!(values(&pattern), vec![0.0, 7.0, 0.9]);
+        // playback state is not affected
+        pattern.reset_parameters_to_defaults();
+        assert_eq!(values(&pattern), vec![1.0, 2.0, 0.5]);
+        assert_eq!(pattern.next().map(|e| e.time), Some(22050));
+    }
+
+    #[test]
+    fn output_filter() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let kick = InstrumentId::from(1);
+        let hihat = InstrumentId::from(2);
+        let mut pattern = time_base
+            .every_nth_beat(1.0)
+            .emit(vec![new_note((Note::C4, kick)), new_note((Note::C4, hihat))].to_emitter());
+        let note_instruments = |event: Option<PatternEvent>| match event.and_then(|e| e.event) {
+            Some(Event::NoteEvents(notes)) => notes
+                .iter()
+                .map(|n| n.as_ref().and_then(|n| n.instrument))
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        };
+        assert_eq!(
+            note_instruments(pattern.next()),
+            vec![Some(kick), Some(hihat)]
+        );
+        // filtered notes are removed, but keep their voice slot
+        pattern.set_output_filter(Some(Rc::new(move |note| note.instrument != Some(hihat))));
+        assert_eq!(note_instruments(pattern.next()), vec![Some(kick), None]);
+        pattern.set_output_filter(None);
+        assert_eq!(
+            note_instruments(pattern.next()),
+            vec![Some(kick), Some(hihat)]
+        );
+    }
+
+    #[test]
+    fn custom_emitter() {
+        #[derive(Clone, Debug)]
+        struct CustomEmitter {
+            note: Note,
+        }
+
+        impl Emitter for CustomEmitter {
+            fn set_time_base(&mut self, _time_base: &BeatTimeBase) {}
+            fn set_trigger_event(&mut self, _event: &Event) {}
+            fn set_parameters(&mut self, _parameters: ParameterSet) {}
+            fn run(&mut self, _pulse: RhythmEvent, emit_event: bool) -> Option<Vec<EmitterEvent>> {
+                let event = Event::NoteEvents(vec![new_note(self.note)]);
+                emit_event.then(|| vec![EmitterEvent::new(event)])
+            }
+            fn duplicate(&self) -> Box<dyn Emitter> {
+                Box::new(self.clone())
+            }
+            fn reset(&mut self) {}
+        }
+
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let mut pattern = BeatTimePattern::with_emitter(
+            time_base,
+            BeatTimeStep::Beats(1.0),
+            Box::new(CustomEmitter { note: Note::C4 }),
+        );
+        for time in [0, 22050, 44100] {
+            let event = pattern.next().unwrap();
+            assert_eq!(event.time, time);
+            assert_eq!(
+                event.event,
+                Some(Event::NoteEvents(vec![new_note(Note::C4)]))
+            );
+        }
+    }
+
+    #[test]
+    fn current_sample_time() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // a single note, followed by a long rest
+        let mut pattern = time_base
+            .every_nth_bar(1.0)
+            .with_rhythm([1, 0, 0, 0].to_rhythm())
+            .emit(new_note(Note::C4).to_emitter());
+        assert_eq!(pattern.current_sample_time(), 0);
+        assert!(pattern.run_until_time(1024).is_some());
+        assert_eq!(pattern.current_sample_time(), 1024);
+        // the transport time keeps advancing through the rest
+        for block in 2..=300 {
+            let sample_time = block * 1024;
+            while let Some(event) = pattern.run_until_time(sample_time) {
+                assert!(note_volumes(Some(event)).is_empty());
+            }
+            assert_eq!(pattern.current_sample_time(), sample_time);
+        }
+        pattern.advance_until_time(400 * 1024);
+        assert_eq!(pattern.current_sample_time(), 400 * 1024);
+        pattern.reset();
+        assert_eq!(pattern.current_sample_time(), 0);
+    }
+
+    #[test]
+    fn external_clock_ticks() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 133.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let new_pattern = || {
+            time_base
+                .every_nth_sixteenth(1.0)
+                .with_rhythm([1, 0, 1, 1, 0, 1].to_rhythm())
+                .emit(new_note(Note::C4).to_emitter())
+        };
+        // run two bars on sample time
+        let mut pattern = new_pattern();
+        let mut sample_events = Vec::new();
+        let end_time = (2.0 * time_base.samples_per_bar()) as SampleTime;
+        while let Some(event) = pattern.run_until_time(end_time) {
+            sample_events.push(event);
+        }
+        // run two bars on a 24 ppqn clock: a sixteenth is 6 ticks
+        let samples_per_tick = time_base.samples_per_beat() / 24.0;
+        let mut pattern = new_pattern();
+        let mut tick_events = Vec::new();
+        let mut event_ticks = Vec::new();
+        for tick in 0..(2 * 4 * 24) {
+            while let Some(event) = pattern.run_on_tick(tick, 24, samples_per_tick) {
+                event_ticks.push(tick);
+                tick_events.push(event);
+            }
+        }
+        assert_eq!(tick_events, sample_events);
+        assert_eq!(
+            event_ticks,
+            (0..2 * 4 * 4).map(|step| step * 6).collect::<Vec<_>>()
+        );
+        // advancing skips events before the tick
+        let mut pattern = new_pattern();
+        pattern.advance_tick(12, 24);
+        let event = pattern.run_on_tick(12, 24, samples_per_tick).unwrap();
+        assert_eq!(event.time, pattern.tick_to_sample_time(12, 24));
+        assert_eq!(note_volumes(Some(event)), vec![1.0]);
+        assert!(pattern.run_on_tick(12, 24, samples_per_tick).is_none());
+    }
+
+    #[test]
+    fn external_clock_ticks_with_other_tempo() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let mut pattern = time_base
+            .every_nth_sixteenth(1.0)
+            .with_rhythm([1, 0, 1, 1, 0, 1].to_rhythm())
+            .emit(new_note(Note::C4).to_emitter());
+        // run two bars on a 24 ppqn clock at 140 bpm
+        let samples_per_tick = 44100.0 * 60.0 / 140.0 / 24.0;
+        let mut events = Vec::new();
+        for tick in 0..(2 * 4 * 24) {
+            while let Some(event) = pattern.run_on_tick(tick, 24, samples_per_tick) {
+                if event.event.is_some() {
+                    events.push((tick, event.time, event.duration));
+                }
+            }
+        }
+        // events follow the clock's ticks, and get timed with the clock's tempo
+        assert_eq!(
+            events,
+            [1, 0, 1, 1, 0, 1]
+                .into_iter()
+                .cycle()
+                .take(2 * 4 * 4)
+                .enumerate()
+                .filter(|(_, pulse)| *pulse == 1)
+                .map(|(step, _)| {
+                    let tick = step as u64 * 6;
+                    let time = (tick as f64 * samples_per_tick).round() as SampleTime;
+                    (tick, time, (6.0 * samples_per_tick).round() as SampleTime)
+                })
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn seek_to_step() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let mut pattern = time_base
+            .every_nth_sixteenth(1.0)
+            .with_offset(BeatTimeStep::Sixteenth(2.0))
+            .with_rhythm([1, 0, 1, 1].to_rhythm())
+            .emit(
+                vec![new_note(Note::C4), new_note(Note::D4), new_note(Note::E4)]
+                    .to_sequence_emitter(),
+            );
+        let step_length = pattern.step_length();
+        let step_time = |step: f64| (step * step_length) as SampleTime;
+        let note = |event: Option<PatternEvent>| match event.and_then(|e| e.event) {
+            Some(Event::NoteEvents(notes)) => notes[0].as_ref().map(|n| n.note),
+            _ => None,
+        };
+        // seek forward: skipped pulses advance the emitter
+        pattern.seek_to_step(3);
+        let event = pattern.next();
+        assert_eq!(event.as_ref().map(|e| e.time), Some(step_time(5.0)));
+        assert_eq!(note(event), Some(Note::E4));
+        // seek backwards
+        pattern.seek_to_step(0);
+        let event = pattern.next();
+        assert_eq!(event.as_ref().map(|e| e.time), Some(step_time(2.0)));
+        assert_eq!(note(event), Some(Note::C4));
+        // sample offsets are kept
+        pattern.set_sample_offset(1000.0);
+        pattern.seek_to_step(2);
+        let event = pattern.next();
+        assert_eq!(event.as_ref().map(|e| e.time), Some(1000 + step_time(4.0)));
+        assert_eq!(note(event), Some(Note::D4));
+    }
+
+    #[test]
+    fn sample_rate_changes() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let mut pattern = time_base
+            .every_nth_beat(1.0)
+            .with_offset(BeatTimeStep::Beats(0.5))
+            .emit(new_note(Note::C4).to_emitter());
+        let mut event_times = Vec::new();
+        while let Some(event) = pattern.run_until_time(50000) {
+            event_times.push(event.time);
+        }
+        assert_eq!(event_times, [11025, 33075]);
+        // switch to 48kHz between two beats: 5125 samples at 44.1kHz are left until the next event
+        let new_time_base = BeatTimeBase {
+            samples_per_sec: 48000,
+            ..time_base
+        };
+        pattern.set_time_base(&new_time_base);
+        let event_times = (0..3)
+            .map(|_| pattern.run_until_time(SampleTime::MAX).unwrap().time)
+            .collect::<Vec<_>>();
+        let next_time = 50000 + (5125.0 * 48000.0 / 44100.0) as SampleTime;
+        assert_eq!(
+            event_times,
+            [next_time, next_time + 24000, next_time + 2 * 24000]
+        );
+    }
+
+    #[test]
+    fn warnings() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // all-zero pulses produce no events over a full cycle (at least a bar)
+        let mut pattern = time_base
+            .every_nth_sixteenth(1.0)
+            .with_rhythm([0, 0, 0, 0].to_rhythm())
+            .emit(new_note(Note::C4).to_emitter());
+        assert_eq!(pattern.warning(), None);
+        while pattern.run_until_time(44100).is_some() {}
+        assert_eq!(pattern.warning(), None);
+        while pattern.run_until_time(88200).is_some() {}
+        assert_eq!(pattern.warning(), Some(PatternWarning::NoEvents));
+        pattern.reset();
+        assert_eq!(pattern.warning(), None);
+        // a single pulse in a full cycle is fine
+        let mut pattern = time_base
+            .every_nth_beat(1.0)
+            .with_rhythm([0, 0, 0, 1].to_rhythm())
+            .emit(new_note(Note::C4).to_emitter());
+        while pattern.run_until_time(44100 * 8).is_some() {}
+        assert_eq!(pattern.warning(), None);
+        // zero step lengths stop the pattern
+        let mut pattern = time_base
+            .every_nth_beat(0.0)
+            .emit(new_note(Note::C4).to_emitter());
+        assert_eq!(pattern.next(), None);
+        assert_eq!(pattern.warning(), Some(PatternWarning::ZeroStepLength));
+    }
+}
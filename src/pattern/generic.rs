@@ -10,12 +10,12 @@ use std::borrow::BorrowMut;
 
 use crate::{
     emitter::{fixed::FixedEmitter, Emitter, EmitterEvent},
-    event::{Event, InstrumentId},
+    event::{apply_note_filter, Event, InstrumentId, NoteEvent},
     gate::threshold::ThresholdGate,
     rhythm::{fixed::FixedRhythm, Rhythm},
-    time::BeatTimeBase,
-    EventTransform, ExactSampleTime, Gate, Parameter, ParameterSet, Pattern, PatternEvent,
-    RhythmEvent, SampleTime,
+    time::{BeatTimeBase, BeatTimeStep, SampleTimeRounding},
+    Accent, DebugOutput, EventTransform, ExactSampleTime, Gate, Note, NoteFilter, Parameter,
+    ParameterSet, Pattern, PatternEvent, PatternWarning, RhythmEvent, SampleTime, Swing,
 };
 
 // -------------------------------------------------------------------------------------------------
@@ -47,13 +47,23 @@ pub struct GenericPattern<Step: GenericPatternTimeStep, Offset: GenericPatternTi
     rhythm_event: RhythmEvent,
     rhythm_repeat_count: Option<usize>,
     rhythm_playback_finished: bool,
+    rhythm_silent_pulses: usize,
     gate: Box<dyn Gate>,
+    gate_hold: Option<(usize, Event)>,
     emitter: Box<dyn Emitter>,
     emitter_sample_time: SampleTime,
     emitter_next_sample_time: ExactSampleTime,
     events: VecDeque<EmitterEvent>,
     event_transform: Option<EventTransform>,
-    sample_offset: SampleTime,
+    output_filter: Option<NoteFilter>,
+    debug_output: Option<DebugOutput>,
+    warning: Option<PatternWarning>,
+    swing: Swing,
+    swing_division: Option<BeatTimeStep>,
+    accent: Accent,
+    velocity_scale: f32,
+    sample_rounding: SampleTimeRounding,
+    sample_offset: ExactSampleTime,
 }
 
 impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> Debug
@@ -69,9 +79,17 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> Debug
             .field("rhythm", &self.rhythm)
             .field("rhythm_repeat_count", &self.rhythm_repeat_count)
             .field("rhythm_playback_finished", &self.rhythm_playback_finished)
+            .field("warning", &self.warning)
             .field("gate", &self.gate)
+            .field("gate_hold", &self.gate_hold)
             .field("emitter", &self.emitter)
-            // Skip event_transform, which has no Debug impl and event_iter state to reduce noise
+            // Skip event_transform and output_filter, which have no Debug impl, and event_iter
+            // state to reduce noise
+            .field("swing", &self.swing)
+            .field("swing_division", &self.swing_division)
+            .field("accent", &self.accent)
+            .field("velocity_scale", &self.velocity_scale)
+            .field("sample_rounding", &self.sample_rounding)
             .field("sample_offset", &self.sample_offset)
             .finish()
     }
@@ -87,13 +105,23 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> GenericPatter
         let rhythm_event = RhythmEvent::default();
         let rhythm_repeat_count = None;
         let rhythm_playback_finished = false;
+        let rhythm_silent_pulses = 0;
         let gate = Box::new(ThresholdGate::new());
+        let gate_hold = None;
         let emitter = Box::<FixedEmitter>::default();
         let emitter_sample_time = 0;
         let emitter_next_sample_time = offset.to_samples(&time_base);
         let events = VecDeque::new();
         let event_transform = None;
-        let sample_offset = 0;
+        let output_filter = None;
+        let debug_output = None;
+        let warning = None;
+        let swing = Swing::default();
+        let swing_division = None;
+        let accent = Accent::default();
+        let velocity_scale = 1.0;
+        let sample_rounding = SampleTimeRounding::default();
+        let sample_offset = 0.0;
         Self {
             time_base,
             step,
@@ -104,16 +132,32 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> GenericPatter
             rhythm_event,
             rhythm_repeat_count,
             rhythm_playback_finished,
+            rhythm_silent_pulses,
             gate,
+            gate_hold,
             emitter,
             emitter_sample_time,
             emitter_next_sample_time,
             events,
             event_transform,
+            output_filter,
+            debug_output,
+            warning,
+            swing,
+            swing_division,
+            accent,
+            velocity_scale,
+            sample_rounding,
             sample_offset,
         }
     }
 
+    /// Create a new pattern which emits events from the given custom [`Emitter`] every
+    /// `beat_time_base` `step`.
+    pub fn with_emitter(time_base: BeatTimeBase, step: Step, emitter: Box<dyn Emitter>) -> Self {
+        Self::new(time_base, step).trigger_dyn(emitter)
+    }
+
     /// Get current time base.
     pub fn time_base(&self) -> BeatTimeBase {
         self.time_base
@@ -126,6 +170,18 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> GenericPatter
     pub fn offset(&self) -> Offset {
         self.offset
     }
+    /// Get current swing.
+    pub fn swing(&self) -> &Swing {
+        &self.swing
+    }
+    /// Get current swing grid step length. When `None`, swing uses the pattern's step length.
+    pub fn swing_division(&self) -> Option<BeatTimeStep> {
+        self.swing_division
+    }
+    /// Get current accent.
+    pub fn accent(&self) -> &Accent {
+        &self.accent
+    }
     /// Get current rhythm.
     pub fn rhythm(&self) -> &dyn Rhythm {
         self.rhythm.borrow()
@@ -231,6 +287,7 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> GenericPatter
         let mut new = self;
         new.emitter = emitter;
         new.emitter.set_time_base(&time_base);
+        new.emitter.set_step_length(new.step_beats());
         new.emitter.set_parameters(parameters);
         new
     }
@@ -244,6 +301,49 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> GenericPatter
         }
     }
 
+    /// Return a new pattern instance which captures debug output in the given buffer.
+    #[must_use]
+    pub fn with_debug_output(self, debug_output: DebugOutput) -> Self {
+        Self {
+            debug_output: Some(debug_output),
+            ..self
+        }
+    }
+
+    /// Return a new pattern instance which drops notes the given output filter rejects.
+    #[must_use]
+    pub fn with_output_filter(self, filter: NoteFilter) -> Self {
+        Self {
+            output_filter: Some(filter),
+            ..self
+        }
+    }
+
+    /// Return a new pattern instance which applies the given swing to all note events.
+    #[must_use]
+    pub fn with_swing(self, swing: Swing) -> Self {
+        Self { swing, ..self }
+    }
+
+    /// Return a new pattern instance which applies swing on the given grid instead of the
+    /// pattern's steps, e.g. to swing sixteenth notes in a pattern which runs at 1/32.
+    /// Only events which start on every second step of the swing grid get delayed.
+    /// Pass `None` to swing the pattern's steps again.
+    #[must_use]
+    pub fn with_swing_division<D: Into<Option<BeatTimeStep>>>(self, division: D) -> Self {
+        let swing_division = division.into();
+        Self {
+            swing_division,
+            ..self
+        }
+    }
+
+    /// Return a new pattern instance which applies the given accent to all note events.
+    #[must_use]
+    pub fn with_accent(self, accent: Accent) -> Self {
+        Self { accent, ..self }
+    }
+
     /// Return current pulse duration in samples.
     #[inline]
     pub fn current_steps_sample_duration(&self) -> ExactSampleTime {
@@ -254,9 +354,10 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> GenericPatter
     #[inline]
     fn event_iter_item_start_time(&self, start: &Fraction) -> SampleTime {
         let step_time = self.current_steps_sample_duration();
-        let event_iter_time = self.sample_offset as f64 + self.emitter_next_sample_time;
+        let event_iter_time = self.sample_offset + self.emitter_next_sample_time;
         let start = start.to_f64().unwrap_or(0.0);
-        (event_iter_time + (step_time * start)) as SampleTime
+        self.sample_rounding
+            .to_sample_time(event_iter_time + (step_time * start))
     }
 
     /// Return duration in sample time of the given emitter event length.
@@ -264,44 +365,227 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> GenericPatter
     fn event_iter_item_duration(&self, length: &Fraction) -> SampleTime {
         let step_time = self.current_steps_sample_duration();
         let length = length.to_f64().unwrap_or(1.0);
-        (step_time * length) as SampleTime
+        self.sample_rounding.to_sample_time(step_time * length)
     }
 
-    /// Set a default instrument, if set, and apply event transform functions.
+    /// Length of a single pattern step in beats.
+    fn step_beats(&self) -> f64 {
+        let beat_samples = self.time_base.samples_per_beat();
+        if beat_samples > 0.0 {
+            self.step.to_samples(&self.time_base) / beat_samples
+        } else {
+            0.0
+        }
+    }
+
+    /// Position of the given emitter event in pattern steps, relative to the pattern's offset.
+    fn event_step_position(&self, event_item: &EmitterEvent, step_samples: f64) -> f64 {
+        let offset_samples = self.offset.to_samples(&self.time_base);
+        (self.emitter_next_sample_time - offset_samples) / step_samples
+            + self.rhythm_event.step_time * event_item.start.to_f64().unwrap_or(0.0)
+    }
+
+    /// Set a default instrument, if set, and apply velocity scale, accent and event transform
+    /// functions.
     fn apply_event_transform(&self, event_item: &mut EmitterEvent) {
+        if self.velocity_scale != 1.0 {
+            if let Event::NoteEvents(note_events) = &mut event_item.event {
+                for note_event in note_events.iter_mut().flatten() {
+                    note_event.volume *= self.velocity_scale;
+                }
+            }
+        }
+        if !self.accent.is_neutral() {
+            // accents are applied on the straight step grid, before swinging notes
+            let step_samples = self.step.to_samples(&self.time_base);
+            if step_samples > 0.0 {
+                let step_position = self.event_step_position(event_item, step_samples);
+                self.accent.apply(&mut event_item.event, step_position);
+            }
+        }
         if let Some(instrument) = self.instrument {
             if let Event::NoteEvents(note_events) = &mut event_item.event {
                 for note_event in note_events.iter_mut().flatten() {
                     note_event.instrument = note_event.instrument.or(Some(instrument));
                 }
             }
-            if let Some(transform) = &self.event_transform {
-                transform(&mut event_item.event);
+        }
+        if let Some(transform) = &self.event_transform {
+            transform(&mut event_item.event);
+        }
+        if !self.swing.is_straight() {
+            let step_samples = self.step.to_samples(&self.time_base);
+            let event_samples = self.event_iter_item_duration(&event_item.length);
+            if step_samples > 0.0 && event_samples > 0 {
+                let step_position = self.event_step_position(event_item, step_samples);
+                // convert step positions and lengths to the swing grid
+                let grid_samples = self.swing_division.map_or(step_samples, |division| {
+                    division.to_samples(&self.time_base)
+                });
+                if grid_samples > 0.0 {
+                    let grid_position = step_position * step_samples / grid_samples;
+                    let grid_length = grid_samples / event_samples as f64;
+                    self.swing
+                        .apply(&mut event_item.event, grid_position, grid_length);
+                }
+            }
+        }
+        if let Some(filter) = &self.output_filter {
+            apply_note_filter(filter, &mut event_item.event);
+        }
+    }
+
+    /// Set or clear the pattern's warning, logging newly raised warnings.
+    fn set_warning(&mut self, warning: Option<PatternWarning>) {
+        if warning != self.warning {
+            if let Some(warning) = warning {
+                log::warn!("{}", warning);
+            }
+            self.warning = warning;
+        }
+    }
+
+    /// Number of silent pulses after which the pattern is considered to produce no events:
+    /// the rhythm's length, but at least a bar, as generated rhythms have no known length.
+    fn silent_pulses_limit(&self) -> usize {
+        let step_samples = self.step.to_samples(&self.time_base);
+        let bar_steps = (self.time_base.samples_per_bar() / step_samples).ceil() as usize;
+        self.rhythm.len().max(bar_steps).max(1)
+    }
+
+    /// Create note-off events for all note-ons in the given emitter events.
+    fn gate_note_offs(events: &VecDeque<EmitterEvent>) -> Option<Event> {
+        let mut note_offs = Vec::<Option<NoteEvent>>::new();
+        for event in events {
+            if let Event::NoteEvents(note_events) = &event.event {
+                if note_offs.len() < note_events.len() {
+                    note_offs.resize(note_events.len(), None);
+                }
+                for (voice, note_event) in note_events.iter().enumerate() {
+                    if let Some(note_event) = note_event.as_ref().filter(|e| e.note.is_note_on()) {
+                        note_offs[voice] = Some(NoteEvent {
+                            note: Note::OFF,
+                            delay: 0.0,
+                            ..note_event.clone()
+                        });
+                    }
+                }
             }
         }
+        if note_offs.iter().any(Option::is_some) {
+            Some(Event::NoteEvents(note_offs))
+        } else {
+            None
+        }
     }
 
-    fn run_rhythm(&mut self) -> Option<(RhythmEvent, bool)> {
+    /// Musical length of the given event's notes at the given sample time, when the notes in
+    /// the current pulse are held by the gate for a fixed number of pulses.
+    fn gate_hold_note_duration(&self, event: &Event, time: SampleTime) -> Option<SampleTime> {
+        let (pulses, _) = self.gate_hold.as_ref()?;
+        let Event::NoteEvents(note_events) = event else {
+            return None;
+        };
+        if !note_events.iter().flatten().any(|e| e.note.is_note_on()) {
+            return None;
+        }
+        let hold_end = self.sample_rounding.to_sample_time(
+            self.sample_offset
+                + self.emitter_next_sample_time
+                + self.current_steps_sample_duration() * *pulses as ExactSampleTime,
+        );
+        Some(hold_end.saturating_sub(time))
+    }
+
+    /// Run the rhythm and gate. Returns the pulse, the gate state and note-offs of notes,
+    /// which got held by the gate and should be stopped at the start of the new pulse.
+    fn run_rhythm(&mut self) -> Option<(RhythmEvent, bool, Option<Event>)> {
         debug_assert!(
             self.events.is_empty(),
             "Should only run rhythms when there are no pending emitter items"
         );
+        // patterns with a zero step length would emit infinitely many events: stop them
+        let step_samples = self.step.to_samples(&self.time_base);
+        if step_samples <= 0.0 || step_samples.is_nan() {
+            self.set_warning(Some(PatternWarning::ZeroStepLength));
+            return None;
+        }
         if let Some(event) = self.rhythm.run() {
+            // count down notes which are held by the gate
+            let note_offs = match self.gate_hold.take() {
+                Some((pulses, note_offs)) if pulses <= 1 => Some(note_offs),
+                Some((pulses, note_offs)) => {
+                    self.gate_hold = Some((pulses - 1, note_offs));
+                    None
+                }
+                None => None,
+            };
             let emit_event = self.gate.run(&event);
             self.rhythm_event = event;
-            Some((event, emit_event))
+            if emit_event {
+                self.rhythm_silent_pulses = 0;
+                self.set_warning(None);
+            } else {
+                self.rhythm_silent_pulses += 1;
+                if self.rhythm_silent_pulses >= self.silent_pulses_limit() {
+                    self.set_warning(Some(PatternWarning::NoEvents));
+                }
+            }
+            Some((event, emit_event, note_offs))
         } else {
             None
         }
     }
 
+    /// Generate new events for the given gated pulse and prepend due gate note-offs.
+    fn run_emitter(
+        &mut self,
+        pulse: RhythmEvent,
+        emit_event: bool,
+        note_offs: Option<Event>,
+    ) -> VecDeque<EmitterEvent> {
+        let mut events = self
+            .emitter
+            .run(pulse, emit_event)
+            .map_or_else(VecDeque::default, VecDeque::from);
+        if emit_event {
+            // memorize note-offs for notes which should be held for a fixed number of pulses
+            self.gate_hold = self.gate.hold_length().and_then(|pulses| {
+                Self::gate_note_offs(&events).map(|note_offs| (pulses, note_offs))
+            });
+        }
+        if let Some(note_offs) = note_offs {
+            events.push_front(EmitterEvent::new(note_offs));
+        }
+        events
+    }
+
+    /// Move emitter time to the next pulse and advance smoothed parameter values accordingly.
+    fn advance_to_next_pulse(&mut self) {
+        self.emitter_next_sample_time += self.current_steps_sample_duration();
+        self.advance_parameter_smoothing();
+    }
+
+    /// Advance smoothed parameter values up to the current emitter time. Parameters may be
+    /// shared with duplicates of this pattern, so they get advanced to the absolute time and
+    /// not by the pulse duration.
+    fn advance_parameter_smoothing(&self) {
+        let seconds = (self.sample_offset + self.emitter_next_sample_time)
+            / self.time_base.samples_per_sec as ExactSampleTime;
+        for parameter in &self.parameters {
+            RefCell::borrow_mut(parameter).advance_smoothing_until(seconds);
+        }
+    }
+
     fn run(&mut self, sample_time: SampleTime, fetch_new_events: bool) -> Option<PatternEvent> {
         // quickly check if pattern playback finished
         if self.rhythm_playback_finished {
             return None;
         }
         // quickly check if the next event is due before the given target time
-        let next_sample_time = self.sample_offset + self.emitter_next_sample_time as SampleTime;
+        let next_sample_time = self
+            .sample_rounding
+            .to_sample_time(self.sample_offset + self.emitter_next_sample_time);
         if next_sample_time >= sample_time {
             // next event is not yet due
             return None;
@@ -313,12 +597,12 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> GenericPatter
                 return None;
             }
             // generate a pulse from the pattern and pass the pulse to the gate
-            if let Some((pulse, emit_event)) = self.run_rhythm() {
+            if let Some((pulse, emit_event, note_offs)) = self.run_rhythm() {
                 // generate new events from the gated pulse
-                self.events = self
-                    .emitter
-                    .run(pulse, emit_event)
-                    .map_or_else(VecDeque::default, VecDeque::from);
+                self.events = self.run_emitter(pulse, emit_event, note_offs);
+            } else if let Some((_, note_offs)) = self.gate_hold.take() {
+                // stop notes which still are held by the gate at the end of the rhythm
+                self.events.push_back(EmitterEvent::new(note_offs));
             } else {
                 // pattern playback finished
                 self.rhythm_playback_finished = true;
@@ -337,17 +621,19 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> GenericPatter
                 self.events.push_front(event_item);
                 return None;
             }
+            let note_duration = self.gate_hold_note_duration(&event_item.event, time);
             let event = Some(event_item.event);
             let duration = self.event_iter_item_duration(&event_item.length);
             // advance to the next pulse in the next iteration when all events got consumed
             if self.events.is_empty() {
-                self.emitter_next_sample_time += self.current_steps_sample_duration();
+                self.advance_to_next_pulse();
             }
             // return event as rhythm iter item
             Some(PatternEvent {
                 time,
                 event,
                 duration,
+                note_duration,
             })
         } else {
             // return 'None' event as sample timed rhythm iter item
@@ -356,12 +642,13 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> GenericPatter
             let event = None;
             let duration = self.event_iter_item_duration(&Fraction::ONE);
             // advance to the next pulse in the next iteration
-            self.emitter_next_sample_time += self.current_steps_sample_duration();
+            self.advance_to_next_pulse();
             // return event as rhythm iter item
             Some(PatternEvent {
                 time,
                 event,
                 duration,
+                note_duration: None,
             })
         }
     }
@@ -377,7 +664,12 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> Clone
             emitter: self.emitter.duplicate(),
             events: self.events.clone(),
             event_transform: self.event_transform.clone(),
+            output_filter: self.output_filter.clone(),
+            debug_output: self.debug_output.clone(),
             gate: self.gate.duplicate(),
+            gate_hold: self.gate_hold.clone(),
+            swing: self.swing.clone(),
+            accent: self.accent.clone(),
             ..*self
         }
     }
@@ -400,18 +692,23 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> Pattern
         &self.time_base
     }
     fn set_time_base(&mut self, time_base: &BeatTimeBase) {
-        // reschedule next event's sample time to the new time base
-        if self.emitter_sample_time > 0 {
-            self.emitter_next_sample_time = self.emitter_sample_time as ExactSampleTime
-                + (self.emitter_next_sample_time - self.emitter_sample_time as ExactSampleTime)
-                    / self.step.to_samples(&self.time_base)
-                    * self.step.to_samples(time_base);
+        // rescale sample times around the current playback position, so the pattern keeps
+        // its musical phase on tempo and sample rate changes
+        let old_step_samples = self.step.to_samples(&self.time_base);
+        let new_step_samples = self.step.to_samples(time_base);
+        if old_step_samples > 0.0 && old_step_samples != new_step_samples {
+            let scale = new_step_samples / old_step_samples;
+            let current_time =
+                (self.emitter_sample_time as ExactSampleTime).max(self.sample_offset);
+            self.sample_offset = current_time + (self.sample_offset - current_time) * scale;
+            self.emitter_next_sample_time *= scale;
         }
         self.time_base.clone_from(time_base);
         // update pattern, gate and emitter
         self.rhythm.set_time_base(time_base);
         self.gate.set_time_base(time_base);
         self.emitter.set_time_base(time_base);
+        self.emitter.set_step_length(self.step_beats());
     }
 
     fn step_length(&self) -> ExactSampleTime {
@@ -421,6 +718,10 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> Pattern
         self.rhythm.len()
     }
 
+    fn step_offset(&self) -> ExactSampleTime {
+        self.offset.to_samples(&self.time_base)
+    }
+
     fn parameters(&self) -> &[Rc<RefCell<Parameter>>] {
         &self.parameters
     }
@@ -431,14 +732,56 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> Pattern
         self.emitter.set_trigger_event(event);
     }
 
+    fn set_output_level(&mut self, level: f32) {
+        self.rhythm.set_output_level(level);
+        self.gate.set_output_level(level);
+        self.emitter.set_output_level(level);
+    }
+
+    fn set_playing_notes(&mut self, notes: &[NoteEvent]) {
+        self.rhythm.set_playing_notes(notes);
+        self.gate.set_playing_notes(notes);
+        self.emitter.set_playing_notes(notes);
+    }
+
     fn set_event_transform(&mut self, transform: Option<EventTransform>) {
         self.event_transform = transform;
     }
 
-    fn sample_offset(&self) -> SampleTime {
+    fn set_output_filter(&mut self, filter: Option<NoteFilter>) {
+        self.output_filter = filter;
+    }
+
+    fn debug_output(&self) -> Option<DebugOutput> {
+        self.debug_output.clone()
+    }
+
+    fn warning(&self) -> Option<PatternWarning> {
+        self.warning
+    }
+
+    fn velocity_scale(&self) -> f32 {
+        self.velocity_scale
+    }
+    fn set_velocity_scale(&mut self, scale: f32) {
+        self.velocity_scale = if scale.is_finite() {
+            scale.max(0.0)
+        } else {
+            1.0
+        };
+    }
+
+    fn sample_rounding(&self) -> SampleTimeRounding {
+        self.sample_rounding
+    }
+    fn set_sample_rounding(&mut self, rounding: SampleTimeRounding) {
+        self.sample_rounding = rounding;
+    }
+
+    fn sample_offset(&self) -> ExactSampleTime {
         self.sample_offset
     }
-    fn set_sample_offset(&mut self, sample_offset: SampleTime) {
+    fn set_sample_offset(&mut self, sample_offset: ExactSampleTime) {
         self.sample_offset = sample_offset;
     }
 
@@ -450,6 +793,10 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> Pattern
         self.run(sample_time, fetch_new_items)
     }
 
+    fn current_sample_time(&self) -> SampleTime {
+        self.emitter_sample_time
+    }
+
     fn advance_until_time(&mut self, sample_time: SampleTime) {
         // memorize current time
         self.emitter_sample_time = sample_time;
@@ -471,35 +818,40 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> Pattern
         // batch advance events in full pulse steps
         loop {
             // quickly check if the next event is due before the given target time
-            let next_sample_time =
-                self.sample_offset as ExactSampleTime + self.emitter_next_sample_time;
-            if (next_sample_time as SampleTime) >= sample_time {
+            let next_sample_time = self.sample_offset + self.emitter_next_sample_time;
+            if self.sample_rounding.to_sample_time(next_sample_time) >= sample_time {
                 // next event is not yet due: we're done
                 return;
             }
             // generate a pulse from the pattern and pass the pulse to the gate
-            if let Some((pulse, emit_event)) = self.run_rhythm() {
+            if let Some((pulse, emit_event, note_offs)) = self.run_rhythm() {
                 // test if the event crosses the target time
                 let step_duration = self.current_steps_sample_duration();
-                if ((next_sample_time + step_duration) as SampleTime) < sample_time {
+                if self
+                    .sample_rounding
+                    .to_sample_time(next_sample_time + step_duration)
+                    < sample_time
+                {
                     // skip all events from the gated pulse
                     self.emitter.advance(pulse, emit_event);
+                    if emit_event {
+                        self.gate_hold = None;
+                    }
                     self.emitter_next_sample_time += step_duration;
+                    self.advance_parameter_smoothing();
                 } else {
                     // generate new events from the gated pulse
-                    self.events = self
-                        .emitter
-                        .run(pulse, emit_event)
-                        .map_or_else(VecDeque::default, VecDeque::from);
+                    self.events = self.run_emitter(pulse, emit_event, note_offs);
                     // when the remaining step is empty advance to next step
                     if self.events.is_empty() {
-                        self.emitter_next_sample_time += self.current_steps_sample_duration();
+                        self.advance_to_next_pulse();
                     }
                     // we're done either way now...
                     break;
                 }
             } else {
-                // pattern playback finished: we're done here
+                // pattern playback finished: we're done here, held notes got skipped too
+                self.gate_hold = None;
                 self.rhythm_playback_finished = true;
                 return;
             }
@@ -519,16 +871,23 @@ impl<Step: GenericPatternTimeStep, Offset: GenericPatternTimeStep> Pattern
 
     fn reset(&mut self) {
         // reset sample offset
-        self.sample_offset = 0;
+        self.sample_offset = 0.0;
         // reset pattern and gate
         self.rhythm.reset();
         self.rhythm_playback_finished = false;
+        self.rhythm_silent_pulses = 0;
         self.gate.reset();
+        self.gate_hold = None;
+        self.warning = None;
         // reset iterator state
         self.emitter.reset();
         self.emitter_sample_time = 0;
         self.emitter_next_sample_time = self.offset.to_samples(&self.time_base);
         self.rhythm_event = RhythmEvent::default();
         self.events.clear();
+        // reset parameter smoothing time
+        for parameter in &self.parameters {
+            RefCell::borrow_mut(parameter).reset_smoothing_clock();
+        }
     }
 }
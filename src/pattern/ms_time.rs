@@ -0,0 +1,102 @@
+//! Wallclock time based `Pattern` implementation with millisecond steps.
+
+use crate::{
+    pattern::generic::{GenericPattern, GenericPatternTimeStep},
+    prelude::SampleTimeBase,
+    time::MsTimeStep,
+    BeatTimeBase, ExactSampleTime,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+impl GenericPatternTimeStep for MsTimeStep {
+    #[inline]
+    fn default_offset() -> Self {
+        MsTimeStep(0.0)
+    }
+
+    #[inline]
+    fn default_step() -> Self {
+        MsTimeStep(1.0)
+    }
+
+    #[inline]
+    fn to_samples(&self, time_base: &BeatTimeBase) -> ExactSampleTime {
+        self.0 as ExactSampleTime * time_base.samples_per_second() as ExactSampleTime / 1000.0
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A Pattern with a millisecond time offset and millisecond time step.
+///
+/// Steps are specified in milliseconds and only get converted to samples when running the
+/// pattern, so sample rate changes re-derive the step's sample length.
+pub type MsTimePattern = GenericPattern<MsTimeStep, MsTimeStep>;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Shortcuts for creating millisecond-time based patterns.
+impl BeatTimeBase {
+    pub fn every_nth_ms(&self, step: f64) -> MsTimePattern {
+        MsTimePattern::new(*self, MsTimeStep(step))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{emitter::fixed::new_note_emitter, Note, Pattern, SampleTime};
+
+    #[test]
+    fn step_times() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // step sizes which can't be represented exactly in samples must not drift
+        for (step, step_samples) in [(700.0, 30870.0), (1.0, 44.1), (0.3, 13.23)] {
+            let mut pattern = time_base
+                .every_nth_ms(step)
+                .emit(new_note_emitter(Note::C4));
+            assert_eq!(pattern.step_length(), step_samples);
+            for (index, event) in pattern.by_ref().take(10000).enumerate() {
+                let expected_time = (index as f64 * step_samples + 1e-3).floor() as SampleTime;
+                assert_eq!(event.time, expected_time);
+            }
+        }
+    }
+
+    #[test]
+    fn sample_rate_changes() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let mut pattern = time_base
+            .every_nth_ms(250.0)
+            .with_offset(MsTimeStep(100.0))
+            .emit(new_note_emitter(Note::C4));
+        assert_eq!(pattern.step_length(), 11025.0);
+        let mut event_times = Vec::new();
+        while let Some(event) = pattern.run_until_time(20000) {
+            event_times.push(event.time);
+        }
+        assert_eq!(event_times, [4410, 15435]);
+        // switch to 48kHz between two steps: 6460 samples at 44.1kHz are left until the next step
+        pattern.set_time_base(&BeatTimeBase {
+            samples_per_sec: 48000,
+            ..time_base
+        });
+        assert_eq!(pattern.step_length(), 12000.0);
+        let event_times = (0..2)
+            .map(|_| pattern.run_until_time(SampleTime::MAX).unwrap().time)
+            .collect::<Vec<_>>();
+        let next_time = 20000 + (6460.0 * 48000.0 / 44100.0) as SampleTime;
+        assert_eq!(event_times, [next_time, next_time + 12000]);
+    }
+}
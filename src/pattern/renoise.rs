@@ -0,0 +1,352 @@
+//! Render [`Pattern`] events into a static Renoise instrument phrase.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{time::to_sample_time, Event, ExactSampleTime, Note, NoteEvent, Pattern, SampleTime};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Max number of lines in a Renoise phrase.
+const MAX_LINES: usize = 512;
+/// Max number of note columns in a Renoise phrase.
+const MAX_NOTE_COLUMNS: usize = 12;
+/// Highest note value Renoise can display (B-9).
+const MAX_NOTE: u8 = 119;
+
+// -------------------------------------------------------------------------------------------------
+
+/// A single note column entry in a Renoise phrase line.
+#[derive(Clone, Debug, PartialEq)]
+struct NoteColumn {
+    note: Note,
+    instrument: Option<usize>,
+    volume: Option<u8>,
+    panning: Option<u8>,
+    delay: Option<u8>,
+}
+
+impl NoteColumn {
+    fn note_off(delay: Option<u8>) -> Self {
+        Self {
+            note: Note::OFF,
+            instrument: None,
+            volume: None,
+            panning: None,
+            delay,
+        }
+    }
+
+    fn from_note_event(note_event: &NoteEvent, delay: Option<u8>) -> Self {
+        // Renoise volumes are in range [0 - 0x80], where 0x80 (or no value) is full volume
+        let volume = if note_event.volume < 1.0 {
+            Some((note_event.volume.max(0.0) * 128.0).round() as u8)
+        } else {
+            None
+        };
+        // Renoise pannings are in range [0 - 0x80], where 0x40 (or no value) is center
+        let panning = if note_event.panning != 0.0 {
+            Some(((note_event.panning.clamp(-1.0, 1.0) + 1.0) * 64.0).round() as u8)
+        } else {
+            None
+        };
+        let instrument = note_event
+            .instrument
+            .filter(|_| note_event.note.is_note_on())
+            .map(usize::from)
+            .filter(|instrument| *instrument <= 0xFE);
+        Self {
+            note: note_event.note,
+            instrument,
+            volume,
+            panning,
+            delay,
+        }
+    }
+
+    /// Renoise's note name display, e.g. "C-4", "C#4" or "OFF".
+    fn note_name(&self) -> String {
+        if self.note.is_note_off() {
+            "OFF".to_string()
+        } else {
+            let name = self.note.to_string();
+            let (key, octave) = name.split_at(name.len() - 1);
+            if key.len() == 1 {
+                format!("{}-{}", key, octave)
+            } else {
+                format!("{}{}", key, octave)
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A note which got emitted by the pattern, before it gets placed into the phrase.
+struct EmittedNote {
+    time: SampleTime,
+    end_time: SampleTime,
+    voice_index: usize,
+    note_event: NoteEvent,
+}
+
+/// Reset the given pattern, run it for the given number of cycles and render all emitted
+/// note events into a Renoise instrument phrase XML document.
+///
+/// Each pattern step becomes a phrase line. Note events are placed into the note column of
+/// their voice index, or the next free column when the line already is occupied. Sub-step
+/// timings are written into the delay column. Note durations, which end before the next note
+/// in the same column starts, insert note-offs. Parameter change events are ignored.
+pub(crate) fn export_phrase(pattern: &mut dyn Pattern, cycles: usize) -> String {
+    pattern.reset();
+    let step_length = pattern.step_length();
+    let line_count = (pattern.step_count().max(1) * cycles).clamp(1, MAX_LINES);
+    let end_time = to_sample_time(line_count as ExactSampleTime * step_length);
+
+    // collect all emitted notes
+    let mut emitted_notes = Vec::new();
+    if step_length > 0.0 {
+        while let Some(pattern_event) = pattern.run_until_time(end_time) {
+            if let Some(Event::NoteEvents(note_events)) = &pattern_event.event {
+                for (voice_index, note_event) in note_events.iter().enumerate() {
+                    if let Some(note_event) = note_event {
+                        let note = note_event.note;
+                        if note == Note::EMPTY || (note.is_note_on() && note as u8 > MAX_NOTE) {
+                            continue;
+                        }
+                        emitted_notes.push(EmittedNote {
+                            time: pattern_event.note_event_time(note_event),
+                            end_time: pattern_event.time + pattern_event.duration,
+                            voice_index,
+                            note_event: note_event.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // line index and delay column value of a sample time
+    let line_position = |time: SampleTime| -> (usize, Option<u8>) {
+        let position = time as ExactSampleTime / step_length;
+        let line = position.floor() as usize;
+        let delay = ((position - position.floor()) * 256.0).round().min(255.0) as u8;
+        (line, if delay > 0 { Some(delay) } else { None })
+    };
+
+    // place notes into lines and columns
+    let mut lines = BTreeMap::<(usize, usize), NoteColumn>::new();
+    let mut column_notes = vec![Vec::<(SampleTime, SampleTime)>::new(); MAX_NOTE_COLUMNS];
+    for emitted_note in &emitted_notes {
+        let (line, delay) = line_position(emitted_note.time);
+        if line >= line_count {
+            continue;
+        }
+        let Some(column) = (emitted_note.voice_index..MAX_NOTE_COLUMNS)
+            .find(|column| !lines.contains_key(&(line, *column)))
+        else {
+            continue;
+        };
+        lines.insert(
+            (line, column),
+            NoteColumn::from_note_event(&emitted_note.note_event, delay),
+        );
+        if emitted_note.note_event.note.is_note_on() {
+            column_notes[column].push((emitted_note.time, emitted_note.end_time));
+        }
+    }
+
+    // insert note-offs for notes which end before the next note in the column starts
+    for (column, notes) in column_notes.iter_mut().enumerate() {
+        notes.sort_unstable();
+        for (index, (_, note_end_time)) in notes.iter().enumerate() {
+            let next_time = notes.get(index + 1).map_or(end_time, |(time, _)| *time);
+            if *note_end_time < next_time {
+                let (line, delay) = line_position(*note_end_time);
+                lines
+                    .entry((line, column))
+                    .or_insert_with(|| NoteColumn::note_off(delay));
+            }
+        }
+    }
+
+    write_phrase_xml(&lines, line_count, step_length, pattern)
+}
+
+fn write_phrase_xml(
+    lines: &BTreeMap<(usize, usize), NoteColumn>,
+    line_count: usize,
+    step_length: ExactSampleTime,
+    pattern: &dyn Pattern,
+) -> String {
+    let visible_note_columns = lines
+        .keys()
+        .map(|(_, column)| column + 1)
+        .max()
+        .unwrap_or(1);
+    let volume_column_visible = lines.values().any(|column| column.volume.is_some());
+    let panning_column_visible = lines.values().any(|column| column.panning.is_some());
+    let delay_column_visible = lines.values().any(|column| column.delay.is_some());
+    // lines per beat, approximated from the pattern's step length
+    let lines_per_beat = if step_length > 0.0 {
+        (pattern.time_base().samples_per_beat() / step_length)
+            .round()
+            .clamp(1.0, 256.0) as u32
+    } else {
+        4
+    };
+
+    let mut xml = String::new();
+    let _ = writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(xml, "<InstrumentPhrase>");
+    let _ = writeln!(xml, "  <Lines>");
+    let mut current_line = None;
+    let mut next_column = 0;
+    for ((line, column), note_column) in lines {
+        if current_line != Some(*line) {
+            if current_line.is_some() {
+                let _ = writeln!(xml, "      </NoteColumns>");
+                let _ = writeln!(xml, "    </Line>");
+            }
+            let _ = writeln!(xml, r#"    <Line index="{}">"#, line);
+            let _ = writeln!(xml, "      <NoteColumns>");
+            current_line = Some(*line);
+            next_column = 0;
+        }
+        // skip unused columns with empty elements
+        for _ in next_column..*column {
+            let _ = writeln!(xml, "        <NoteColumn/>");
+        }
+        next_column = column + 1;
+        let _ = writeln!(xml, "        <NoteColumn>");
+        let _ = writeln!(xml, "          <Note>{}</Note>", note_column.note_name());
+        if let Some(instrument) = note_column.instrument {
+            let _ = writeln!(xml, "          <Instrument>{:02X}</Instrument>", instrument);
+        }
+        if let Some(volume) = note_column.volume {
+            let _ = writeln!(xml, "          <Volume>{:02X}</Volume>", volume);
+        }
+        if let Some(panning) = note_column.panning {
+            let _ = writeln!(xml, "          <Panning>{:02X}</Panning>", panning);
+        }
+        if let Some(delay) = note_column.delay {
+            let _ = writeln!(xml, "          <Delay>{:02X}</Delay>", delay);
+        }
+        let _ = writeln!(xml, "        </NoteColumn>");
+    }
+    if current_line.is_some() {
+        let _ = writeln!(xml, "      </NoteColumns>");
+        let _ = writeln!(xml, "    </Line>");
+    }
+    let _ = writeln!(xml, "  </Lines>");
+    let _ = writeln!(xml, "  <NumberOfLines>{}</NumberOfLines>", line_count);
+    let _ = writeln!(
+        xml,
+        "  <VisibleNoteColumns>{}</VisibleNoteColumns>",
+        visible_note_columns
+    );
+    let _ = writeln!(
+        xml,
+        "  <VolumeColumnVisible>{}</VolumeColumnVisible>",
+        volume_column_visible
+    );
+    let _ = writeln!(
+        xml,
+        "  <PanningColumnVisible>{}</PanningColumnVisible>",
+        panning_column_visible
+    );
+    let _ = writeln!(
+        xml,
+        "  <DelayColumnVisible>{}</DelayColumnVisible>",
+        delay_column_visible
+    );
+    let _ = writeln!(xml, "  <LinesPerBeat>{}</LinesPerBeat>", lines_per_beat);
+    let _ = writeln!(xml, "</InstrumentPhrase>");
+    xml
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        emitter::fixed::ToFixedEmitterSequence,
+        event::{new_note, InstrumentId},
+        rhythm::fixed::ToFixedRhythm,
+        BeatTimeBase, Note, Pattern,
+    };
+
+    // Note column contents of all phrase lines as "line: note note..." strings.
+    fn note_column_layout(phrase: &str) -> Vec<String> {
+        let mut layout = Vec::new();
+        for line in phrase.lines().map(str::trim) {
+            if let Some(index) = line
+                .strip_prefix("<Line index=\"")
+                .and_then(|line| line.strip_suffix("\">"))
+            {
+                layout.push(format!("{}:", index));
+            } else if let Some(note) = line
+                .strip_prefix("<Note>")
+                .and_then(|line| line.strip_suffix("</Note>"))
+            {
+                layout.last_mut().unwrap().push_str(&format!(" {}", note));
+            } else if line == "<NoteColumn/>" {
+                layout.last_mut().unwrap().push_str(" ---");
+            }
+        }
+        layout
+    }
+
+    #[test]
+    fn export_phrase() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // a chord, a rest and two notes with volume, panning and delay
+        let pattern = time_base
+            .every_nth_sixteenth(1.0)
+            .with_rhythm([1, 0, 1, 1].to_rhythm())
+            .with_instrument(InstrumentId::from(1))
+            .emit(
+                vec![
+                    vec![new_note(Note::C4), new_note(Note::E4)],
+                    vec![new_note((Note::Cs5, None, 0.5, -1.0))],
+                    vec![new_note((Note::G4, None, 1.0, 0.0, 0.5))],
+                ]
+                .to_sequence_emitter(),
+            );
+        let phrase = pattern.export_renoise_phrase(2);
+        assert_eq!(
+            note_column_layout(&phrase),
+            vec![
+                "0: C-4 E-4",
+                "2: C#5 OFF",
+                "3: G-4",
+                "4: C-4 E-4",
+                "6: C#5 OFF",
+                "7: G-4",
+            ]
+        );
+        assert!(phrase.contains("<NumberOfLines>8</NumberOfLines>"));
+        assert!(phrase.contains("<VisibleNoteColumns>2</VisibleNoteColumns>"));
+        assert!(phrase.contains("<LinesPerBeat>4</LinesPerBeat>"));
+        assert!(phrase.contains(
+            "<Note>C#5</Note>\n          <Instrument>01</Instrument>\n          \
+            <Volume>40</Volume>\n          <Panning>00</Panning>"
+        ));
+        assert!(phrase.contains(
+            "<Note>G-4</Note>\n          <Instrument>01</Instrument>\n          \
+            <Delay>80</Delay>"
+        ));
+        // empty patterns produce empty phrases
+        let pattern = time_base
+            .every_nth_beat(1.0)
+            .with_rhythm([0, 0].to_rhythm())
+            .emit(vec![vec![new_note(Note::C4)]].to_sequence_emitter());
+        let phrase = pattern.export_renoise_phrase(1);
+        assert!(note_column_layout(&phrase).is_empty());
+        assert!(phrase.contains("<NumberOfLines>2</NumberOfLines>"));
+        assert!(phrase.contains("<VisibleNoteColumns>1</VisibleNoteColumns>"));
+    }
+}
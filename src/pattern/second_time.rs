@@ -39,3 +39,66 @@ impl BeatTimeBase {
         SecondTimePattern::new(*self, step)
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        emitter::fixed::{new_note_emitter, ToFixedEmitter},
+        event::NoteEvent,
+        Event, Note, PatternEvent, SampleTime,
+    };
+
+    fn note_event_times(event: &PatternEvent) -> Vec<SampleTime> {
+        match &event.event {
+            Some(Event::NoteEvents(notes)) => notes
+                .iter()
+                .flatten()
+                .map(|note| event.note_event_time(note))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn step_times() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // step sizes which can't be represented exactly as floats must not drift
+        for (step, step_samples) in [(0.7, 30870.0), (0.3, 13230.0), (0.001, 44.1)] {
+            let mut pattern = time_base
+                .every_nth_seconds(step)
+                .emit(new_note_emitter(Note::C4));
+            for (index, event) in pattern.by_ref().take(10000).enumerate() {
+                let expected_time = (index as f64 * step_samples).floor() as SampleTime;
+                assert_eq!(event.time, expected_time);
+            }
+        }
+    }
+
+    #[test]
+    fn note_delays() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 48000,
+        };
+        let delayed_note = NoteEvent::from((Note::C4, None, 1.0, 0.0, 0.25));
+        let undelayed_note = NoteEvent::from((Note::E4, None, 1.0, 0.0, 0.0));
+        let mut pattern = time_base
+            .every_nth_seconds(0.7)
+            .with_offset(0.1)
+            .emit(vec![Some(delayed_note), Some(undelayed_note)].to_emitter());
+        for (index, event) in pattern.by_ref().take(10000).enumerate() {
+            let step_time = 4800 + index as SampleTime * 33600;
+            assert_eq!(event.time, step_time);
+            assert_eq!(event.duration, 33600);
+            assert_eq!(note_event_times(&event), vec![step_time + 8400, step_time]);
+        }
+    }
+}
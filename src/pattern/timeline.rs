@@ -0,0 +1,172 @@
+//! Render [`Pattern`] events into a JSON event timeline for external tools.
+
+use std::fmt::Write;
+
+use crate::{time::to_sample_time, Event, ExactSampleTime, Pattern};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Reset the given pattern, run it for the given number of cycles and render all emitted
+/// events into a JSON array.
+///
+/// Each note event and parameter change becomes an array entry. Note events of all voices
+/// are written as separate entries, ordered by their time. Rests are not written.
+///
+/// Note entries have the fields: `type` ("note"), `time` (samples), `beats`, `duration`
+/// (samples), `voice`, `note` (name), `key` (note number), `instrument` (or null), `volume`,
+/// `panning` and `delay`.
+///
+/// Parameter change entries have the fields: `type` ("parameter"), `time` (samples), `beats`,
+/// `duration` (samples), `parameter` (or null) and `value`.
+pub(crate) fn export_timeline(pattern: &mut dyn Pattern, cycles: usize) -> String {
+    pattern.reset();
+    let step_length = pattern.step_length();
+    let end_time =
+        to_sample_time((pattern.step_count().max(1) * cycles) as ExactSampleTime * step_length);
+    let samples_per_beat = pattern.time_base().samples_per_beat();
+    let beats = |time: u64| -> f64 {
+        if samples_per_beat > 0.0 {
+            time as f64 / samples_per_beat
+        } else {
+            0.0
+        }
+    };
+
+    let mut entries = Vec::new();
+    if step_length > 0.0 {
+        while let Some(pattern_event) = pattern.run_until_time(end_time) {
+            match &pattern_event.event {
+                Some(Event::NoteEvents(note_events)) => {
+                    for (voice_index, note_event) in note_events.iter().enumerate() {
+                        let Some(note_event) = note_event else {
+                            continue;
+                        };
+                        let time = pattern_event.note_event_time(note_event);
+                        let duration =
+                            (pattern_event.time + pattern_event.duration).saturating_sub(time);
+                        let mut entry = String::new();
+                        let _ = write!(
+                            entry,
+                            "{{\"type\":\"note\",\"time\":{},\"beats\":{},\"duration\":{},\
+                            \"voice\":{},\"note\":\"{}\",\"key\":{},\"instrument\":{},\
+                            \"volume\":{},\"panning\":{},\"delay\":{}}}",
+                            time,
+                            json_number(beats(time)),
+                            duration,
+                            voice_index,
+                            note_event.note,
+                            note_event.note as u8,
+                            note_event
+                                .instrument
+                                .map_or("null".to_string(), |id| usize::from(id).to_string()),
+                            json_number(note_event.volume as f64),
+                            json_number(note_event.panning as f64),
+                            json_number(note_event.delay as f64)
+                        );
+                        entries.push(entry);
+                    }
+                }
+                Some(Event::ParameterChangeEvent(change)) => {
+                    let time = pattern_event.time;
+                    let mut entry = String::new();
+                    let _ = write!(
+                        entry,
+                        "{{\"type\":\"parameter\",\"time\":{},\"beats\":{},\"duration\":{},\
+                        \"parameter\":{},\"value\":{}}}",
+                        time,
+                        json_number(beats(time)),
+                        pattern_event.duration,
+                        change
+                            .parameter
+                            .map_or("null".to_string(), |id| u64::from(id).to_string()),
+                        json_number(change.value as f64)
+                    );
+                    entries.push(entry);
+                }
+                None => (),
+            }
+        }
+    }
+
+    format!("[{}]", entries.join(","))
+}
+
+/// JSON has no representation for NaN or infinite values: write them as null.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        emitter::{fixed::ToFixedEmitterSequence, new_parameter_change_emitter},
+        event::{new_note, InstrumentId, ParameterId},
+        rhythm::fixed::ToFixedRhythm,
+        BeatTimeBase, Note, Pattern,
+    };
+
+    #[test]
+    fn export_timeline() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // a chord, a rest, note-offs for the chord and a delayed note
+        let mut pattern = time_base
+            .every_nth_beat(1.0)
+            .with_rhythm([1, 0, 1, 1].to_rhythm())
+            .emit(
+                vec![
+                    vec![
+                        new_note((Note::C4, InstrumentId::from(1), 0.5)),
+                        new_note(Note::E4),
+                    ],
+                    vec![new_note(Note::OFF)],
+                    vec![new_note((Note::G4, None, 1.0, -0.5, 0.5))],
+                ]
+                .to_sequence_emitter(),
+            );
+        let timeline = pattern.export_timeline_json(1);
+        assert_eq!(
+            timeline,
+            "[\
+            {\"type\":\"note\",\"time\":0,\"beats\":0,\"duration\":22050,\"voice\":0,\
+             \"note\":\"C4\",\"key\":48,\"instrument\":1,\"volume\":0.5,\"panning\":0,\"delay\":0},\
+            {\"type\":\"note\",\"time\":0,\"beats\":0,\"duration\":22050,\"voice\":1,\
+             \"note\":\"E4\",\"key\":52,\"instrument\":null,\"volume\":1,\"panning\":0,\"delay\":0},\
+            {\"type\":\"note\",\"time\":44100,\"beats\":2,\"duration\":22050,\"voice\":0,\
+             \"note\":\"off\",\"key\":255,\"instrument\":null,\"volume\":1,\"panning\":0,\"delay\":0},\
+            {\"type\":\"note\",\"time\":44100,\"beats\":2,\"duration\":22050,\"voice\":1,\
+             \"note\":\"off\",\"key\":255,\"instrument\":null,\"volume\":1,\"panning\":0,\"delay\":0},\
+            {\"type\":\"note\",\"time\":77175,\"beats\":3.5,\"duration\":11025,\"voice\":0,\
+             \"note\":\"G4\",\"key\":55,\"instrument\":null,\"volume\":1,\"panning\":-0.5,\"delay\":0.5}\
+            ]"
+        );
+
+        // exporting does not affect the pattern's playback state
+        assert!(pattern.next().is_some());
+        assert_eq!(pattern.export_timeline_json(1), timeline);
+
+        // parameter changes
+        let pattern = time_base
+            .every_nth_beat(1.0)
+            .emit(new_parameter_change_emitter(ParameterId::from(2), 0.25));
+        let timeline = pattern.export_timeline_json(2);
+        assert_eq!(
+            timeline,
+            "[\
+            {\"type\":\"parameter\",\"time\":0,\"beats\":0,\"duration\":22050,\
+             \"parameter\":2,\"value\":0.25},\
+            {\"type\":\"parameter\",\"time\":22050,\"beats\":1,\"duration\":22050,\
+             \"parameter\":2,\"value\":0.25}\
+            ]"
+        );
+    }
+}
@@ -3,8 +3,9 @@
 use std::{cell::RefCell, cmp::Ordering, fmt::Debug, rc::Rc};
 
 use crate::{
-    BeatTimeBase, BeatTimeStep, Event, EventTransform, ExactSampleTime, Parameter, ParameterSet,
-    Pattern, PatternEvent, SampleTime,
+    event::apply_note_filter, BeatTimeBase, BeatTimeStep, DebugOutput, Event, EventTransform,
+    ExactSampleTime, Note, NoteEvent, NoteFilter, Parameter, ParameterSet, Pattern, PatternEvent,
+    PatternWarning, SampleTime, SampleTimeRounding,
 };
 
 // -------------------------------------------------------------------------------------------------
@@ -50,15 +51,45 @@ pub type PhraseEvent = (PatternIndex, PatternEvent);
 // -------------------------------------------------------------------------------------------------
 
 /// Combines multiple [`Pattern`]s into a new pattern stack.
-#[derive(Clone)]
 pub struct Phrase {
     time_base: BeatTimeBase,
     length: BeatTimeStep,
     parameters: ParameterSet,
     pattern_slots: Vec<PatternSlot>,
+    replaced_pattern_slots: Vec<(PatternIndex, PatternSlot)>,
     next_events: Vec<Option<PhraseEvent>>,
+    note_off_at_end: bool,
+    common_cycle: bool,
+    note_columns: Vec<usize>,
     event_transform: Option<EventTransform>,
-    sample_offset: SampleTime,
+    output_filter: Option<NoteFilter>,
+    velocity_scale: f32,
+    sample_rounding: SampleTimeRounding,
+    sample_offset: ExactSampleTime,
+    current_sample_time: SampleTime,
+}
+
+impl Clone for Phrase {
+    fn clone(&self) -> Self {
+        Self {
+            time_base: self.time_base,
+            length: self.length,
+            parameters: self.parameters.clone(),
+            pattern_slots: self.pattern_slots.clone(),
+            // replaced slots belong to the original phrase's playback
+            replaced_pattern_slots: Vec::new(),
+            next_events: self.next_events.clone(),
+            note_off_at_end: self.note_off_at_end,
+            common_cycle: self.common_cycle,
+            note_columns: self.note_columns.clone(),
+            event_transform: self.event_transform.clone(),
+            output_filter: self.output_filter.clone(),
+            velocity_scale: self.velocity_scale,
+            sample_rounding: self.sample_rounding,
+            sample_offset: self.sample_offset,
+            current_sample_time: self.current_sample_time,
+        }
+    }
 }
 
 impl Debug for Phrase {
@@ -68,7 +99,12 @@ impl Debug for Phrase {
             .field("length", &self.length)
             .field("parameters", &self.parameters)
             .field("pattern_slots", &self.pattern_slots)
-            // Skip event_transform, which has no Debug impl and next_events to reduce noise
+            .field("note_off_at_end", &self.note_off_at_end)
+            .field("common_cycle", &self.common_cycle)
+            // Skip event_transform and output_filter, which have no Debug impl, and next_events
+            // and replaced_pattern_slots to reduce noise
+            .field("velocity_scale", &self.velocity_scale)
+            .field("sample_rounding", &self.sample_rounding)
             .field("sample_offset", &self.sample_offset)
             .finish()
     }
@@ -103,24 +139,109 @@ impl Phrase {
                 }
             }
         }
+        let replaced_pattern_slots = Vec::new();
         let next_events = vec![None; pattern_slots.len()];
+        let note_off_at_end = false;
+        let common_cycle = false;
+        let note_columns = vec![0; pattern_slots.len()];
         let event_transform = None;
-        let sample_offset = 0;
+        let output_filter = None;
+        let velocity_scale = 1.0;
+        let sample_rounding = SampleTimeRounding::default();
+        let sample_offset = 0.0;
+        let current_sample_time = 0;
         Self {
             time_base,
             length,
             parameters,
             pattern_slots,
+            replaced_pattern_slots,
             next_events,
+            note_off_at_end,
+            common_cycle,
+            note_columns,
             event_transform,
+            output_filter,
+            velocity_scale,
+            sample_rounding,
             sample_offset,
+            current_sample_time,
         }
     }
 
-    /// Read-only access to our phrase length.
+    /// Read-only access to our phrase length: the common cycle of all pattern slots, when
+    /// enabled, else the phrase's default length.
     /// This is applied in [`Sequence`][crate::Sequence] only.
     pub fn length(&self) -> BeatTimeStep {
-        self.length
+        if self.common_cycle {
+            self.common_cycle_length().unwrap_or(self.length)
+        } else {
+            self.length
+        }
+    }
+
+    /// Returns true when the phrase's length is the common cycle of all its pattern slots.
+    pub fn common_cycle(&self) -> bool {
+        self.common_cycle
+    }
+
+    /// Set whether the phrase's length should be the common cycle, the least common multiple
+    /// of the cycles of all pattern slots, instead of the phrase's default length. This way
+    /// polyrhythmic patterns, e.g. 3 against 4 steps, loop and realign together.
+    ///
+    /// A slot's cycle is its step count times its step length. Generated rhythms have no known
+    /// step count and report a single step, so they only contribute their step length. Falls
+    /// back to the phrase's default length when no slot has a cycle. By default disabled.
+    pub fn set_common_cycle(&mut self, enabled: bool) {
+        self.common_cycle = enabled;
+    }
+
+    /// Compute the least common multiple of the cycles of all pattern slots, quantized to
+    /// ticks of [`BeatTimeBase::TICKS_PER_BEAT`]. Returns None when no slot has a cycle or
+    /// when the common cycle gets too long.
+    fn common_cycle_length(&self) -> Option<BeatTimeStep> {
+        fn gcd(a: u64, b: u64) -> u64 {
+            if b == 0 {
+                a
+            } else {
+                gcd(b, a % b)
+            }
+        }
+        let samples_per_beat = self.time_base.samples_per_beat();
+        if samples_per_beat <= 0.0 {
+            return None;
+        }
+        let mut common_ticks: Option<u64> = None;
+        for pattern_slot in &self.pattern_slots {
+            if let PatternSlot::Pattern(pattern) = pattern_slot {
+                let pattern = pattern.borrow();
+                let cycle_beats =
+                    pattern.step_count() as f64 * pattern.step_length() / samples_per_beat;
+                let ticks = (cycle_beats * BeatTimeBase::TICKS_PER_BEAT as f64).round() as u64;
+                if ticks > 0 {
+                    common_ticks = Some(match common_ticks {
+                        Some(common) => (common / gcd(common, ticks)).checked_mul(ticks)?,
+                        None => ticks,
+                    });
+                }
+            }
+        }
+        common_ticks.map(|ticks| {
+            BeatTimeStep::Beats((ticks as f64 / BeatTimeBase::TICKS_PER_BEAT as f64) as f32)
+        })
+    }
+
+    /// Returns true when notes of all patterns get stopped at the end of the phrase.
+    pub fn note_off_at_end(&self) -> bool {
+        self.note_off_at_end
+    }
+
+    /// Set whether notes, which still are playing at the end of the phrase, should be stopped
+    /// with note-offs at the phrase's end. This gives clean loop points for sustained notes,
+    /// when the phrase loops or the next phrase starts playing in a [`Sequence`][crate::Sequence].
+    /// By default disabled.
+    pub fn set_note_off_at_end(&mut self, enabled: bool) {
+        self.note_off_at_end = enabled;
     }
 
     /// Read-only access to our pattern slots.
@@ -129,10 +250,48 @@ impl Phrase {
     }
 
     /// Mut access to our pattern slots.
+    ///
+    /// NB: To swap patterns while playing, use [`Self::set_pattern_slot`] instead, so players
+    /// can stop the notes of the replaced patterns.
     pub fn pattern_slots_mut(&mut self) -> &mut [PatternSlot] {
         &mut self.pattern_slots
     }
 
+    /// Replace the pattern slot at the given index, returning the previous slot.
+    ///
+    /// The replaced slot gets memorized until it's fetched via
+    /// [`Self::take_replaced_pattern_slots`], so players can look up the note-offs of the
+    /// replaced pattern to stop its playing notes, e.g. the `SamplePlayer` in its next run.
+    /// Only the first replaced slot of each index is memorized, as this is the slot which
+    /// played before. Memorized slots get dropped when the phrase is reset or advanced.
+    ///
+    /// ### Panics
+    /// Panics if the given index is out of bounds.
+    pub fn set_pattern_slot<P: Into<PatternSlot>>(
+        &mut self,
+        index: PatternIndex,
+        pattern_slot: P,
+    ) -> PatternSlot {
+        let previous_slot = std::mem::replace(&mut self.pattern_slots[index], pattern_slot.into());
+        // drop already fetched events of the previous pattern
+        self.next_events[index] = None;
+        if !self
+            .replaced_pattern_slots
+            .iter()
+            .any(|(replaced_index, _)| *replaced_index == index)
+        {
+            self.replaced_pattern_slots
+                .push((index, previous_slot.clone()));
+        }
+        previous_slot
+    }
+
+    /// Fetch and forget all pattern slots which got replaced via [`Self::set_pattern_slot`]
+    /// as pattern index and previous slot pairs, in the order they got replaced.
+    pub fn take_replaced_pattern_slots(&mut self) -> Vec<(PatternIndex, PatternSlot)> {
+        std::mem::take(&mut self.replaced_pattern_slots)
+    }
+
     /// Run patterns to generate the next pending event, if any, calling the given `consumer`
     /// visitor function with the emitted event.
     pub fn consume_event<F>(&mut self, consumer: &mut F)
@@ -154,6 +313,8 @@ impl Phrase {
     where
         F: FnMut(PatternIndex, PatternEvent),
     {
+        // memorize current time
+        self.current_sample_time = sample_time;
         // emit and consume next events until we've reached the desired sample_time
         while let Some((pattern_index, mut pattern_event)) = self.next_event_until_time(sample_time)
         {
@@ -165,6 +326,10 @@ impl Phrase {
 
     /// Move patterns until a given sample time is reached, ignoring all events until that time.
     pub fn advance_until_time(&mut self, sample_time: SampleTime) {
+        // memorize current time
+        self.current_sample_time = sample_time;
+        // notes of replaced slots no longer play after seeking
+        self.replaced_pattern_slots.clear();
         // skip next events in all patterns
         for (pattern_slot, next_event) in self
             .pattern_slots
@@ -187,7 +352,9 @@ impl Phrase {
 
     /// reset playback status and shift events to the given sample position.
     /// Further take over patterns from the passed previously playing phrase for `PatternSlot::Continue` slots.   
-    pub fn reset_with_offset(&mut self, sample_offset: SampleTime, previous_phrase: &Phrase) {
+    pub fn reset_with_offset(&mut self, sample_offset: ExactSampleTime, previous_phrase: &Phrase) {
+        // forget replaced slots from the last playback
+        self.replaced_pattern_slots.clear();
         // reset pattern iters, unless they are in continue mode. in continue mode, copy the slot
         // from the previously playing phrase and adjust sample offsets to fit.
         for (pattern_index, pattern_slot) in self.pattern_slots.iter_mut().enumerate() {
@@ -199,14 +366,21 @@ impl Phrase {
                         pattern.set_sample_offset(sample_offset);
                     }
                     self.next_events[pattern_index] = None;
+                    self.note_columns[pattern_index] = 0;
                 }
                 PatternSlot::Stop => {
                     self.next_events[pattern_index] = None;
+                    self.note_columns[pattern_index] = 0;
                 }
                 PatternSlot::Continue => {
                     // take over pending events
                     self.next_events[pattern_index]
                         .clone_from(&previous_phrase.next_events[pattern_index]);
+                    self.note_columns[pattern_index] = previous_phrase
+                        .note_columns
+                        .get(pattern_index)
+                        .copied()
+                        .unwrap_or(0);
                     // take over pattern
                     pattern_slot.clone_from(&previous_phrase.pattern_slots[pattern_index]);
                 }
@@ -214,12 +388,40 @@ impl Phrase {
         }
     }
 
+    /// Create note-off events at the given sample time for all patterns which emitted notes
+    /// since the last call, when notes should be stopped at the end of the phrase.
+    pub(crate) fn take_note_off_events(&mut self, sample_time: SampleTime) -> Vec<PhraseEvent> {
+        if !self.note_off_at_end {
+            return vec![];
+        }
+        let mut note_off_events = Vec::new();
+        for (pattern_index, note_columns) in self.note_columns.iter_mut().enumerate() {
+            if *note_columns > 0 {
+                let note_offs = vec![Some(NoteEvent::from(Note::OFF)); *note_columns];
+                note_off_events.push((
+                    pattern_index,
+                    PatternEvent {
+                        time: sample_time,
+                        event: Some(Event::NoteEvents(note_offs)),
+                        duration: 0,
+                        note_duration: None,
+                    },
+                ));
+                *note_columns = 0;
+            }
+        }
+        note_off_events
+    }
+
     /// Apply custom event transform function, if any, to all emitted events.
     fn apply_event_transform(&self, pattern_event: &mut PatternEvent) {
-        if let Some(transform) = &self.event_transform {
-            if let Some(event) = &mut pattern_event.event {
+        if let Some(event) = &mut pattern_event.event {
+            if let Some(transform) = &self.event_transform {
                 transform(event);
             }
+            if let Some(filter) = &self.output_filter {
+                apply_note_filter(filter, event);
+            }
         }
     }
 
@@ -264,7 +466,16 @@ impl Phrase {
             if let Some((pattern_index, event)) = next_due.clone() {
                 if event.time < sample_time {
                     *next_due = None; // consume
-                    Some((pattern_index, event.with_offset(self.sample_offset)))
+
+                    // memorize note columns, in order to stop notes at the end of the phrase
+                    if let Some(Event::NoteEvents(note_events)) = &event.event {
+                        let note_columns = &mut self.note_columns[pattern_index];
+                        *note_columns = (*note_columns).max(note_events.len());
+                    }
+                    Some((
+                        pattern_index,
+                        event.with_offset(self.sample_rounding.to_sample_time(self.sample_offset)),
+                    ))
                 } else {
                     None // not yet due
                 }
@@ -283,20 +494,41 @@ impl Pattern for Phrase {
     }
     fn set_time_base(&mut self, time_base: &BeatTimeBase) {
         self.time_base.clone_from(time_base);
-        for pattern_slot in &mut self.pattern_slots {
+        let current_time = self.current_sample_time as ExactSampleTime;
+        for (pattern_slot, next_event) in self
+            .pattern_slots
+            .iter_mut()
+            .zip(self.next_events.iter_mut())
+        {
             if let PatternSlot::Pattern(pattern) = pattern_slot {
-                pattern.borrow_mut().set_time_base(time_base);
+                let mut pattern = pattern.borrow_mut();
+                let old_step_length = pattern.step_length();
+                pattern.set_time_base(time_base);
+                let new_step_length = pattern.step_length();
+                // reschedule already fetched events to keep their musical phase
+                if let Some((_, event)) = next_event {
+                    if old_step_length > 0.0 && old_step_length != new_step_length {
+                        let scale = new_step_length / old_step_length;
+                        let time = event.time as ExactSampleTime;
+                        event.time = self
+                            .sample_rounding
+                            .to_sample_time(current_time + (time - current_time) * scale);
+                        event.duration = self
+                            .sample_rounding
+                            .to_sample_time(event.duration as ExactSampleTime * scale);
+                    }
+                }
             }
         }
     }
 
     fn step_length(&self) -> ExactSampleTime {
         // use our length's step, likely won't be used anyway for phrases
-        self.length.samples_per_step(&self.time_base)
+        self.length().samples_per_step(&self.time_base)
     }
     fn step_count(&self) -> usize {
         // use our length's step, likely won't be used anyway for phrases
-        self.length.steps() as usize
+        self.length().steps() as usize
     }
 
     fn parameters(&self) -> &[Rc<RefCell<Parameter>>] {
@@ -311,22 +543,87 @@ impl Pattern for Phrase {
         }
     }
 
+    fn set_output_level(&mut self, level: f32) {
+        for pattern_slot in &mut self.pattern_slots {
+            if let PatternSlot::Pattern(pattern) = pattern_slot {
+                pattern.borrow_mut().set_output_level(level);
+            }
+        }
+    }
+
+    fn set_playing_notes(&mut self, notes: &[NoteEvent]) {
+        for pattern_slot in &mut self.pattern_slots {
+            if let PatternSlot::Pattern(pattern) = pattern_slot {
+                pattern.borrow_mut().set_playing_notes(notes);
+            }
+        }
+    }
+
     fn set_event_transform(&mut self, transform: Option<EventTransform>) {
         self.event_transform = transform;
     }
 
-    fn sample_offset(&self) -> SampleTime {
+    fn set_output_filter(&mut self, filter: Option<NoteFilter>) {
+        self.output_filter = filter;
+    }
+
+    fn debug_output(&self) -> Option<DebugOutput> {
+        // phrases have no own output: patterns in the slots may have one
+        None
+    }
+
+    fn warning(&self) -> Option<PatternWarning> {
+        self.pattern_slots.iter().find_map(|slot| match slot {
+            PatternSlot::Pattern(pattern) => pattern.borrow().warning(),
+            PatternSlot::Stop | PatternSlot::Continue => None,
+        })
+    }
+
+    fn velocity_scale(&self) -> f32 {
+        self.velocity_scale
+    }
+    fn set_velocity_scale(&mut self, scale: f32) {
+        self.velocity_scale = if scale.is_finite() {
+            scale.max(0.0)
+        } else {
+            1.0
+        };
+        for pattern_slot in &mut self.pattern_slots {
+            if let PatternSlot::Pattern(pattern) = pattern_slot {
+                pattern.borrow_mut().set_velocity_scale(self.velocity_scale);
+            }
+        }
+    }
+
+    fn sample_rounding(&self) -> SampleTimeRounding {
+        self.sample_rounding
+    }
+    fn set_sample_rounding(&mut self, rounding: SampleTimeRounding) {
+        self.sample_rounding = rounding;
+        for pattern_slot in &mut self.pattern_slots {
+            if let PatternSlot::Pattern(pattern) = pattern_slot {
+                pattern.borrow_mut().set_sample_rounding(rounding);
+            }
+        }
+    }
+
+    fn sample_offset(&self) -> ExactSampleTime {
         self.sample_offset
     }
-    fn set_sample_offset(&mut self, sample_offset: SampleTime) {
+    fn set_sample_offset(&mut self, sample_offset: ExactSampleTime) {
         self.sample_offset = sample_offset;
     }
 
     fn run_until_time(&mut self, sample_time: SampleTime) -> Option<PatternEvent> {
+        self.current_sample_time = sample_time;
         self.next_event_until_time(sample_time)
             .map(|(_, event)| event)
     }
 
+    fn current_sample_time(&self) -> SampleTime {
+        self.current_sample_time
+    }
+
     fn advance_until_time(&mut self, sample_time: SampleTime) {
         self.advance_until_time(sample_time)
     }
@@ -337,9 +634,12 @@ impl Pattern for Phrase {
 
     fn reset(&mut self) {
         // reset sample offset
-        self.sample_offset = 0;
+        self.sample_offset = 0.0;
         // reset iterator state
+        self.current_sample_time = 0;
+        self.replaced_pattern_slots.clear();
         self.next_events.fill(None);
+        self.note_columns.fill(0);
         // reset all patterns in all slots as well
         for pattern_slot in &mut self.pattern_slots {
             if let PatternSlot::Pattern(pattern) = pattern_slot {
@@ -362,7 +662,7 @@ impl Iterator for Phrase {
 
 #[cfg(test)]
 mod test {
-    use std::rc::Rc;
+    use std::{cell::RefCell, rc::Rc};
 
     use crate::prelude::*;
 
@@ -379,7 +679,7 @@ mod test {
             seed,
         )?;
         let mut kick_pattern = beat_time.every_nth_beat(16.0).emit(kick_cycle);
-        kick_pattern.set_sample_offset(20); // test with offsets
+        kick_pattern.set_sample_offset(20.0); // test with offsets
 
         let snare_pattern = beat_time
             .every_nth_beat(2.0)
@@ -529,16 +829,227 @@ mod test {
         phrase.advance_until_time(time)
     }
 
+    #[test]
+    fn common_cycle() {
+        let time_base = BeatTimeBase {
+            samples_per_sec: 44100,
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+        };
+        // 3 against 4 sixteenth steps
+        let three_steps = time_base
+            .every_nth_sixteenth(1.0)
+            .with_rhythm([1, 1, 1].to_rhythm())
+            .emit(
+                vec![new_note(Note::C4), new_note(Note::D4), new_note(Note::E4)]
+                    .to_sequence_emitter(),
+            );
+        let four_steps = time_base
+            .every_nth_sixteenth(1.0)
+            .with_rhythm([1, 1, 1, 1].to_rhythm())
+            .emit(
+                vec![
+                    new_note(Note::C5),
+                    new_note(Note::D5),
+                    new_note(Note::E5),
+                    new_note(Note::F5),
+                ]
+                .to_sequence_emitter(),
+            );
+        let mut phrase = Phrase::new(
+            time_base,
+            vec![
+                PatternSlot::from(three_steps),
+                PatternSlot::from(four_steps),
+            ],
+            BeatTimeStep::Bar(1.0),
+        );
+        assert_eq!(phrase.length(), BeatTimeStep::Bar(1.0));
+        phrase.set_common_cycle(true);
+        assert_eq!(phrase.length(), BeatTimeStep::Beats(3.0));
+
+        // both slots realign after 12 steps when the phrase loops
+        let mut sequence = Sequence::new(time_base, vec![phrase]);
+        let step_samples = time_base.samples_per_beat() / 4.0;
+        let mut first_notes = Vec::new();
+        sequence.consume_events_until_time(
+            (24.0 * step_samples) as SampleTime,
+            &mut |pattern_index, event| {
+                if let Some(Event::NoteEvents(notes)) = event.event {
+                    let note = notes[0].as_ref().unwrap().note;
+                    if note == Note::C4 || note == Note::C5 {
+                        let step = (event.time as f64 / step_samples).round() as usize;
+                        first_notes.push((pattern_index, step));
+                    }
+                }
+            },
+        );
+        assert_eq!(
+            first_notes,
+            vec![
+                (0, 0),
+                (1, 0),
+                (0, 3),
+                (1, 4),
+                (0, 6),
+                (1, 8),
+                (0, 9),
+                (0, 12),
+                (1, 12),
+                (0, 15),
+                (1, 16),
+                (0, 18),
+                (1, 20),
+                (0, 21),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_pattern_slot() {
+        let time_base = BeatTimeBase {
+            samples_per_sec: 44100,
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+        };
+        let new_pattern = |note: Note| -> Rc<RefCell<dyn Pattern>> {
+            Rc::new(RefCell::new(
+                time_base.every_nth_beat(1.0).emit(new_note_emitter(note)),
+            ))
+        };
+        let old_pattern = new_pattern(Note::D4);
+        let mut phrase = Phrase::new(
+            time_base,
+            vec![
+                PatternSlot::from(new_pattern(Note::C4)),
+                PatternSlot::from(Rc::clone(&old_pattern)),
+            ],
+            BeatTimeStep::Bar(4.0),
+        );
+        let run_notes = |phrase: &mut Phrase, time: SampleTime| {
+            let mut notes = Vec::new();
+            phrase.consume_events_until_time(time, &mut |pattern_index, event| {
+                if let Some(Event::NoteEvents(events)) = event.event {
+                    notes.push((pattern_index, events[0].as_ref().unwrap().note));
+                }
+            });
+            notes
+        };
+        assert_eq!(
+            run_notes(&mut phrase, 1),
+            vec![(0, Note::C4), (1, Note::D4)]
+        );
+        assert!(phrase.take_replaced_pattern_slots().is_empty());
+
+        // replacing a slot returns and memorizes the previous slot
+        let previous_slot = phrase.set_pattern_slot(1, new_pattern(Note::E4));
+        assert!(
+            matches!(previous_slot, PatternSlot::Pattern(pattern) if Rc::ptr_eq(&pattern, &old_pattern))
+        );
+        let replaced_slots = phrase.take_replaced_pattern_slots();
+        assert_eq!(replaced_slots.len(), 1);
+        assert!(
+            matches!(&replaced_slots[0], (1, PatternSlot::Pattern(pattern)) if Rc::ptr_eq(pattern, &old_pattern))
+        );
+        assert!(phrase.take_replaced_pattern_slots().is_empty());
+
+        // the new pattern plays in the slot without pending events of the old one
+        let notes = run_notes(&mut phrase, 22050 + 1);
+        assert!(notes.contains(&(1, Note::E4)));
+        assert!(!notes.contains(&(1, Note::D4)));
+
+        // only the first replaced slot of an index is memorized
+        phrase.set_pattern_slot(1, Rc::clone(&old_pattern));
+        phrase.set_pattern_slot(1, new_pattern(Note::F4));
+        phrase.set_pattern_slot(1, new_pattern(Note::G4));
+        let replaced_slots = phrase.take_replaced_pattern_slots();
+        assert_eq!(replaced_slots.len(), 1);
+        assert!(
+            matches!(&replaced_slots[0], (1, PatternSlot::Pattern(pattern)) if !Rc::ptr_eq(pattern, &old_pattern))
+        );
+
+        // clones, resets and seeks forget replaced slots
+        phrase.set_pattern_slot(0, new_pattern(Note::A4));
+        assert!(phrase.clone().take_replaced_pattern_slots().is_empty());
+        phrase.advance_until_time(44100);
+        assert!(phrase.take_replaced_pattern_slots().is_empty());
+        phrase.set_pattern_slot(0, new_pattern(Note::B4));
+        phrase.reset();
+        assert!(phrase.take_replaced_pattern_slots().is_empty());
+    }
+
+    #[test]
+    fn clone_into_phrase() {
+        let time_base = BeatTimeBase {
+            samples_per_sec: 44100,
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+        };
+        let pattern = time_base
+            .every_nth_beat(1.0)
+            .emit(vec![new_note("c4"), new_note("d4"), new_note("e4")].to_sequence_emitter());
+        let mut phrase = pattern.clone_into_phrase(3, time_base, BeatTimeStep::Bar(1.0));
+        assert_eq!(phrase.pattern_slots().len(), 3);
+
+        let slot_pattern = |phrase: &Phrase, index: usize| match &phrase.pattern_slots()[index] {
+            PatternSlot::Pattern(pattern) => Rc::clone(pattern),
+            _ => panic!("expected a pattern slot"),
+        };
+        let first_note = |pattern: &Rc<RefCell<dyn Pattern>>| {
+            pattern
+                .borrow_mut()
+                .next()
+                .and_then(|event| match event.event {
+                    Some(Event::NoteEvents(notes)) => notes[0].as_ref().map(|n| n.note),
+                    _ => None,
+                })
+        };
+        // slots are independent instances with independent playback states
+        assert!(!Rc::ptr_eq(
+            &slot_pattern(&phrase, 0),
+            &slot_pattern(&phrase, 1)
+        ));
+        assert_eq!(first_note(&slot_pattern(&phrase, 0)), Some(Note::C4));
+        assert_eq!(first_note(&slot_pattern(&phrase, 0)), Some(Note::D4));
+        assert_eq!(first_note(&slot_pattern(&phrase, 1)), Some(Note::C4));
+        assert_eq!(first_note(&slot_pattern(&phrase, 2)), Some(Note::C4));
+
+        // the source pattern is not affected
+        let mut pattern = pattern;
+        assert_eq!(
+            pattern
+                .run_until_time(SampleTime::MAX)
+                .and_then(|event| match event.event {
+                    Some(Event::NoteEvents(notes)) => notes[0].as_ref().map(|n| n.note),
+                    _ => None,
+                }),
+            Some(Note::C4)
+        );
+
+        // all slots emit their events in the phrase
+        phrase.reset();
+        let mut indices = phrase
+            .by_ref()
+            .take(3)
+            .map(|(index, event)| {
+                assert_eq!(event.time, 0);
+                index
+            })
+            .collect::<Vec<_>>();
+        indices.sort();
+        assert_eq!(indices, vec![0, 1, 2]);
+    }
+
     #[test]
     fn skip_events() -> Result<(), String> {
         let sample_offset = 2345676;
 
         let mut phrase1 = create_phrase()?;
-        phrase1.set_sample_offset(sample_offset);
+        phrase1.set_sample_offset(sample_offset as f64);
         let mut events1 = Vec::new();
 
         let mut phrase2 = create_phrase()?;
-        phrase2.set_sample_offset(sample_offset);
+        phrase2.set_sample_offset(sample_offset as f64);
         let mut events2 = Vec::new();
 
         // run_time, advance_time
@@ -2,33 +2,40 @@
 //! via the [`phonic`](https://crates.io/crates/phonic) crate.
 
 use std::{
-    collections::HashMap,
-    path::Path,
+    collections::{HashMap, HashSet, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
+        atomic::{AtomicU32, AtomicU8, AtomicUsize, Ordering},
+        Arc, Mutex,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use dashmap::DashMap;
 
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender};
 
 use phonic::{
-    sources::PreloadedFileSource, utils::speed_from_note, DefaultOutputDevice, Error,
-    FilePlaybackOptions, PlaybackId, PlaybackStatusContext, PlaybackStatusEvent,
-    Player as PhonicPlayer,
+    sources::{PreloadedFileSource, StreamedFileSource},
+    utils::speed_from_note,
+    DefaultOutputDevice, Error, FilePlaybackOptions, PlaybackId, PlaybackStatusContext,
+    PlaybackStatusEvent, Player as PhonicPlayer, Source,
 };
 
 use crate::{
     time::{SampleTimeBase, SampleTimeDisplay},
-    BeatTimeBase, Event, ExactSampleTime, InstrumentId, Note, PatternEvent, PatternSlot,
-    SampleTime, Sequence,
+    BeatTimeBase, Event, ExactSampleTime, InstrumentId, Note, NoteEvent, Pattern, PatternEvent,
+    PatternSlot, SampleTime, Sequence, TimeSignature,
 };
 
 // -------------------------------------------------------------------------------------------------
 
+mod metadata;
+pub use metadata::SampleMetadata;
+
+// -------------------------------------------------------------------------------------------------
+
 /// [`phonic`](https://crates.io/crates/phonic) effects.
 pub use phonic::{
     effects, Effect, EffectId, EffectMessage, EffectMessagePayload, EffectTime, MixerId,
@@ -47,12 +54,303 @@ pub use phonic::{
 /// shared buffer, so cloning is very cheap.
 ///
 /// The pool also memorizes default mixer_ids for [`SamplePlayer`] so samples in the pool can
-/// be assigned to different mixers (DSP effect chains) as well.
+/// be assigned to different mixers (DSP effect chains) as well. Additionally, samples can send
+/// a portion of their signal to shared aux bus mixers, e.g. a reverb or delay bus, which got
+/// created via [`SamplePlayer::add_aux_bus`].
+///
+/// The original files of all samples are memorized too, so samples which are used in a project
+/// can be exported via [`Self::export_used_samples`]. Musical metadata of the files, such as
+/// the tempo of loops, gets parsed on load and can be accessed via [`Self::sample_metadata`].
+///
+/// To avoid machine-gun artifacts from rapid retriggers, a minimum retrigger interval can be
+/// set per instrument via [`Self::set_retrigger_interval`].
+///
+/// Samples which got recorded at different pitches can set their own root notes via
+/// [`Self::set_root_note`]. Otherwise the player's global sample root note is used.
+///
+/// To balance kits of samples with different levels, samples optionally can be normalized via
+/// [`Self::load_sample_normalized`] or [`Self::normalize_sample`]. The measured gains then get
+/// applied when playing the samples.
+///
+/// Long files, such as ambient loops, can be loaded via [`Self::load_streamed_sample`]: they
+/// are not decoded into memory, but get streamed from disk each time they are played.
 
 #[derive(Default)]
 pub struct SamplePool {
-    pool: DashMap<InstrumentId, PreloadedFileSource>,
+    pool: DashMap<InstrumentId, PooledSample>,
+    files: DashMap<InstrumentId, SampleFile>,
+    metadata: DashMap<InstrumentId, SampleMetadata>,
     routing: DashMap<InstrumentId, MixerId>,
+    sends: DashMap<InstrumentId, Vec<(MixerId, f32)>>,
+    retrigger_intervals: DashMap<InstrumentId, Duration>,
+    root_notes: DashMap<InstrumentId, Note>,
+    gains: DashMap<InstrumentId, f32>,
+}
+
+/// Level normalization of samples in the [`SamplePool`].
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum SampleNormalization {
+    /// Play samples as they are.
+    #[default]
+    Off,
+    /// Scale samples so their peak level matches the given linear target level.
+    Peak(f32),
+    /// Scale samples so their RMS level, a rough estimate of their loudness, matches the given
+    /// linear target level.
+    Rms(f32),
+}
+
+impl SampleNormalization {
+    /// Max gain which gets applied to quiet samples, to avoid boosting noise: +24 dB.
+    pub const MAX_GAIN: f32 = 16.0;
+
+    /// Measure the gain which normalizes the given interleaved sample buffer.
+    /// Returns 1 for silent buffers or when normalization is off.
+    pub fn gain(&self, buffer: &[f32]) -> f32 {
+        let (level, target) = match *self {
+            Self::Off => return 1.0,
+            Self::Peak(target) => {
+                let peak = buffer
+                    .iter()
+                    .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+                (peak, target)
+            }
+            Self::Rms(target) => {
+                if buffer.is_empty() {
+                    return 1.0;
+                }
+                let sum = buffer
+                    .iter()
+                    .map(|sample| *sample as f64 * *sample as f64)
+                    .sum::<f64>();
+                ((sum / buffer.len() as f64).sqrt() as f32, target)
+            }
+        };
+        if level > 0.0 && level.is_finite() {
+            (target.max(0.0) / level).min(Self::MAX_GAIN)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A sample in the [`SamplePool`].
+pub enum PooledSample {
+    /// Sample got decoded into memory. Clones share the decoded buffer.
+    Preloaded(PreloadedFileSource),
+    /// Sample gets streamed from disk when it's played.
+    Streamed(StreamedSample),
+}
+
+impl PooledSample {
+    /// true when the sample gets streamed from disk instead of being preloaded.
+    pub fn is_streamed(&self) -> bool {
+        matches!(self, Self::Streamed(_))
+    }
+
+    /// Create a new playable file source of the sample with the given playback options.
+    ///
+    /// ### Errors
+    /// Returns an error if a streamed sample file could not be opened.
+    pub fn source(
+        &self,
+        playback_options: FilePlaybackOptions,
+        playback_sample_rate: u32,
+    ) -> Result<SampleSource, Error> {
+        match self {
+            Self::Preloaded(sample) => sample
+                .clone(playback_options, playback_sample_rate)
+                .map(SampleSource::Preloaded),
+            Self::Streamed(sample) => sample
+                .source(playback_options, playback_sample_rate)
+                .map(SampleSource::Streamed),
+        }
+    }
+}
+
+/// A streamed sample in the [`SamplePool`].
+///
+/// To avoid opening files when triggering notes, the sample keeps a stream which got opened
+/// and primed ahead of time. Notes which use the primed stream's playback options take it over,
+/// and a new stream gets primed via [`SamplePool::prime_streamed_samples`] afterwards.
+pub struct StreamedSample {
+    path: PathBuf,
+    primed: Mutex<Option<PrimedStream>>,
+    last_options: Mutex<Option<(FilePlaybackOptions, u32)>>,
+}
+
+/// A pre-opened stream of a [`StreamedSample`] and the playback options it got opened with.
+struct PrimedStream {
+    options: String,
+    source: StreamedFileSource,
+}
+
+impl StreamedSample {
+    /// Open the given file and prime a stream with the given playback options.
+    ///
+    /// ### Errors
+    /// Returns an error if the file could not be opened.
+    fn open(
+        path: PathBuf,
+        playback_options: FilePlaybackOptions,
+        playback_sample_rate: u32,
+    ) -> Result<Self, Error> {
+        let sample = Self {
+            path,
+            primed: Mutex::new(None),
+            last_options: Mutex::new(Some((playback_options, playback_sample_rate))),
+        };
+        sample.prime()?;
+        Ok(sample)
+    }
+
+    /// Path of the streamed file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// true when a primed stream is ready to play.
+    pub fn is_primed(&self) -> bool {
+        self.primed.lock().is_ok_and(|primed| primed.is_some())
+    }
+
+    /// Create a new stream with the given playback options: takes over the primed stream when
+    /// it got opened with the same options, else opens the file.
+    ///
+    /// ### Errors
+    /// Returns an error if the file could not be opened.
+    fn source(
+        &self,
+        playback_options: FilePlaybackOptions,
+        playback_sample_rate: u32,
+    ) -> Result<StreamedFileSource, Error> {
+        let options = Self::options_key(&playback_options, playback_sample_rate);
+        if let Ok(mut last_options) = self.last_options.lock() {
+            *last_options = Some((playback_options.clone(), playback_sample_rate));
+        }
+        let primed = self.primed.lock().ok().and_then(|mut primed| primed.take());
+        match primed {
+            Some(primed) if primed.options == options => Ok(primed.source),
+            _ => StreamedFileSource::from_file(
+                &self.path,
+                None,
+                playback_options,
+                playback_sample_rate,
+            ),
+        }
+    }
+
+    /// Open and prime a new stream with the last used playback options, if needed.
+    ///
+    /// ### Errors
+    /// Returns an error if the file could not be opened.
+    fn prime(&self) -> Result<(), Error> {
+        if self.is_primed() {
+            return Ok(());
+        }
+        let last_options = self
+            .last_options
+            .lock()
+            .ok()
+            .and_then(|last_options| last_options.clone());
+        if let Some((playback_options, playback_sample_rate)) = last_options {
+            let options = Self::options_key(&playback_options, playback_sample_rate);
+            let source = StreamedFileSource::from_file(
+                &self.path,
+                None,
+                playback_options,
+                playback_sample_rate,
+            )?;
+            if let Ok(mut primed) = self.primed.lock() {
+                *primed = Some(PrimedStream { options, source });
+            }
+        }
+        Ok(())
+    }
+
+    fn options_key(playback_options: &FilePlaybackOptions, playback_sample_rate: u32) -> String {
+        format!("{:?}@{}", playback_options, playback_sample_rate)
+    }
+}
+
+/// A playable file source of a sample in the [`SamplePool`], as fetched via
+/// [`SamplePool::sample`].
+pub enum SampleSource {
+    Preloaded(PreloadedFileSource),
+    Streamed(StreamedFileSource),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Original, encoded file of a sample in the [`SamplePool`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SampleFile {
+    /// Sample got loaded from the given file path.
+    Path(PathBuf),
+    /// Sample got loaded from a raw encoded file buffer with the given path. The encoded file
+    /// is not kept in memory: the decoded sample buffer, which is shared with the pool's sample,
+    /// gets written as 32-bit float WAV file instead.
+    Buffer {
+        path: String,
+        buffer: Arc<Vec<f32>>,
+        channel_count: usize,
+        sample_rate: u32,
+    },
+}
+
+impl SampleFile {
+    /// File name of the sample, without its directory.
+    pub fn file_name(&self) -> String {
+        let path = match self {
+            Self::Path(path) => path.to_path_buf(),
+            Self::Buffer { path, .. } => Path::new(path).with_extension("wav"),
+        };
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "sample".to_string())
+    }
+
+    /// Write the sample's original file to the given file path.
+    ///
+    /// ### Errors
+    /// Returns an error if the original file can not be read or the new file not be written.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        match self {
+            Self::Path(source) => fs::copy(source, path).map(|_| ()),
+            Self::Buffer {
+                buffer,
+                channel_count,
+                sample_rate,
+                ..
+            } => fs::write(path, Self::wav_file(buffer, *channel_count, *sample_rate)),
+        }
+    }
+
+    // Encode the given interleaved sample buffer as 32-bit float WAV file.
+    fn wav_file(buffer: &[f32], channel_count: usize, sample_rate: u32) -> Vec<u8> {
+        const FORMAT_IEEE_FLOAT: u16 = 3;
+        const BYTES_PER_SAMPLE: u32 = 4;
+        let channel_count = channel_count.max(1) as u32;
+        let data_size = buffer.len() as u32 * BYTES_PER_SAMPLE;
+        let mut file = Vec::with_capacity(44 + data_size as usize);
+        file.extend_from_slice(b"RIFF");
+        file.extend_from_slice(&(36 + data_size).to_le_bytes());
+        file.extend_from_slice(b"WAVE");
+        file.extend_from_slice(b"fmt ");
+        file.extend_from_slice(&16_u32.to_le_bytes());
+        file.extend_from_slice(&FORMAT_IEEE_FLOAT.to_le_bytes());
+        file.extend_from_slice(&(channel_count as u16).to_le_bytes());
+        file.extend_from_slice(&sample_rate.to_le_bytes());
+        file.extend_from_slice(&(sample_rate * channel_count * BYTES_PER_SAMPLE).to_le_bytes());
+        file.extend_from_slice(&((channel_count * BYTES_PER_SAMPLE) as u16).to_le_bytes());
+        file.extend_from_slice(&((BYTES_PER_SAMPLE * 8) as u16).to_le_bytes());
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&data_size.to_le_bytes());
+        for sample in buffer {
+            file.extend_from_slice(&sample.to_le_bytes());
+        }
+        file
+    }
 }
 
 impl SamplePool {
@@ -60,22 +358,48 @@ impl SamplePool {
     pub fn new() -> Self {
         Self {
             pool: DashMap::new(),
+            files: DashMap::new(),
+            metadata: DashMap::new(),
             routing: DashMap::new(),
+            sends: DashMap::new(),
+            retrigger_intervals: DashMap::new(),
+            root_notes: DashMap::new(),
+            gains: DashMap::new(),
         }
     }
 
-    /// Fetch a clone of a preloaded sample with the given playback options.
+    /// Fetch a clone of a single preloaded sample with the given playback options.
     ///
     /// ### Errors
-    /// Returns an error if the instrument id is unknown.
+    /// Returns an error if the instrument id is unknown or the sample is a streamed sample.
+    /// Use [`Self::sample_source`] to fetch streamed samples.
     pub fn sample(
         &self,
         id: InstrumentId,
         playback_options: FilePlaybackOptions,
         playback_sample_rate: u32,
     ) -> Result<PreloadedFileSource, Error> {
+        match self.pool.get(&id).as_deref() {
+            Some(PooledSample::Preloaded(sample)) => {
+                sample.clone(playback_options, playback_sample_rate)
+            }
+            _ => Err(Error::MediaFileNotFound),
+        }
+    }
+
+    /// Fetch a clone of a preloaded or a stream of a streamed sample with the given playback
+    /// options.
+    ///
+    /// ### Errors
+    /// Returns an error if the instrument id is unknown or a streamed file can not be opened.
+    pub fn sample_source(
+        &self,
+        id: InstrumentId,
+        playback_options: FilePlaybackOptions,
+        playback_sample_rate: u32,
+    ) -> Result<SampleSource, Error> {
         if let Some(sample) = self.pool.get(&id) {
-            sample.clone(playback_options, playback_sample_rate)
+            sample.source(playback_options, playback_sample_rate)
         } else {
             Err(Error::MediaFileNotFound)
         }
@@ -88,34 +412,205 @@ impl SamplePool {
     /// Returns an error if the sample file could not be loaded.
     pub fn load_sample<P: AsRef<Path>>(&self, path: P) -> Result<InstrumentId, Error> {
         let options = FilePlaybackOptions::default();
-        let sample = PreloadedFileSource::from_file(path, None, options, 44100)?;
+        let sample = PreloadedFileSource::from_file(path.as_ref(), None, options, 44100)?;
+        let id = Self::unique_id();
+        self.pool.insert(id, PooledSample::Preloaded(sample));
+        self.files
+            .insert(id, SampleFile::Path(path.as_ref().to_path_buf()));
+        self.metadata
+            .insert(id, SampleMetadata::from_file(path.as_ref()));
+        Ok(id)
+    }
+
+    /// Loads a sample file as streamed sample and return its unique id. Streamed samples are
+    /// not decoded into memory, but get streamed from disk each time they are played, which
+    /// keeps the memory footprint of long files low.
+    ///
+    /// The file gets opened and primed for playback with the given playback sample rate on load,
+    /// see [`StreamedSample`]. Streamed samples can not be normalized.
+    ///
+    /// ### Errors
+    /// Returns an error if the sample file could not be opened.
+    pub fn load_streamed_sample<P: AsRef<Path>>(
+        &self,
+        path: P,
+        playback_sample_rate: u32,
+    ) -> Result<InstrumentId, Error> {
+        let options = FilePlaybackOptions::default();
+        let sample =
+            StreamedSample::open(path.as_ref().to_path_buf(), options, playback_sample_rate)?;
         let id = Self::unique_id();
-        self.pool.insert(id, sample);
+        self.pool.insert(id, PooledSample::Streamed(sample));
+        self.files
+            .insert(id, SampleFile::Path(path.as_ref().to_path_buf()));
+        self.metadata
+            .insert(id, SampleMetadata::from_file(path.as_ref()));
+        Ok(id)
+    }
+
+    /// Loads a sample file like [`Self::load_sample`] and normalizes it with the given
+    /// normalization mode. The measured gain can be queried via [`Self::normalization_gain`].
+    ///
+    /// ### Errors
+    /// Returns an error if the sample file could not be loaded.
+    pub fn load_sample_normalized<P: AsRef<Path>>(
+        &self,
+        path: P,
+        normalization: SampleNormalization,
+    ) -> Result<InstrumentId, Error> {
+        let id = self.load_sample(path)?;
+        self.normalize_sample(id, normalization);
         Ok(id)
     }
 
     /// Loads a sample file from a raw encoded file buffer as [`PreloadedFileSource`] and return
     /// its unique id. Given path is used to identify the file in status messages only.
     ///
+    /// The encoded buffer is consumed by the decoder: [`Self::export_used_samples`] writes
+    /// the decoded sample as WAV file instead of the original file.
+    ///
     /// ### Errors
     /// Returns an error if the sample file could not be loaded.
     pub fn load_sample_buffer(&self, buffer: Vec<u8>, path: &str) -> Result<InstrumentId, Error> {
         let options = FilePlaybackOptions::default();
+        let metadata = SampleMetadata::from_buffer(&buffer);
         let sample = PreloadedFileSource::from_file_buffer(buffer, path, None, options, 44100)?;
+        let file = SampleFile::Buffer {
+            path: path.to_string(),
+            buffer: sample.buffer(),
+            channel_count: sample.channel_count(),
+            sample_rate: sample.sample_rate(),
+        };
         let id = Self::unique_id();
-        self.pool.insert(id, sample);
+        self.pool.insert(id, PooledSample::Preloaded(sample));
+        self.files.insert(id, file);
+        self.metadata.insert(id, metadata);
         Ok(id)
     }
 
+    /// Returns true when a sample with the given id is loaded into the pool.
+    pub fn contains_sample(&self, id: InstrumentId) -> bool {
+        self.pool.contains_key(&id)
+    }
+
+    /// Returns true when the sample with the given id is a streamed sample.
+    pub fn is_streamed_sample(&self, id: InstrumentId) -> bool {
+        self.pool
+            .get(&id)
+            .is_some_and(|sample| sample.is_streamed())
+    }
+
     /// Removes the sample with the given id from the pool.
-    /// Returns the removed sample, or None when it was not found.
+    /// Returns the removed sample, or None when it was not found or is a streamed sample.
     pub fn remove_sample(&self, id: InstrumentId) -> Option<PreloadedFileSource> {
+        match self.remove_pooled_sample(id)? {
+            PooledSample::Preloaded(sample) => Some(sample),
+            PooledSample::Streamed(_) => None,
+        }
+    }
+
+    /// Removes the preloaded or streamed sample with the given id from the pool.
+    /// Returns the removed sample, or None when it was not found.
+    pub fn remove_pooled_sample(&self, id: InstrumentId) -> Option<PooledSample> {
+        self.files.remove(&id);
+        self.metadata.remove(&id);
+        self.gains.remove(&id);
         self.pool.remove(&id).map(|(_, v)| v)
     }
 
+    /// Prime new streams of all streamed samples which got played since the last call, so
+    /// following notes can take them over without opening their files.
+    pub fn prime_streamed_samples(&self) {
+        for sample in self.pool.iter() {
+            if let PooledSample::Streamed(sample) = sample.value() {
+                if let Err(err) = sample.prime() {
+                    log::warn!(target: "Player",
+                        "Failed to prime streamed sample '{}': {}", sample.path.display(), err
+                    );
+                }
+            }
+        }
+    }
+
     /// Retains samples where the given predicate returns true and discards all others.
     pub fn retain_samples(&self, mut func: impl FnMut(InstrumentId) -> bool) {
-        self.pool.retain(move |k, _| func(*k))
+        self.pool.retain(move |k, _| func(*k));
+        self.files.retain(|k, _| self.pool.contains_key(k));
+        self.metadata.retain(|k, _| self.pool.contains_key(k));
+        self.gains.retain(|k, _| self.pool.contains_key(k));
+    }
+
+    /// Analyze the level of the sample with the given id and memorize a gain which normalizes
+    /// the sample with the given normalization mode when playing it. `SampleNormalization::Off`
+    /// removes a previously applied normalization.
+    /// Returns the applied gain, or None when the sample was not found or is streamed.
+    pub fn normalize_sample(
+        &self,
+        id: InstrumentId,
+        normalization: SampleNormalization,
+    ) -> Option<f32> {
+        let gain = match &*self.pool.get(&id)? {
+            PooledSample::Preloaded(sample) => normalization.gain(&sample.buffer()),
+            PooledSample::Streamed(_) => return None,
+        };
+        if normalization == SampleNormalization::Off {
+            self.gains.remove(&id);
+        } else {
+            self.gains.insert(id, gain);
+        }
+        Some(gain)
+    }
+
+    /// Get the normalization gain of a single sample or None when it's not normalized.
+    pub fn normalization_gain(&self, id: InstrumentId) -> Option<f32> {
+        self.gains.get(&id).map(|g| *g)
+    }
+
+    /// Get musical metadata, such as the tempo, of the sample with the given id.
+    /// Returns None when the sample was not found.
+    pub fn sample_metadata(&self, id: InstrumentId) -> Option<SampleMetadata> {
+        self.metadata.get(&id).map(|metadata| metadata.clone())
+    }
+
+    /// Get the original files of all samples in the pool which are referenced by the given
+    /// instruments, sorted by instrument id. Unknown and duplicate instruments are skipped.
+    pub fn used_samples(&self, instruments: &[InstrumentId]) -> Vec<(InstrumentId, SampleFile)> {
+        let mut instruments = instruments.to_vec();
+        instruments.sort_by_key(|id| usize::from(*id));
+        instruments.dedup();
+        instruments
+            .into_iter()
+            .filter_map(|id| self.files.get(&id).map(|file| (id, file.clone())))
+            .collect()
+    }
+
+    /// Write the original files of all samples which are referenced by the given instruments
+    /// into the given directory, e.g. to bundle a project with the samples it uses.
+    /// Returns the instrument ids and paths of all written files.
+    ///
+    /// Files are named after the original sample file names. Duplicate names get prefixed
+    /// with their instrument ids to avoid overwriting each other.
+    ///
+    /// ### Errors
+    /// Returns an error if the directory can not be created or a file can not be written.
+    pub fn export_used_samples<P: AsRef<Path>>(
+        &self,
+        instruments: &[InstrumentId],
+        directory: P,
+    ) -> io::Result<Vec<(InstrumentId, PathBuf)>> {
+        fs::create_dir_all(directory.as_ref())?;
+        let mut exported: Vec<(InstrumentId, PathBuf)> = Vec::new();
+        for (id, file) in self.used_samples(instruments) {
+            let mut path = directory.as_ref().join(file.file_name());
+            if exported.iter().any(|(_, other)| *other == path) {
+                path = directory
+                    .as_ref()
+                    .join(format!("{}_{}", id, file.file_name()));
+            }
+            file.write(&path)?;
+            exported.push((id, path));
+        }
+        Ok(exported)
     }
 
     /// Get a single default instrument routing or None when there was none set.
@@ -132,13 +627,85 @@ impl SamplePool {
         }
     }
 
+    /// Get all aux sends of a single instrument as bus mixer id and amount pairs.
+    pub fn sends(&self, instrument: InstrumentId) -> Vec<(MixerId, f32)> {
+        self.sends
+            .get(&instrument)
+            .map(|sends| sends.clone())
+            .unwrap_or_default()
+    }
+
+    /// Set, update or remove an aux send of a single instrument: the signal of the instrument's
+    /// notes then additionally gets mixed into the given aux bus mixer, scaled by the given
+    /// amount in range \[0 - 1\]. An amount of 0 removes the send.
+    ///
+    /// Bus mixers must be created via [`SamplePlayer::add_aux_bus`]. Sends to other mixers are
+    /// ignored by the player.
+    pub fn set_send(&self, instrument: InstrumentId, bus_mixer_id: MixerId, amount: f32) {
+        let amount = amount.clamp(0.0, 1.0);
+        let mut sends = self.sends.entry(instrument).or_default();
+        sends.retain(|(mixer_id, _)| *mixer_id != bus_mixer_id);
+        if amount > 0.0 {
+            sends.push((bus_mixer_id, amount));
+        }
+    }
+
+    /// Get the minimum retrigger interval of a single instrument or None when there was none set.
+    pub fn retrigger_interval(&self, instrument: InstrumentId) -> Option<Duration> {
+        self.retrigger_intervals.get(&instrument).map(|i| *i)
+    }
+
+    /// Set or unset the minimum retrigger interval of a single instrument: note-ons of the same
+    /// note and instrument which follow a previous trigger faster than the interval are ignored.
+    pub fn set_retrigger_interval(&self, instrument: InstrumentId, interval: Option<Duration>) {
+        match interval {
+            Some(interval) if !interval.is_zero() => {
+                self.retrigger_intervals.insert(instrument, interval);
+            }
+            _ => {
+                self.retrigger_intervals.remove(&instrument);
+            }
+        }
+    }
+
+    /// Get the root note of a single instrument or None when there was none set.
+    pub fn root_note(&self, instrument: InstrumentId) -> Option<Note> {
+        self.root_notes.get(&instrument).map(|n| *n)
+    }
+
+    /// Set or unset the root note of a single instrument: the note at which the instrument's
+    /// sample plays back at its original speed. When unset, the player's global sample root
+    /// note is used. Note-offs and empty notes unset the root note.
+    pub fn set_root_note(&self, instrument: InstrumentId, root_note: Option<Note>) {
+        match root_note {
+            Some(root_note) if root_note.is_note_on() => {
+                self.root_notes.insert(instrument, root_note);
+            }
+            _ => {
+                self.root_notes.remove(&instrument);
+            }
+        }
+    }
+
+    /// Playback volume of a note with the given instrument and volume, including the
+    /// instrument's normalization gain.
+    fn output_volume(&self, instrument: InstrumentId, volume: f32) -> f32 {
+        volume * self.normalization_gain(instrument).unwrap_or(1.0)
+    }
+
     /// Clears all preloaded samples and routings from the pool.
     ///
     /// ### Panics
     /// Panics if the sample pool can not be accessed
     pub fn clear(&self) {
         self.pool.clear();
+        self.files.clear();
+        self.metadata.clear();
         self.routing.clear();
+        self.sends.clear();
+        self.retrigger_intervals.clear();
+        self.root_notes.clear();
+        self.gains.clear();
     }
 
     // Generate a new unique instrument id.
@@ -153,165 +720,1490 @@ impl SamplePool {
 /// Sample player's behavior when playing a new note on the same voice channel.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum NewNoteAction {
-    /// Continue playing the old note and start a new one.
+    /// Continue playing the old note and start a new one. Old notes get faded out with the
+    /// player's default fade-out duration when they get stopped by note-offs.
     Continue,
-    /// Stop the playing note before starting a new one.
+    /// Stop the playing note with the player's default fade-out duration before starting a
+    /// new one.
     Stop,
-    /// Stop the playing note before with the given fade-out duration
+    /// Stop the playing note with the given fade-out duration before starting a new one.
+    /// When the duration is `None`, playing notes get stopped immediately without fade-out.
     Off(Option<Duration>),
 }
 
+impl NewNoteAction {
+    /// Default fade-out duration of the sample player, when stopping playing notes.
+    pub const DEFAULT_FADE_OUT_DURATION: Duration = Duration::from_millis(100);
+
+    /// Resolve the fade-out duration for playing notes, using the given default fade-out
+    /// duration for actions that have no explicit fade-out duration. `None` stops immediately.
+    pub fn fade_out_duration(&self, default_duration: Duration) -> Option<Duration> {
+        match self {
+            Self::Continue | Self::Stop => Some(default_duration),
+            Self::Off(duration) => *duration,
+        }
+    }
+}
+
 impl Default for NewNoteAction {
     fn default() -> Self {
-        Self::Off(Some(Duration::from_millis(100)))
+        Self::Off(Some(Self::DEFAULT_FADE_OUT_DURATION))
     }
 }
 
 // -------------------------------------------------------------------------------------------------
 
-/// Context, passed along serialized when triggering new notes from the sample player.   
-#[derive(Clone)]
-pub struct SamplePlaybackContext {
-    pub pattern_index: Option<usize>,
-    pub voice_index: Option<usize>,
+/// Sample player's behavior when a single note event contains the same note for the same
+/// instrument multiple times, e.g. when a script returns duplicate notes in a chord.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum DuplicateNoteAction {
+    /// Play all notes, even when they are duplicates. This will start overlapping voices.
+    #[default]
+    Allow,
+    /// Play the first note only and ignore all following duplicates.
+    Merge,
+    /// Play the note with the highest volume only and ignore all other duplicates.
+    KeepLoudest,
 }
 
-impl SamplePlaybackContext {
-    pub fn from_event(context: Option<PlaybackStatusContext>) -> Self {
-        if let Some(context) = context {
-            if let Some(context) = context.downcast_ref::<SamplePlaybackContext>() {
-                return context.clone();
-            }
+impl DuplicateNoteAction {
+    /// Apply the action to the given note events of a single pattern event: duplicates which
+    /// should not be played get replaced with `None`, so the voice layout stays untouched.
+    pub fn apply(&self, notes: &mut [Option<NoteEvent>]) {
+        if *self == Self::Allow {
+            return;
         }
-        SamplePlaybackContext {
-            pattern_index: None,
-            voice_index: None,
+        for index in 0..notes.len() {
+            let (note, instrument) = match &notes[index] {
+                Some(note_event) if note_event.note.is_note_on() => {
+                    (note_event.note, note_event.instrument)
+                }
+                _ => continue,
+            };
+            for other_index in (index + 1)..notes.len() {
+                let other_volume = match &notes[other_index] {
+                    Some(other) if other.note == note && other.instrument == instrument => {
+                        other.volume
+                    }
+                    _ => continue,
+                };
+                let volume = notes[index].as_ref().map_or(0.0, |n| n.volume);
+                if *self == Self::KeepLoudest && other_volume > volume {
+                    notes.swap(index, other_index);
+                }
+                notes[other_index] = None;
+            }
         }
     }
 }
 
 // -------------------------------------------------------------------------------------------------
 
-/// A simple example player implementation as wrapper around [`phonic`](https://crates.io/crates/phonic),
-/// which plays back a [`Sequence`] using the default audio output device, using plain samples loaded
-/// from a file as instruments.
-///
-/// Uses an existing, shared sample pool, so the pool can also be maintained outside of the player.
-/// To add/remove samples, see [`SamplePool`].
-///
-/// To use DSP effects, use the [`Self::inner_mut`] function to access the underlying phonic player
-/// and use the [`SamplePool::set_target_mixer`] to route specific samples though specific mixers.  
-pub struct SamplePlayer {
-    inner: PhonicPlayer,
-    sample_pool: Arc<SamplePool>,
-    playing_notes: Vec<HashMap<usize, (PlaybackId, Note)>>,
-    new_note_action: NewNoteAction,
-    sample_root_note: Note,
-    playback_preload_time: Duration,
-    playback_pos_emit_rate: Duration,
-    show_events: bool,
-    playback_sample_time: SampleTime,
-    emitted_sample_time: SampleTime,
+/// Sample player's behavior when a new note exceeds the player's global voice limit.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum VoiceStealMode {
+    /// Stop the voice which started playing first.
+    #[default]
+    Oldest,
+    /// Stop the voice with the lowest volume. Voices with equal volumes get stolen oldest first.
+    Quietest,
 }
 
-impl SamplePlayer {
-    /// Default preload time of the player's `run_until` function.
-    /// Quite high by default and bigger in slower debug builds.
-    const DEFAULT_PLAYBACK_PRELOAD_MS: u64 = if cfg!(debug_assertions) { 500 } else { 250 };
-
-    /// Create a new sample player from the given shared SamplePool.
-    ///
-    /// # Errors
-    /// returns an error if the player could not be created.
-    pub fn new<S: Into<Option<Sender<PlaybackStatusEvent>>>>(
-        sample_pool: Arc<SamplePool>,
-        playback_status_sender: S,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        // create player
-        let audio_output = DefaultOutputDevice::open()?;
-        let inner = PhonicPlayer::new(audio_output, playback_status_sender);
-        let playing_notes = Vec::new();
-        let new_note_action = NewNoteAction::default();
-        let sample_root_note = Note::C5;
-        let playback_preload = Duration::from_millis(Self::DEFAULT_PLAYBACK_PRELOAD_MS);
-        let playback_pos_emit_rate = Duration::from_secs(1);
-        let show_events = false;
-        let playback_sample_time = inner.output_sample_frame_position();
-        let emitted_sample_time = 0;
-        Ok(Self {
-            inner,
-            sample_pool,
-            playing_notes,
-            new_note_action,
-            sample_root_note,
-            playback_preload_time: playback_preload,
-            playback_pos_emit_rate,
-            show_events,
-            playback_sample_time,
-            emitted_sample_time,
-        })
+impl VoiceStealMode {
+    /// Select the voice which should be stolen from the given playing voices: tuples of
+    /// a voice key, the voice's start time and its volume. Returns None for empty voices.
+    pub fn select<K: Copy>(
+        &self,
+        voices: impl IntoIterator<Item = (K, SampleTime, f32)>,
+    ) -> Option<K> {
+        voices
+            .into_iter()
+            .min_by(|(_, a_time, a_volume), (_, b_time, b_volume)| match self {
+                Self::Oldest => a_time.cmp(b_time),
+                Self::Quietest => a_volume.total_cmp(b_volume).then(a_time.cmp(b_time)),
+            })
+            .map(|(key, _, _)| key)
     }
+}
 
-    /// Access to the player's inner phonic instance.
-    pub fn inner(&self) -> &PhonicPlayer {
-        &self.inner
-    }
-    /// Mutable access to the player's inner phonic instance.
-    pub fn inner_mut(&mut self) -> &mut PhonicPlayer {
-        &mut self.inner
-    }
+// -------------------------------------------------------------------------------------------------
 
-    /// Return the output backend's sample rate. All sources will be played back at this rate.
-    pub fn sample_rate(&self) -> u32 {
-        self.inner.output_sample_rate()
+/// Sample player's behavior when a note references an instrument which is not loaded into the
+/// player's sample pool, e.g. when a script uses a wrong or no longer existing instrument id.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum MissingInstrumentAction {
+    /// Silently drop the note.
+    Drop,
+    /// Drop the note and report a warning once for each missing instrument.
+    #[default]
+    DropWithWarning,
+    /// Play the note with the given instrument instead and report a warning once for each
+    /// missing instrument. Notes get dropped when the substitute is missing too.
+    Substitute(InstrumentId),
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Replace note-ons with zero volume with note-offs, as done in MIDI by convention.
+fn convert_zero_volume_note_ons(notes: &mut [Option<NoteEvent>]) {
+    for note_event in notes.iter_mut().flatten() {
+        if note_event.note.is_note_on() && note_event.volume <= 0.0 {
+            note_event.note = Note::OFF;
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A note which is currently playing in a pattern slot's voice of the sample player.
+struct PlayingNote {
+    playback_id: PlaybackId,
+    note: Note,
+    instrument: Option<InstrumentId>,
+    start_time: SampleTime,
+    volume: f32,
+    panning: f32,
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Key of a voice in [`PlayingVoices`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum VoiceKey {
+    /// A note which plays in a pattern slot's voice: `(pattern_index, voice_index)`.
+    Pattern(usize, usize),
+    /// A note which continues playing after a new note got triggered in its voice.
+    Continued(PlaybackId),
+}
+
+/// Keeps track of all notes which are playing in the sample player.
+///
+/// Notes are playing in the voices of the pattern slots which triggered them, until they get
+/// stopped. Notes which got replaced by new notes with [`NewNoteAction::Continue`] no longer
+/// own a pattern slot voice, but still play and thus are kept as continued notes. Notes which
+/// finished playing on their own get removed via their playback ids.
+#[derive(Default)]
+struct PlayingVoices {
+    notes: Vec<HashMap<usize, PlayingNote>>,
+    continued_notes: HashMap<PlaybackId, (usize, PlayingNote)>,
+}
+
+impl PlayingVoices {
+    /// Number of pattern slots.
+    fn slot_count(&self) -> usize {
+        self.notes.len()
+    }
+
+    /// Update the number of pattern slots.
+    fn resize(&mut self, pattern_slot_count: usize) {
+        self.notes.resize_with(pattern_slot_count, HashMap::new);
+    }
+
+    /// Number of notes which are currently playing across all pattern slots.
+    fn voice_count(&self) -> usize {
+        self.notes.iter().map(HashMap::len).sum::<usize>() + self.continued_notes.len()
+    }
+
+    /// Returns true when no notes are playing.
+    fn is_empty(&self) -> bool {
+        self.voice_count() == 0
+    }
+
+    /// Returns true when no notes of the given pattern slot are playing.
+    fn is_slot_empty(&self, pattern_index: usize) -> bool {
+        self.notes.get(pattern_index).is_none_or(HashMap::is_empty)
+            && !self
+                .continued_notes
+                .values()
+                .any(|(index, _)| *index == pattern_index)
+    }
+
+    /// Note events of the notes which play in the voices of the given pattern slot, ordered by
+    /// voice. Continued notes are not included, as they no longer play in the slot's voices.
+    fn slot_note_events(&self, pattern_index: usize) -> Vec<NoteEvent> {
+        let mut voices = self.notes[pattern_index].iter().collect::<Vec<_>>();
+        voices.sort_by_key(|(voice_index, _)| **voice_index);
+        voices
+            .into_iter()
+            .map(|(_, playing_note)| NoteEvent {
+                instrument: playing_note.instrument,
+                volume: playing_note.volume,
+                panning: playing_note.panning,
+                ..NoteEvent::from(playing_note.note)
+            })
+            .collect()
+    }
+
+    /// Add a new note to the given pattern slot's voice. A note which still plays in the voice
+    /// continues playing as continued note.
+    fn insert(&mut self, pattern_index: usize, voice_index: usize, playing_note: PlayingNote) {
+        if let Some(previous_note) = self.notes[pattern_index].insert(voice_index, playing_note) {
+            self.continued_notes
+                .insert(previous_note.playback_id, (pattern_index, previous_note));
+        }
+    }
+
+    /// Remove the note which plays in the given pattern slot's voice.
+    fn remove(&mut self, pattern_index: usize, voice_index: usize) -> Option<PlayingNote> {
+        self.notes[pattern_index].remove(&voice_index)
+    }
+
+    /// Remove all notes of the given pattern slot, including continued notes.
+    fn take_slot(&mut self, pattern_index: usize) -> Vec<PlayingNote> {
+        let mut playing_notes = Vec::new();
+        if let Some(notes) = self.notes.get_mut(pattern_index) {
+            playing_notes.extend(notes.drain().map(|(_, playing_note)| playing_note));
+        }
+        let continued_ids = self
+            .continued_notes
+            .iter()
+            .filter(|(_, (index, _))| *index == pattern_index)
+            .map(|(playback_id, _)| *playback_id)
+            .collect::<Vec<_>>();
+        for playback_id in continued_ids {
+            if let Some((_, playing_note)) = self.continued_notes.remove(&playback_id) {
+                playing_notes.push(playing_note);
+            }
+        }
+        playing_notes
+    }
+
+    /// Remove all notes of all pattern slots, including continued notes.
+    fn take_all(&mut self) -> Vec<PlayingNote> {
+        let mut playing_notes = self
+            .notes
+            .iter_mut()
+            .flat_map(|notes| notes.drain().map(|(_, playing_note)| playing_note))
+            .collect::<Vec<_>>();
+        playing_notes.extend(
+            self.continued_notes
+                .drain()
+                .map(|(_, (_, playing_note))| playing_note),
+        );
+        playing_notes
+    }
+
+    /// Forget the note with the given playback id, after it stopped playing.
+    fn remove_stopped(&mut self, playback_id: PlaybackId) {
+        if self.continued_notes.remove(&playback_id).is_none() {
+            for notes in &mut self.notes {
+                notes.retain(|_, playing_note| playing_note.playback_id != playback_id);
+            }
+        }
+    }
+
+    /// Remove notes, using the given steal mode to select them, until there's room for a new
+    /// note within the given voice limit. Returns the removed notes, which should be stopped.
+    fn steal(&mut self, max_voices: usize, mode: VoiceStealMode) -> Vec<PlayingNote> {
+        let mut stolen_notes = Vec::new();
+        while self.voice_count() >= max_voices {
+            let pattern_voices =
+                self.notes
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(pattern_index, notes)| {
+                        notes.iter().map(move |(voice_index, playing_note)| {
+                            (
+                                VoiceKey::Pattern(pattern_index, *voice_index),
+                                playing_note.start_time,
+                                playing_note.volume,
+                            )
+                        })
+                    });
+            let continued_voices =
+                self.continued_notes
+                    .iter()
+                    .map(|(playback_id, (_, playing_note))| {
+                        (
+                            VoiceKey::Continued(*playback_id),
+                            playing_note.start_time,
+                            playing_note.volume,
+                        )
+                    });
+            let playing_note = match mode.select(pattern_voices.chain(continued_voices)) {
+                Some(VoiceKey::Pattern(pattern_index, voice_index)) => {
+                    self.remove(pattern_index, voice_index)
+                }
+                Some(VoiceKey::Continued(playback_id)) => self
+                    .continued_notes
+                    .remove(&playback_id)
+                    .map(|(_, playing_note)| playing_note),
+                None => break,
+            };
+            stolen_notes.extend(playing_note);
+        }
+        stolen_notes
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Memorizes the last trigger times of notes per instrument to debounce rapid retriggers.
+#[derive(Clone, Debug, Default)]
+struct RetriggerDebounce {
+    last_triggers: HashMap<(InstrumentId, Note), SampleTime>,
+}
+
+impl RetriggerDebounce {
+    /// Returns true and memorizes the trigger time when the given note may be triggered at the
+    /// given time. Returns false when it follows a previous trigger faster than the given interval.
+    fn trigger(
+        &mut self,
+        instrument: InstrumentId,
+        note: Note,
+        time: SampleTime,
+        interval: SampleTime,
+    ) -> bool {
+        if let Some(last_time) = self.last_triggers.get(&(instrument, note)) {
+            if time >= *last_time && time - *last_time < interval {
+                return false;
+            }
+        }
+        self.last_triggers.insert((instrument, note), time);
+        true
+    }
+
+    /// Forget all memorized trigger times.
+    fn reset(&mut self) {
+        self.last_triggers.clear();
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Resolves instruments of notes which are missing in the sample pool, reporting each missing
+/// instrument only once to avoid flooding the warnings with repeated notes.
+#[derive(Clone, Debug, Default)]
+struct MissingInstruments {
+    reported: HashSet<InstrumentId>,
+}
+
+impl MissingInstruments {
+    /// Returns the instrument which should be played for the given note instrument, or None
+    /// when the note should be dropped.
+    fn resolve(
+        &mut self,
+        action: MissingInstrumentAction,
+        sample_pool: &SamplePool,
+        instrument: InstrumentId,
+    ) -> Option<InstrumentId> {
+        if sample_pool.contains_sample(instrument) {
+            // forget reports, so the instrument gets reported again when it gets removed
+            if !self.reported.is_empty() {
+                self.reported.remove(&instrument);
+            }
+            return Some(instrument);
+        }
+        let substitute = match action {
+            MissingInstrumentAction::Drop => return None,
+            MissingInstrumentAction::DropWithWarning => None,
+            MissingInstrumentAction::Substitute(substitute) => {
+                Some(substitute).filter(|substitute| sample_pool.contains_sample(*substitute))
+            }
+        };
+        if self.reported.insert(instrument) {
+            let message = match substitute {
+                Some(substitute) => format!(
+                    "instrument {} is not loaded: playing instrument {} instead",
+                    instrument, substitute
+                ),
+                None => format!(
+                    "instrument {} is not loaded: dropping its notes",
+                    instrument
+                ),
+            };
+            log::warn!(target: "Player", "{}", message);
+            #[cfg(feature = "scripting")]
+            crate::bindings::add_lua_callback_warning("player", &message);
+        }
+        substitute
+    }
+
+    /// Forget all reported instruments.
+    fn reset(&mut self) {
+        self.reported.clear();
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Computes a tempo in beats per minute from the intervals of repeated taps.
+///
+/// The tempo is averaged over the last few taps. Taps which follow a previous tap after a long
+/// pause start a new tap sequence.
+#[derive(Clone, Debug, Default)]
+pub struct TapTempo {
+    taps: VecDeque<Instant>,
+}
+
+impl TapTempo {
+    /// Max number of taps which are used to average the tempo.
+    const MAX_TAPS: usize = 5;
+    /// Max duration between two taps, before a new tap sequence starts.
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Create a new tap tempo detector without any taps.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new tap at the given time and return the detected tempo in beats per minute.
+    /// Returns `None` when there are not yet enough taps to detect a tempo.
+    pub fn tap(&mut self, time: Instant) -> Option<f32> {
+        if self
+            .taps
+            .back()
+            .is_some_and(|last| time <= *last || time.duration_since(*last) > Self::TIMEOUT)
+        {
+            self.taps.clear();
+        }
+        self.taps.push_back(time);
+        while self.taps.len() > Self::MAX_TAPS {
+            self.taps.pop_front();
+        }
+        self.beats_per_min()
+    }
+
+    /// Tempo in beats per minute from the current tap sequence, if any.
+    pub fn beats_per_min(&self) -> Option<f32> {
+        if let (Some(first), Some(last)) = (self.taps.front(), self.taps.back()) {
+            if self.taps.len() > 1 {
+                let interval =
+                    last.duration_since(*first).as_secs_f32() / (self.taps.len() - 1) as f32;
+                return Some(60.0 / interval);
+            }
+        }
+        None
+    }
+
+    /// Forget all taps.
+    pub fn reset(&mut self) {
+        self.taps.clear();
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Unique id of an envelope follower in a [`SamplePlayer`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FollowerId(usize);
+
+impl FollowerId {
+    // Generate a new unique follower id.
+    fn unique() -> Self {
+        static ID: AtomicUsize = AtomicUsize::new(0);
+        Self(ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Envelope value of an [`EnvelopeFollower`], which can be shared with and read from other
+/// threads while the effect writes it in the audio thread.
+#[derive(Clone, Debug, Default)]
+pub struct FollowerValue(Arc<AtomicU32>);
+
+impl FollowerValue {
+    /// Create a new shared value, initialized with zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current envelope value in range \[0 - 1\].
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// An [`Effect`] which passes audio through unmodified and tracks the peak envelope of the
+/// signal in a [`FollowerValue`].
+///
+/// Insert it into a mixer to read the mixer's current signal level, e.g. to duck other sounds
+/// when a kick drum hits by feeding the value into pattern parameters.
+pub struct EnvelopeFollower {
+    value: FollowerValue,
+    channel_count: usize,
+    attack_coeff: f32,
+    release_coeff: f32,
+    envelope: f32,
+}
+
+impl EnvelopeFollower {
+    /// Name of the effect.
+    pub const EFFECT_NAME: &'static str = "EnvelopeFollower";
+    /// Time in seconds it takes for the envelope to rise by a factor of e.
+    const ATTACK_TIME: f32 = 0.005;
+    /// Time in seconds it takes for the envelope to decay by a factor of e.
+    const RELEASE_TIME: f32 = 0.15;
+
+    /// Create a new envelope follower which writes its envelope into the given value.
+    pub fn new(value: FollowerValue) -> Self {
+        let mut follower = Self {
+            value,
+            channel_count: 1,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            envelope: 0.0,
+        };
+        follower.set_sample_rate(44100);
+        follower
+    }
+
+    /// The shared value this follower writes to.
+    pub fn value(&self) -> &FollowerValue {
+        &self.value
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        let coeff = |time: f32| (-1.0 / (time * sample_rate.max(1) as f32)).exp();
+        self.attack_coeff = coeff(Self::ATTACK_TIME);
+        self.release_coeff = coeff(Self::RELEASE_TIME);
+    }
+
+    // Track the envelope of the given interleaved audio buffer.
+    fn follow(&mut self, buffer: &[f32]) {
+        for frame in buffer.chunks_exact(self.channel_count) {
+            let peak = frame
+                .iter()
+                .fold(0.0_f32, |peak, sample| peak.max(sample.abs()))
+                .min(1.0);
+            let coeff = if peak > self.envelope {
+                self.attack_coeff
+            } else {
+                self.release_coeff
+            };
+            self.envelope = peak + coeff * (self.envelope - peak);
+        }
+        self.value.set(self.envelope);
+    }
+}
+
+impl Effect for EnvelopeFollower {
+    fn name(&self) -> &'static str {
+        Self::EFFECT_NAME
+    }
+
+    fn initialize(
+        &mut self,
+        sample_rate: u32,
+        channel_count: usize,
+        _max_frames: usize,
+    ) -> Result<(), Error> {
+        self.channel_count = channel_count.max(1);
+        self.set_sample_rate(sample_rate);
+        self.envelope = 0.0;
+        self.value.set(0.0);
+        Ok(())
+    }
+
+    fn process(&mut self, output: &mut [f32], _time: &EffectTime) {
+        self.follow(output);
+    }
+
+    fn process_message(&mut self, _message: &EffectMessagePayload) -> Result<(), Error> {
+        // nothing to do
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Amount of an [`AuxSend`] in range \[0 - 1\], which can be updated from other threads while
+/// the effect reads it in the audio thread.
+#[derive(Clone, Debug, Default)]
+struct SendAmount(Arc<AtomicU32>);
+
+impl SendAmount {
+    fn new(amount: f32) -> Self {
+        let send_amount = Self::default();
+        send_amount.set(amount);
+        send_amount
+    }
+
+    fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, amount: f32) {
+        self.0
+            .store(amount.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Audio buffer of an aux bus: [`AuxSend`] effects mix their signals into it and the bus'
+/// [`AuxReturn`] effect moves it into the bus mixer's signal.
+#[derive(Clone, Debug, Default)]
+struct AuxBusBuffer(Arc<Mutex<Vec<f32>>>);
+
+/// An [`Effect`] which passes audio through unmodified and mixes a portion of the signal into
+/// an aux bus.
+struct AuxSend {
+    buffer: AuxBusBuffer,
+    amount: SendAmount,
+}
+
+impl AuxSend {
+    const EFFECT_NAME: &'static str = "AuxSend";
+
+    fn new(buffer: AuxBusBuffer, amount: SendAmount) -> Self {
+        Self { buffer, amount }
+    }
+
+    // Mix the given interleaved audio buffer into the bus buffer.
+    fn send(&self, input: &[f32]) {
+        let amount = self.amount.get();
+        if amount <= 0.0 {
+            return;
+        }
+        // never block the audio thread: skip the block when the bus buffer is busy
+        if let Ok(mut buffer) = self.buffer.0.try_lock() {
+            if buffer.len() < input.len() {
+                buffer.resize(input.len(), 0.0);
+            }
+            for (bus_sample, sample) in buffer.iter_mut().zip(input) {
+                *bus_sample += sample * amount;
+            }
+        }
+    }
+}
+
+impl Effect for AuxSend {
+    fn name(&self) -> &'static str {
+        Self::EFFECT_NAME
+    }
+
+    fn initialize(
+        &mut self,
+        _sample_rate: u32,
+        _channel_count: usize,
+        _max_frames: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn process(&mut self, output: &mut [f32], _time: &EffectTime) {
+        self.send(output);
+    }
+
+    fn process_message(&mut self, _message: &EffectMessagePayload) -> Result<(), Error> {
+        // nothing to do
+        Ok(())
+    }
+}
+
+/// An [`Effect`] which adds the signals of all [`AuxSend`]s of an aux bus to the bus mixer's
+/// signal. It's the first effect of the bus mixer, so other effects process the sent signals.
+struct AuxReturn {
+    buffer: AuxBusBuffer,
+}
+
+impl AuxReturn {
+    const EFFECT_NAME: &'static str = "AuxReturn";
+
+    fn new(buffer: AuxBusBuffer) -> Self {
+        Self { buffer }
+    }
+
+    // Move the bus buffer's content into the given interleaved audio buffer.
+    fn receive(&self, output: &mut [f32]) {
+        // never block the audio thread: receive the signal with the next block when busy
+        if let Ok(mut buffer) = self.buffer.0.try_lock() {
+            for (sample, bus_sample) in output.iter_mut().zip(buffer.iter_mut()) {
+                *sample += *bus_sample;
+                *bus_sample = 0.0;
+            }
+        }
+    }
+}
+
+impl Effect for AuxReturn {
+    fn name(&self) -> &'static str {
+        Self::EFFECT_NAME
+    }
+
+    fn initialize(
+        &mut self,
+        _sample_rate: u32,
+        channel_count: usize,
+        max_frames: usize,
+    ) -> Result<(), Error> {
+        // preallocate the bus buffer, so sends don't need to allocate in the audio thread
+        let mut buffer = self.buffer.0.lock().expect("Failed to lock aux bus buffer");
+        buffer.clear();
+        buffer.resize(channel_count * max_frames, 0.0);
+        Ok(())
+    }
+
+    fn process(&mut self, output: &mut [f32], _time: &EffectTime) {
+        self.receive(output);
+    }
+
+    fn process_message(&mut self, _message: &EffectMessagePayload) -> Result<(), Error> {
+        // nothing to do
+        Ok(())
+    }
+}
+
+/// Mixer of an instrument with aux sends, which routes the instrument's notes into the
+/// instrument's target mixer and feeds the instrument's [`AuxSend`] effects.
+struct SendMixer {
+    parent: Option<MixerId>,
+    mixer_id: MixerId,
+    sends: HashMap<MixerId, SendAmount>,
+}
+
+impl SendMixer {
+    fn new(parent: Option<MixerId>, mixer_id: MixerId) -> Self {
+        let sends = HashMap::new();
+        Self {
+            parent,
+            mixer_id,
+            sends,
+        }
+    }
+
+    /// Stop sending any signals to the aux busses.
+    fn mute(&self) {
+        for amount in self.sends.values() {
+            amount.set(0.0);
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Estimates smoothed output levels of the patterns in a sequence's phrase from the volumes of
+/// played notes: levels rise instantly with new notes and then decay exponentially.
+#[derive(Clone, Debug, Default)]
+struct OutputLevelMeter {
+    levels: Vec<f32>,
+    time: SampleTime,
+}
+
+impl OutputLevelMeter {
+    /// Time in seconds it takes for a level to decay by a factor of e.
+    const RELEASE_TIME: f64 = 0.3;
+
+    /// Create a new meter without any levels.
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current levels of all pattern slots.
+    fn levels(&self) -> &[f32] {
+        &self.levels
+    }
+
+    /// Reset all levels to zero for the given pattern slot count, starting at the given time.
+    fn reset(&mut self, pattern_slot_count: usize, time: SampleTime) {
+        self.levels.clear();
+        self.levels.resize(pattern_slot_count, 0.0);
+        self.time = time;
+    }
+
+    /// Add zero levels for new pattern slots, keeping the levels of existing slots.
+    fn grow(&mut self, pattern_slot_count: usize) {
+        if self.levels.len() < pattern_slot_count {
+            self.levels.resize(pattern_slot_count, 0.0);
+        }
+    }
+
+    /// Register a new note with the given volume in the given pattern slot.
+    fn note_on(&mut self, pattern_index: usize, volume: f32) {
+        if let Some(level) = self.levels.get_mut(pattern_index) {
+            *level = level.max(volume.clamp(0.0, 1.0));
+        }
+    }
+
+    /// Decay all levels from the last time up to the given sample time.
+    fn advance(&mut self, time: SampleTime, samples_per_sec: u32) {
+        if time > self.time && samples_per_sec > 0 {
+            let seconds = (time - self.time) as f64 / samples_per_sec as f64;
+            let factor = (-seconds / Self::RELEASE_TIME).exp() as f32;
+            for level in &mut self.levels {
+                *level *= factor;
+            }
+        }
+        self.time = time;
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Shared pause state of a [`SamplePlayer`]'s transport. Clones share the state, so a handle can
+/// pause and resume the transport from other threads while the player is running a sequence
+/// via [`SamplePlayer::run_until`].
+#[derive(Clone, Debug, Default)]
+pub struct PauseHandle(Arc<AtomicU8>);
+
+impl PauseHandle {
+    const RUNNING: u8 = 0;
+    const PAUSED: u8 = 1;
+    const RESUMED: u8 = 2;
+
+    /// Create a new, not paused handle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// true when the transport is paused.
+    pub fn is_paused(&self) -> bool {
+        self.0.load(Ordering::Acquire) == Self::PAUSED
+    }
+
+    /// Pause the transport: the player stops emitting new events, but already scheduled events
+    /// and playing notes continue to play.
+    pub fn pause(&self) {
+        self.0.store(Self::PAUSED, Ordering::Release);
+    }
+
+    /// Resume a paused transport. The player continues the sequence's event stream where it got
+    /// paused. Does nothing when the transport is not paused.
+    pub fn resume(&self) {
+        let _ = self.0.compare_exchange(
+            Self::PAUSED,
+            Self::RESUMED,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+    }
+
+    /// Returns true, once, when the transport got resumed since the last call.
+    fn take_resumed(&self) -> bool {
+        self.0
+            .compare_exchange(
+                Self::RESUMED,
+                Self::RUNNING,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+    }
+
+    /// Unpause without resuming, e.g. when playback restarts.
+    fn reset(&self) {
+        self.0.store(Self::RUNNING, Ordering::Release);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// What the [`SamplePlayer`]'s `run_until` loop should do next.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum PlaybackAction {
+    /// Emit the given number of samples ahead.
+    Emit(SampleTime),
+    /// Wait the given number of seconds until the next batch is due.
+    Wait(f64),
+    /// Don't emit anything while the transport is paused.
+    Paused,
+}
+
+/// Playback time state of the [`SamplePlayer`]'s `run_until` loop, which maps emitted sequence
+/// sample times to output sample times.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct PlaybackClock {
+    /// Output sample time at which the sequence starts playing.
+    playback_sample_time: SampleTime,
+    /// Sequence sample time up to which events got emitted.
+    emitted_sample_time: SampleTime,
+    /// Set when a count-in got scheduled, which the next run starts playing after.
+    count_in_pending: bool,
+}
+
+impl PlaybackClock {
+    /// Create a new clock, which starts playing at the given output sample time.
+    fn new(output_sample_time: SampleTime) -> Self {
+        Self {
+            playback_sample_time: output_sample_time,
+            emitted_sample_time: 0,
+            count_in_pending: false,
+        }
+    }
+
+    /// Restart playback at the given output sample time, dropping pending count-ins.
+    fn reset(&mut self, output_sample_time: SampleTime) {
+        *self = Self::new(output_sample_time);
+    }
+
+    /// Restart playback after a count-in, which ends at the given output sample time.
+    fn reset_with_count_in(&mut self, playback_sample_time: SampleTime) {
+        self.playback_sample_time = playback_sample_time;
+        self.emitted_sample_time = 0;
+        self.count_in_pending = true;
+    }
+
+    /// Start running a sequence. Returns true when the playback position needs a reset, which is
+    /// the case when starting the first time or when explicitly requested. A pending count-in
+    /// is never reset, so the sequence starts playing at the count-in's end.
+    fn start(&mut self, reset_playback_pos: bool) -> bool {
+        if self.count_in_pending {
+            self.count_in_pending = false;
+            false
+        } else {
+            reset_playback_pos || self.emitted_sample_time == 0
+        }
+    }
+
+    /// Calculate what to do next at the given output sample time, to run the sequence ahead of
+    /// the output by the given preload time. Resumed transports continue emitting events a
+    /// preload time ahead of the output.
+    fn next_action(
+        &mut self,
+        pause_handle: &PauseHandle,
+        output_sample_time: SampleTime,
+        time_base: &dyn SampleTimeBase,
+        playback_preload_secs: f64,
+    ) -> PlaybackAction {
+        if pause_handle.is_paused() {
+            return PlaybackAction::Paused;
+        }
+        if pause_handle.take_resumed() {
+            self.resume(
+                output_sample_time,
+                time_base.seconds_to_samples(playback_preload_secs),
+            );
+        }
+        let seconds_emitted = time_base.samples_to_seconds(self.emitted_sample_time);
+        // playback may start in future, e.g. after a count-in
+        let seconds_played = time_base
+            .samples_to_seconds(output_sample_time.saturating_sub(self.playback_sample_time));
+        let seconds_to_emit = seconds_played - seconds_emitted + playback_preload_secs * 2.0;
+        if seconds_to_emit >= playback_preload_secs || self.emitted_sample_time == 0 {
+            PlaybackAction::Emit(time_base.seconds_to_samples(seconds_to_emit))
+        } else {
+            PlaybackAction::Wait((playback_preload_secs - seconds_to_emit).max(0.0))
+        }
+    }
+
+    /// Mark the given number of samples as emitted.
+    fn advance(&mut self, samples: SampleTime) {
+        self.emitted_sample_time += samples;
+    }
+
+    /// Calculate a new playback start offset for a resumed transport: the first not yet emitted
+    /// event gets scheduled a preload time ahead of the current output position, but never before
+    /// the already scheduled events played.
+    fn resume(&mut self, output_sample_time: SampleTime, preload_samples: SampleTime) {
+        self.playback_sample_time = (output_sample_time + preload_samples)
+            .saturating_sub(self.emitted_sample_time)
+            .max(self.playback_sample_time);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Context, passed along serialized when triggering new notes from the sample player.   
+#[derive(Clone)]
+pub struct SamplePlaybackContext {
+    pub pattern_index: Option<usize>,
+    pub voice_index: Option<usize>,
+    /// Custom host tag of the pattern slot, see [`SamplePlayer::set_pattern_tag`].
+    pub tag: Option<u64>,
+}
+
+impl SamplePlaybackContext {
+    pub fn from_event(context: Option<PlaybackStatusContext>) -> Self {
+        if let Some(context) = context {
+            if let Some(context) = context.downcast_ref::<SamplePlaybackContext>() {
+                return context.clone();
+            }
+        }
+        SamplePlaybackContext {
+            pattern_index: None,
+            voice_index: None,
+            tag: None,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Receives phonic's playback status events and forwards them to the player's optional status
+/// sender as soon as they arrive, while memorizing the ids of stopped sources, so the player can
+/// forget notes which finished playing on their own.
+enum PlaybackStatusForwarder {
+    /// A thread forwards events: the player only receives the ids of stopped sources.
+    Thread {
+        stopped_receiver: Receiver<PlaybackId>,
+    },
+    /// Threads are not available: events get forwarded when the player handles them.
+    Inline {
+        status_receiver: Receiver<PlaybackStatusEvent>,
+        status_sender: Option<Sender<PlaybackStatusEvent>>,
+    },
+}
+
+impl PlaybackStatusForwarder {
+    /// Create a new forwarder for the given optional sender. Returns the sender which should be
+    /// passed to the phonic player and the forwarder.
+    fn new(
+        status_sender: Option<Sender<PlaybackStatusEvent>>,
+    ) -> (Sender<PlaybackStatusEvent>, Self) {
+        let (sender, status_receiver) = crossbeam_channel::unbounded();
+        if cfg!(target_family = "wasm") {
+            let forwarder = Self::Inline {
+                status_receiver,
+                status_sender,
+            };
+            return (sender, forwarder);
+        }
+        let (stopped_sender, stopped_receiver) = crossbeam_channel::unbounded();
+        std::thread::Builder::new()
+            .name("pattrns-playback-status".to_string())
+            .spawn(move || {
+                // runs until the phonic player, which owns the event sender, got dropped
+                while let Ok(event) = status_receiver.recv() {
+                    if let PlaybackStatusEvent::Stopped { id, .. } = &event {
+                        // ignore result: the sample player maybe got dropped
+                        let _ = stopped_sender.send(*id);
+                    }
+                    if let Some(sender) = &status_sender {
+                        // ignore result: receiver maybe got dropped
+                        let _ = sender.send(event);
+                    }
+                }
+            })
+            .expect("Failed to spawn playback status thread");
+        (sender, Self::Thread { stopped_receiver })
+    }
+
+    /// Ids of all sources which stopped playing since the last call.
+    fn stopped_playback_ids(&self) -> Vec<PlaybackId> {
+        match self {
+            Self::Thread { stopped_receiver } => stopped_receiver.try_iter().collect(),
+            Self::Inline {
+                status_receiver,
+                status_sender,
+            } => {
+                let mut stopped_ids = Vec::new();
+                for event in status_receiver.try_iter() {
+                    if let PlaybackStatusEvent::Stopped { id, .. } = &event {
+                        stopped_ids.push(*id);
+                    }
+                    if let Some(sender) = status_sender {
+                        // ignore result: receiver maybe got dropped
+                        let _ = sender.send(event);
+                    }
+                }
+                stopped_ids
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A simple example player implementation as wrapper around [`phonic`](https://crates.io/crates/phonic),
+/// which plays back a [`Sequence`] using the default audio output device, using plain samples loaded
+/// from a file as instruments.
+///
+/// Uses an existing, shared sample pool, so the pool can also be maintained outside of the player.
+/// To add/remove samples, see [`SamplePool`].
+///
+/// To use DSP effects, use the [`Self::inner_mut`] function to access the underlying phonic player
+/// and use the [`SamplePool::set_target_mixer`] to route specific samples though specific mixers.  
+pub struct SamplePlayer {
+    inner: PhonicPlayer,
+    sample_pool: Arc<SamplePool>,
+    playback_status: PlaybackStatusForwarder,
+    playing_notes: PlayingVoices,
+    new_note_action: NewNoteAction,
+    default_fade_out_duration: Duration,
+    duplicate_note_action: DuplicateNoteAction,
+    global_max_voices: Option<usize>,
+    voice_steal_mode: VoiceStealMode,
+    sample_root_note: Note,
+    sync_sample_tempo: bool,
+    zero_volume_note_off: bool,
+    retrigger_debounce: RetriggerDebounce,
+    missing_instrument_action: MissingInstrumentAction,
+    missing_instruments: MissingInstruments,
+    playback_preload_time: Duration,
+    playback_pos_emit_rate: Duration,
+    show_events: bool,
+    tap_tempo: TapTempo,
+    output_levels: OutputLevelMeter,
+    followers: HashMap<FollowerId, FollowerValue>,
+    aux_buses: HashMap<MixerId, AuxBusBuffer>,
+    send_mixers: HashMap<InstrumentId, SendMixer>,
+    pattern_tags: HashMap<usize, u64>,
+    count_in_instrument: Option<InstrumentId>,
+    time_signature: Option<TimeSignature>,
+    pause_handle: PauseHandle,
+    clock: PlaybackClock,
+}
+
+impl SamplePlayer {
+    /// Default preload time of the player's `run_until` function.
+    /// Quite high by default and bigger in slower debug builds.
+    const DEFAULT_PLAYBACK_PRELOAD_MS: u64 = if cfg!(debug_assertions) { 500 } else { 250 };
+
+    /// Create a new sample player from the given shared SamplePool.
+    ///
+    /// Playback status events of played samples are forwarded to the given optional sender as
+    /// soon as they arrive. On targets without threads, such as wasm, they get forwarded when
+    /// running the player.
+    ///
+    /// # Errors
+    /// returns an error if the player could not be created.
+    pub fn new<S: Into<Option<Sender<PlaybackStatusEvent>>>>(
+        sample_pool: Arc<SamplePool>,
+        playback_status_sender: S,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        // create player
+        let audio_output = DefaultOutputDevice::open()?;
+        // receive status events to track finished notes: forward them to the given sender
+        let (status_sender, playback_status) =
+            PlaybackStatusForwarder::new(playback_status_sender.into());
+        let inner = PhonicPlayer::new(audio_output, status_sender);
+        let playing_notes = PlayingVoices::default();
+        let new_note_action = NewNoteAction::default();
+        let default_fade_out_duration = NewNoteAction::DEFAULT_FADE_OUT_DURATION;
+        let duplicate_note_action = DuplicateNoteAction::default();
+        let global_max_voices = None;
+        let voice_steal_mode = VoiceStealMode::default();
+        let sample_root_note = Note::C5;
+        let sync_sample_tempo = false;
+        let zero_volume_note_off = false;
+        let retrigger_debounce = RetriggerDebounce::default();
+        let missing_instrument_action = MissingInstrumentAction::default();
+        let missing_instruments = MissingInstruments::default();
+        let playback_preload = Duration::from_millis(Self::DEFAULT_PLAYBACK_PRELOAD_MS);
+        let playback_pos_emit_rate = Duration::from_secs(1);
+        let show_events = false;
+        let tap_tempo = TapTempo::new();
+        let output_levels = OutputLevelMeter::new();
+        let followers = HashMap::new();
+        let aux_buses = HashMap::new();
+        let send_mixers = HashMap::new();
+        let pattern_tags = HashMap::new();
+        let count_in_instrument = None;
+        let time_signature = None;
+        let pause_handle = PauseHandle::new();
+        let clock = PlaybackClock::new(inner.output_sample_frame_position());
+        Ok(Self {
+            inner,
+            sample_pool,
+            playback_status,
+            playing_notes,
+            new_note_action,
+            default_fade_out_duration,
+            duplicate_note_action,
+            global_max_voices,
+            voice_steal_mode,
+            sample_root_note,
+            sync_sample_tempo,
+            zero_volume_note_off,
+            retrigger_debounce,
+            missing_instrument_action,
+            missing_instruments,
+            playback_preload_time: playback_preload,
+            playback_pos_emit_rate,
+            show_events,
+            tap_tempo,
+            output_levels,
+            followers,
+            aux_buses,
+            send_mixers,
+            pattern_tags,
+            count_in_instrument,
+            time_signature,
+            pause_handle,
+            clock,
+        })
+    }
+
+    /// Access to the player's inner phonic instance.
+    pub fn inner(&self) -> &PhonicPlayer {
+        &self.inner
+    }
+    /// Mutable access to the player's inner phonic instance.
+    pub fn inner_mut(&mut self) -> &mut PhonicPlayer {
+        &mut self.inner
+    }
+
+    /// Return the output backend's sample rate. All sources will be played back at this rate.
+    pub fn sample_rate(&self) -> u32 {
+        self.inner.output_sample_rate()
+    }
+
+    /// true when events are dumped to stdout while playing them.
+    pub fn show_events(&self) -> bool {
+        self.show_events
+    }
+    /// By default false: set to true to dump events to stdout while playing them.
+    pub fn set_show_events(&mut self, show: bool) {
+        self.show_events = show;
+    }
+
+    /// Preload time of the `run_until` function. By default 1/2 seconds in debug builds
+    /// and 1/4 seconds in release builds. Should be big enough to ensure that events are
+    /// scheduled ahead of playback time, but small enough to avoid too much latency.
+    ///
+    /// NB: real audio latency is twice the amount of the given time duration, plus the
+    /// audio driver's output latency.
+    pub fn playback_preload_time(&self) -> Duration {
+        self.playback_preload_time
+    }
+    pub fn set_playback_preload_time(&mut self, preload_time: Duration) {
+        self.playback_preload_time = preload_time;
+    }
+
+    /// Playback pos emit rate of triggered files. by default one second.
+    pub fn playback_pos_emit_rate(&self) -> Duration {
+        self.playback_pos_emit_rate
+    }
+    pub fn set_playback_pos_emit_rate(&mut self, emit_rate: Duration) {
+        self.playback_pos_emit_rate = emit_rate;
+    }
+
+    /// Get current new note action behavior.
+    pub fn new_note_action(&self) -> NewNoteAction {
+        self.new_note_action
+    }
+    // Set a new new note action behavior.
+    pub fn set_new_note_action(&mut self, action: NewNoteAction) {
+        self.new_note_action = action;
+    }
+
+    /// Get fade-out duration which is used when stopping notes with new note actions that
+    /// have no explicit fade-out duration set. By default 100ms.
+    pub fn default_fade_out_duration(&self) -> Duration {
+        self.default_fade_out_duration
+    }
+    // Set a new default fade-out duration.
+    pub fn set_default_fade_out_duration(&mut self, duration: Duration) {
+        self.default_fade_out_duration = duration;
+    }
+
+    /// Get current duplicate note action behavior.
+    pub fn duplicate_note_action(&self) -> DuplicateNoteAction {
+        self.duplicate_note_action
+    }
+    // Set a new duplicate note action behavior. By default duplicates are allowed.
+    pub fn set_duplicate_note_action(&mut self, action: DuplicateNoteAction) {
+        self.duplicate_note_action = action;
+    }
+
+    /// Max number of voices which may play at the same time across all pattern slots.
+    /// When None, the number of voices is unlimited.
+    pub fn global_max_voices(&self) -> Option<usize> {
+        self.global_max_voices
+    }
+    // Set a new global voice limit. Values < 1 are clamped to 1. By default None.
+    pub fn set_global_max_voices(&mut self, max_voices: Option<usize>) {
+        self.global_max_voices = max_voices.map(|max| max.max(1));
+    }
+
+    /// Get current voice stealing behavior, which is applied when exceeding the global voice limit.
+    pub fn voice_steal_mode(&self) -> VoiceStealMode {
+        self.voice_steal_mode
+    }
+    // Set a new voice stealing behavior. By default the oldest voice gets stolen.
+    pub fn set_voice_steal_mode(&mut self, mode: VoiceStealMode) {
+        self.voice_steal_mode = mode;
+    }
+
+    /// Number of voices which are currently playing across all pattern slots. This includes
+    /// notes which continue playing after new notes got triggered in their voices, but not notes
+    /// which finished playing on their own.
+    pub fn active_voice_count(&self) -> usize {
+        self.playing_notes.voice_count()
+    }
+
+    /// Get current behavior for notes which reference instruments that are not loaded.
+    pub fn missing_instrument_action(&self) -> MissingInstrumentAction {
+        self.missing_instrument_action
+    }
+    // Set a new missing instrument behavior. By default notes get dropped with a warning.
+    pub fn set_missing_instrument_action(&mut self, action: MissingInstrumentAction) {
+        self.missing_instrument_action = action;
+        self.missing_instruments.reset();
+    }
+
+    /// Get root note used when converting event note values to sample playback speed, for
+    /// instruments which have no root note set in the [`SamplePool`].
+    pub fn sample_root_note(&self) -> Note {
+        self.sample_root_note
+    }
+    // Set a new global root note.
+    pub fn set_sample_root_note(&mut self, root_note: Note) {
+        self.sample_root_note = root_note;
+    }
+
+    /// true when samples with a tempo in their [`SampleMetadata`] get played back with a speed
+    /// that matches the sequence's tempo. Note that this changes the pitch of the samples too.
+    pub fn sync_sample_tempo(&self) -> bool {
+        self.sync_sample_tempo
+    }
+    // By default false: set to true to sync the playback speed of loops to the sequence tempo.
+    pub fn set_sync_sample_tempo(&mut self, sync: bool) {
+        self.sync_sample_tempo = sync;
+    }
+
+    /// true when note-ons with a zero volume stop the voice instead of playing a silent note,
+    /// just like MIDI note-ons with zero velocity act as note-offs.
+    pub fn zero_volume_note_off(&self) -> bool {
+        self.zero_volume_note_off
+    }
+    // By default false: set to true to treat zero volume note-ons as note-offs.
+    pub fn set_zero_volume_note_off(&mut self, enabled: bool) {
+        self.zero_volume_note_off = enabled;
+    }
+
+    /// Register a tap for the tap tempo detection. Call this on each tap. Returns a suggested
+    /// `beats_per_min` value for the sequence's time base, when enough taps are present.
+    pub fn tap_tempo(&mut self) -> Option<f32> {
+        self.tap_tempo.tap(Instant::now())
+    }
+
+    /// Custom host tag of the given pattern slot, which is passed along in the
+    /// [`SamplePlaybackContext`] of all notes triggered by the slot.
+    pub fn pattern_tag(&self, pattern_index: usize) -> Option<u64> {
+        self.pattern_tags.get(&pattern_index).copied()
+    }
+    // Set or clear a custom host tag, e.g. a UI track id, for the given pattern slot.
+    pub fn set_pattern_tag(&mut self, pattern_index: usize, tag: Option<u64>) {
+        if let Some(tag) = tag {
+            self.pattern_tags.insert(pattern_index, tag);
+        } else {
+            self.pattern_tags.remove(&pattern_index);
+        }
     }
 
-    /// true when events are dumped to stdout while playing them.
-    pub fn show_events(&self) -> bool {
-        self.show_events
+    /// Sample which plays the clicks of a count-in. When None, count-ins are silent.
+    pub fn count_in_instrument(&self) -> Option<InstrumentId> {
+        self.count_in_instrument
     }
-    /// By default false: set to true to dump events to stdout while playing them.
-    pub fn set_show_events(&mut self, show: bool) {
-        self.show_events = show;
+    // Set a new sample for count-in clicks. By default None.
+    pub fn set_count_in_instrument(&mut self, instrument: Option<InstrumentId>) {
+        self.count_in_instrument = instrument;
     }
 
-    /// Preload time of the `run_until` function. By default 1/2 seconds in debug builds
-    /// and 1/4 seconds in release builds. Should be big enough to ensure that events are
-    /// scheduled ahead of playback time, but small enough to avoid too much latency.
+    /// Time signature, which defines the bars and beats of count-ins. When None, the
+    /// sequence time base's signature is used: `beats_per_bar` quarter notes per bar.
+    pub fn time_signature(&self) -> Option<TimeSignature> {
+        self.time_signature
+    }
+    // Set a new custom time signature for count-ins. By default None.
+    pub fn set_time_signature(&mut self, time_signature: Option<TimeSignature>) {
+        self.time_signature = time_signature;
+    }
+
+    /// Start playback with a count-in of the given number of bars.
     ///
-    /// NB: real audio latency is twice the amount of the given time duration, plus the
-    /// audio driver's output latency.
-    pub fn playback_preload_time(&self) -> Duration {
-        self.playback_preload_time
+    /// Stops all playing sources and plays a click with the count-in instrument, if set, on each
+    /// beat of the count-in, starting at the given output sample time. Downbeats are accented.
+    ///
+    /// Returns the output sample time of the downbeat after the count-in, where the sequence
+    /// should start playing: use it as `time_offset` in [`Self::run_until_time`].
+    pub fn start_playing_with_count_in(
+        &mut self,
+        time_base: &BeatTimeBase,
+        start_time: SampleTime,
+        bars: u32,
+    ) -> SampleTime {
+        self.stop_all_sources();
+        let time_signature = self
+            .time_signature
+            .unwrap_or_else(|| time_base.time_signature());
+        let (clicks, count_in_duration) = Self::count_in_clicks(time_base, &time_signature, bars);
+        if let Some(instrument) = self.count_in_instrument {
+            let playback_sample_rate = self.inner.output_sample_rate();
+            for (time, is_downbeat) in clicks {
+                let volume = if is_downbeat { 1.0 } else { 0.5 };
+                let playback_options = FilePlaybackOptions::default().volume(volume);
+                match self.sample_pool.sample_source(
+                    instrument,
+                    playback_options,
+                    playback_sample_rate,
+                ) {
+                    Ok(sample) => {
+                        // ignore result: a failing click should not prevent playback
+                        let _ = self.play_sample_source(sample, Some(start_time + time), None);
+                    }
+                    Err(err) => {
+                        log::error!(target: "Player", "Failed to get count-in sample: {}", err);
+                        break;
+                    }
+                }
+            }
+        }
+        self.clock
+            .reset_with_count_in(start_time + count_in_duration);
+        self.pause_handle.reset();
+        self.clock.playback_sample_time
     }
-    pub fn set_playback_preload_time(&mut self, preload_time: Duration) {
-        self.playback_preload_time = preload_time;
+
+    /// true when the player got paused via [`Self::pause`].
+    pub fn is_paused(&self) -> bool {
+        self.pause_handle.is_paused()
     }
 
-    /// Playback pos emit rate of triggered files. by default one second.
-    pub fn playback_pos_emit_rate(&self) -> Duration {
-        self.playback_pos_emit_rate
+    /// Pause the transport: `run_until` stops emitting new events, but already scheduled events
+    /// and playing notes continue to play. Playing notes and time counters are kept, so a
+    /// [`Self::resume`] continues the sequence exactly where it got paused.
+    ///
+    /// To pause the transport while `run_until` is running, use a [`Self::pause_handle`].
+    pub fn pause(&self) {
+        self.pause_handle.pause();
     }
-    pub fn set_playback_pos_emit_rate(&mut self, emit_rate: Duration) {
-        self.playback_pos_emit_rate = emit_rate;
+
+    /// Resume a paused transport, continuing the sequence's event stream seamlessly.
+    pub fn resume(&self) {
+        self.pause_handle.resume();
     }
 
-    /// Get current new note action behavior.
-    pub fn new_note_action(&self) -> NewNoteAction {
-        self.new_note_action
+    /// Shared handle to pause and resume the transport from other threads, e.g. while
+    /// `run_until` is running.
+    pub fn pause_handle(&self) -> PauseHandle {
+        self.pause_handle.clone()
     }
-    // Set a new new note action behavior.
-    pub fn set_new_note_action(&mut self, action: NewNoteAction) {
-        self.new_note_action = action;
+
+    /// Add a new [`EnvelopeFollower`] to the given mixer or the main mixer, which tracks the
+    /// mixer's signal level. Use [`Self::follower_value`] to read the follower's envelope.
+    ///
+    /// # Errors
+    /// returns an error if the follower effect could not be added to the mixer.
+    pub fn add_envelope_follower(
+        &mut self,
+        mixer_id: Option<MixerId>,
+    ) -> Result<FollowerId, Error> {
+        let value = FollowerValue::new();
+        self.inner
+            .add_effect(EnvelopeFollower::new(value.clone()), mixer_id)?;
+        let id = FollowerId::unique();
+        self.followers.insert(id, value);
+        Ok(id)
     }
 
-    /// Get root note used when converting event note values to sample playback speed.
-    pub fn sample_root_note(&self) -> Note {
-        self.sample_root_note
+    /// Current envelope value in range \[0 - 1\] of the given envelope follower, if it exists.
+    pub fn follower_value(&self, id: FollowerId) -> Option<f32> {
+        self.followers.get(&id).map(FollowerValue::get)
     }
-    // Set a new global root note.
-    pub fn set_sample_root_note(&mut self, root_note: Note) {
-        self.sample_root_note = root_note;
+
+    /// Add a new aux bus mixer to the given parent mixer or the main mixer. Instruments can send
+    /// portions of their signals to the bus via [`SamplePool::set_send`]. Add effects such as
+    /// reverbs or delays to the returned mixer to process the bus' signal.
+    ///
+    /// Sent signals arrive in the bus with a latency of up to one processing block.
+    ///
+    /// # Errors
+    /// returns an error if the bus mixer could not be created.
+    pub fn add_aux_bus(&mut self, parent: Option<MixerId>) -> Result<MixerId, Error> {
+        let mixer_id = self.inner.add_mixer(parent)?;
+        let buffer = AuxBusBuffer::default();
+        self.inner
+            .add_effect(AuxReturn::new(buffer.clone()), Some(mixer_id))?;
+        self.aux_buses.insert(mixer_id, buffer);
+        Ok(mixer_id)
     }
 
     /// Stop all currently playing sources.
@@ -319,18 +2211,14 @@ impl SamplePlayer {
         self.inner
             .stop_all_sources()
             .expect("Failed to stop all sources");
-        for notes in &mut self.playing_notes {
-            notes.clear();
-        }
+        self.playing_notes.take_all();
+        self.retrigger_debounce.reset();
     }
 
     /// Stop all currently playing sources in the given pattern slot index.
     pub fn stop_sources_in_pattern_slot(&mut self, pattern_index: usize) {
-        for (playback_id, _) in self.playing_notes[pattern_index].values() {
-            // ignore result: source maybe already is stopped
-            let _ = self.inner.stop_source(*playback_id, None);
-        }
-        self.playing_notes[pattern_index].clear();
+        let playing_notes = self.playing_notes.take_slot(pattern_index);
+        self.stop_playing_notes(playing_notes, None);
     }
 
     /// Run/play the given sequence until it stops.
@@ -362,48 +2250,54 @@ impl SamplePlayer {
     ) {
         // reset time counters when starting the first time or when explicitly requested, else continue
         // playing from our previous time to avoid interrupting playback streams
-        if reset_playback_pos || self.emitted_sample_time == 0 {
+        if self.clock.start(reset_playback_pos) {
             self.reset_playback_position(sequence);
             log::debug!(target: "Player", "Resetting playback pos");
         } else {
             self.prepare_run_until_time(
                 previous_sequence,
                 sequence,
-                self.playback_sample_time,
-                self.emitted_sample_time,
+                self.clock.playback_sample_time,
+                self.clock.emitted_sample_time,
             );
             log::debug!(target: "Player",
                 "Advance sequence to time {:.2}",
-                time_base.samples_to_seconds(self.emitted_sample_time)
+                time_base.samples_to_seconds(self.clock.emitted_sample_time)
             );
         }
         while !stop_fn() {
-            let playback_preload_secs = self.playback_preload_time.as_secs_f64();
-            // calculate emitted and playback time differences
-            let seconds_emitted = time_base.samples_to_seconds(self.emitted_sample_time);
-            let seconds_played = time_base.samples_to_seconds(
-                self.inner.output_sample_frame_position() - self.playback_sample_time,
-            );
-            let seconds_to_emit = seconds_played - seconds_emitted + playback_preload_secs * 2.0;
+            // track finished notes and forward status events while waiting
+            self.handle_playback_status_events();
             // run sequence ahead of player by the self.playback_preload time
-            if seconds_to_emit >= playback_preload_secs || self.emitted_sample_time == 0 {
-                let samples_to_emit = time_base.seconds_to_samples(seconds_to_emit);
-                self.run_until_time(
-                    sequence,
-                    self.playback_sample_time,
-                    self.emitted_sample_time + samples_to_emit,
-                );
-                self.emitted_sample_time += samples_to_emit;
-            } else {
-                // wait until next events are due, but check stop_fn at least every...
-                const MAX_SLEEP_TIME: f64 = 0.1;
-                let time_until_next_emit_batch = (playback_preload_secs - seconds_to_emit).max(0.0);
-                let mut time_slept = 0.0;
-                while time_slept < time_until_next_emit_batch && !stop_fn() {
-                    let sleep_amount = time_until_next_emit_batch.min(MAX_SLEEP_TIME);
-                    std::thread::sleep(std::time::Duration::from_secs_f64(sleep_amount));
-                    // log::debug!(target: "Player", "Slept {} seconds", sleep_amount);
-                    time_slept += sleep_amount;
+            match self.clock.next_action(
+                &self.pause_handle,
+                self.inner.output_sample_frame_position(),
+                time_base,
+                self.playback_preload_time.as_secs_f64(),
+            ) {
+                PlaybackAction::Emit(samples_to_emit) => {
+                    self.run_until_time(
+                        sequence,
+                        self.clock.playback_sample_time,
+                        self.clock.emitted_sample_time + samples_to_emit,
+                    );
+                    self.clock.advance(samples_to_emit);
+                }
+                PlaybackAction::Wait(time_until_next_emit_batch) => {
+                    // wait until next events are due, but check stop_fn at least every...
+                    const MAX_SLEEP_TIME: f64 = 0.1;
+                    let mut time_slept = 0.0;
+                    while time_slept < time_until_next_emit_batch && !stop_fn() {
+                        let sleep_amount = time_until_next_emit_batch.min(MAX_SLEEP_TIME);
+                        std::thread::sleep(std::time::Duration::from_secs_f64(sleep_amount));
+                        // log::debug!(target: "Player", "Slept {} seconds", sleep_amount);
+                        time_slept += sleep_amount;
+                    }
+                }
+                PlaybackAction::Paused => {
+                    // don't emit new events while paused, but check stop_fn at least every...
+                    const PAUSED_SLEEP_TIME: f64 = 0.1;
+                    std::thread::sleep(std::time::Duration::from_secs_f64(PAUSED_SLEEP_TIME));
                 }
             }
         }
@@ -417,6 +2311,10 @@ impl SamplePlayer {
     /// When `previous_sequence` is set, it's run to lookup note-off and stop events that
     /// would have happened in future to stop pending notes. When its none, all playing notes
     /// will be stopped at the time the new sequence starts playing.
+    ///
+    /// Notes of patterns which got replaced in the sequence's current phrase via
+    /// [`Phrase::set_pattern_slot`](crate::Phrase::set_pattern_slot) are stopped the same way,
+    /// by looking up note-offs in the replaced patterns.
     pub fn prepare_run_until_time(
         &mut self,
         previous_sequence: Option<&mut Sequence>,
@@ -424,8 +2322,10 @@ impl SamplePlayer {
         time_offset: SampleTime,
         time: SampleTime,
     ) {
+        // stop notes of replaced patterns in the new sequence
+        self.stop_replaced_pattern_slots(sequence, time_offset, time);
         // stop playing notes, if needed
-        if self.playing_notes.iter().any(|notes| !notes.is_empty()) {
+        if !self.playing_notes.is_empty() {
             // Process note stop events from the previous sequence
             let stop_time = if let Some(previous_sequence) = previous_sequence {
                 // Get maximum step length in samples of all currently playing back patterns
@@ -441,7 +2341,7 @@ impl SamplePlayer {
                 previous_sequence.consume_events_until_time(
                     note_stope_lookup_time,
                     &mut |pattern_index, pattern_event| {
-                        if !self.playing_notes[pattern_index].is_empty() {
+                        if !self.playing_notes.is_slot_empty(pattern_index) {
                             self.handle_pattern_event_note_offs(
                                 time_offset,
                                 pattern_index,
@@ -457,19 +2357,16 @@ impl SamplePlayer {
                 time_offset + time
             };
             // stop remaining playing notes at the time we're applying the new sequence
-            for playing_notes in &mut self.playing_notes {
-                for (playback_id, _) in playing_notes.values() {
-                    // ignore result: source maybe already is stopped
-                    let _ = self.inner.stop_source(*playback_id, stop_time);
-                }
-                playing_notes.clear();
-            }
+            let playing_notes = self.playing_notes.take_all();
+            self.stop_playing_notes(playing_notes, Some(stop_time));
         }
         // update playing notes state to fit the new sequence
         self.playing_notes
-            .resize_with(sequence.phrase_pattern_slot_count(), HashMap::new);
+            .resize(sequence.phrase_pattern_slot_count());
         // and finally prepare the new sequence by advancing it to the target time
         sequence.advance_until_time(time);
+        self.output_levels
+            .reset(sequence.phrase_pattern_slot_count(), time);
     }
 
     /// Manually seek the given sequence to the given time offset and actual position.
@@ -478,8 +2375,23 @@ impl SamplePlayer {
         sequence.advance_until_time(time);
     }
 
+    /// Restart the given, currently playing sequence from its first phrase at the current
+    /// playback position, without stopping playing notes, e.g. for seamless restarts of live
+    /// loops.
+    ///
+    /// Unlike running the sequence with `reset_playback_pos`, which stops all sources, playing
+    /// notes keep ringing until the restarted patterns play new notes or note-offs in their
+    /// voices. The player's playback position is not affected.
+    pub fn reset_preserving_sound(&mut self, sequence: &mut Sequence) {
+        log::debug!(target: "Player", "Restarting sequence, preserving playing notes");
+        sequence.reset_preserving_sound();
+    }
+
     /// Manually run the given sequence with the given time offset and actual position.
     /// When exchanging the sequence, call `prepare_run_until_time` before calling `run_until_time`.
+    ///
+    /// Output levels and playing notes are fed back to the sequence's patterns after running,
+    /// so patterns see the levels and notes of the previous run with a latency of one run.
     pub fn run_until_time(
         &mut self,
         sequence: &mut Sequence,
@@ -487,9 +2399,83 @@ impl SamplePlayer {
         time: SampleTime,
     ) {
         let time_base = *sequence.time_base();
+        // phrases with more pattern slots may have been inserted while playing
+        let pattern_slot_count = sequence.phrase_pattern_slot_count();
+        if self.playing_notes.slot_count() < pattern_slot_count {
+            self.playing_notes.resize(pattern_slot_count);
+            self.output_levels.grow(pattern_slot_count);
+        }
+        // forget notes which finished playing
+        self.handle_playback_status_events();
+        // stop notes of patterns which got replaced while playing
+        let current_time = sequence.current_phrase().current_sample_time();
+        self.stop_replaced_pattern_slots(sequence, time_offset, current_time);
+        self.output_levels.advance(time, time_base.samples_per_sec);
         sequence.consume_events_until_time(time, &mut |pattern_index, pattern_event| {
             self.handle_pattern_event(pattern_index, pattern_event, time_base, time_offset);
         });
+        // open new streams for streamed samples which got played
+        self.sample_pool.prime_streamed_samples();
+        // feed back output levels and playing notes
+        Self::feed_back_pattern_state(sequence, self.output_levels.levels(), &self.playing_notes);
+    }
+
+    /// Feed back the given output levels and playing notes to the patterns in the given
+    /// sequence's current phrase.
+    fn feed_back_pattern_state(sequence: &Sequence, levels: &[f32], playing_notes: &PlayingVoices) {
+        let pattern_slots = sequence.current_phrase().pattern_slots();
+        for (pattern_index, (pattern_slot, level)) in pattern_slots.iter().zip(levels).enumerate() {
+            if let PatternSlot::Pattern(pattern) = pattern_slot {
+                let mut pattern = pattern.borrow_mut();
+                pattern.set_output_level(*level);
+                pattern.set_playing_notes(&playing_notes.slot_note_events(pattern_index));
+            }
+        }
+    }
+
+    /// Stop playing notes of pattern slots which got replaced in the given sequence's current
+    /// phrase. Replaced patterns are run to look up note-offs and stop events, which would have
+    /// happened in future, within a step of the given time. Remaining notes get stopped at the
+    /// given time, when the pattern got replaced.
+    fn stop_replaced_pattern_slots(
+        &mut self,
+        sequence: &mut Sequence,
+        time_offset: SampleTime,
+        time: SampleTime,
+    ) {
+        for (pattern_index, pattern_slot) in
+            sequence.current_phrase_mut().take_replaced_pattern_slots()
+        {
+            if self.playing_notes.is_slot_empty(pattern_index) {
+                continue;
+            }
+            if let PatternSlot::Pattern(pattern) = pattern_slot {
+                let mut pattern = pattern.borrow_mut();
+                let note_stop_lookup_time = time.max(pattern.current_sample_time())
+                    + pattern.step_length().ceil() as SampleTime;
+                while let Some(pattern_event) = pattern.run_until_time(note_stop_lookup_time) {
+                    self.handle_pattern_event_note_offs(time_offset, pattern_index, pattern_event);
+                }
+            }
+            let playing_notes = self.playing_notes.take_slot(pattern_index);
+            self.stop_playing_notes(playing_notes, Some(time_offset + time));
+        }
+    }
+
+    /// Stop the given playing notes at the given time or immediately.
+    fn stop_playing_notes(&mut self, playing_notes: Vec<PlayingNote>, time: Option<SampleTime>) {
+        for playing_note in playing_notes {
+            // ignore result: source maybe already is stopped
+            let _ = self.inner.stop_source(playing_note.playback_id, time);
+        }
+    }
+
+    /// Forget playing notes which stopped playing, e.g. one-shot samples which finished
+    /// playing on their own.
+    fn handle_playback_status_events(&mut self) {
+        for id in self.playback_status.stopped_playback_ids() {
+            self.playing_notes.remove_stopped(id);
+        }
     }
 
     /// Handle pattern event note offs and new note actions only, skipping note-ons.
@@ -499,8 +2485,11 @@ impl SamplePlayer {
         pattern_index: usize,
         pattern_event: PatternEvent,
     ) {
-        let playing_notes_in_pattern = &mut self.playing_notes[pattern_index];
-        if let Some(Event::NoteEvents(notes)) = pattern_event.event {
+        if let Some(Event::NoteEvents(mut notes)) = pattern_event.event {
+            self.duplicate_note_action.apply(&mut notes);
+            if self.zero_volume_note_off {
+                convert_zero_volume_note_ons(&mut notes);
+            }
             for (voice_index, note_event) in notes.iter().enumerate() {
                 let note_event = match note_event {
                     None => continue,
@@ -511,12 +2500,14 @@ impl SamplePlayer {
                     || (note_event.note.is_note_on()
                         && self.new_note_action != NewNoteAction::Continue)
                 {
-                    if let Some((playback_id, _)) = playing_notes_in_pattern.get(&voice_index) {
+                    if let Some(playing_note) =
+                        self.playing_notes.remove(pattern_index, voice_index)
+                    {
                         // ignore result: source maybe already is stopped
-                        let _ = self
-                            .inner
-                            .stop_source(*playback_id, time_offset + pattern_event.time);
-                        playing_notes_in_pattern.remove(&voice_index);
+                        let _ = self.inner.stop_source(
+                            playing_note.playback_id,
+                            time_offset + pattern_event.time,
+                        );
                     }
                 }
             }
@@ -527,7 +2518,7 @@ impl SamplePlayer {
     fn handle_pattern_event(
         &mut self,
         pattern_index: usize,
-        pattern_event: PatternEvent,
+        mut pattern_event: PatternEvent,
         time_base: BeatTimeBase,
         time_offset: SampleTime,
     ) {
@@ -545,70 +2536,117 @@ impl SamplePlayer {
         }
 
         // Process note events
-        let playing_notes_in_pattern = &mut self.playing_notes[pattern_index];
-        if let Some(Event::NoteEvents(notes)) = pattern_event.event {
+        if let Some(Event::NoteEvents(mut notes)) = pattern_event.event.take() {
+            self.duplicate_note_action.apply(&mut notes);
+            if self.zero_volume_note_off {
+                convert_zero_volume_note_ons(&mut notes);
+            }
             for (voice_index, note_event) in notes.iter().enumerate() {
                 let note_event = match note_event {
                     None => continue,
                     Some(note_event) => note_event,
                 };
+                // Ignore retriggers which are faster than the instrument's retrigger interval
+                if note_event.note.is_note_on()
+                    && self.is_debounced_retrigger(
+                        note_event,
+                        time_offset + pattern_event.note_event_time(note_event),
+                    )
+                {
+                    continue;
+                }
                 // Handle note off or stop action
                 if note_event.note.is_note_off()
                     || (note_event.note.is_note_on()
                         && self.new_note_action != NewNoteAction::Continue)
                 {
-                    if let Some((playback_id, _)) = playing_notes_in_pattern.get(&voice_index) {
+                    if let Some(playing_note) =
+                        self.playing_notes.remove(pattern_index, voice_index)
+                    {
                         // ignore result: source maybe already is stopped
-                        let _ = self
-                            .inner
-                            .stop_source(*playback_id, time_offset + pattern_event.time);
-                        playing_notes_in_pattern.remove(&voice_index);
+                        let _ = self.inner.stop_source(
+                            playing_note.playback_id,
+                            time_offset + pattern_event.time,
+                        );
                     }
                 }
                 // Play new note
                 if !note_event.note.is_note_on() {
                     continue;
                 }
-                if let Some(instrument) = note_event.instrument {
-                    let midi_note = (note_event.note as i32 + 60 - self.sample_root_note as i32)
-                        .clamp(0, 127) as u8;
+                if let Some(instrument) = note_event.instrument.and_then(|instrument| {
+                    self.missing_instruments.resolve(
+                        self.missing_instrument_action,
+                        &self.sample_pool,
+                        instrument,
+                    )
+                }) {
+                    let root_note = self
+                        .sample_pool
+                        .root_note(instrument)
+                        .unwrap_or(self.sample_root_note);
+                    let midi_note =
+                        (note_event.note as i32 + 60 - root_note as i32).clamp(0, 127) as u8;
                     let volume = note_event.volume.max(0.0);
+                    self.output_levels.note_on(pattern_index, volume);
                     let panning = note_event.panning.clamp(-1.0, 1.0);
+                    let playback_pos_emit_rate = self.playback_pos_emit_rate;
+                    let mut speed = match note_event.speed {
+                        Some(speed) => speed.max(0.0) as f64,
+                        None => speed_from_note(midi_note),
+                    };
+                    if self.sync_sample_tempo {
+                        if let Some(tempo_speed) = self
+                            .sample_pool
+                            .sample_metadata(instrument)
+                            .and_then(|metadata| metadata.speed_for_tempo(time_base.beats_per_min))
+                        {
+                            speed *= tempo_speed;
+                        }
+                    }
                     let mut playback_options = FilePlaybackOptions::default()
-                        .speed(speed_from_note(midi_note))
-                        .volume(volume)
+                        .speed(speed)
+                        .volume(self.sample_pool.output_volume(instrument, volume))
                         .panning(panning)
-                        .playback_pos_emit_rate(self.playback_pos_emit_rate);
-                    playback_options.fade_out_duration = match self.new_note_action {
-                        NewNoteAction::Continue | NewNoteAction::Stop => {
-                            Some(Duration::from_millis(100))
-                        }
-                        NewNoteAction::Off(duration) => duration,
-                    };
-                    playback_options.target_mixer = self.sample_pool.target_mixer(instrument);
+                        .playback_pos_emit_rate(playback_pos_emit_rate);
+                    playback_options.fade_out_duration = self
+                        .new_note_action
+                        .fade_out_duration(self.default_fade_out_duration);
+                    // aux sends get applied by the instrument's send mixer
+                    playback_options.target_mixer = self.instrument_target_mixer(instrument);
 
                     let playback_sample_rate = self.inner.output_sample_rate();
-                    if let Ok(sample) =
-                        self.sample_pool
-                            .sample(instrument, playback_options, playback_sample_rate)
-                    {
-                        let sample_delay =
-                            (note_event.delay * pattern_event.duration as f32) as SampleTime;
-                        let start_time = Some(time_offset + pattern_event.time + sample_delay);
-
+                    let note_start_time = time_offset + pattern_event.note_event_time(note_event);
+                    let start_time = Some(note_start_time);
+                    // make room for the new note, if needed
+                    self.steal_voices(note_start_time);
+                    if let Ok(sample) = self.sample_pool.sample_source(
+                        instrument,
+                        playback_options,
+                        playback_sample_rate,
+                    ) {
                         let context: Option<PlaybackStatusContext> =
                             Some(Arc::new(SamplePlaybackContext {
                                 pattern_index: Some(pattern_index),
                                 voice_index: Some(voice_index),
+                                tag: self.pattern_tags.get(&pattern_index).copied(),
                             }));
 
                         let playback_id = self
-                            .inner
-                            .play_file_source_with_context(sample, start_time, context)
+                            .play_sample_source(sample, start_time, context)
                             .expect("Failed to play file source");
-
-                        playing_notes_in_pattern
-                            .insert(voice_index, (playback_id, note_event.note));
+                        self.playing_notes.insert(
+                            pattern_index,
+                            voice_index,
+                            PlayingNote {
+                                playback_id,
+                                note: note_event.note,
+                                instrument: note_event.instrument,
+                                start_time: note_start_time,
+                                volume,
+                                panning,
+                            },
+                        );
                     } else {
                         log::error!(target: "Player", "Failed to get sample with id {}", instrument);
                     }
@@ -617,14 +2655,978 @@ impl SamplePlayer {
         }
     }
 
+    /// Target mixer of a new note of the given instrument: the instrument's send mixer, when
+    /// the instrument has aux sends, else the instrument's default routing. Send mixers get
+    /// created on demand and their send amounts get synced with the pool's sends.
+    fn instrument_target_mixer(&mut self, instrument: InstrumentId) -> Option<MixerId> {
+        let target_mixer = self.sample_pool.target_mixer(instrument);
+        let sends = self.sample_pool.sends(instrument);
+        // mute send mixers of instruments which got routed to other mixers
+        if self
+            .send_mixers
+            .get(&instrument)
+            .is_some_and(|send_mixer| send_mixer.parent != target_mixer)
+        {
+            if let Some(send_mixer) = self.send_mixers.remove(&instrument) {
+                send_mixer.mute();
+            }
+        }
+        if sends.is_empty() && !self.send_mixers.contains_key(&instrument) {
+            return target_mixer;
+        }
+        if !self.send_mixers.contains_key(&instrument) {
+            match self.inner.add_mixer(target_mixer) {
+                Ok(mixer_id) => {
+                    self.send_mixers
+                        .insert(instrument, SendMixer::new(target_mixer, mixer_id));
+                }
+                Err(err) => {
+                    log::error!(target: "Player", "Failed to create send mixer for instrument {}: {}", instrument, err);
+                    return target_mixer;
+                }
+            }
+        }
+        let send_mixer = self
+            .send_mixers
+            .get_mut(&instrument)
+            .expect("Failed to access send mixer");
+        // mute removed sends
+        for (bus_mixer_id, amount) in &send_mixer.sends {
+            if !sends.iter().any(|(mixer_id, _)| mixer_id == bus_mixer_id) {
+                amount.set(0.0);
+            }
+        }
+        // update or add new sends
+        for (bus_mixer_id, amount) in sends {
+            if let Some(send_amount) = send_mixer.sends.get(&bus_mixer_id) {
+                send_amount.set(amount);
+            } else if let Some(buffer) = self.aux_buses.get(&bus_mixer_id) {
+                let send_amount = SendAmount::new(amount);
+                let send = AuxSend::new(buffer.clone(), send_amount.clone());
+                match self.inner.add_effect(send, Some(send_mixer.mixer_id)) {
+                    Ok(_) => {
+                        send_mixer.sends.insert(bus_mixer_id, send_amount);
+                    }
+                    Err(err) => {
+                        log::error!(target: "Player", "Failed to add send to mixer {}: {}", bus_mixer_id, err);
+                    }
+                }
+            } else {
+                log::error!(target: "Player", "Ignoring send of instrument {} to mixer {}: mixer is no aux bus", instrument, bus_mixer_id);
+            }
+        }
+        Some(send_mixer.mixer_id)
+    }
+
+    /// Play a sample source from the sample pool, regardless of whether it's preloaded or
+    /// streamed, at the given output sample time.
+    fn play_sample_source(
+        &mut self,
+        sample: SampleSource,
+        start_time: Option<SampleTime>,
+        context: Option<PlaybackStatusContext>,
+    ) -> Result<PlaybackId, Error> {
+        match sample {
+            SampleSource::Preloaded(sample) => self
+                .inner
+                .play_file_source_with_context(sample, start_time, context),
+            SampleSource::Streamed(sample) => self
+                .inner
+                .play_file_source_with_context(sample, start_time, context),
+        }
+    }
+
+    /// Returns true when the given note-on event retriggers the same note and instrument faster
+    /// than the instrument's retrigger interval in the sample pool and thus should be ignored.
+    fn is_debounced_retrigger(&mut self, note_event: &NoteEvent, time: SampleTime) -> bool {
+        let instrument = match note_event.instrument {
+            Some(instrument) => instrument,
+            None => return false,
+        };
+        match self.sample_pool.retrigger_interval(instrument) {
+            Some(interval) => {
+                let interval = (interval.as_secs_f64() * self.inner.output_sample_rate() as f64)
+                    .round() as SampleTime;
+                !self
+                    .retrigger_debounce
+                    .trigger(instrument, note_event.note, time, interval)
+            }
+            None => false,
+        }
+    }
+
+    /// Stop playing voices at the given time until there's room for a new voice within
+    /// the global voice limit, using the voice steal mode to select the voices to stop.
+    fn steal_voices(&mut self, time: SampleTime) {
+        let max_voices = match self.global_max_voices {
+            Some(max_voices) => max_voices,
+            None => return,
+        };
+        for playing_note in self.playing_notes.steal(max_voices, self.voice_steal_mode) {
+            log::debug!(target: "Player", "Stealing voice with note {}", playing_note.note);
+            self.stop_playing_notes(vec![playing_note], Some(time));
+        }
+    }
+
+    /// Calculate click times and downbeat flags of a count-in with the given number of bars,
+    /// relative to the count-in start, and the total count-in duration in samples.
+    fn count_in_clicks(
+        time_base: &BeatTimeBase,
+        time_signature: &TimeSignature,
+        bars: u32,
+    ) -> (Vec<(SampleTime, bool)>, SampleTime) {
+        let samples_per_bar = time_signature.samples_per_bar(time_base);
+        let beat_times = time_signature.beat_sample_times(time_base);
+        let clicks = (0..bars)
+            .flat_map(|bar| {
+                let bar_time = bar as ExactSampleTime * samples_per_bar;
+                beat_times.iter().enumerate().map(move |(beat, beat_time)| {
+                    let time = (bar_time + beat_time).round() as SampleTime;
+                    (time, beat == 0)
+                })
+            })
+            .collect();
+        let duration = (bars as ExactSampleTime * samples_per_bar).round() as SampleTime;
+        (clicks, duration)
+    }
+
     fn reset_playback_position(&mut self, sequence: &Sequence) {
         // stop whatever is playing in case we're restarting
         self.stop_all_sources();
         // rebuild playing notes vec
         self.playing_notes
-            .resize_with(sequence.phrase_pattern_slot_count(), HashMap::new);
+            .resize(sequence.phrase_pattern_slot_count());
         // fetch player's actual position and use it as start offset
-        self.playback_sample_time = self.inner.output_sample_frame_position();
-        self.emitted_sample_time = 0;
+        self.clock.reset(self.inner.output_sample_frame_position());
+        self.output_levels
+            .reset(sequence.phrase_pattern_slot_count(), 0);
+        self.pause_handle.reset();
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{event::new_note, Pattern};
+
+    #[test]
+    fn zero_volume_note_ons() {
+        let mut notes = vec![
+            new_note((Note::C4, None, 0.0)),
+            new_note((Note::D4, None, 0.5)),
+            None,
+            new_note(Note::OFF),
+        ];
+        convert_zero_volume_note_ons(&mut notes);
+        assert_eq!(
+            notes
+                .iter()
+                .map(|n| n.as_ref().map(|n| n.note))
+                .collect::<Vec<_>>(),
+            vec![Some(Note::OFF), Some(Note::D4), None, Some(Note::OFF)]
+        );
+    }
+
+    #[test]
+    fn playback_context_tags() {
+        let context: PlaybackStatusContext = Arc::new(SamplePlaybackContext {
+            pattern_index: Some(1),
+            voice_index: Some(2),
+            tag: Some(1234),
+        });
+        let context = SamplePlaybackContext::from_event(Some(context));
+        assert_eq!(context.pattern_index, Some(1));
+        assert_eq!(context.voice_index, Some(2));
+        assert_eq!(context.tag, Some(1234));
+
+        // contexts of other sources
+        let context: PlaybackStatusContext = Arc::new(42_u32);
+        let context = SamplePlaybackContext::from_event(Some(context));
+        assert_eq!(context.pattern_index, None);
+        assert_eq!(context.tag, None);
+        assert_eq!(SamplePlaybackContext::from_event(None).tag, None);
+    }
+
+    #[test]
+    fn root_notes() {
+        let pool = SamplePool::new();
+        let instrument = InstrumentId::from(1);
+        assert_eq!(pool.root_note(instrument), None);
+        pool.set_root_note(instrument, Some(Note::A3));
+        assert_eq!(pool.root_note(instrument), Some(Note::A3));
+        pool.set_root_note(instrument, Some(Note::OFF));
+        assert_eq!(pool.root_note(instrument), None);
+        pool.set_root_note(instrument, Some(Note::C4));
+        pool.clear();
+        assert_eq!(pool.root_note(instrument), None);
+    }
+
+    #[test]
+    fn normalization() {
+        let pool = SamplePool::new();
+        // kick and snare have different peak levels
+        let kick = pool
+            .load_sample("examples/assets/kick.wav")
+            .expect("Failed to load sample");
+        let snare = pool
+            .load_sample("examples/assets/snare.wav")
+            .expect("Failed to load sample");
+        let peak = |id: InstrumentId| {
+            let gain = pool.normalization_gain(id).unwrap_or(1.0);
+            let routed_volume = pool.output_volume(id, 1.0);
+            assert_eq!(routed_volume, gain);
+            let sample = pool.pool.get(&id).unwrap();
+            let PooledSample::Preloaded(sample) = &*sample else {
+                panic!("Expected a preloaded sample");
+            };
+            sample.buffer().iter().fold(0.0_f32, |p, s| p.max(s.abs())) * gain
+        };
+        assert!((peak(kick) - peak(snare)).abs() > 0.1);
+        assert_eq!(pool.normalization_gain(kick), None);
+
+        // normalized samples play back at matched peak levels
+        let target = 0.8;
+        assert!(pool
+            .normalize_sample(kick, SampleNormalization::Peak(target))
+            .is_some());
+        let snare = pool
+            .load_sample_normalized(
+                "examples/assets/snare.wav",
+                SampleNormalization::Peak(target),
+            )
+            .expect("Failed to load sample");
+        assert!((peak(kick) - target).abs() < 1e-4);
+        assert!((peak(snare) - target).abs() < 1e-4);
+
+        // normalization can be removed again
+        assert_eq!(
+            pool.normalize_sample(kick, SampleNormalization::Off),
+            Some(1.0)
+        );
+        assert_eq!(pool.normalization_gain(kick), None);
+        assert_eq!(
+            pool.normalize_sample(
+                InstrumentId::from(usize::MAX),
+                SampleNormalization::Peak(1.0)
+            ),
+            None
+        );
+
+        // gains are limited and silent buffers are left untouched
+        assert_eq!(SampleNormalization::Rms(1.0).gain(&[0.5, -0.5]), 2.0);
+        assert_eq!(
+            SampleNormalization::Peak(1.0).gain(&[0.001, 0.0]),
+            SampleNormalization::MAX_GAIN
+        );
+        assert_eq!(SampleNormalization::Peak(1.0).gain(&[0.0; 4]), 1.0);
+        assert_eq!(SampleNormalization::Off.gain(&[0.5]), 1.0);
+    }
+
+    #[test]
+    fn missing_instruments() {
+        let pool = SamplePool::new();
+        let kick = pool
+            .load_sample("examples/assets/kick.wav")
+            .expect("Failed to load sample");
+        let missing = InstrumentId::from(usize::MAX);
+
+        let mut missing_instruments = MissingInstruments::default();
+        let mut resolve =
+            |action, instrument| missing_instruments.resolve(action, &pool, instrument);
+        // loaded instruments are passed as they are
+        assert_eq!(resolve(MissingInstrumentAction::Drop, kick), Some(kick));
+        // missing instruments get dropped or substituted
+        assert_eq!(resolve(MissingInstrumentAction::Drop, missing), None);
+        assert_eq!(
+            resolve(MissingInstrumentAction::DropWithWarning, missing),
+            None
+        );
+        assert_eq!(
+            resolve(MissingInstrumentAction::Substitute(kick), missing),
+            Some(kick)
+        );
+        assert_eq!(
+            resolve(MissingInstrumentAction::Substitute(missing), missing),
+            None
+        );
+        // missing instruments get reported once only
+        assert_eq!(missing_instruments.reported, HashSet::from([missing]));
+        missing_instruments.reset();
+        assert!(missing_instruments.reported.is_empty());
+    }
+
+    #[test]
+    fn streamed_samples() {
+        let pool = SamplePool::new();
+        let preloaded = pool
+            .load_sample("examples/assets/bass.wav")
+            .expect("Failed to load sample");
+        let streamed = pool
+            .load_streamed_sample("examples/assets/bass.wav", 44100)
+            .expect("Failed to load streamed sample");
+        assert!(pool
+            .load_streamed_sample("examples/assets/missing.wav", 44100)
+            .is_err());
+        assert!(!pool.is_streamed_sample(preloaded));
+        assert!(pool.is_streamed_sample(streamed));
+        // both kinds of samples create playable sources
+        assert!(pool
+            .sample(preloaded, FilePlaybackOptions::default(), 44100)
+            .is_ok());
+        assert!(pool
+            .sample(streamed, FilePlaybackOptions::default(), 44100)
+            .is_err());
+        assert!(matches!(
+            pool.sample_source(preloaded, FilePlaybackOptions::default(), 44100),
+            Ok(SampleSource::Preloaded(_))
+        ));
+        // streamed samples get primed on load and then hand out their primed stream
+        let is_primed = |id| match pool.pool.get(&id).as_deref() {
+            Some(PooledSample::Streamed(sample)) => sample.is_primed(),
+            _ => false,
+        };
+        assert!(is_primed(streamed));
+        assert!(matches!(
+            pool.sample_source(streamed, FilePlaybackOptions::default(), 44100),
+            Ok(SampleSource::Streamed(_))
+        ));
+        assert!(!is_primed(streamed));
+        // until new streams get primed with the last used options
+        pool.prime_streamed_samples();
+        assert!(is_primed(streamed));
+        // streamed samples are memorized like preloaded ones, but can't be normalized
+        assert_eq!(
+            pool.sample_metadata(streamed),
+            pool.sample_metadata(preloaded)
+        );
+        assert_eq!(
+            pool.used_samples(&[streamed])[0].1,
+            SampleFile::Path(PathBuf::from("examples/assets/bass.wav"))
+        );
+        assert_eq!(
+            pool.normalize_sample(streamed, SampleNormalization::Peak(1.0)),
+            None
+        );
+        // removing streamed samples only returns them as pooled samples
+        assert!(pool.remove_sample(streamed).is_none());
+        assert!(!pool.contains_sample(streamed));
+        assert!(matches!(
+            pool.remove_pooled_sample(preloaded),
+            Some(PooledSample::Preloaded(_))
+        ));
+    }
+
+    #[test]
+    fn retrigger_debounce() {
+        let pool = SamplePool::new();
+        let instrument = InstrumentId::from(1);
+        assert_eq!(pool.retrigger_interval(instrument), None);
+        pool.set_retrigger_interval(instrument, Some(Duration::from_millis(50)));
+        assert_eq!(
+            pool.retrigger_interval(instrument),
+            Some(Duration::from_millis(50))
+        );
+
+        // two triggers of the same note within 50ms at 44100 Hz yield one voice
+        let interval = 2205;
+        let mut debounce = RetriggerDebounce::default();
+        let voices = [0, 1000]
+            .into_iter()
+            .filter(|time| debounce.trigger(instrument, Note::C4, *time, interval))
+            .count();
+        assert_eq!(voices, 1);
+        // other notes and instruments and triggers after the interval are passed
+        assert!(debounce.trigger(instrument, Note::D4, 1000, interval));
+        assert!(debounce.trigger(InstrumentId::from(2), Note::C4, 1000, interval));
+        assert!(debounce.trigger(instrument, Note::C4, 2205, interval));
+        assert!(!debounce.trigger(instrument, Note::C4, 4000, interval));
+        debounce.reset();
+        assert!(debounce.trigger(instrument, Note::C4, 4000, interval));
+
+        pool.set_retrigger_interval(instrument, None);
+        assert_eq!(pool.retrigger_interval(instrument), None);
+    }
+
+    #[test]
+    fn playback_status_forwarding() {
+        let (host_sender, host_receiver) = crossbeam_channel::unbounded();
+        let (status_sender, forwarder) = PlaybackStatusForwarder::new(Some(host_sender));
+        status_sender
+            .send(PlaybackStatusEvent::Stopped {
+                id: 42,
+                context: None,
+                path: Arc::new("sample.wav".to_string()),
+                exhausted: true,
+            })
+            .unwrap();
+        // events reach the caller's channel without running the player
+        let event = host_receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Expected a forwarded status event");
+        assert!(matches!(event, PlaybackStatusEvent::Stopped { id: 42, .. }));
+        // and the player still gets informed about stopped sources
+        assert_eq!(forwarder.stopped_playback_ids(), vec![42]);
+        assert_eq!(forwarder.stopped_playback_ids(), vec![]);
+        // forwarding stops when the phonic player's sender got dropped
+        drop(status_sender);
+        assert!(host_receiver.recv_timeout(Duration::from_secs(5)).is_err());
+    }
+
+    #[test]
+    fn voice_stealing() {
+        // (pattern_index, voice_index), start time, volume
+        let voices = [((0, 0), 100, 0.5), ((1, 0), 50, 0.8), ((1, 1), 200, 0.2)];
+        assert_eq!(VoiceStealMode::Oldest.select(voices), Some((1, 0)));
+        assert_eq!(VoiceStealMode::Quietest.select(voices), Some((1, 1)));
+        let equal_volumes = [((0, 0), 100, 0.5), ((0, 1), 50, 0.5)];
+        assert_eq!(VoiceStealMode::Quietest.select(equal_volumes), Some((0, 1)));
+        assert_eq!(
+            VoiceStealMode::Oldest.select(Vec::<(usize, _, _)>::new()),
+            None
+        );
+
+        // play notes in two pattern slots with a global limit of 3 voices
+        const MAX_VOICES: usize = 3;
+        let playing_note = |playback_id: PlaybackId, time: SampleTime, volume: f32| PlayingNote {
+            playback_id,
+            note: Note::C4,
+            instrument: None,
+            start_time: time,
+            volume,
+            panning: 0.0,
+        };
+        for mode in [VoiceStealMode::Oldest, VoiceStealMode::Quietest] {
+            let mut voices = PlayingVoices::default();
+            voices.resize(2);
+            let mut stolen = Vec::new();
+            for time in 0..8 {
+                let volume = 1.0 - time as f32 / 10.0;
+                stolen.extend(
+                    voices
+                        .steal(MAX_VOICES, mode)
+                        .iter()
+                        .map(|playing_note| playing_note.playback_id),
+                );
+                let playback_id = time as PlaybackId;
+                voices.insert(
+                    time as usize % 2,
+                    time as usize,
+                    playing_note(playback_id, time, volume),
+                );
+                assert!(voices.voice_count() <= MAX_VOICES);
+            }
+            assert_eq!(voices.voice_count(), MAX_VOICES);
+            match mode {
+                VoiceStealMode::Oldest => {
+                    assert_eq!(stolen, vec![0, 1, 2, 3, 4]);
+                }
+                VoiceStealMode::Quietest => {
+                    // later notes are quieter, so the newest voice gets stolen
+                    assert_eq!(stolen, vec![2, 3, 4, 5, 6]);
+                }
+            }
+        }
+
+        // notes which got replaced with the continue action still count and get stolen
+        let mut voices = PlayingVoices::default();
+        voices.resize(1);
+        voices.insert(0, 0, playing_note(1, 0, 1.0));
+        voices.insert(0, 0, playing_note(2, 10, 1.0));
+        voices.insert(0, 1, playing_note(3, 20, 1.0));
+        assert_eq!(voices.voice_count(), 3);
+        let stolen = voices.steal(3, VoiceStealMode::Oldest);
+        assert_eq!(
+            stolen.iter().map(|n| n.playback_id).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(voices.voice_count(), 2);
+
+        // notes which finished playing no longer count
+        voices.insert(0, 1, playing_note(4, 30, 1.0));
+        assert_eq!(voices.voice_count(), 3);
+        voices.remove_stopped(3);
+        assert_eq!(voices.voice_count(), 2);
+        voices.remove_stopped(4);
+        assert_eq!(voices.voice_count(), 1);
+        assert!(!voices.is_slot_empty(0));
+        voices.remove_stopped(2);
+        assert!(voices.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "scripting")]
+    fn playing_notes_feedback() -> Result<(), crate::bindings::PatternError> {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // harmonize with the slot's playing notes
+        let pattern = crate::bindings::new_pattern_from_string(
+            time_base,
+            None,
+            r#"
+            return pattern {
+                unit = "1/4",
+                event = function(context)
+                  local notes = context.playing_notes
+                  if #notes == 0 then
+                    return { key = "c4", instrument = 1 }
+                  end
+                  local last = notes[#notes]
+                  return { key = note_number(last.key) + 4, instrument = last.instrument }
+                end
+            }
+            "#,
+            "playing_notes",
+        )?;
+        let mut sequence = Sequence::new(
+            time_base,
+            vec![crate::Phrase::new(
+                time_base,
+                vec![PatternSlot::Pattern(pattern)],
+                crate::BeatTimeStep::Bar(4.0),
+            )],
+        );
+        // run the sequence like `run_until_time` does, playing all note-ons
+        let mut playing_notes = PlayingVoices::default();
+        playing_notes.resize(1);
+        let mut played_notes = Vec::new();
+        let run = |sequence: &mut Sequence,
+                   playing_notes: &mut PlayingVoices,
+                   time: SampleTime,
+                   played_notes: &mut Vec<(Note, Option<InstrumentId>)>| {
+            sequence.consume_events_until_time(time, &mut |pattern_index, pattern_event| {
+                if let Some(Event::NoteEvents(notes)) = pattern_event.event {
+                    for (voice_index, note_event) in notes.into_iter().enumerate() {
+                        let note_event = match note_event {
+                            Some(note_event) if note_event.note.is_note_on() => note_event,
+                            _ => continue,
+                        };
+                        played_notes.push((note_event.note, note_event.instrument));
+                        playing_notes.remove(pattern_index, voice_index);
+                        playing_notes.insert(
+                            pattern_index,
+                            voice_index,
+                            PlayingNote {
+                                playback_id: played_notes.len(),
+                                note: note_event.note,
+                                instrument: note_event.instrument,
+                                start_time: pattern_event.time,
+                                volume: note_event.volume,
+                                panning: note_event.panning,
+                            },
+                        );
+                    }
+                }
+            });
+            SamplePlayer::feed_back_pattern_state(sequence, &[0.0], playing_notes);
+        };
+        let instrument = Some(InstrumentId::from(1));
+        for step in 1..=3 {
+            run(
+                &mut sequence,
+                &mut playing_notes,
+                step * 22050,
+                &mut played_notes,
+            );
+        }
+        assert_eq!(
+            played_notes,
+            vec![
+                (Note::C4, instrument),
+                (Note::E4, instrument),
+                (Note::Gs4, instrument)
+            ]
+        );
+        // notes which finished playing on their own are no longer visible
+        playing_notes.remove_stopped(3);
+        SamplePlayer::feed_back_pattern_state(&sequence, &[0.0], &playing_notes);
+        run(
+            &mut sequence,
+            &mut playing_notes,
+            4 * 22050,
+            &mut played_notes,
+        );
+        assert_eq!(played_notes.last(), Some(&(Note::C4, instrument)));
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_notes() {
+        let notes = vec![
+            new_note((Note::C4, InstrumentId::from(1), 0.5)),
+            new_note((Note::E4, InstrumentId::from(1), 0.5)),
+            new_note((Note::C4, InstrumentId::from(1), 0.8)),
+            new_note((Note::C4, InstrumentId::from(2), 0.2)),
+        ];
+
+        let mut allowed = notes.clone();
+        DuplicateNoteAction::Allow.apply(&mut allowed);
+        assert_eq!(allowed, notes);
+
+        let mut merged = notes.clone();
+        DuplicateNoteAction::Merge.apply(&mut merged);
+        assert_eq!(
+            merged,
+            vec![
+                new_note((Note::C4, InstrumentId::from(1), 0.5)),
+                new_note((Note::E4, InstrumentId::from(1), 0.5)),
+                None,
+                new_note((Note::C4, InstrumentId::from(2), 0.2)),
+            ]
+        );
+
+        let mut loudest = notes.clone();
+        DuplicateNoteAction::KeepLoudest.apply(&mut loudest);
+        assert_eq!(
+            loudest,
+            vec![
+                new_note((Note::C4, InstrumentId::from(1), 0.8)),
+                new_note((Note::E4, InstrumentId::from(1), 0.5)),
+                None,
+                new_note((Note::C4, InstrumentId::from(2), 0.2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn sample_buffer_files() {
+        let file = SampleFile::Buffer {
+            path: "samples/loop.flac".to_string(),
+            buffer: Arc::new(vec![0.5, -0.5, 1.0, -1.0]),
+            channel_count: 2,
+            sample_rate: 48000,
+        };
+        // decoded buffers get written as WAV files
+        assert_eq!(file.file_name(), "loop.wav");
+        let path = std::env::temp_dir().join(format!(
+            "pattrns-sample-buffer-file-{}.wav",
+            std::process::id()
+        ));
+        file.write(&path).unwrap();
+        let wav_file = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(wav_file.len(), 44 + 4 * 4);
+        assert_eq!(&wav_file[0..4], b"RIFF");
+        assert_eq!(&wav_file[8..16], b"WAVEfmt ");
+        assert_eq!(u16::from_le_bytes([wav_file[22], wav_file[23]]), 2);
+        assert_eq!(
+            u32::from_le_bytes(wav_file[24..28].try_into().unwrap()),
+            48000
+        );
+        assert_eq!(&wav_file[36..40], b"data");
+        assert_eq!(
+            f32::from_le_bytes(wav_file[48..52].try_into().unwrap()),
+            -0.5
+        );
+    }
+
+    #[test]
+    fn export_used_samples() {
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/assets");
+        let pool = SamplePool::new();
+        let kick = pool.load_sample(assets.join("kick.wav")).unwrap();
+        let snare = pool.load_sample(assets.join("snare.wav")).unwrap();
+        let hihat = pool
+            .load_sample_buffer(fs::read(assets.join("hihat.wav")).unwrap(), "hihat.wav")
+            .unwrap();
+        let unknown = InstrumentId::from(usize::MAX);
+        // only referenced and known samples are used
+        let used = pool.used_samples(&[hihat, kick, unknown, kick]);
+        assert_eq!(
+            used.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![kick, hihat]
+        );
+        // only used samples are written
+        let directory = std::env::temp_dir().join(format!(
+            "pattrns-export-used-samples-{}",
+            std::process::id()
+        ));
+        let exported = pool
+            .export_used_samples(&[kick, hihat], &directory)
+            .unwrap();
+        let mut file_names = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        file_names.sort();
+        assert_eq!(file_names, vec!["hihat.wav", "kick.wav"]);
+        assert_eq!(exported.len(), 2);
+        assert_eq!(
+            fs::read(directory.join("kick.wav")).unwrap(),
+            fs::read(assets.join("kick.wav")).unwrap()
+        );
+        // removed samples are no longer exported
+        pool.remove_sample(kick);
+        assert_eq!(pool.used_samples(&[kick, snare]).len(), 1);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn tap_tempo() {
+        let mut tap_tempo = TapTempo::new();
+        let start = Instant::now();
+        assert_eq!(tap_tempo.tap(start), None);
+        // steady taps at 120 bpm with a bit of jitter
+        let mut bpm = None;
+        for (index, jitter) in [3_i64, -4, 2, -1, 5, -2].iter().enumerate() {
+            let millis = (index as i64 + 1) * 500 + jitter;
+            bpm = tap_tempo.tap(start + Duration::from_millis(millis as u64));
+        }
+        assert!(bpm.is_some_and(|bpm| (bpm - 120.0).abs() < 1.0));
+        // a long pause restarts detection
+        assert_eq!(tap_tempo.tap(start + Duration::from_secs(10)), None);
+        let bpm = tap_tempo.tap(start + Duration::from_millis(10_750));
+        assert!(bpm.is_some_and(|bpm| (bpm - 80.0).abs() < 0.1));
+    }
+
+    #[test]
+    fn new_note_action_fade_out() {
+        let default = Duration::from_millis(50);
+        assert_eq!(
+            NewNoteAction::Continue.fade_out_duration(default),
+            Some(default)
+        );
+        assert_eq!(
+            NewNoteAction::Stop.fade_out_duration(default),
+            Some(default)
+        );
+        assert_eq!(
+            NewNoteAction::Off(Some(Duration::from_millis(200))).fade_out_duration(default),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(NewNoteAction::Off(None).fade_out_duration(default), None);
+        assert_eq!(
+            NewNoteAction::default().fade_out_duration(default),
+            Some(NewNoteAction::DEFAULT_FADE_OUT_DURATION)
+        );
+    }
+
+    #[test]
+    fn pause_resume() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let mut pattern = time_base
+            .every_nth_beat(1.0)
+            .emit(crate::emitter::fixed::new_note_emitter(Note::C4));
+        let pause_handle = PauseHandle::new();
+        let mut clock = PlaybackClock::new(1000);
+        // run the pattern like `run_until` does with an output device which advances in blocks
+        let mut run = |clock: &mut PlaybackClock, output_times: std::ops::Range<SampleTime>| {
+            let mut scheduled_times = Vec::new();
+            for output_sample_time in output_times.step_by(512) {
+                if let PlaybackAction::Emit(samples_to_emit) =
+                    clock.next_action(&pause_handle, output_sample_time, &time_base, 0.25)
+                {
+                    let time = clock.emitted_sample_time + samples_to_emit;
+                    while let Some(event) = pattern.run_until_time(time) {
+                        scheduled_times.push((event.time, clock.playback_sample_time + event.time));
+                    }
+                    clock.advance(samples_to_emit);
+                }
+            }
+            scheduled_times
+        };
+        // play
+        let mut scheduled_times = run(&mut clock, 1000..50000);
+        let scheduled_before_pause = scheduled_times.len();
+        // pause from another thread: no new events get emitted
+        let handle = pause_handle.clone();
+        std::thread::spawn(move || handle.pause()).join().unwrap();
+        assert!(pause_handle.is_paused());
+        scheduled_times.extend(run(&mut clock, 50000..500000));
+        assert_eq!(scheduled_times.len(), scheduled_before_pause);
+        // resume from another thread: the event stream continues without duplicates or gaps
+        let handle = pause_handle.clone();
+        std::thread::spawn(move || handle.resume()).join().unwrap();
+        assert!(!pause_handle.is_paused());
+        scheduled_times.extend(run(&mut clock, 500000..600000));
+        let event_times = scheduled_times.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+        assert_eq!(
+            event_times,
+            (0..event_times.len() as SampleTime)
+                .map(|i| i * 22050)
+                .collect::<Vec<_>>()
+        );
+        // events scheduled before the pause keep their times
+        for (time, output_time) in &scheduled_times[..scheduled_before_pause] {
+            assert_eq!(*output_time, 1000 + time);
+        }
+        // and events after the pause got shifted by the pause duration
+        let (time, output_time) = scheduled_times[scheduled_before_pause];
+        assert!(output_time >= 500000 + 11025);
+        assert_eq!(output_time - time, clock.playback_sample_time);
+        assert!(clock.playback_sample_time > 1000);
+        // resuming a not paused transport does nothing
+        pause_handle.resume();
+        assert!(!pause_handle.take_resumed());
+    }
+
+    #[test]
+    fn count_in() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let time_signature = time_base.time_signature();
+        // no count-in
+        assert_eq!(
+            SamplePlayer::count_in_clicks(&time_base, &time_signature, 0),
+            (vec![], 0)
+        );
+        // compound meters click on dotted quarter beats
+        let six_eight = TimeSignature::new(6, 8).unwrap();
+        assert_eq!(
+            SamplePlayer::count_in_clicks(&time_base, &six_eight, 1),
+            (vec![(0, true), (33075, false)], 66150)
+        );
+        // two bars of clicks with accented downbeats
+        let (clicks, duration) = SamplePlayer::count_in_clicks(&time_base, &time_signature, 2);
+        assert_eq!(duration, 2 * 88200);
+        assert_eq!(clicks.len(), 8);
+        assert_eq!(clicks[1], (22050, false));
+        assert_eq!(clicks[4], (88200, true));
+        // run a sequence after the count-in like `run_until` does, with an output device which
+        // advances in blocks, starting before the count-in's start time
+        let start_time = 1000;
+        let mut clock = PlaybackClock::new(0);
+        clock.reset_with_count_in(start_time + duration);
+        // a pending count-in is not reset when starting to run, even when requested
+        assert!(!clock.start(true));
+        let pattern = time_base
+            .every_nth_beat(1.0)
+            .emit(crate::emitter::fixed::new_note_emitter(Note::C4));
+        let mut sequence = Sequence::new(
+            time_base,
+            vec![crate::Phrase::new(
+                time_base,
+                vec![PatternSlot::from(pattern)],
+                crate::BeatTimeStep::Bar(1.0),
+            )],
+        );
+        let mut event_times = Vec::new();
+        for output_sample_time in (0..start_time + duration + 44100).step_by(512) {
+            if let PlaybackAction::Emit(samples_to_emit) =
+                clock.next_action(&PauseHandle::new(), output_sample_time, &time_base, 0.25)
+            {
+                let time = clock.emitted_sample_time + samples_to_emit;
+                sequence.consume_events_until_time(time, &mut |_, event| {
+                    event_times.push(clock.playback_sample_time + event.time);
+                });
+                clock.advance(samples_to_emit);
+            }
+        }
+        // the clicks play before the first pattern event, which starts on the downbeat
+        let last_click_time = start_time + clicks.last().unwrap().0;
+        assert!(event_times.iter().all(|time| *time > last_click_time));
+        assert_eq!(event_times[0], start_time + duration);
+        assert_eq!(event_times[1], start_time + duration + 22050);
+        // once started, runs continue playing
+        assert!(!clock.start(false));
+        clock.reset(0);
+        assert!(clock.start(false));
+    }
+
+    #[test]
+    fn output_levels() {
+        let mut meter = OutputLevelMeter::new();
+        meter.reset(2, 0);
+        assert_eq!(meter.levels(), &[0.0, 0.0]);
+        // levels rise instantly with the loudest note
+        meter.note_on(0, 0.5);
+        meter.note_on(0, 0.8);
+        meter.note_on(1, 0.2);
+        meter.note_on(2, 1.0); // out of range: ignored
+        assert_eq!(meter.levels(), &[0.8, 0.2]);
+        // and decay with the release time
+        meter.advance(44100, 44100);
+        let factor = (-1.0 / OutputLevelMeter::RELEASE_TIME).exp() as f32;
+        assert!((meter.levels()[0] - 0.8 * factor).abs() < 1e-6);
+        assert!((meter.levels()[1] - 0.2 * factor).abs() < 1e-6);
+        // seeking backwards does not decay
+        let levels = meter.levels().to_vec();
+        meter.advance(0, 44100);
+        assert_eq!(meter.levels(), levels.as_slice());
+        // resets clear all levels
+        meter.reset(1, 0);
+        assert_eq!(meter.levels(), &[0.0]);
+    }
+
+    #[test]
+    fn envelope_follower() {
+        let mut follower = EnvelopeFollower::new(FollowerValue::new());
+        follower.channel_count = 2;
+        follower.set_sample_rate(44100);
+        // silence keeps the value at zero
+        follower.follow(&[0.0; 2 * 1024]);
+        assert_eq!(follower.value().get(), 0.0);
+        // signal on the bus raises the value
+        let signal = (0..1024)
+            .flat_map(|i| {
+                let sample = if i % 2 == 0 { 0.8 } else { -0.8 };
+                [sample, 0.0]
+            })
+            .collect::<Vec<f32>>();
+        follower.follow(&signal);
+        let value = follower.value().get();
+        assert!(value > 0.7 && value <= 0.8);
+        // and silence lets it decay again
+        follower.follow(&[0.0; 2 * 1024]);
+        let decayed_value = follower.value().get();
+        assert!(decayed_value > 0.0 && decayed_value < value);
+        // values are shared with clones
+        let shared_value = follower.value().clone();
+        assert_eq!(shared_value.get(), decayed_value);
+    }
+
+    #[test]
+    fn aux_sends() {
+        let pool = SamplePool::new();
+        let instrument = InstrumentId::from(1);
+        let (reverb_bus, delay_bus): (MixerId, MixerId) = (2, 3);
+        pool.set_send(instrument, reverb_bus, 0.5);
+        pool.set_send(instrument, delay_bus, 0.25);
+        assert_eq!(
+            pool.sends(instrument),
+            vec![(reverb_bus, 0.5), (delay_bus, 0.25)]
+        );
+        // updating sends replaces existing amounts and zero amounts remove sends
+        pool.set_send(instrument, reverb_bus, 2.0);
+        pool.set_send(instrument, delay_bus, 0.0);
+        assert_eq!(pool.sends(instrument), vec![(reverb_bus, 1.0)]);
+        assert_eq!(pool.sends(InstrumentId::from(2)), vec![]);
+        pool.clear();
+        assert_eq!(pool.sends(instrument), vec![]);
+
+        // sends mix their signal into the bus proportional to their amounts
+        let bus_buffer = AuxBusBuffer::default();
+        let mut aux_return = AuxReturn::new(bus_buffer.clone());
+        aux_return.initialize(44100, 2, 4).unwrap();
+        let amount = SendAmount::new(0.5);
+        let aux_send = AuxSend::new(bus_buffer.clone(), amount.clone());
+        let other_aux_send = AuxSend::new(bus_buffer.clone(), SendAmount::new(0.25));
+        let signal = [0.5_f32, -0.5, 1.0, -1.0];
+        aux_send.send(&signal);
+        other_aux_send.send(&signal);
+        // the bus adds the sent signals to its own signal
+        let mut bus_signal = [0.25_f32; 4];
+        aux_return.receive(&mut bus_signal);
+        assert_eq!(bus_signal, [0.625, -0.125, 1.0, -0.5]);
+        // and consumes them
+        let mut bus_signal = [0.0_f32; 4];
+        aux_return.receive(&mut bus_signal);
+        assert_eq!(bus_signal, [0.0; 4]);
+        // muted sends send nothing
+        amount.set(0.0);
+        aux_send.send(&signal);
+        aux_return.receive(&mut bus_signal);
+        assert_eq!(bus_signal, [0.0; 4]);
     }
 }
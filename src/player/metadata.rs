@@ -0,0 +1,349 @@
+//! Tempo and key metadata of sample files.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Cursor, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::Note;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Musical metadata of a sample file, such as the tempo of a loop.
+///
+/// Parsed from ACIDized WAV files (`acid` chunks) and ID3v2 tags (`TBPM` and `TKEY` frames),
+/// either at the start of the file, as in MP3 files, or within `id3 ` chunks of WAV files.
+/// All properties are `None` when a file contains no such metadata.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SampleMetadata {
+    /// Tempo of the sample in beats per minute.
+    pub bpm: Option<f32>,
+    /// Length of the sample in beats.
+    pub beats: Option<u32>,
+    /// Root note of the sample.
+    pub root_note: Option<Note>,
+    /// Musical key of the sample as specified in the file, e.g. "Am" or "F#".
+    pub key: Option<String>,
+}
+
+impl SampleMetadata {
+    /// Parse metadata from the given sample file path.
+    /// Files which can't be read or parsed result in empty metadata.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Self {
+        match File::open(path) {
+            Ok(file) => Self::from_reader(BufReader::new(file)).unwrap_or_default(),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Parse metadata from the given raw encoded sample file buffer.
+    /// Buffers which can't be parsed result in empty metadata.
+    pub fn from_buffer(buffer: &[u8]) -> Self {
+        Self::from_reader(Cursor::new(buffer)).unwrap_or_default()
+    }
+
+    /// Parse metadata from the given sample file reader.
+    ///
+    /// ### Errors
+    /// Returns an error if the file can not be read. Unknown file types and files without
+    /// metadata result in empty metadata.
+    pub fn from_reader<R: Read + Seek>(mut reader: R) -> io::Result<Self> {
+        let mut metadata = Self::default();
+        let mut header = [0; 12];
+        if reader.read_exact(&mut header).is_err() {
+            return Ok(metadata);
+        }
+        if &header[0..4] == b"RIFF" && &header[8..12] == b"WAVE" {
+            metadata.parse_riff_chunks(&mut reader)?;
+        } else if &header[0..3] == b"ID3" {
+            reader.seek(SeekFrom::Start(0))?;
+            metadata.parse_id3(&mut reader)?;
+        }
+        Ok(metadata)
+    }
+
+    /// Playback speed factor which syncs the sample's tempo to the given tempo, if the
+    /// sample has a tempo.
+    pub fn speed_for_tempo(&self, beats_per_min: f32) -> Option<f64> {
+        self.bpm
+            .filter(|bpm| *bpm > 0.0 && beats_per_min > 0.0)
+            .map(|bpm| beats_per_min as f64 / bpm as f64)
+    }
+
+    fn parse_riff_chunks<R: Read + Seek>(&mut self, reader: &mut R) -> io::Result<()> {
+        let mut chunk_header = [0; 8];
+        while reader.read_exact(&mut chunk_header).is_ok() {
+            let id = [
+                chunk_header[0],
+                chunk_header[1],
+                chunk_header[2],
+                chunk_header[3],
+            ];
+            let size = u32::from_le_bytes([
+                chunk_header[4],
+                chunk_header[5],
+                chunk_header[6],
+                chunk_header[7],
+            ]) as u64;
+            let chunk_start = reader.stream_position()?;
+            match &id {
+                b"acid" => {
+                    let mut chunk = [0; 24];
+                    if size >= chunk.len() as u64 {
+                        reader.read_exact(&mut chunk)?;
+                        self.parse_acid_chunk(&chunk);
+                    }
+                }
+                b"id3 " | b"ID3 " => self.parse_id3(reader)?,
+                _ => (),
+            }
+            // chunks are padded to an even size
+            reader.seek(SeekFrom::Start(chunk_start + size + (size & 1)))?;
+        }
+        Ok(())
+    }
+
+    fn parse_acid_chunk(&mut self, chunk: &[u8; 24]) {
+        const ONE_SHOT: u32 = 0x01;
+        const ROOT_NOTE_SET: u32 = 0x02;
+        let flags = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        let root_note = u16::from_le_bytes([chunk[4], chunk[5]]);
+        let beats = u32::from_le_bytes([chunk[12], chunk[13], chunk[14], chunk[15]]);
+        let tempo = f32::from_le_bytes([chunk[20], chunk[21], chunk[22], chunk[23]]);
+        if flags & ROOT_NOTE_SET != 0 && root_note < 128 {
+            self.root_note = Some(Note::from(root_note as u8));
+        }
+        // one-shots have no musical tempo
+        if flags & ONE_SHOT == 0 {
+            if beats > 0 {
+                self.beats = Some(beats);
+            }
+            if tempo.is_finite() && tempo > 0.0 {
+                self.bpm = Some(tempo);
+            }
+        }
+    }
+
+    fn parse_id3<R: Read>(&mut self, reader: &mut R) -> io::Result<()> {
+        let mut header = [0; 10];
+        reader.read_exact(&mut header)?;
+        let version = header[3];
+        if &header[0..3] != b"ID3" || !(3..=4).contains(&version) {
+            return Ok(());
+        }
+        let tag_size = Self::syncsafe_u32(&header[6..10]) as usize;
+        let mut tag = Vec::new();
+        reader.take(tag_size as u64).read_to_end(&mut tag)?;
+        let mut offset = 0;
+        while offset + 10 <= tag.len() {
+            let id = &tag[offset..offset + 4];
+            if id[0] == 0 {
+                break; // padding
+            }
+            let size = if version == 4 {
+                Self::syncsafe_u32(&tag[offset + 4..offset + 8])
+            } else {
+                u32::from_be_bytes([
+                    tag[offset + 4],
+                    tag[offset + 5],
+                    tag[offset + 6],
+                    tag[offset + 7],
+                ])
+            } as usize;
+            let frame_start = offset + 10;
+            let frame_end = frame_start.saturating_add(size).min(tag.len());
+            let frame = &tag[frame_start..frame_end];
+            match id {
+                b"TBPM" => {
+                    self.bpm = Self::id3_text(frame)
+                        .and_then(|text| text.trim().parse::<f32>().ok())
+                        .filter(|bpm| bpm.is_finite() && *bpm > 0.0)
+                        .or(self.bpm);
+                }
+                b"TKEY" => {
+                    self.key = Self::id3_text(frame)
+                        .map(|text| text.trim().to_string())
+                        .filter(|key| !key.is_empty())
+                        .or(self.key.take());
+                }
+                _ => (),
+            }
+            offset = frame_end;
+        }
+        Ok(())
+    }
+
+    fn syncsafe_u32(bytes: &[u8]) -> u32 {
+        bytes
+            .iter()
+            .take(4)
+            .fold(0, |value, byte| (value << 7) | (*byte & 0x7f) as u32)
+    }
+
+    fn id3_text(frame: &[u8]) -> Option<String> {
+        let (encoding, text) = frame.split_first()?;
+        let text = match encoding {
+            // ISO-8859-1
+            0 => text.iter().map(|c| *c as char).collect(),
+            // UTF-16 with BOM or UTF-16BE
+            1 | 2 => {
+                let mut text = text;
+                let mut big_endian = *encoding == 2;
+                if text.starts_with(&[0xFF, 0xFE]) {
+                    big_endian = false;
+                    text = &text[2..];
+                } else if text.starts_with(&[0xFE, 0xFF]) {
+                    big_endian = true;
+                    text = &text[2..];
+                }
+                let units = text
+                    .chunks_exact(2)
+                    .map(|c| {
+                        if big_endian {
+                            u16::from_be_bytes([c[0], c[1]])
+                        } else {
+                            u16::from_le_bytes([c[0], c[1]])
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                String::from_utf16_lossy(&units)
+            }
+            // UTF-8
+            3 => String::from_utf8_lossy(text).to_string(),
+            _ => return None,
+        };
+        Some(text.trim_end_matches('\0').to_string())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn acid_chunk(flags: u32, root_note: u16, beats: u32, tempo: f32) -> Vec<u8> {
+        let mut chunk = b"acid".to_vec();
+        chunk.extend(24_u32.to_le_bytes());
+        chunk.extend(flags.to_le_bytes());
+        chunk.extend(root_note.to_le_bytes());
+        chunk.extend(0x8000_u16.to_le_bytes());
+        chunk.extend(0.0_f32.to_le_bytes());
+        chunk.extend(beats.to_le_bytes());
+        chunk.extend(4_u16.to_le_bytes());
+        chunk.extend(4_u16.to_le_bytes());
+        chunk.extend(tempo.to_le_bytes());
+        chunk
+    }
+
+    fn id3_tag(frames: &[(&[u8; 4], &str)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (id, text) in frames {
+            body.extend(*id);
+            body.extend((text.len() as u32 + 1).to_be_bytes());
+            body.extend([0, 0, 0]);
+            body.extend(text.as_bytes());
+        }
+        let size = body.len() as u32;
+        let mut tag = b"ID3".to_vec();
+        tag.extend([3, 0, 0]);
+        tag.extend([
+            ((size >> 21) & 0x7f) as u8,
+            ((size >> 14) & 0x7f) as u8,
+            ((size >> 7) & 0x7f) as u8,
+            (size & 0x7f) as u8,
+        ]);
+        tag.extend(body);
+        tag
+    }
+
+    fn wav_file(chunks: &[Vec<u8>]) -> Vec<u8> {
+        // minimal mono 16 bit PCM file with a single sample frame
+        let mut body = b"WAVE".to_vec();
+        body.extend(b"fmt ");
+        body.extend(16_u32.to_le_bytes());
+        body.extend(1_u16.to_le_bytes());
+        body.extend(1_u16.to_le_bytes());
+        body.extend(44100_u32.to_le_bytes());
+        body.extend((44100_u32 * 2).to_le_bytes());
+        body.extend(2_u16.to_le_bytes());
+        body.extend(16_u16.to_le_bytes());
+        body.extend(b"data");
+        body.extend(2_u32.to_le_bytes());
+        body.extend([0, 0]);
+        for chunk in chunks {
+            body.extend(chunk);
+        }
+        let mut file = b"RIFF".to_vec();
+        file.extend((body.len() as u32).to_le_bytes());
+        file.extend(body);
+        file
+    }
+
+    #[test]
+    fn acidized_wav() {
+        let file = wav_file(&[acid_chunk(0x02, 57, 8, 128.0)]);
+        let metadata = SampleMetadata::from_buffer(&file);
+        assert_eq!(
+            metadata,
+            SampleMetadata {
+                bpm: Some(128.0),
+                beats: Some(8),
+                root_note: Some(Note::from(57_u8)),
+                key: None,
+            }
+        );
+        assert_eq!(metadata.speed_for_tempo(96.0), Some(0.75));
+
+        // one-shots have no tempo
+        let file = wav_file(&[acid_chunk(0x01, 0, 1, 120.0)]);
+        let metadata = SampleMetadata::from_buffer(&file);
+        assert_eq!(metadata, SampleMetadata::default());
+        assert_eq!(metadata.speed_for_tempo(120.0), None);
+    }
+
+    #[test]
+    fn id3_tags() {
+        // mp3 alike file with a leading ID3 tag
+        let mut file = id3_tag(&[(b"TIT2", "Loop"), (b"TBPM", "140"), (b"TKEY", "F#m")]);
+        file.extend([0xFF, 0xFB, 0x90, 0x00]);
+        let metadata = SampleMetadata::from_buffer(&file);
+        assert_eq!(metadata.bpm, Some(140.0));
+        assert_eq!(metadata.key.as_deref(), Some("F#m"));
+
+        // ID3 chunk in a WAV file: odd sized chunks are padded
+        let mut tag = id3_tag(&[(b"TKEY", "Am")]);
+        let mut chunk = b"id3 ".to_vec();
+        chunk.extend((tag.len() as u32).to_le_bytes());
+        if !tag.len().is_multiple_of(2) {
+            tag.push(0);
+        }
+        chunk.extend(tag);
+        let file = wav_file(&[chunk, acid_chunk(0x00, 0, 4, 90.0)]);
+        let metadata = SampleMetadata::from_buffer(&file);
+        assert_eq!(metadata.bpm, Some(90.0));
+        assert_eq!(metadata.key.as_deref(), Some("Am"));
+    }
+
+    #[test]
+    fn missing_metadata() {
+        assert_eq!(
+            SampleMetadata::from_buffer(&wav_file(&[])),
+            SampleMetadata::default()
+        );
+        assert_eq!(SampleMetadata::from_buffer(&[]), SampleMetadata::default());
+        assert_eq!(
+            SampleMetadata::from_buffer(b"RIFF\xff\xff\xff\xffWAVEacid\x18\0\0\0\x02"),
+            SampleMetadata::default()
+        );
+        assert_eq!(
+            SampleMetadata::from_file("examples/assets/kick.wav"),
+            SampleMetadata::default()
+        );
+        assert_eq!(
+            SampleMetadata::from_file("does/not/exist.wav"),
+            SampleMetadata::default()
+        );
+    }
+}
@@ -15,31 +15,44 @@ pub use super::{
         new_parameter_change_emitter, new_polyphonic_note_emitter,
         new_polyphonic_note_sequence_emitter,
     },
-    event::{new_empty_note, new_note, InstrumentId, NoteEvent, ParameterChangeEvent, ParameterId},
+    event::{
+        compose_event_transforms, new_empty_note, new_note, new_note_event_transform, InstrumentId,
+        NoteEvent, ParameterChangeEvent, ParameterId,
+    },
     gate::{probability::ProbabilityGate, threshold::ThresholdGate},
-    pattern::{beat_time::BeatTimePattern, second_time::SecondTimePattern},
-    rhythm::{euclidean, fixed::ToFixedRhythm},
-    time::{BeatTimeStep, SecondTimeStep},
+    pattern::{beat_time::BeatTimePattern, ms_time::MsTimePattern, second_time::SecondTimePattern},
+    rhythm::{euclidean, euclidean::euclidean, fixed::ToFixedRhythm},
+    time::{BeatTimeStep, MsTimeStep, SecondTimeStep},
     // all public basic types
+    Accent,
     BeatTimeBase,
     Chord,
     Cycle,
     CycleEvent,
+    CycleOperator,
+    CycleOperatorKind,
+    CycleSignal,
     CycleSpan,
+    CycleSyntaxError,
     CycleTarget,
+    CycleToken,
+    CycleTokenKind,
     CycleValue,
+    DebugOutput,
     Emitter,
     EmitterEvent,
     Event,
     EventTransform,
     Gate,
     Note,
+    NoteFilter,
     Parameter,
     ParameterSet,
     ParameterType,
     Pattern,
     PatternEvent,
     PatternSlot,
+    PatternWarning,
     Phrase,
     Pulse,
     Rhythm,
@@ -48,14 +61,19 @@ pub use super::{
     Scale,
     SecondTimeBase,
     Sequence,
+    Swing,
+    TimeSignature,
+    TransposeMode,
 };
 
 #[cfg(feature = "scripting")]
 // all public scripting types
 pub use super::{
     bindings::{
-        clear_lua_callback_errors, has_lua_callback_errors, lua_callback_errors,
-        new_pattern_from_file, new_pattern_from_string,
+        clear_lua_callback_errors, clear_lua_callback_warnings, has_lua_callback_errors,
+        has_lua_callback_warnings, lua_callback_errors, lua_callback_warnings,
+        new_pattern_from_file, new_pattern_from_string, new_pattern_from_string_sandboxed,
+        new_pattern_from_string_with_env, ErrorRange, PatternError, SandboxConfig,
     },
     emitter::{scripted::ScriptedEmitter, scripted_cycle::ScriptedCycleEmitter},
     gate::scripted::ScriptedGate,
@@ -64,4 +82,9 @@ pub use super::{
 
 #[cfg(feature = "player")]
 // all public player types
-pub use super::player::{NewNoteAction, SamplePlaybackContext, SamplePlayer, SamplePool};
+pub use super::player::{
+    DuplicateNoteAction, EnvelopeFollower, FollowerId, FollowerValue, MissingInstrumentAction,
+    NewNoteAction, PauseHandle, PooledSample, SampleFile, SampleMetadata, SampleNormalization,
+    SamplePlaybackContext, SamplePlayer, SamplePool, SampleSource, StreamedSample, TapTempo,
+    VoiceStealMode,
+};
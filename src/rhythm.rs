@@ -2,7 +2,7 @@
 
 use std::fmt::Debug;
 
-use crate::{BeatTimeBase, Event, ParameterSet, Pulse};
+use crate::{BeatTimeBase, Event, NoteEvent, ParameterSet, Pulse};
 
 pub mod empty;
 pub mod euclidean;
@@ -135,6 +135,14 @@ pub trait Rhythm: Debug {
     /// Set or update and optional parameter map for callbacks.
     fn set_parameters(&mut self, parameters: ParameterSet);
 
+    /// Set or update the pattern's current, smoothed output level for callbacks.
+    /// Ignored by default.
+    fn set_output_level(&mut self, _level: f32) {}
+
+    /// Set or update the notes which currently are playing in the pattern's slot for callbacks.
+    /// Ignored by default.
+    fn set_playing_notes(&mut self, _notes: &[NoteEvent]) {}
+
     /// Set how many times the rhythm pattern should be repeated. If 0, the rhythm will be run
     /// once. When None, which is the default, the rhythm will be repeated indefinitely.
     fn set_repeat_count(&mut self, count: Option<usize>);
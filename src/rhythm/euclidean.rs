@@ -1,6 +1,18 @@
 // -------------------------------------------------------------------------------------------------
 
 /// Generates a Euclidean rhythm with the given number of steps, pulses, and rotation offset.
+///
+/// `steps` is the number of onsets, which get distributed as evenly as possible within the
+/// given number of `pulses`. Positive offsets rotate the rhythm to the left, negative ones to
+/// the right. This is also used by the cycle's `(steps,pulses,offset)` operator and the Lua
+/// `euclidean` function.
+///
+/// ```rust
+/// use pattrns::prelude::*;
+///
+/// let rhythm = euclidean(5, 8, 0);
+/// assert_eq!(rhythm, [true, false, true, true, false, true, true, false]);
+/// ```
 pub fn euclidean(steps: u32, pulses: u32, offset: i32) -> Vec<bool> {
     type Pattern = Vec<bool>;
     type Patterns = Vec<Pattern>;
@@ -43,11 +55,9 @@ pub fn euclidean(steps: u32, pulses: u32, offset: i32) -> Vec<bool> {
             }
         }
 
-        match offset {
-            n if n > 0 => rhythm.rotate_left((n as usize) % (pulses as usize)),
-            n if n < 0 => rhythm.rotate_right((-n as usize) % (pulses as usize)),
-            _ => (),
-        }
+        // negative offsets rotate to the right, which is a left rotation in the wrapped range
+        let rotation = (offset as i64).rem_euclid(pulses as i64) as usize;
+        rhythm.rotate_left(rotation);
 
         rhythm
     }
@@ -112,5 +122,8 @@ mod test {
         // rotate and wrap
         assert_eq!(euclidean(3, 8, 5), euclidean(3, 8, 5 + 8));
         assert_eq!(euclidean(3, 8, -3), euclidean(3, 8, -3 - 8));
+        // extreme rotations
+        assert_eq!(euclidean(3, 8, i32::MIN), euclidean(3, 8, 0));
+        assert_eq!(euclidean(3, 8, i32::MAX), euclidean(3, 8, 7));
     }
 }
@@ -3,7 +3,7 @@ use mlua::prelude::LuaResult;
 use crate::{
     bindings::{pulse_from_value, LuaCallback, LuaTimeoutHook},
     rhythm::RhythmEventIterator,
-    BeatTimeBase, ParameterSet, Pulse, Rhythm, RhythmEvent,
+    BeatTimeBase, NoteEvent, ParameterSet, Pulse, Rhythm, RhythmEvent,
 };
 
 // -------------------------------------------------------------------------------------------------
@@ -164,6 +164,24 @@ impl Rhythm for ScriptedRhythm {
         }
     }
 
+    fn set_output_level(&mut self, level: f32) {
+        // reset timeout
+        self.timeout_hook.reset();
+        // update function context with the new level
+        if let Err(err) = self.callback.set_context_output_level(level) {
+            self.callback.handle_error(&err);
+        }
+    }
+
+    fn set_playing_notes(&mut self, notes: &[NoteEvent]) {
+        // reset timeout
+        self.timeout_hook.reset();
+        // update function context with the new notes
+        if let Err(err) = self.callback.set_context_playing_notes(notes) {
+            self.callback.handle_error(&err);
+        }
+    }
+
     fn set_repeat_count(&mut self, count: Option<usize>) {
         self.repeat_count_option = count;
     }
@@ -187,6 +205,10 @@ impl Rhythm for ScriptedRhythm {
         {
             self.callback.handle_error(&err);
         }
+        // restore random number generator
+        if let Err(err) = self.callback.reset_context_rng() {
+            self.callback.handle_error(&err);
+        }
         // reset function
         if let Err(err) = self.callback.reset() {
             self.callback.handle_error(&err);
@@ -1,34 +1,73 @@
 //! Arrange `Phrase`s into a playback sequence.
 
-use crate::{phrase::PatternIndex, BeatTimeBase, Pattern, PatternEvent, Phrase, SampleTime};
+use std::fmt::Debug;
+
+use crate::{
+    phrase::{PatternIndex, PhraseEvent},
+    BeatTimeBase, BeatTimeStep, EventTransform, ExactSampleTime, Pattern, PatternEvent, Phrase,
+    SampleTime, SampleTimeRounding, Swing,
+};
 
 // -------------------------------------------------------------------------------------------------
 
 /// Sequentially arrange [`Phrase`]s to form simple arrangements.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct Sequence {
     time_base: BeatTimeBase,
     phrases: Vec<Phrase>,
     phrase_index: usize,
-    sample_position_in_phrase: SampleTime,
+    phrase_start_time: ExactSampleTime,
+    pending_note_offs: Vec<PhraseEvent>,
     sample_position: SampleTime,
     sample_offset: SampleTime,
+    sample_rounding: SampleTimeRounding,
+    global_swing: Swing,
+    global_swing_step: BeatTimeStep,
+    event_transform: Option<EventTransform>,
+}
+
+impl Debug for Sequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sequence")
+            .field("time_base", &self.time_base)
+            .field("phrases", &self.phrases)
+            .field("phrase_index", &self.phrase_index)
+            .field("phrase_start_time", &self.phrase_start_time)
+            .field("pending_note_offs", &self.pending_note_offs)
+            .field("sample_position", &self.sample_position)
+            .field("sample_offset", &self.sample_offset)
+            .field("sample_rounding", &self.sample_rounding)
+            .field("global_swing", &self.global_swing)
+            .field("global_swing_step", &self.global_swing_step)
+            // Skip event_transform, which has no Debug impl
+            .finish()
+    }
 }
 
 impl Sequence {
     /// Create a new sequence from a vector of [`Phrase`]s.
     pub fn new(time_base: BeatTimeBase, phrases: Vec<Phrase>) -> Self {
         let phrase_index = 0;
-        let sample_position_in_phrase = 0;
+        let phrase_start_time = 0.0;
+        let pending_note_offs = Vec::new();
         let sample_position = 0;
         let sample_offset = 0;
+        let sample_rounding = SampleTimeRounding::default();
+        let global_swing = Swing::default();
+        let global_swing_step = BeatTimeStep::Sixteenth(1.0);
+        let event_transform = None;
         Self {
             time_base,
             phrases,
             phrase_index,
-            sample_position_in_phrase,
+            phrase_start_time,
+            pending_note_offs,
             sample_position,
             sample_offset,
+            sample_rounding,
+            global_swing,
+            global_swing_step,
+            event_transform,
         }
     }
 
@@ -37,14 +76,72 @@ impl Sequence {
         &self.time_base
     }
 
-    /// Update the sequence's internal time bases with a new time base.
+    /// Update the sequence's internal time bases with a new time base. The current playback
+    /// position keeps its musical phase, so tempo or sample rate changes don't cause jumps.
     pub fn set_time_base(&mut self, time_base: &BeatTimeBase) {
+        // rescale the elapsed part of the current phrase to the new time base
+        let old_beat_samples = self.time_base.samples_per_beat();
+        let new_beat_samples = time_base.samples_per_beat();
+        if old_beat_samples > 0.0 {
+            let current_time = self.sample_position as ExactSampleTime;
+            self.phrase_start_time = current_time
+                - (current_time - self.phrase_start_time) * new_beat_samples / old_beat_samples;
+        }
         self.time_base = *time_base;
         for phrase in &mut self.phrases {
             phrase.set_time_base(time_base);
         }
     }
 
+    /// Rounding mode which is used to convert exact phrase and event times to sample times.
+    pub fn sample_rounding(&self) -> SampleTimeRounding {
+        self.sample_rounding
+    }
+
+    /// Set a new rounding mode for phrase and event times in all phrases. Phrase start times
+    /// are tracked exactly, so rounding errors don't accumulate over long playback runs.
+    pub fn set_sample_rounding(&mut self, rounding: SampleTimeRounding) {
+        self.sample_rounding = rounding;
+        for phrase in &mut self.phrases {
+            phrase.set_sample_rounding(rounding);
+        }
+    }
+
+    /// Swing amount which gets applied to all events of all phrases, in range \[0 - 1\].
+    pub fn global_swing(&self) -> f32 {
+        self.global_swing.amount()
+    }
+
+    /// Set a new global swing amount, which delays all note events that start on every second
+    /// step of the global swing grid. See [`Swing`] for details about the amount.
+    ///
+    /// Global swing gets combined with the swing of patterns: the global swing delay is added
+    /// on top of the pattern's own swing delays, so patterns can swing on their own or play
+    /// straight with the global groove.
+    pub fn set_global_swing(&mut self, amount: f32) {
+        self.global_swing = Swing::new(amount);
+    }
+
+    /// Step length of the global swing grid. By default sixteenth notes.
+    pub fn global_swing_step(&self) -> BeatTimeStep {
+        self.global_swing_step
+    }
+
+    /// Set a new global swing grid step length.
+    pub fn set_global_swing_step(&mut self, step: BeatTimeStep) {
+        self.global_swing_step = step;
+    }
+
+    /// Set an optional global event transform function, which gets invoked for all events of
+    /// all phrases and patterns, e.g. to transpose the whole arrangement.
+    ///
+    /// The global transform is applied *after* the pattern's own event transforms, so it sees
+    /// and may override the values pattern transforms produced. Like pattern transforms, it can
+    /// not change event times but only event values.
+    pub fn set_event_transform(&mut self, transform: Option<EventTransform>) {
+        self.event_transform = transform;
+    }
+
     /// Read-only access to the currently played back phrase.
     pub fn current_phrase(&self) -> &Phrase {
         &self.phrases[self.phrase_index]
@@ -65,6 +162,52 @@ impl Sequence {
         &mut self.phrases
     }
 
+    /// Insert a new phrase at the given index, shifting all phrases after it, e.g. to add
+    /// phrases to a playing sequence. The currently playing phrase keeps playing. The new
+    /// phrase starts playing when the sequence reaches it, and its time base and sample
+    /// rounding are set to the sequence's ones.
+    ///
+    /// # Panics
+    /// Panics if `index > phrases().len()`.
+    pub fn insert_phrase(&mut self, index: usize, mut phrase: Phrase) {
+        phrase.set_time_base(&self.time_base);
+        phrase.set_sample_rounding(self.sample_rounding);
+        self.phrases.insert(index, phrase);
+        if index <= self.phrase_index && self.phrases.len() > 1 {
+            self.phrase_index += 1;
+        }
+    }
+
+    /// Remove the phrase at the given index, e.g. to remove phrases from a playing sequence.
+    /// Returns None when the index is out of bounds or when the phrase is the sequence's
+    /// only phrase, which can't be removed.
+    ///
+    /// When removing the currently playing phrase, the next phrase starts playing right away
+    /// at the current playback position. Notes of the removed phrase get stopped with note-offs
+    /// when the phrase has [`Phrase::set_note_off_at_end`] enabled. Otherwise they keep playing
+    /// until the following phrase's patterns play new notes or note-offs in the same pattern
+    /// slots, so players can still look them up and stop them via their pattern slot index.
+    pub fn remove_phrase(&mut self, index: usize) -> Option<Phrase> {
+        if index >= self.phrases.len() || self.phrases.len() == 1 {
+            return None;
+        }
+        let mut removed_phrase = self.phrases.remove(index);
+        if index < self.phrase_index {
+            self.phrase_index -= 1;
+        } else if index == self.phrase_index {
+            // stop notes of the removed phrase, when enabled
+            let note_offs = removed_phrase.take_note_off_events(self.sample_position);
+            self.pending_note_offs.extend(note_offs);
+            // continue with the next phrase at the current position
+            self.phrase_index %= self.phrases.len();
+            self.phrase_start_time = self.sample_position as ExactSampleTime;
+            let sample_offset = self.phrase_start_time;
+            self.current_phrase_mut()
+                .reset_with_offset(sample_offset, &removed_phrase);
+        }
+        Some(removed_phrase)
+    }
+
     /// returns maximum pattern count in all phrases.
     pub fn phrase_pattern_slot_count(&self) -> usize {
         let mut count = 0;
@@ -81,21 +224,55 @@ impl Sequence {
         F: FnMut(PatternIndex, PatternEvent),
     {
         debug_assert!(time >= self.sample_position, "can not rewind playback here");
+        let swing = (!self.global_swing.is_straight()).then(|| {
+            let step_samples = self.global_swing_step.to_samples(&self.time_base);
+            (self.global_swing.clone(), step_samples)
+        });
+        let transform = self.event_transform.clone();
+        if swing.is_some() || transform.is_some() {
+            // apply global swing and transforms to all emitted events
+            self.consume_events_until_time_impl(time, &mut |pattern_index, mut pattern_event| {
+                if let Some((swing, step_samples)) = &swing {
+                    Self::apply_global_swing(swing, *step_samples, &mut pattern_event);
+                }
+                if let (Some(transform), Some(event)) = (&transform, &mut pattern_event.event) {
+                    transform(event);
+                }
+                consumer(pattern_index, pattern_event);
+            });
+        } else {
+            self.consume_events_until_time_impl(time, consumer);
+        }
+    }
+
+    fn consume_events_until_time_impl<F>(&mut self, time: SampleTime, consumer: &mut F)
+    where
+        F: FnMut(PatternIndex, PatternEvent),
+    {
         while time - self.sample_position > 0 {
+            // emit note-offs from the previous phrase end, which now are due
+            for (pattern_index, pattern_event) in self.pending_note_offs.drain(..) {
+                consumer(pattern_index, pattern_event);
+            }
             let (next_phrase_start, samples_to_run) = self.samples_until_next_phrase(time);
             if next_phrase_start <= samples_to_run {
                 // run current phrase until it ends
                 let sample_position = self.sample_position;
                 self.current_phrase_mut()
                     .consume_events_until_time(sample_position + next_phrase_start, consumer);
+                // stop notes at the phrase end, when enabled. NB: the note-offs are due at
+                // the phrase end time, which may not be part of the requested time range yet.
+                self.pending_note_offs = self
+                    .current_phrase_mut()
+                    .take_note_off_events(sample_position + next_phrase_start);
                 // select next phrase in the sequence
                 let previous_phrase = self.current_phrase_mut().clone();
                 self.phrase_index = (self.phrase_index + 1) % self.phrases().len();
-                self.sample_position_in_phrase = 0;
                 self.sample_position += next_phrase_start;
+                self.phrase_start_time = self.next_phrase_start_time(&previous_phrase);
                 // reset the new phrase or apply continues modes
                 if self.phrases().len() > 1 {
-                    let sample_offset = self.sample_position;
+                    let sample_offset = self.phrase_start_time;
                     self.current_phrase_mut()
                         .reset_with_offset(sample_offset, &previous_phrase);
                 }
@@ -104,7 +281,6 @@ impl Sequence {
                 let sample_position = self.sample_position;
                 self.current_phrase_mut()
                     .consume_events_until_time(sample_position + samples_to_run, consumer);
-                self.sample_position_in_phrase += samples_to_run;
                 self.sample_position += samples_to_run;
             }
         }
@@ -116,6 +292,7 @@ impl Sequence {
             sample_time >= self.sample_position,
             "can not rewind playback here"
         );
+        self.pending_note_offs.clear();
         while sample_time - self.sample_position > 0 {
             let (next_phrase_start, samples_to_run) = self.samples_until_next_phrase(sample_time);
             if next_phrase_start <= samples_to_run {
@@ -126,11 +303,11 @@ impl Sequence {
                 // select next phrase in the sequence
                 let previous_phrase = self.current_phrase_mut().clone();
                 self.phrase_index = (self.phrase_index + 1) % self.phrases().len();
-                self.sample_position_in_phrase = 0;
                 self.sample_position += next_phrase_start;
+                self.phrase_start_time = self.next_phrase_start_time(&previous_phrase);
                 // reset the new phrase or apply continues modes
                 if self.phrases().len() > 1 {
-                    let sample_offset = self.sample_position;
+                    let sample_offset = self.phrase_start_time;
                     self.current_phrase_mut()
                         .reset_with_offset(sample_offset, &previous_phrase);
                 }
@@ -139,30 +316,507 @@ impl Sequence {
                 let sample_position = self.sample_position;
                 self.current_phrase_mut()
                     .advance_until_time(sample_position + samples_to_run);
-                self.sample_position_in_phrase += samples_to_run;
                 self.sample_position += samples_to_run;
             }
         }
     }
 
+    /// Render all events of a freshly reset sequence from the given start until the given end
+    /// sample time, e.g. for sample-accurate offline renders. Unlike realtime players, which run
+    /// ahead of the playback position by some preload time, this includes all events which are
+    /// due at or after `start_time` and before `end_time`, so an event at `start_time` is never
+    /// missed. Returns the events with their pattern slot indices and absolute sample times.
+    pub fn render_from(
+        &mut self,
+        start_time: SampleTime,
+        end_time: SampleTime,
+    ) -> Vec<(PatternIndex, PatternEvent)> {
+        self.reset();
+        self.advance_until_time(start_time);
+        let mut events = Vec::new();
+        if end_time > start_time {
+            self.consume_events_until_time(end_time, &mut |pattern_index, pattern_event| {
+                events.push((pattern_index, pattern_event));
+            });
+        }
+        events
+    }
+
     /// Reset phrases to their initial state.
     pub fn reset(&mut self) {
         // reset sample offset
         self.sample_offset = 0;
         // reset our own iter state
         self.sample_position = 0;
-        self.sample_position_in_phrase = 0;
+        self.phrase_start_time = 0.0;
+        self.pending_note_offs.clear();
         // reset all our phrase iters
         for phrase in &mut self.phrases {
             phrase.reset();
         }
     }
 
+    /// Restart the sequence from its first phrase at the current playback position, e.g. for
+    /// seamless restarts of live loops.
+    ///
+    /// Unlike [`Self::reset`], which rewinds the sequence to sample time 0, the sequence keeps
+    /// its timeline, just like when it loops from its last to the first phrase, but without
+    /// emitting note-offs for the current phrase. Notes which are still ringing thus keep
+    /// playing until the restarted patterns play new notes or note-offs in the same pattern
+    /// slots.
+    pub fn reset_preserving_sound(&mut self) {
+        let previous_phrase = self.current_phrase().clone();
+        self.phrase_index = 0;
+        self.phrase_start_time = self.sample_position as ExactSampleTime;
+        let sample_offset = self.phrase_start_time;
+        self.current_phrase_mut()
+            .reset_with_offset(sample_offset, &previous_phrase);
+    }
+
+    fn apply_global_swing(swing: &Swing, step_samples: f64, pattern_event: &mut PatternEvent) {
+        if step_samples <= 0.0 || pattern_event.duration == 0 {
+            return;
+        }
+        if let Some(event) = &mut pattern_event.event {
+            // snap event times which got rounded to sample times to the swing grid
+            let step_position = pattern_event.time as f64 / step_samples;
+            let step = step_position.round();
+            if (step * step_samples - pattern_event.time as f64).abs() <= 1.0 {
+                let step_length = step_samples / pattern_event.duration as f64;
+                swing.apply(event, step, step_length);
+            }
+        }
+    }
+
+    fn phrase_end_time(&self, phrase: &Phrase) -> ExactSampleTime {
+        self.phrase_start_time + phrase.length().to_samples(&self.time_base)
+    }
+
+    fn next_phrase_start_time(&self, previous_phrase: &Phrase) -> ExactSampleTime {
+        // continue with the exact end time of the previous phrase to avoid that rounding
+        // errors accumulate, unless the end got skipped by tempo changes
+        let phrase_end_time = self.phrase_end_time(previous_phrase);
+        if self.sample_rounding.to_sample_time(phrase_end_time) < self.sample_position {
+            self.sample_position as ExactSampleTime
+        } else {
+            phrase_end_time
+        }
+    }
+
     fn samples_until_next_phrase(&self, time: u64) -> (u64, u64) {
-        let phrase_length_in_samples =
-            self.current_phrase().length().to_samples(&self.time_base) as SampleTime;
-        let next_phrase_start = phrase_length_in_samples - self.sample_position_in_phrase;
+        let phrase_end = self
+            .sample_rounding
+            .to_sample_time(self.phrase_end_time(self.current_phrase()));
+        let next_phrase_start = phrase_end.saturating_sub(self.sample_position);
         let samples_to_run = time - self.sample_position;
         (next_phrase_start, samples_to_run)
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{prelude::*, PatternSlot};
+
+    fn new_sequence(time_base: BeatTimeBase) -> Sequence {
+        let new_phrase = || {
+            let pattern = time_base
+                .every_nth_beat(1.0)
+                .emit(new_note_emitter(Note::C4));
+            Phrase::new(
+                time_base,
+                vec![PatternSlot::from(pattern)],
+                BeatTimeStep::Beats(1.0),
+            )
+        };
+        Sequence::new(time_base, vec![new_phrase(), new_phrase()])
+    }
+
+    #[test]
+    fn phrase_times() {
+        // a beat can't be represented exactly in samples
+        let time_base = BeatTimeBase {
+            beats_per_min: 133.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let beat_samples = time_base.samples_per_beat();
+        for (rounding, max_error) in [
+            (SampleTimeRounding::Floor, 1.0),
+            (SampleTimeRounding::Nearest, 0.5),
+        ] {
+            let mut sequence = new_sequence(time_base);
+            sequence.set_sample_rounding(rounding);
+            // phrase switches must not drift
+            let mut event_times = Vec::new();
+            let end_time = (10000.0 * beat_samples) as SampleTime;
+            sequence.consume_events_until_time(end_time, &mut |_, event| {
+                event_times.push(event.time);
+            });
+            assert_eq!(event_times.len(), 10000);
+            for (index, time) in event_times.into_iter().enumerate() {
+                let exact_time = index as f64 * beat_samples;
+                assert!(
+                    (time as f64 - exact_time).abs() <= max_error,
+                    "event {index} at {time} drifted from {exact_time}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn global_swing() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let new_phrase = |note: Note| {
+            let new_pattern = || {
+                time_base
+                    .every_nth_sixteenth(1.0)
+                    .emit(new_note_emitter(note))
+            };
+            Phrase::new(
+                time_base,
+                vec![
+                    PatternSlot::from(new_pattern()),
+                    PatternSlot::from(new_pattern()),
+                ],
+                BeatTimeStep::Beats(1.0),
+            )
+        };
+        let mut sequence =
+            Sequence::new(time_base, vec![new_phrase(Note::C4), new_phrase(Note::D4)]);
+        sequence.set_global_swing(0.5);
+        assert_eq!(sequence.global_swing(), 0.5);
+
+        let mut note_times = Vec::new();
+        sequence.consume_events_until_time(2 * 22050, &mut |index, event| {
+            if let Some(Event::NoteEvents(notes)) = &event.event {
+                let note = notes[0].as_ref().unwrap();
+                note_times.push((index, note.note, event.note_event_time(note)));
+            }
+        });
+        // odd sixteenth steps are delayed by half a step in all slots and phrases
+        let mut expected = Vec::new();
+        for (phrase, note) in [Note::C4, Note::D4].into_iter().enumerate() {
+            for step in 0..4 {
+                let time = phrase as u64 * 22050 + step as u64 * 5512 + (step as u64 / 2);
+                let delay = if step % 2 == 1 { 2756 } else { 0 };
+                for index in 0..2 {
+                    expected.push((index, note, time + delay));
+                }
+            }
+        }
+        assert_eq!(note_times, expected);
+    }
+
+    #[test]
+    fn event_transform() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let new_phrase = || {
+            let c4 = time_base
+                .every_nth_beat(1.0)
+                .emit(new_note_emitter(Note::C4));
+            // pattern transforms are applied before the sequence transform
+            let e4 = time_base
+                .every_nth_beat(1.0)
+                .emit(new_note_emitter(Note::C4))
+                .with_event_transform(Rc::new(|event: &mut Event| {
+                    if let Event::NoteEvents(notes) = event {
+                        for note in notes.iter_mut().flatten() {
+                            note.note = note.note.transposed(4);
+                        }
+                    }
+                }));
+            Phrase::new(
+                time_base,
+                vec![PatternSlot::from(c4), PatternSlot::from(e4)],
+                BeatTimeStep::Beats(1.0),
+            )
+        };
+        let mut sequence = Sequence::new(time_base, vec![new_phrase(), new_phrase()]);
+        sequence.set_event_transform(Some(Rc::new(|event: &mut Event| {
+            if let Event::NoteEvents(notes) = event {
+                for note in notes.iter_mut().flatten() {
+                    note.note = note.note.transposed(12);
+                }
+            }
+        })));
+        let mut notes = Vec::new();
+        sequence.consume_events_until_time(2 * 22050, &mut |index, event| {
+            if let Some(Event::NoteEvents(events)) = event.event {
+                notes.push((index, events[0].as_ref().unwrap().note));
+            }
+        });
+        assert_eq!(
+            notes,
+            vec![(0, Note::C5), (1, Note::E5), (0, Note::C5), (1, Note::E5)]
+        );
+        // removing the transform restores the original notes
+        sequence.set_event_transform(None);
+        let mut notes = Vec::new();
+        sequence.consume_events_until_time(3 * 22050, &mut |index, event| {
+            if let Some(Event::NoteEvents(events)) = event.event {
+                notes.push((index, events[0].as_ref().unwrap().note));
+            }
+        });
+        assert_eq!(notes, vec![(0, Note::C4), (1, Note::E4)]);
+    }
+
+    #[test]
+    fn sample_rate_changes() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let mut sequence = new_sequence(time_base);
+        let mut event_times = Vec::new();
+        sequence.consume_events_until_time(30000, &mut |_, event| event_times.push(event.time));
+        assert_eq!(event_times, [0, 22050]);
+        // switch to 48kHz in the middle of the second phrase
+        sequence.set_time_base(&BeatTimeBase {
+            samples_per_sec: 48000,
+            ..time_base
+        });
+        let mut event_times = Vec::new();
+        sequence.consume_events_until_time(100000, &mut |_, event| event_times.push(event.time));
+        // the next phrase starts at the same musical position
+        let next_time = 30000.0 + (44100.0 - 30000.0) * 48000.0 / 44100.0;
+        assert_eq!(event_times.len(), 3);
+        for (index, time) in event_times.into_iter().enumerate() {
+            let expected_time = next_time + index as f64 * 24000.0;
+            assert!(
+                (time as f64 - expected_time).abs() <= 1.0,
+                "event {index} at {time} should be at {expected_time}"
+            );
+        }
+    }
+
+    #[test]
+    fn insert_remove_phrases() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // beat long phrases, playing a single note
+        let new_phrase = |note: Note, note_off_at_end: bool| {
+            let pattern = time_base.every_nth_bar(1.0).emit(new_note_emitter(note));
+            let mut phrase = Phrase::new(
+                time_base,
+                vec![PatternSlot::from(pattern)],
+                BeatTimeStep::Beats(1.0),
+            );
+            phrase.set_note_off_at_end(note_off_at_end);
+            phrase
+        };
+        let run_sequence = |sequence: &mut Sequence, time: SampleTime| {
+            let mut events = Vec::new();
+            sequence.consume_events_until_time(time, &mut |_, event| {
+                if let Some(Event::NoteEvents(notes)) = event.event {
+                    events.push((event.time, notes[0].as_ref().unwrap().note));
+                }
+            });
+            events
+        };
+
+        let mut sequence = Sequence::new(
+            time_base,
+            vec![new_phrase(Note::C4, false), new_phrase(Note::D4, false)],
+        );
+        assert_eq!(run_sequence(&mut sequence, 11025), vec![(0, Note::C4)]);
+        // inserting phrases before the playing phrase does not affect the playing phrase
+        sequence.insert_phrase(0, new_phrase(Note::E4, false));
+        sequence.insert_phrase(2, new_phrase(Note::F4, false));
+        assert_eq!(sequence.phrases().len(), 4);
+        assert_eq!(
+            run_sequence(&mut sequence, 22050 * 4 + 1),
+            vec![
+                (22050, Note::F4),
+                (44100, Note::D4),
+                (66150, Note::E4),
+                (88200, Note::C4)
+            ]
+        );
+        // removing phrases after the playing phrase
+        assert!(sequence.remove_phrase(2).is_some());
+        assert_eq!(
+            run_sequence(&mut sequence, 22050 * 6 + 1),
+            vec![(110250, Note::D4), (132300, Note::E4)]
+        );
+        // removing the playing phrase starts the next phrase right away
+        assert!(run_sequence(&mut sequence, 22050 * 6 + 11025).is_empty());
+        assert!(sequence.remove_phrase(0).is_some());
+        assert_eq!(
+            run_sequence(&mut sequence, 22050 * 7 + 11025 + 1),
+            vec![(143325, Note::C4), (165375, Note::D4)]
+        );
+        // invalid indices and the last phrase can't be removed
+        assert!(sequence.remove_phrase(2).is_none());
+        assert!(sequence.remove_phrase(1).is_some());
+        assert!(sequence.remove_phrase(0).is_none());
+        assert_eq!(sequence.phrases().len(), 1);
+
+        // removed phrases stop their notes, when enabled
+        let mut sequence = Sequence::new(
+            time_base,
+            vec![new_phrase(Note::C4, true), new_phrase(Note::D4, true)],
+        );
+        assert_eq!(run_sequence(&mut sequence, 11025), vec![(0, Note::C4)]);
+        assert!(sequence.remove_phrase(0).is_some());
+        assert_eq!(
+            run_sequence(&mut sequence, 11025 + 1),
+            vec![(11025, Note::OFF), (11025, Note::D4)]
+        );
+    }
+
+    #[test]
+    fn reset_preserving_sound() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // bar long phrases, playing a single note which gets stopped at the phrase end
+        let new_phrase = |note: Note| {
+            let pattern = time_base.every_nth_bar(1.0).emit(new_note_emitter(note));
+            let mut phrase = Phrase::new(
+                time_base,
+                vec![PatternSlot::from(pattern)],
+                BeatTimeStep::Bar(1.0),
+            );
+            phrase.set_note_off_at_end(true);
+            phrase
+        };
+        let run_sequence = |sequence: &mut Sequence, time: SampleTime| {
+            let mut events = Vec::new();
+            sequence.consume_events_until_time(time, &mut |_, event| {
+                if let Some(Event::NoteEvents(notes)) = event.event {
+                    events.push((event.time, notes[0].as_ref().unwrap().note));
+                }
+            });
+            events
+        };
+
+        let mut sequence =
+            Sequence::new(time_base, vec![new_phrase(Note::C4), new_phrase(Note::D4)]);
+        assert_eq!(run_sequence(&mut sequence, 22050), vec![(0, Note::C4)]);
+        // restarts at the current position without stopping the playing note
+        sequence.reset_preserving_sound();
+        assert_eq!(
+            run_sequence(&mut sequence, 22050 + 1),
+            vec![(22050, Note::C4)]
+        );
+        // the restarted phrase plays a whole bar
+        assert_eq!(
+            run_sequence(&mut sequence, 22050 + 88200 + 1),
+            vec![(110250, Note::OFF), (110250, Note::D4)]
+        );
+
+        // restarting while playing another phrase
+        sequence.reset_preserving_sound();
+        assert_eq!(
+            run_sequence(&mut sequence, 110251 + 88200 + 1),
+            vec![(110251, Note::C4), (198451, Note::OFF), (198451, Note::D4)]
+        );
+
+        // patterns restart their step counter at the given time
+        let mut pattern = time_base
+            .every_nth_beat(1.0)
+            .emit(new_note_emitter(Note::C4));
+        assert_eq!(pattern.run_until_time(11025).map(|e| e.time), Some(0));
+        pattern.reset_preserving_sound(11025);
+        assert_eq!(pattern.run_until_time(11025), None);
+        assert_eq!(pattern.run_until_time(11026).map(|e| e.time), Some(11025));
+        assert_eq!(pattern.run_until_time(33076).map(|e| e.time), Some(33075));
+    }
+
+    #[test]
+    fn render_from() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let mut sequence = new_sequence(time_base);
+        let event_times = |events: Vec<(PatternIndex, PatternEvent)>| {
+            events
+                .into_iter()
+                .map(|(_, event)| event.time)
+                .collect::<Vec<_>>()
+        };
+        // the first event at time 0 is included
+        assert_eq!(event_times(sequence.render_from(0, 1)), vec![0]);
+        assert_eq!(
+            event_times(sequence.render_from(0, 66150)),
+            vec![0, 22050, 44100]
+        );
+        // events at the start time are included, events at the end time are not
+        assert_eq!(
+            event_times(sequence.render_from(22050, 66150)),
+            vec![22050, 44100]
+        );
+        assert_eq!(event_times(sequence.render_from(22051, 66150)), vec![44100]);
+        assert!(sequence.render_from(44100, 44100).is_empty());
+    }
+
+    #[test]
+    fn note_off_at_end() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // a bar long note in a beat long, looping phrase
+        let new_sequence = |note_off_at_end: bool| {
+            let pattern = time_base
+                .every_nth_bar(1.0)
+                .emit(new_note_emitter(Note::C4));
+            let mut phrase = Phrase::new(
+                time_base,
+                vec![PatternSlot::from(pattern)],
+                BeatTimeStep::Beats(1.0),
+            );
+            phrase.set_note_off_at_end(note_off_at_end);
+            Sequence::new(time_base, vec![phrase])
+        };
+        let run_sequence = |sequence: &mut Sequence, time: SampleTime| {
+            let mut events = Vec::new();
+            sequence.consume_events_until_time(time, &mut |_, event| {
+                if let Some(Event::NoteEvents(notes)) = event.event {
+                    events.push((event.time, notes[0].as_ref().unwrap().note));
+                }
+            });
+            events
+        };
+        // by default, notes keep playing
+        let mut sequence = new_sequence(false);
+        assert_eq!(
+            run_sequence(&mut sequence, 88200 + 1),
+            vec![(0, Note::C4), (88200, Note::C4)]
+        );
+        // notes get cut at the phrase end
+        let mut sequence = new_sequence(true);
+        assert_eq!(run_sequence(&mut sequence, 22050), vec![(0, Note::C4)]);
+        assert_eq!(
+            run_sequence(&mut sequence, 88200 + 1),
+            vec![(22050, Note::OFF), (88200, Note::C4)]
+        );
+        // the new note gets cut again
+        assert_eq!(
+            run_sequence(&mut sequence, 88200 + 22050 + 1),
+            vec![(88200 + 22050, Note::OFF)]
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Swing timing for `Pattern` events.
+
+use crate::{Event, InstrumentId};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Delays note events which start on every second step of a [`Pattern`](crate::Pattern) to
+/// create a swing feel.
+///
+/// The swing amount is a fraction of the pattern's step length: 0.0 plays straight, 0.33 delays
+/// offbeat notes by a third of a step (triplet feel) and 1.0 delays them by a whole step.
+///
+/// Amounts can be set per instrument, so e.g. hihats can swing while the kick drum plays straight.
+/// Notes with instruments that have no custom amount set use the default amount.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Swing {
+    amount: f32,
+    instrument_amounts: Vec<(InstrumentId, f32)>,
+}
+
+impl Swing {
+    /// Create a new swing with the given default amount in range \[0 - 1\].
+    pub fn new(amount: f32) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+        let instrument_amounts = Vec::new();
+        Self {
+            amount,
+            instrument_amounts,
+        }
+    }
+
+    /// Return a new swing which uses the given amount for notes of the given instrument.
+    #[must_use]
+    pub fn with_instrument_amount(self, instrument: InstrumentId, amount: f32) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+        let mut instrument_amounts = self.instrument_amounts;
+        instrument_amounts.retain(|(id, _)| *id != instrument);
+        instrument_amounts.push((instrument, amount));
+        Self {
+            instrument_amounts,
+            ..self
+        }
+    }
+
+    /// Default swing amount, used for notes without custom instrument amounts.
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    /// Swing amount for notes of the given instrument.
+    pub fn instrument_amount(&self, instrument: Option<InstrumentId>) -> f32 {
+        instrument
+            .and_then(|instrument| {
+                self.instrument_amounts
+                    .iter()
+                    .find(|(id, _)| *id == instrument)
+                    .map(|(_, amount)| *amount)
+            })
+            .unwrap_or(self.amount)
+    }
+
+    /// Returns true when no note gets delayed at all.
+    pub fn is_straight(&self) -> bool {
+        self.amount == 0.0 && self.instrument_amounts.iter().all(|(_, a)| *a == 0.0)
+    }
+
+    /// Apply swing to the given event, which starts at the given position in pattern steps.
+    /// `step_length` is the pattern's step length relative to the event's duration, which
+    /// is used to convert swing amounts into note delays.
+    pub(crate) fn apply(&self, event: &mut Event, step_position: f64, step_length: f64) {
+        const EPSILON: f64 = 1e-6;
+        // only events which start on an offbeat step get delayed
+        let step = step_position.round();
+        if (step_position - step).abs() > EPSILON || (step as u64).is_multiple_of(2) {
+            return;
+        }
+        if let Event::NoteEvents(note_events) = event {
+            for note_event in note_events.iter_mut().flatten() {
+                let amount = self.instrument_amount(note_event.instrument) as f64;
+                if amount > 0.0 {
+                    let delay = note_event.delay as f64 + amount * step_length;
+                    note_event.delay = delay.clamp(0.0, 1.0) as f32;
+                }
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        event::{new_note, NoteEvent},
+        prelude::*,
+    };
+
+    #[test]
+    fn instrument_amounts() {
+        let kick = InstrumentId::from(1);
+        let hihat = InstrumentId::from(2);
+        let swing = Swing::new(0.25).with_instrument_amount(kick, 0.0);
+        assert_eq!(swing.instrument_amount(Some(kick)), 0.0);
+        assert_eq!(swing.instrument_amount(Some(hihat)), 0.25);
+        assert_eq!(swing.instrument_amount(None), 0.25);
+        assert!(!swing.is_straight());
+        assert!(Swing::new(0.0).is_straight());
+    }
+
+    #[test]
+    fn pattern_swing() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let kick = InstrumentId::from(1);
+        let hihat = InstrumentId::from(2);
+        let mut pattern = time_base
+            .every_nth_eighth(1.0)
+            .with_swing(Swing::new(0.0).with_instrument_amount(hihat, 0.5))
+            .emit(vec![new_note((Note::C4, kick)), new_note((Note::C4, hihat))].to_emitter());
+
+        let note_event_times = |event: &PatternEvent| match &event.event {
+            Some(Event::NoteEvents(notes)) => notes
+                .iter()
+                .flatten()
+                .map(|note: &NoteEvent| event.note_event_time(note))
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        };
+
+        // onbeats are straight
+        let event = pattern.next().unwrap();
+        assert_eq!(event.time, 0);
+        assert_eq!(note_event_times(&event), vec![0, 0]);
+        // offbeats are swinging for the hihat only
+        let event = pattern.next().unwrap();
+        assert_eq!(event.time, 11025);
+        assert_eq!(note_event_times(&event), vec![11025, 11025 + 5512]);
+        let event = pattern.next().unwrap();
+        assert_eq!(event.time, 22050);
+        assert_eq!(note_event_times(&event), vec![22050, 22050]);
+    }
+
+    #[test]
+    fn pattern_swing_division() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // swing sixteenths in a pattern which runs at 1/32
+        let mut pattern = time_base
+            .every_nth_sixteenth(0.5)
+            .with_swing(Swing::new(0.25))
+            .with_swing_division(BeatTimeStep::Sixteenth(1.0))
+            .emit(new_note_emitter(Note::C4));
+        assert_eq!(pattern.swing_division(), Some(BeatTimeStep::Sixteenth(1.0)));
+
+        // only 32ths which start on every second 16th get delayed by a quarter of a 16th
+        let sixteenth = 5512.5;
+        let note_times = pattern
+            .by_ref()
+            .take(8)
+            .map(|event| match &event.event {
+                Some(Event::NoteEvents(notes)) => event.note_event_time(notes[0].as_ref().unwrap()),
+                _ => panic!("expected a note event"),
+            })
+            .collect::<Vec<_>>();
+        let expected_times = (0..8)
+            .map(|step| {
+                let delay = if step % 4 == 2 { 0.25 * sixteenth } else { 0.0 };
+                (step as f64 * sixteenth / 2.0 + delay) as SampleTime
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(note_times, expected_times);
+
+        // without division, the pattern's 32th steps swing
+        let mut pattern = pattern.with_swing_division(None);
+        pattern.reset();
+        let delayed = pattern
+            .take(4)
+            .map(|event| match &event.event {
+                Some(Event::NoteEvents(notes)) => notes[0].as_ref().unwrap().delay > 0.0,
+                _ => false,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(delayed, vec![false, true, false, true]);
+    }
+}
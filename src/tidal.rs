@@ -1,4 +1,7 @@
 //! Tidal mini parser and event generator, used as `Emitter`.
 
 mod cycle;
-pub use cycle::{Cycle, Event, Span, Target, Value};
+pub use cycle::{
+    operators, tokenize, Cycle, Event, OperatorInfo, OperatorKind, Signal, Span, SyntaxError,
+    Target, Token, TokenKind, Value,
+};
@@ -3,6 +3,8 @@ use std::rc::Rc;
 #[cfg(test)]
 use std::fmt::Display;
 
+use lazy_static::lazy_static;
+
 use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
 
@@ -32,13 +34,23 @@ pub struct Cycle {
 
 impl Cycle {
     /// Default value for the cycle's event limit option.
-    const EVENT_LIMIT_DEFAULT: usize = 0x1000;
+    pub(crate) const EVENT_LIMIT_DEFAULT: usize = 0x1000;
 
     /// Create a Cycle from a mini-notation string, using an unseeded random number generator
     /// and the default event limit setting.
     ///
     /// Returns a parse error, when the given string is not a valid mini notation expression.
     pub fn from(input: &str) -> Result<Self, String> {
+        Self::from_with_bindings(input, &[])
+    }
+
+    /// Create a Cycle from a mini-notation string, resolving `$name` references in the input
+    /// with the given named sub-cycles. e.g. with a binding `("verse", Cycle::from("a b c")?)`,
+    /// the input `"[$verse $verse d]"` expands to `"[[a b c] [a b c] d]"`.
+    ///
+    /// Bound cycles are inserted as groups and run with the state (seed) of the new cycle.
+    /// Returns an error when the input references a name which is not bound.
+    pub fn from_with_bindings(input: &str, bindings: &[(&str, Cycle)]) -> Result<Self, String> {
         match CycleParser::parse(Rule::mini, input) {
             Ok(mut tree) => {
                 if let Some(mini) = tree.next() {
@@ -48,7 +60,8 @@ impl Cycle {
                         Self::print_pairs(&mini, 0);
                     }
                     let input = input.to_string();
-                    let root = CycleParser::step(mini)?;
+                    let mut root = CycleParser::step(mini)?;
+                    root.resolve_references(bindings)?;
                     let state = CycleState {
                         events: 0,
                         iteration: 0,
@@ -100,7 +113,7 @@ impl Cycle {
     /// Check if a cycle may give different outputs between cycles.
     pub fn is_stateful(&self) -> bool {
         // TODO improve: * and / can change the output, <1> does not etc..
-        self.input.contains(['<', '{', '|', '?', '/', '*'])
+        self.input.contains(['<', '{', '|', '?', '/', '*', '$']) || self.root.has_random_signals()
     }
 
     /// Query for the next iteration of output.
@@ -115,6 +128,7 @@ impl Cycle {
         let mut events = Self::output(&self.root, &mut self.state, cycle, self.event_limit, false)?;
         self.state.iteration += 1;
         events.transform_spans(&Span::default());
+        events.sample_signals(&mut self.state.rng);
         Ok(events.export())
     }
 
@@ -138,6 +152,8 @@ pub struct Event {
     value: Value,
     string: Rc<str>,
     targets: Vec<Target>,
+    // named targets with continuous signal values, which get sampled when generating
+    signal_targets: Vec<(Rc<str>, Signal)>,
 }
 
 impl Default for Event {
@@ -148,6 +164,7 @@ impl Default for Event {
             value: Value::default(),
             string: Rc::from("~"),
             targets: vec![],
+            signal_targets: vec![],
         }
     }
 }
@@ -219,6 +236,70 @@ pub enum Value {
     Chord(Pitch, Rc<str>),
     Target(Target),
     Name(Rc<str>),
+    Signal(Signal),
+}
+
+/// Continuous signals for cycle parameter applications such as `"a b c # pan sine"`, which get
+/// sampled at the center of each left event's step in the cycle. Generated [`Event`]s contain
+/// the sampled values as named targets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    /// Sine wave in range \[0 - 1\], starting at 0.5.
+    Sine,
+    /// Cosine wave in range \[0 - 1\]: a sine wave shifted by a quarter cycle.
+    Cosine,
+    /// Ramp up from 0 to 1.
+    Saw,
+    /// Ramp down from 1 to 0.
+    ISaw,
+    /// Triangle wave: ramp up from 0 to 1 in the first, and down again in the second half.
+    Tri,
+    /// Square wave: 0 in the first, 1 in the second half.
+    Square,
+    /// Random values in range \[0 - 1\].
+    Rand,
+}
+
+impl Signal {
+    /// Sample the signal at the given phase in range \[0 - 1\].
+    /// `rng` is used for random signals only.
+    pub fn sample<R: Rng>(&self, phase: f64, rng: &mut R) -> f64 {
+        let phase = phase - phase.floor();
+        match self {
+            Self::Sine => ((std::f64::consts::TAU * phase).sin() + 1.0) / 2.0,
+            Self::Cosine => Self::Sine.sample(phase + 0.25, rng),
+            Self::Saw => phase,
+            Self::ISaw => 1.0 - phase,
+            Self::Tri => {
+                if phase < 0.5 {
+                    phase * 2.0
+                } else {
+                    2.0 - phase * 2.0
+                }
+            }
+            Self::Square => {
+                if phase < 0.5 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Self::Rand => rng.random_range(0.0..1.0),
+        }
+    }
+
+    fn parse(str: &str) -> Option<Self> {
+        match str {
+            "sine" => Some(Self::Sine),
+            "cosine" => Some(Self::Cosine),
+            "saw" => Some(Self::Saw),
+            "isaw" => Some(Self::ISaw),
+            "tri" => Some(Self::Tri),
+            "square" => Some(Self::Square),
+            "rand" => Some(Self::Rand),
+            _ => None,
+        }
+    }
 }
 
 /// Sample/instrument target information for cycle events.
@@ -265,9 +346,13 @@ enum Step {
     Choices(Choices),
     SpeedExpression(SpeedExpression),
     TargetExpression(TargetExpression),
+    ParameterExpression(ParameterExpression),
     Degrade(Degrade),
+    Weight(Weight),
+    Rotation(Rotation),
     Bjorklund(Bjorklund),
     Static(Static),
+    Reference(Rc<str>),
 }
 
 impl Step {
@@ -282,7 +367,10 @@ impl Step {
             Step::Stack(st) => st.stack.iter().collect(),
             Step::SpeedExpression(e) => vec![&e.left, &e.right],
             Step::Degrade(e) => vec![&e.step],
+            Step::Weight(w) => vec![&w.step],
+            Step::Rotation(r) => vec![&r.step],
             Step::TargetExpression(e) => vec![&e.left, &e.right],
+            Step::ParameterExpression(e) => vec![&e.left, &e.right],
             Step::Bjorklund(b) => {
                 if let Some(rotation) = &b.rotation {
                     vec![&b.left, &b.steps, &b.pulses, &**rotation]
@@ -295,6 +383,7 @@ impl Step {
                 Static::Expression(e) => vec![&e.left],
                 Static::Range(_) => vec![],
             },
+            Step::Reference(_) => vec![],
         }
     }
 
@@ -308,13 +397,86 @@ impl Step {
             Step::Polymeter(pm) => pm.steps.as_mut().inner_steps_mut(),
             Step::Stack(st) => st.stack.iter_mut().collect(),
             Step::Degrade(e) => vec![&mut e.step],
+            Step::Weight(w) => vec![&mut w.step],
+            Step::Rotation(r) => vec![&mut r.step],
             Step::TargetExpression(e) => vec![&mut e.left],
+            Step::ParameterExpression(e) => vec![&mut e.left],
             Step::Bjorklund(b) => vec![&mut b.left],
             Step::Static(s) => match s {
                 Static::Repeat => vec![],
                 Static::Range(_) => vec![],
                 Static::Expression(_) => vec![],
             },
+            Step::Reference(_) => vec![],
+        }
+    }
+
+    /// recursively replace all references with the bound steps
+    fn resolve_references(&mut self, bindings: &[(&str, Cycle)]) -> Result<(), String> {
+        let steps: Vec<&mut Step> = match self {
+            Step::Reference(name) => {
+                let (_, cycle) = bindings
+                    .iter()
+                    .find(|(n, _)| *n == name.as_ref())
+                    .ok_or_else(|| format!("unknown cycle reference '${}'", name))?;
+                *self = cycle.root.clone();
+                return Ok(());
+            }
+            Step::Single(_) => vec![],
+            Step::Alternating(a) => a.steps.iter_mut().collect(),
+            Step::Subdivision(sd) => sd.steps.iter_mut().collect(),
+            Step::Polymeter(pm) => vec![&mut pm.steps, &mut pm.count],
+            Step::Choices(cs) => cs.choices.iter_mut().collect(),
+            Step::Stack(st) => st.stack.iter_mut().collect(),
+            Step::SpeedExpression(e) => vec![&mut e.left, &mut e.right],
+            Step::TargetExpression(e) => vec![&mut e.left, &mut e.right],
+            Step::ParameterExpression(e) => vec![&mut e.left, &mut e.right],
+            Step::Degrade(e) => vec![&mut e.step],
+            Step::Weight(w) => vec![&mut w.step],
+            Step::Rotation(r) => vec![&mut r.step],
+            Step::Bjorklund(b) => {
+                let mut steps = vec![&mut *b.left, &mut *b.steps, &mut *b.pulses];
+                if let Some(rotation) = &mut b.rotation {
+                    steps.push(rotation);
+                }
+                steps
+            }
+            Step::Static(s) => match s {
+                Static::Expression(e) => vec![&mut e.left],
+                Static::Repeat | Static::Range(_) => vec![],
+            },
+        };
+        for step in steps {
+            step.resolve_references(bindings)?;
+        }
+        Ok(())
+    }
+
+    /// recursively check if the step or its inner steps use random signals
+    fn has_random_signals(&self) -> bool {
+        match self {
+            Step::Single(s) => s.value == Value::Signal(Signal::Rand),
+            Step::Alternating(a) => a.steps.iter().any(Step::has_random_signals),
+            Step::Subdivision(sd) => sd.steps.iter().any(Step::has_random_signals),
+            Step::Polymeter(pm) => pm.steps.has_random_signals() || pm.count.has_random_signals(),
+            Step::Choices(cs) => cs.choices.iter().any(Step::has_random_signals),
+            Step::Stack(st) => st.stack.iter().any(Step::has_random_signals),
+            Step::SpeedExpression(e) => e.left.has_random_signals() || e.right.has_random_signals(),
+            Step::TargetExpression(e) => {
+                e.left.has_random_signals() || e.right.has_random_signals()
+            }
+            Step::ParameterExpression(e) => {
+                e.left.has_random_signals() || e.right.has_random_signals()
+            }
+            Step::Degrade(e) => e.step.has_random_signals(),
+            Step::Weight(w) => w.step.has_random_signals(),
+            Step::Rotation(r) => r.step.has_random_signals(),
+            Step::Bjorklund(b) => b.left.has_random_signals(),
+            Step::Static(s) => match s {
+                Static::Expression(e) => e.left.has_random_signals(),
+                Static::Repeat | Static::Range(_) => false,
+            },
+            Step::Reference(_) => false,
         }
     }
 
@@ -346,6 +508,26 @@ impl Step {
             count: Box::new(count),
         })
     }
+    fn weight(step: Step, weight: &Value) -> Self {
+        // non positive or invalid weights fall back to the default weight of 1
+        let weight = weight
+            .to_float()
+            .filter(|weight| *weight > 0.0)
+            .and_then(Fraction::from_f64)
+            .unwrap_or(Fraction::ONE);
+        Step::Weight(Weight {
+            step: Box::new(step),
+            weight,
+        })
+    }
+
+    /// relative length of the step within its parent group
+    fn step_weight(&self) -> Fraction {
+        match self {
+            Step::Weight(w) => w.weight,
+            _ => Fraction::ONE,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -387,11 +569,11 @@ struct Polymeter {
 }
 
 impl Polymeter {
-    fn length(&self) -> usize {
+    fn length(&self) -> Fraction {
         if let Step::Subdivision(s) = self.steps.as_ref() {
-            s.steps.len()
+            s.steps.iter().map(Step::step_weight).sum()
         } else {
-            1
+            Fraction::ONE
         } // unreachable
     }
 }
@@ -423,8 +605,10 @@ enum Operator {
     Static(StaticOp),
     Speed(SpeedOp),
     Target(),    // :
+    Parameter(), // #
     Bjorklund(), // (p,s,r)
     Degrade(),   // ?
+    Rotate(),    // ~> <~
 }
 
 impl Operator {
@@ -436,7 +620,9 @@ impl Operator {
             Rule::op_fast => Ok(Self::Speed(SpeedOp::Fast())),
             Rule::op_slow => Ok(Self::Speed(SpeedOp::Slow())),
             Rule::op_target => Ok(Self::Target()),
+            Rule::op_parameter => Ok(Self::Parameter()),
             Rule::op_bjorklund => Ok(Self::Bjorklund()),
+            Rule::op_rotate => Ok(Self::Rotate()),
             _ => Err(format!("unsupported operator: {:?}", pair.as_rule())),
         }
     }
@@ -462,12 +648,31 @@ struct Degrade {
     chance: Value,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+struct Weight {
+    step: Box<Step>,
+    weight: Fraction,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Rotation {
+    step: Box<Step>,
+    amount: Fraction,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 struct TargetExpression {
     left: Box<Step>,
     right: Box<Step>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+struct ParameterExpression {
+    left: Box<Step>,
+    target: Rc<str>,
+    right: Box<Step>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 struct Bjorklund {
     left: Box<Step>,
@@ -491,7 +696,7 @@ impl Target {
             Value::Integer(i) => Some(Self::from_index(*i)),
             Value::Name(name) => Some(Self::from_name(Rc::clone(name))),
             Value::Target(t) => Some(t.clone()),
-            Value::Float(_) | Value::Pitch(_) | Value::Chord(_, _) => {
+            Value::Float(_) | Value::Pitch(_) | Value::Chord(_, _) | Value::Signal(_) => {
                 // pass unexpected values as raw string and let clients deal with conversions or errors
                 Some(Self::from_name(Rc::clone(value_string)))
             }
@@ -623,6 +828,7 @@ impl Value {
                 Target::Named(_, v) => v.map(|f| f as i32),
             },
             Value::Name(_n) => None,
+            Value::Signal(_s) => None,
         }
     }
 
@@ -639,6 +845,7 @@ impl Value {
                 Target::Named(_, v) => *v,
             },
             Value::Name(_n) => None,
+            Value::Signal(_s) => None,
         }
     }
 
@@ -655,6 +862,7 @@ impl Value {
                 Target::Named(_, v) => v.map(|f| f.clamp(0.0, 1.0)),
             },
             Value::Name(_n) => None,
+            Value::Signal(_s) => None,
         }
     }
 }
@@ -732,6 +940,7 @@ impl Event {
             string: Rc::from("~"),
             value: Value::Rest,
             targets: vec![],
+            signal_targets: vec![],
         }
     }
 
@@ -802,6 +1011,12 @@ impl Event {
         self.length += next.length;
         self.span.end = next.span.end
     }
+
+    /// add the given target, replacing existing targets with the same key
+    fn set_target(&mut self, target: Target) {
+        self.targets.retain(|t| !t.equal_key(&target));
+        self.targets.push(target);
+    }
 }
 
 impl PartialEq<Event> for Event {
@@ -853,6 +1068,7 @@ impl Events {
             string: Rc::from("~"),
             value: Value::Rest,
             targets: vec![],
+            signal_targets: vec![],
         })
     }
 
@@ -872,6 +1088,14 @@ impl Events {
         }
     }
 
+    fn scale_length(&mut self, factor: Fraction) {
+        match self {
+            Events::Single(s) => s.length *= factor,
+            Events::Multi(m) => m.length *= factor,
+            Events::Poly(p) => p.length *= factor,
+        }
+    }
+
     fn get_span(&self) -> Span {
         match self {
             Events::Single(s) => s.span.clone(),
@@ -991,6 +1215,18 @@ impl Events {
         }
     }
 
+    /// replace continuous signal targets with values sampled at the center of their spans
+    fn sample_signals<R: Rng>(&mut self, rng: &mut R) {
+        self.mutate_events(&mut |event: &mut Event| {
+            let center = (event.span.start + event.span.end) / 2;
+            let phase = (center - center.floor()).to_f64().unwrap_or(0.0);
+            for (name, signal) in std::mem::take(&mut event.signal_targets) {
+                let value = signal.sample(phase, rng);
+                event.set_target(Target::Named(name, Some(value)));
+            }
+        });
+    }
+
     /// recursively transform the spans of events from 0..1 to a given span
     fn transform_spans(&mut self, span: &Span) {
         let unit = span.length();
@@ -1166,16 +1402,333 @@ impl Events {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Kind of a [`Token`] in a mini-notation string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A single value, such as a note, number, name or target.
+    Value,
+    /// A rest step: `~` or `-`.
+    Rest,
+    /// A hold step: `_`.
+    Hold,
+    /// A reference to a named sub-cycle: `$name`.
+    Reference,
+    /// Operators and their syntax, e.g. `*`, `!`, `@`, `?`, `:`, `#`, `=`, `%` or `(` and `)`.
+    Operator,
+    /// Opening bracket of a group: `[`, `<` or `{`.
+    GroupOpen,
+    /// Closing bracket of a group: `]`, `>` or `}`.
+    GroupClose,
+    /// Section separators within groups: `,`, `|` or `.`.
+    Separator,
+}
+
+/// A single token of a mini-notation string with its byte range in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub range: std::ops::Range<usize>,
+}
+
+/// Syntax error of a mini-notation string with the byte range of the error in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub message: String,
+    pub range: std::ops::Range<usize>,
+}
+
+/// Split a mini-notation string into tokens, e.g. to highlight the notation in editors.
+///
+/// Tokens are sorted by their position and cover all non-whitespace characters of the input.
+/// Returns a syntax error with the error's position, when the input can not be parsed.
+pub fn tokenize(input: &str) -> Result<Vec<Token>, SyntaxError> {
+    match CycleParser::parse(Rule::mini, input) {
+        Ok(tree) => {
+            let mut tokens = Vec::new();
+            for pair in tree {
+                CycleParser::tokenize_pair(input, &pair, &mut tokens);
+            }
+            Ok(tokens)
+        }
+        Err(err) => {
+            let range = match err.location {
+                pest::error::InputLocation::Pos(pos) => pos..(pos + 1).min(input.len()),
+                pest::error::InputLocation::Span((start, end)) => start..end,
+            };
+            Err(SyntaxError {
+                message: err.variant.message().to_string(),
+                range,
+            })
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Kind of an [`OperatorInfo`] in the mini-notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperatorKind {
+    /// Operator which is appended to a single step or group, e.g. `a*2` or `[a b]!3`.
+    Step,
+    /// Separator of sections within a group, e.g. `[a b, c d]`.
+    Separator,
+    /// Brackets of a group of steps, e.g. `<a b>`.
+    Group,
+    /// Step expression with arguments, e.g. a range `1..4`.
+    Expression,
+}
+
+/// Description of a single operator or syntax element of the mini-notation, as returned by
+/// [`operators`], e.g. to build autocompletion or documentation in editors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperatorInfo {
+    /// Syntax of the operator with argument placeholders, e.g. `*` or `(p,s,r)`.
+    pub syntax: &'static str,
+    /// Short, human readable name of the operator.
+    pub name: &'static str,
+    /// Kind of the operator.
+    pub kind: OperatorKind,
+    /// Minimum number of arguments the operator expects.
+    pub min_arguments: usize,
+    /// Maximum number of arguments the operator accepts.
+    pub max_arguments: usize,
+    /// Short description of the operator's function.
+    pub description: &'static str,
+    /// Example mini-notation which uses the operator.
+    pub example: &'static str,
+}
+
+/// All operators and syntax elements which are supported by the mini-notation parser.
+///
+/// The list is generated from the parser's grammar rules, in the order of the grammar.
+pub fn operators() -> &'static [OperatorInfo] {
+    lazy_static! {
+        static ref OPERATORS: Vec<OperatorInfo> = Rule::all_rules()
+            .iter()
+            .filter_map(|rule| CycleParser::operator_info(*rule))
+            .collect();
+    }
+    &OPERATORS
+}
+
+// -------------------------------------------------------------------------------------------------
+
 #[derive(Parser)]
 #[grammar = "tidal/cycle.pest"]
 struct CycleParser {}
 
 /// the errors here should be unreachable unless there is a bug in the pest grammar
 impl CycleParser {
+    /// description of the operator or syntax element which the given grammar rule defines.
+    /// this intentionally matches all rules, so new grammar rules need to be described here.
+    fn operator_info(rule: Rule) -> Option<OperatorInfo> {
+        match rule {
+            Rule::op_fast => Some(OperatorInfo {
+                syntax: "*",
+                name: "fast",
+                kind: OperatorKind::Step,
+                min_arguments: 1,
+                max_arguments: 1,
+                description: "Speed up a step by the given factor or pattern of factors",
+                example: "a*2 b*[2 3]",
+            }),
+            Rule::op_slow => Some(OperatorInfo {
+                syntax: "/",
+                name: "slow",
+                kind: OperatorKind::Step,
+                min_arguments: 1,
+                max_arguments: 1,
+                description: "Slow down a step by the given factor or pattern of factors",
+                example: "[a b c]/2",
+            }),
+            Rule::op_replicate => Some(OperatorInfo {
+                syntax: "!",
+                name: "replicate",
+                kind: OperatorKind::Step,
+                min_arguments: 0,
+                max_arguments: 1,
+                description: "Repeat a step the given number of times, or once without a number",
+                example: "a!3 b !",
+            }),
+            Rule::op_weight => Some(OperatorInfo {
+                syntax: "@",
+                name: "weight",
+                kind: OperatorKind::Step,
+                min_arguments: 0,
+                max_arguments: 1,
+                description: "Stretch a step relative to other steps, by 2 without a number",
+                example: "a@3 b",
+            }),
+            Rule::op_degrade => Some(OperatorInfo {
+                syntax: "?",
+                name: "degrade",
+                kind: OperatorKind::Step,
+                min_arguments: 0,
+                max_arguments: 1,
+                description: "Randomly drop a step with the given chance, 0.5 without a number",
+                example: "a? b?0.2",
+            }),
+            Rule::op_bjorklund => Some(OperatorInfo {
+                syntax: "(p,s,r)",
+                name: "euclidean",
+                kind: OperatorKind::Step,
+                min_arguments: 2,
+                max_arguments: 3,
+                description: "Distribute pulses over steps with an optional rotation",
+                example: "a(3,8) b(3,8,2)",
+            }),
+            Rule::op_target => Some(OperatorInfo {
+                syntax: ":",
+                name: "target",
+                kind: OperatorKind::Step,
+                min_arguments: 1,
+                max_arguments: 1,
+                description: "Assign target properties such as instruments or volumes to a step",
+                example: "a:#2 b:v=0.5",
+            }),
+            Rule::op_parameter => Some(OperatorInfo {
+                syntax: "#",
+                name: "parameter",
+                kind: OperatorKind::Step,
+                min_arguments: 2,
+                max_arguments: 2,
+                description: "Apply values or signals to the gain, pan or delay of a step's notes",
+                example: "[a b c] # pan sine",
+            }),
+            Rule::rotate_late => Some(OperatorInfo {
+                syntax: "~>",
+                name: "rotate late",
+                kind: OperatorKind::Step,
+                min_arguments: 1,
+                max_arguments: 1,
+                description: "Shift a step later by the given fraction of a cycle",
+                example: "[a b]~>0.25",
+            }),
+            Rule::rotate_early => Some(OperatorInfo {
+                syntax: "<~",
+                name: "rotate early",
+                kind: OperatorKind::Step,
+                min_arguments: 1,
+                max_arguments: 1,
+                description: "Shift a step earlier by the given fraction of a cycle",
+                example: "[a b]<~0.25",
+            }),
+            Rule::polymeter_tail => Some(OperatorInfo {
+                syntax: "%",
+                name: "polymeter steps",
+                kind: OperatorKind::Step,
+                min_arguments: 1,
+                max_arguments: 1,
+                description: "Set the number of steps per cycle of a polymeter group",
+                example: "{a b c}%4",
+            }),
+            Rule::range => Some(OperatorInfo {
+                syntax: "..",
+                name: "range",
+                kind: OperatorKind::Expression,
+                min_arguments: 2,
+                max_arguments: 2,
+                description: "Expand to steps of all integers between the given start and end",
+                example: "1..4",
+            }),
+            Rule::stack_op => Some(OperatorInfo {
+                syntax: ",",
+                name: "stack",
+                kind: OperatorKind::Separator,
+                min_arguments: 2,
+                max_arguments: usize::MAX,
+                description: "Play sections of a group at the same time",
+                example: "[a b, c d]",
+            }),
+            Rule::choice_op => Some(OperatorInfo {
+                syntax: "|",
+                name: "choice",
+                kind: OperatorKind::Separator,
+                min_arguments: 2,
+                max_arguments: usize::MAX,
+                description: "Randomly pick one of the sections of a group in each cycle",
+                example: "[a b | c d]",
+            }),
+            Rule::split_op => Some(OperatorInfo {
+                syntax: ".",
+                name: "split",
+                kind: OperatorKind::Separator,
+                min_arguments: 2,
+                max_arguments: usize::MAX,
+                description: "Split a group into subdivisions, one for each section",
+                example: "a b . c d e",
+            }),
+            Rule::subdivision => Some(OperatorInfo {
+                syntax: "[ ]",
+                name: "subdivision",
+                kind: OperatorKind::Group,
+                min_arguments: 0,
+                max_arguments: usize::MAX,
+                description: "Squeeze all steps of the group into a single step",
+                example: "a [b c]",
+            }),
+            Rule::alternating => Some(OperatorInfo {
+                syntax: "< >",
+                name: "alternating",
+                kind: OperatorKind::Group,
+                min_arguments: 0,
+                max_arguments: usize::MAX,
+                description: "Play one step of the group per cycle, in turns",
+                example: "a <b c>",
+            }),
+            Rule::polymeter => Some(OperatorInfo {
+                syntax: "{ }",
+                name: "polymeter",
+                kind: OperatorKind::Group,
+                min_arguments: 0,
+                max_arguments: usize::MAX,
+                description: "Play steps of the group with the step count of the first section",
+                example: "{a b c, d e}",
+            }),
+            // values, operator arguments and helper rules
+            Rule::EOI
+            | Rule::WHITESPACE
+            | Rule::integer
+            | Rule::float
+            | Rule::number
+            | Rule::octave
+            | Rule::mark
+            | Rule::note
+            | Rule::pitch
+            | Rule::target
+            | Rule::target_name
+            | Rule::target_assign
+            | Rule::mode
+            | Rule::voicing
+            | Rule::chord
+            | Rule::rest
+            | Rule::hold
+            | Rule::name
+            | Rule::signal
+            | Rule::reference
+            | Rule::repeat
+            | Rule::single
+            | Rule::op_rotate
+            | Rule::parameter_name
+            | Rule::sections
+            | Rule::group
+            | Rule::parameter
+            | Rule::single_parameter
+            | Rule::op
+            | Rule::expression
+            | Rule::section
+            | Rule::mini => None,
+        }
+    }
+
     /// recursively parse a pair as a Step
     fn step(pair: Pair<Rule>) -> Result<Step, String> {
         match pair.as_rule() {
             Rule::single => Self::single(pair),
+            Rule::signal => Ok(Step::Single(Single {
+                string: Rc::from(pair.as_str()),
+                value: Self::value(pair)?,
+            })),
             Rule::repeat => Ok(Step::Static(Static::Repeat)),
             Rule::subdivision | Rule::mini => Self::group(pair, Step::subdivision),
             Rule::alternating => Self::group(pair, Step::alternating),
@@ -1190,6 +1743,83 @@ impl CycleParser {
         }
     }
 
+    /// recursively collect tokens of the given pair
+    fn tokenize_pair(input: &str, pair: &Pair<Rule>, tokens: &mut Vec<Token>) {
+        let span = pair.as_span();
+        let kind = match pair.as_rule() {
+            Rule::single => match pair.clone().into_inner().next().map(|p| p.as_rule()) {
+                Some(Rule::rest) => Some(TokenKind::Rest),
+                Some(Rule::hold) => Some(TokenKind::Hold),
+                Some(Rule::reference) => Some(TokenKind::Reference),
+                _ => Some(TokenKind::Value),
+            },
+            Rule::number
+            | Rule::integer
+            | Rule::float
+            | Rule::pitch
+            | Rule::chord
+            | Rule::target
+            | Rule::target_name
+            | Rule::parameter_name
+            | Rule::signal
+            | Rule::name => Some(TokenKind::Value),
+            Rule::rest => Some(TokenKind::Rest),
+            Rule::hold => Some(TokenKind::Hold),
+            Rule::reference => Some(TokenKind::Reference),
+            Rule::repeat | Rule::rotate_late | Rule::rotate_early => Some(TokenKind::Operator),
+            Rule::stack_op | Rule::choice_op | Rule::split_op => Some(TokenKind::Separator),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            if span.start() < span.end() {
+                tokens.push(Token {
+                    kind,
+                    range: span.start()..span.end(),
+                });
+            }
+            return;
+        }
+        // tokenize the syntax in between the inner pairs
+        let mut position = span.start();
+        for inner in pair.clone().into_inner() {
+            Self::tokenize_gap(input, position..inner.as_span().start(), tokens);
+            Self::tokenize_pair(input, &inner, tokens);
+            position = position.max(inner.as_span().end());
+        }
+        Self::tokenize_gap(input, position..span.end(), tokens);
+    }
+
+    /// collect tokens from syntax characters, such as brackets or operators
+    fn tokenize_gap(input: &str, range: std::ops::Range<usize>, tokens: &mut Vec<Token>) {
+        if range.start >= range.end {
+            return;
+        }
+        for (offset, char) in input[range.clone()].char_indices() {
+            let kind = match char {
+                c if c.is_whitespace() => continue,
+                '[' | '<' | '{' => TokenKind::GroupOpen,
+                ']' | '>' | '}' => TokenKind::GroupClose,
+                _ => TokenKind::Operator,
+            };
+            let start = range.start + offset;
+            let end = start + char.len_utf8();
+            match tokens.last_mut() {
+                // merge multi-character operators
+                Some(last)
+                    if kind == TokenKind::Operator
+                        && last.kind == kind
+                        && last.range.end == start =>
+                {
+                    last.range.end = end;
+                }
+                _ => tokens.push(Token {
+                    kind,
+                    range: start..end,
+                }),
+            }
+        }
+    }
+
     /// parse a pair inside a single as a value
     fn value(pair: Pair<Rule>) -> Result<Value, String> {
         match pair.as_rule() {
@@ -1246,6 +1876,9 @@ impl CycleParser {
                 }
             }
             Rule::name => Ok(Value::Name(Rc::from(pair.as_str()))),
+            Rule::signal => Signal::parse(pair.as_str())
+                .map(Value::Signal)
+                .ok_or_else(|| format!("unrecognized signal\n{:?}", pair)),
             _ => Err(format!("unrecognized target value\n{:?}", pair)),
         }
     }
@@ -1256,6 +1889,10 @@ impl CycleParser {
             .next()
             .ok_or_else(|| format!("empty single {}", pair))
             .and_then(|value_pair| {
+                if value_pair.as_rule() == Rule::reference {
+                    let name = value_pair.as_str().trim_start_matches('$');
+                    return Ok(Step::Reference(Rc::from(name)));
+                }
                 Ok(Step::Single(Single {
                     string: Rc::from(value_pair.as_str()),
                     value: Self::value(value_pair)?,
@@ -1283,17 +1920,7 @@ impl CycleParser {
                         }
                     }
                     StaticOp::Weight() => {
-                        steps.push(e.left.as_ref().clone());
-                        if let Some(repeats) = e.right.to_integer() {
-                            if repeats > 0 {
-                                for _i in 1..repeats {
-                                    steps.push(Step::Single(Single {
-                                        value: Value::Hold,
-                                        string: Rc::from("_"),
-                                    }))
-                                }
-                            }
-                        }
+                        steps.push(Step::weight(e.left.as_ref().clone(), &e.right));
                     }
                 },
                 Static::Range(r) => {
@@ -1463,12 +2090,17 @@ impl CycleParser {
             (None, Some(stack), _) => {
                 let count = stack
                     .first()
-                    .map(Vec::len)
+                    .map(|steps| steps.iter().map(Step::step_weight).sum::<Fraction>())
                     .ok_or_else(|| format!("empty stack {:?}", stack))?;
 
-                if stack.len() > 1 && count > 0 {
+                if stack.len() > 1 && count > Fraction::ZERO {
+                    let value = if count.is_integer() {
+                        Value::Integer(count.to_integer())
+                    } else {
+                        Value::Float(count.to_f64().unwrap_or(1.0))
+                    };
                     let count = Step::Single(Single {
-                        value: Value::Integer(count as i32),
+                        value,
                         string: Rc::from(count.to_string()),
                     });
                     // if there is a stack but no count, the first section will determine the count of the rest
@@ -1576,6 +2208,32 @@ impl CycleParser {
         }))
     }
 
+    fn rotation(step: Step, op_pair: Pair<Rule>) -> Result<Step, String> {
+        let mut inner = op_pair.into_inner();
+        let direction = inner.next().ok_or_else(Self::invalid_right_hand)?;
+        let amount = inner
+            .next()
+            .ok_or_else(Self::invalid_right_hand)
+            .and_then(|right_pair| {
+                right_pair
+                    .into_inner()
+                    .next()
+                    .ok_or_else(Self::invalid_right_hand)
+            })
+            .and_then(Self::value)?
+            .to_float()
+            .and_then(Fraction::from_f64)
+            .ok_or_else(|| "invalid rotation amount".to_string())?;
+        let amount = match direction.as_rule() {
+            Rule::rotate_early => -amount,
+            _ => amount,
+        };
+        Ok(Step::Rotation(Rotation {
+            step: Box::new(step),
+            amount,
+        }))
+    }
+
     fn speed_expression(left: Step, op: SpeedOp, op_pair: Pair<Rule>) -> Result<Step, String> {
         let right = op_pair
             .into_inner()
@@ -1601,6 +2259,32 @@ impl CycleParser {
         }))
     }
 
+    fn parameter_expression(left: Step, op_pair: Pair<Rule>) -> Result<Step, String> {
+        let mut inner = op_pair.into_inner();
+        let name = inner.next().ok_or_else(Self::invalid_right_hand)?;
+        // map parameter names to the keys of named targets
+        let target = match name.as_str() {
+            "gain" => "v",
+            "pan" => "p",
+            "delay" => "d",
+            other => {
+                return Err(format!(
+                    "unknown parameter '{}', expecting 'gain', 'pan' or 'delay'",
+                    other
+                ))
+            }
+        };
+        let right = inner
+            .next()
+            .ok_or_else(Self::invalid_right_hand)
+            .and_then(Self::step)?;
+        Ok(Step::ParameterExpression(ParameterExpression {
+            left: Box::new(left),
+            target: Rc::from(target),
+            right: Box::new(right),
+        }))
+    }
+
     fn expression(pair: Pair<Rule>) -> Result<Step, String> {
         let mut inner = pair.clone().into_inner();
         // Initialize 'left' with the first step (single or group).
@@ -1615,8 +2299,10 @@ impl CycleParser {
                 Operator::Static(op) => Self::static_expression(left, op, op_pair)?,
                 Operator::Speed(op) => Self::speed_expression(left, op, op_pair)?,
                 Operator::Target() => Self::target_expression(left, op_pair)?,
+                Operator::Parameter() => Self::parameter_expression(left, op_pair)?,
                 Operator::Degrade() => Self::degrade_expression(left, op_pair)?,
                 Operator::Bjorklund() => Self::bjorklund(left, op_pair)?,
+                Operator::Rotate() => Self::rotation(left, op_pair)?,
             }
         }
         Ok(left)
@@ -1713,10 +2399,8 @@ impl Cycle {
     fn step_multiplier(step: &Step, value: &Value) -> Fraction {
         match step {
             Step::Polymeter(pm) => {
-                let length = pm.length() as f64;
                 let count = value.to_float().unwrap_or(0.0);
-                Fraction::from_f64(count).unwrap_or(Fraction::ZERO)
-                    / Fraction::from_f64(length).unwrap_or(Fraction::ONE)
+                Fraction::from_f64(count).unwrap_or(Fraction::ZERO) / pm.length()
             }
             Step::SpeedExpression(e) => match e.op {
                 SpeedOp::Fast() => {
@@ -1845,6 +2529,113 @@ impl Cycle {
         }
     }
 
+    // apply a single value to the parameter target of the given event. Signals get sampled
+    // later on, when the event's final span is known.
+    fn apply_parameter_value(
+        event: &mut Event,
+        target: &Rc<str>,
+        value: &Value,
+        value_string: &str,
+    ) -> Result<(), String> {
+        match value {
+            Value::Rest | Value::Hold => (),
+            Value::Signal(signal) => {
+                event.signal_targets.retain(|(name, _)| name != target);
+                event.signal_targets.push((Rc::clone(target), *signal));
+            }
+            _ => {
+                let value = value.to_float().ok_or_else(|| {
+                    format!(
+                        "invalid parameter value '{}', expecting numbers or signals",
+                        value_string
+                    )
+                })?;
+                event.signal_targets.retain(|(name, _)| name != target);
+                event.set_target(Target::Named(Rc::clone(target), Some(value)));
+            }
+        }
+        Ok(())
+    }
+
+    // apply the values of the right side's events to the parameter targets of the left side's
+    // events: each left event takes the value of the right event which is active at its start
+    fn apply_parameter(
+        events: &mut [Event],
+        target: &Rc<str>,
+        value_events: &[Event],
+    ) -> Result<(), String> {
+        for event in events.iter_mut() {
+            if let Some(value_event) = value_events.iter().find(|value_event| {
+                value_event.span.start <= event.span.start
+                    && event.span.start < value_event.span.end
+            }) {
+                Self::apply_parameter_value(
+                    event,
+                    target,
+                    &value_event.value,
+                    &value_event.string,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    // generate events from parameter expressions: the left side gives the structure and the
+    // right side the values
+    fn output_with_parameter(
+        expression: &ParameterExpression,
+        state: &mut CycleState,
+        cycle: u32,
+        limit: usize,
+        overlap: bool,
+    ) -> Result<Events, String> {
+        match expression.right.as_ref() {
+            // apply single values to all events
+            Step::Single(single) => {
+                let mut events = Self::output(&expression.left, state, cycle, limit, overlap)?;
+                let mut result = Ok(());
+                events.mutate_events(&mut |event: &mut Event| {
+                    if result.is_ok() {
+                        result = Self::apply_parameter_value(
+                            event,
+                            &expression.target,
+                            &single.value,
+                            &single.string,
+                        );
+                    }
+                });
+                result.map(|_| events)
+            }
+            _ => {
+                // generate all the events as flat vecs from both the left and right side of the expression
+                let (left_channels, left_span) =
+                    Self::output_flat(&expression.left, state, cycle, limit)?;
+                let (value_channels, _) =
+                    Self::output_flat(&expression.right, state, cycle, limit)?;
+
+                // iterate over channels from both sides to create necessary new stacks if the right side is polyphonic
+                let mut channel_events: Vec<Events> = Vec::with_capacity(value_channels.len());
+                for channel in value_channels.into_iter() {
+                    for left_channel in left_channels.iter() {
+                        let mut cloned_left = left_channel.clone();
+                        Self::apply_parameter(&mut cloned_left, &expression.target, &channel)?;
+                        channel_events.push(Events::Multi(MultiEvents {
+                            length: left_span.length(),
+                            span: left_span.clone(),
+                            events: cloned_left.into_iter().map(Events::Single).collect(),
+                        }));
+                    }
+                }
+                // put all the resulting events back together
+                Ok(Events::maybe_poly(PolyEvents {
+                    length: left_span.length(),
+                    span: left_span,
+                    channels: channel_events,
+                }))
+            }
+        }
+    }
+
     // output a multiplied pattern expression with support for patterns on the right side
     fn output_with_speed(
         right: &Step,
@@ -1904,6 +2695,28 @@ impl Cycle {
         }
     }
 
+    // rotate events of a single cycle by the given fraction of the cycle: events which
+    // get shifted out of the cycle wrap around and start playing from the other side
+    fn rotate(events: Events, amount: Fraction, overlap: bool) -> Events {
+        let shift = amount - amount.floor();
+        if shift == Fraction::ZERO {
+            return events;
+        }
+        let span = Span::default();
+        let mut wrapped = events.clone();
+        wrapped.transform_spans(&Span::new(shift - Fraction::ONE, shift));
+        let mut shifted = events;
+        shifted.transform_spans(&Span::new(shift, shift + Fraction::ONE));
+        let mut rotated = Events::Multi(MultiEvents {
+            span: span.clone(),
+            length: Fraction::ONE,
+            events: vec![wrapped, shifted],
+        });
+        rotated.crop(&span, overlap);
+        rotated.normalize_spans(&span);
+        rotated
+    }
+
     // recursively output events for the entire cycle based on some state (random seed)
     fn output(
         step: &Step,
@@ -1927,6 +2740,7 @@ impl Cycle {
                     string: Rc::clone(&s.string),
                     value: s.value.clone(),
                     targets: vec![],
+                    signal_targets: vec![],
                 })
             }
             Step::Subdivision(sd) => {
@@ -1994,6 +2808,16 @@ impl Cycle {
                 });
                 out
             }
+            Step::Weight(w) => {
+                // scale the step's length, which subdivisions use to distribute time
+                let mut out = Self::output(w.step.as_ref(), state, cycle, limit, overlap)?;
+                out.scale_length(w.weight);
+                out
+            }
+            Step::Rotation(r) => {
+                let events = Self::output(r.step.as_ref(), state, cycle, limit, overlap)?;
+                Self::rotate(events, r.amount, overlap)
+            }
             Step::TargetExpression(e) => Self::output_with_target(
                 e.left.as_ref(),
                 e.right.as_ref(),
@@ -2002,6 +2826,9 @@ impl Cycle {
                 limit,
                 overlap,
             )?,
+            Step::ParameterExpression(e) => {
+                Self::output_with_parameter(e, state, cycle, limit, overlap)?
+            }
             Step::SpeedExpression(e) => {
                 Self::output_with_speed(e.right.as_ref(), step, state, cycle, limit, overlap)?
             }
@@ -2064,6 +2891,10 @@ impl Cycle {
                 // Range and Expression should be applied in Self::push_applied
                 Events::empty()
             }
+            Step::Reference(name) => {
+                // references are resolved when creating the cycle
+                return Err(format!("unresolved cycle reference '${}'", name));
+            }
         };
         Ok(events)
     }
@@ -2091,6 +2922,7 @@ impl Cycle {
             Step::Stack(st) => format!("Stack ({})", st.stack.len()),
             Step::SpeedExpression(e) => format!("Speed Expression {:?}", e.op),
             Step::TargetExpression(_e) => String::from("Target Expression"),
+            Step::ParameterExpression(e) => format!("Parameter Expression # {}", e.target),
             Step::Static(s) => match s {
                 Static::Repeat => "Repeat".to_string(),
                 Static::Range(r) => format!("Range {}..{}", r.start, r.end),
@@ -2099,7 +2931,10 @@ impl Cycle {
                 }
             },
             Step::Degrade(d) => format!("Degrade ? {:?}", d.chance),
+            Step::Weight(w) => format!("Weight @ {}", w.weight),
+            Step::Rotation(r) => format!("Rotation ~> {}", r.amount),
             Step::Bjorklund(_b) => format!("Bjorklund {}", ""),
+            Step::Reference(r) => format!("Reference ${}", r),
         };
         println!("{} {}", Self::indent_lines(level), name);
         for step in step.inner_steps() {
@@ -2983,6 +3818,472 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn weights() -> Result<(), String> {
+        assert_eq!(
+            Cycle::from("a@3 b@1")?.generate()?,
+            [[
+                Event::at(Fraction::from(0), Fraction::new(3, 4)).with_note(9, 4),
+                Event::at(Fraction::new(3, 4), Fraction::new(1, 4)).with_note(11, 4),
+            ]]
+        );
+        // default weights are 1
+        assert_cycle_equality("a@1 b@1 c@1", "a b c")?;
+        assert_cycle_equality("a@2 b c", "a _ b c")?;
+        // fractional weights
+        assert_eq!(
+            Cycle::from("a@1.5 b")?.generate()?,
+            [[
+                Event::at(Fraction::from(0), Fraction::new(3, 5)).with_note(9, 4),
+                Event::at(Fraction::new(3, 5), Fraction::new(2, 5)).with_note(11, 4),
+            ]]
+        );
+        // weights compose with groups
+        assert_eq!(
+            Cycle::from("[a b]@3 c")?.generate()?,
+            [[
+                Event::at(Fraction::from(0), Fraction::new(3, 8)).with_note(9, 4),
+                Event::at(Fraction::new(3, 8), Fraction::new(3, 8)).with_note(11, 4),
+                Event::at(Fraction::new(3, 4), Fraction::new(1, 4)).with_note(0, 4),
+            ]]
+        );
+        assert_cycle_equality("[a@3 b, c d]", "[a _ _ b, c d]")?;
+        assert_cycle_equality("{a@2 b}%3", "{a _ b}%3")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn chord_voicings() -> Result<(), String> {
+        // voicings are part of the chord mode
+        assert_eq!(
+            Cycle::from("c4'-^7'ii e4'maj'd2")?.generate()?,
+            [[
+                Event::at(Fraction::from(0), Fraction::new(1, 2)).with_chord(0, 4, "-^7'ii"),
+                Event::at(Fraction::new(1, 2), Fraction::new(1, 2)).with_chord(4, 4, "maj'd2"),
+            ]]
+        );
+        assert!(Cycle::from("c4'-^7'x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn holds() -> Result<(), String> {
+        // holds extend the preceding event instead of retriggering it
+        assert_eq!(
+            Cycle::from("a _ _ b")?.generate()?,
+            [[
+                Event::at(Fraction::from(0), Fraction::new(3, 4)).with_note(9, 4),
+                Event::at(Fraction::new(3, 4), Fraction::new(1, 4)).with_note(11, 4),
+            ]]
+        );
+        assert_eq!(
+            Cycle::from("[a _ _ b] c")?.generate()?,
+            [[
+                Event::at(Fraction::from(0), Fraction::new(3, 8)).with_note(9, 4),
+                Event::at(Fraction::new(3, 8), Fraction::new(1, 8)).with_note(11, 4),
+                Event::at(Fraction::new(1, 2), Fraction::new(1, 2)).with_note(0, 4),
+            ]]
+        );
+        // holds at the start of a sequence have no event to extend
+        assert_eq!(
+            Cycle::from("_ a")?.generate()?,
+            [[Event::at(Fraction::new(1, 2), Fraction::new(1, 2)).with_note(9, 4)]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rotations() -> Result<(), String> {
+        // shifts by a fraction of the cycle, wrapping around
+        assert_eq!(
+            Cycle::from("[a b c d]~>0.25")?.generate()?,
+            [[
+                Event::at(Fraction::from(0), Fraction::new(1, 4)).with_note(2, 4),
+                Event::at(Fraction::new(1, 4), Fraction::new(1, 4)).with_note(9, 4),
+                Event::at(Fraction::new(1, 2), Fraction::new(1, 4)).with_note(11, 4),
+                Event::at(Fraction::new(3, 4), Fraction::new(1, 4)).with_note(0, 4),
+            ]]
+        );
+        assert_cycle_equality("[a b c d]<~0.25", "[b c d a]")?;
+        assert_cycle_equality("[a b c d]~>-0.25", "[b c d a]")?;
+        assert_cycle_equality("[a b c d]~>1.25", "[d a b c]")?;
+        assert_cycle_equality("[a b c d]~>0", "[a b c d]")?;
+        // composes with stacks: offset a layer by an eighth
+        assert_eq!(
+            Cycle::from("[a b], [c c c c]~>0.125")?.generate()?,
+            [
+                vec![
+                    Event::at(Fraction::from(0), Fraction::new(1, 2)).with_note(9, 4),
+                    Event::at(Fraction::new(1, 2), Fraction::new(1, 2)).with_note(11, 4),
+                ],
+                // the wrapped tail of the last step is not a new note
+                vec![
+                    Event::at(Fraction::new(1, 8), Fraction::new(1, 4)).with_note(0, 4),
+                    Event::at(Fraction::new(3, 8), Fraction::new(1, 4)).with_note(0, 4),
+                    Event::at(Fraction::new(5, 8), Fraction::new(1, 4)).with_note(0, 4),
+                    Event::at(Fraction::new(7, 8), Fraction::new(1, 8)).with_note(0, 4),
+                ],
+            ]
+        );
+        // rotated steps keep their place within groups
+        assert_cycle_equality("[a b]~>0.5 c", "[b a] c")?;
+        assert_cycle_equality("<[a b] [c d]>~>0.5", "<[b a] [d c]>")?;
+        assert!(Cycle::from("a~>").is_err());
+        assert!(Cycle::from("a~>b").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn signals() -> Result<(), String> {
+        let sample = |input: &str| -> Result<Vec<f64>, String> {
+            let events = Cycle::from(input)?.with_seed(1234).generate()?;
+            Ok(events[0]
+                .iter()
+                .filter(|event| event.value() != &Value::Rest)
+                .map(|event| match event.targets() {
+                    [Target::Named(name, Some(value))] if name.as_ref() == "v" => *value,
+                    targets => panic!("expected a sampled signal value, got {:?}", targets),
+                })
+                .collect())
+        };
+        let assert_samples = |input: &str, expected: &[f64]| -> Result<(), String> {
+            let samples = sample(input)?;
+            assert_eq!(samples.len(), expected.len(), "{}", input);
+            for (sample, expected) in samples.iter().zip(expected) {
+                assert!((sample - expected).abs() < 1e-9, "{}: {:?}", input, samples);
+            }
+            Ok(())
+        };
+        // signals get sampled at the center of each step
+        let sine = |phase: f64| ((std::f64::consts::TAU * phase).sin() + 1.0) / 2.0;
+        assert_samples(
+            "a*4 # gain sine",
+            &[sine(0.125), sine(0.375), sine(0.625), sine(0.875)],
+        )?;
+        assert_samples("a # gain sine", &[0.5])?;
+        assert_samples("a*2 # gain cosine", &[sine(0.5), sine(1.0)])?;
+        assert_samples("a*4 # gain saw", &[0.125, 0.375, 0.625, 0.875])?;
+        assert_samples("a*4 # gain isaw", &[0.875, 0.625, 0.375, 0.125])?;
+        assert_samples("a*4 # gain tri", &[0.25, 0.75, 0.75, 0.25])?;
+        assert_samples("a*4 # gain square", &[0.0, 0.0, 1.0, 1.0])?;
+        // at the center of the step's actual span
+        assert_samples("[a@3 a] # gain saw", &[0.375, 0.875])?;
+        assert_samples("[~ a]*2 # gain saw", &[0.375, 0.875])?;
+        // random signals are seeded
+        let random = sample("a*8 # gain rand")?;
+        assert!(random.iter().all(|r| (0.0..1.0).contains(r)));
+        assert_eq!(random, sample("a*8 # gain rand")?);
+        // and make cycles stateful
+        assert!(Cycle::from("a # gain rand")?.is_stateful());
+        assert!(Cycle::from("[a b] # pan [0.1 0.2] # gain rand")?.is_stateful());
+        assert!(!Cycle::from("a # gain saw")?.is_stateful());
+        assert!(!Cycle::from("rand random")?.is_stateful());
+        // signals are only valid as parameter values
+        let generate = |input: &str| Cycle::from(input).and_then(|mut cycle| cycle.generate());
+        assert!(generate("a # gain sawtooth").is_err());
+        assert!(generate("a # gain [sine saw]").is_err());
+        // and are no reserved names elsewhere
+        assert_eq!(
+            tokenize("sine sawtooth").map_err(|err| err.message)?,
+            vec![
+                Token {
+                    kind: TokenKind::Value,
+                    range: 0..4
+                },
+                Token {
+                    kind: TokenKind::Value,
+                    range: 5..13
+                },
+            ]
+        );
+        assert_eq!(
+            Cycle::from("sine:saw rand")?.generate()?,
+            [[
+                Event::at(Fraction::from(0), Fraction::new(1, 2))
+                    .with_name("sine")
+                    .with_target(Target::Named("saw".into(), None)),
+                Event::at(Fraction::new(1, 2), Fraction::new(1, 2)).with_name("rand")
+            ]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parameters() -> Result<(), String> {
+        let targets = |input: &str| -> Result<Vec<Vec<Target>>, String> {
+            let events = Cycle::from(input)?.with_seed(1234).generate()?;
+            Ok(events[0]
+                .iter()
+                .map(|event| event.targets().to_vec())
+                .collect())
+        };
+        let named = |name: &str, value: f64| Target::Named(name.into(), Some(value));
+        // continuous signals get sampled at the center of each left step
+        let sine = |phase: f64| ((std::f64::consts::TAU * phase).sin() + 1.0) / 2.0;
+        assert_eq!(
+            targets("[c4 e4 g4] # pan sine")?,
+            vec![
+                vec![named("p", sine(1.0 / 6.0))],
+                vec![named("p", sine(3.0 / 6.0))],
+                vec![named("p", sine(5.0 / 6.0))],
+            ]
+        );
+        assert_eq!(
+            targets("[a [b c]] # gain saw")?,
+            vec![
+                vec![named("v", 0.25)],
+                vec![named("v", 0.625)],
+                vec![named("v", 0.875)],
+            ]
+        );
+        // the left side keeps its structure
+        assert_eq!(
+            Cycle::from("[c4 e4 g4] # pan sine")?.generate()?[0]
+                .iter()
+                .map(|event| (event.span().clone(), event.value().clone()))
+                .collect::<Vec<_>>(),
+            Cycle::from("[c4 e4 g4]")?.generate()?[0]
+                .iter()
+                .map(|event| (event.span().clone(), event.value().clone()))
+                .collect::<Vec<_>>(),
+        );
+        // discrete values are taken from the right event at each left event's start
+        assert_eq!(
+            targets("[a b c d] # delay [0.1 0.2]")?,
+            vec![
+                vec![named("d", 0.1)],
+                vec![named("d", 0.1)],
+                vec![named("d", 0.2)],
+                vec![named("d", 0.2)],
+            ]
+        );
+        let mut cycle = Cycle::from("[a b] # gain <0.5 1>")?;
+        for gain in [0.5, 1.0, 0.5] {
+            let events = cycle.generate()?;
+            assert!(events[0]
+                .iter()
+                .all(|event| event.targets() == [named("v", gain)]));
+        }
+        // only random signals make parameter applications stateful
+        assert!(Cycle::from("[a b] # pan rand")?.is_stateful());
+        assert!(!Cycle::from("[brand random] # pan saw")?.is_stateful());
+        // parameters override existing targets and can be chained
+        assert_eq!(
+            targets("[a:v0.1:#2 b] # gain 0.5 # pan saw")?,
+            vec![
+                vec![Target::Index(2), named("v", 0.5), named("p", 0.25)],
+                vec![named("v", 0.5), named("p", 0.75)],
+            ]
+        );
+        // rests on the right side leave targets untouched
+        assert_eq!(
+            targets("[a:v0.1 b] # gain [~ 0.5]")?,
+            vec![vec![named("v", 0.1)], vec![named("v", 0.5)]]
+        );
+        // instrument targets still parse as steps
+        assert_eq!(targets("a #1")?, vec![vec![], vec![]]);
+        // unknown parameters and invalid values are errors
+        assert!(Cycle::from("[a b] # foo sine")
+            .is_err_and(|err| err.contains("unknown parameter 'foo'")));
+        assert!(Cycle::from("[a b] # gain foo")?.generate().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn operators() -> Result<(), String> {
+        // all operator rules of the grammar are listed
+        for rule in Rule::all_rules() {
+            if format!("{:?}", rule).starts_with("op_") && *rule != Rule::op_rotate {
+                assert!(
+                    CycleParser::operator_info(*rule).is_some(),
+                    "operator rule {:?} is not listed",
+                    rule
+                );
+            }
+        }
+        let mut listed_syntax = Vec::new();
+        for operator in super::operators() {
+            assert!(operator.min_arguments <= operator.max_arguments);
+            listed_syntax.extend(
+                operator
+                    .syntax
+                    .split(|c: char| c.is_whitespace() || c.is_alphabetic() || c == ',')
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string()),
+            );
+            if operator.kind == OperatorKind::Separator || operator.syntax == "(p,s,r)" {
+                listed_syntax.push(",".to_string());
+            }
+        }
+        for operator in super::operators() {
+            // examples are accepted by the parser and use the operator
+            Cycle::from(operator.example)?;
+            let tokens = tokenize(operator.example).map_err(|err| err.message)?;
+            let token_strings = tokens
+                .iter()
+                .filter(|token| {
+                    !matches!(
+                        token.kind,
+                        TokenKind::Value | TokenKind::Rest | TokenKind::Hold
+                    )
+                })
+                .map(|token| &operator.example[token.range.clone()])
+                .collect::<Vec<_>>();
+            let first_symbol = operator.syntax.split([' ', 'p']).next().unwrap();
+            assert!(
+                token_strings.contains(&first_symbol) || operator.syntax == "..",
+                "example '{}' does not use operator '{}'",
+                operator.example,
+                operator.syntax
+            );
+        }
+        // all operators the parser accepts are listed
+        for input in [
+            "bd(3,8,2) hh*[2 3] sn!3 cp@2 x? y?0.2 z/2",
+            "[a b]~>0.25 c<~0.5 {a b c}%4 <a b>",
+            "c4'maj e:v=0.5 f:#2 g:v0.3 $verse a . b | c, d !",
+            "[a b] # pan sine c # gain <0.5 1>",
+        ] {
+            for token in tokenize(input).map_err(|err| err.message)? {
+                let string = &input[token.range];
+                match token.kind {
+                    TokenKind::Operator
+                    | TokenKind::Separator
+                    | TokenKind::GroupOpen
+                    | TokenKind::GroupClose => assert!(
+                        listed_syntax.iter().any(|s| s == string)
+                            // target assignments
+                            || string == "=",
+                        "operator '{string}' is not listed"
+                    ),
+                    _ => (),
+                }
+            }
+        }
+        // operators the parser does not know are rejected
+        for input in ["a^2", "a&b", "a;"] {
+            assert!(Cycle::from(input).is_err(), "'{input}' should not parse");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn tokens() -> Result<(), String> {
+        fn token_strings(input: &str) -> Vec<(TokenKind, &str)> {
+            tokenize(input)
+                .unwrap()
+                .into_iter()
+                .map(|token| (token.kind, &input[token.range]))
+                .collect()
+        }
+        // tokens are sorted and cover all non whitespace characters
+        for input in [
+            "a b c",
+            "[a b, c d] <e f>",
+            "{a b c}%4 ~ _ -",
+            "bd(3,8,2) hh*[2 3] sn!3 cp@2 x? y?0.2",
+            "[a b]~>0.25 c<~0.5",
+            "c4'maj e:v=0.5 f:#2 g:v0.3 $verse 1..4 a . b | c",
+            "a b c!",
+            "",
+        ] {
+            let tokens = tokenize(input).map_err(|err| err.message)?;
+            let mut position = 0;
+            for token in &tokens {
+                assert!(
+                    token.range.start >= position,
+                    "unsorted tokens in '{input}'"
+                );
+                assert!(input[position..token.range.start].trim().is_empty());
+                position = token.range.end;
+            }
+            assert!(input[position..].trim().is_empty());
+        }
+        // classification
+        use TokenKind::*;
+        assert_eq!(
+            token_strings("[a ~, _ $b]"),
+            [
+                (GroupOpen, "["),
+                (Value, "a"),
+                (Rest, "~"),
+                (Separator, ","),
+                (Hold, "_"),
+                (Reference, "$b"),
+                (GroupClose, "]"),
+            ]
+        );
+        assert_eq!(
+            token_strings("<a b>*2 c!3 d@2 e?0.5"),
+            [
+                (GroupOpen, "<"),
+                (Value, "a"),
+                (Value, "b"),
+                (GroupClose, ">"),
+                (Operator, "*"),
+                (Value, "2"),
+                (Value, "c"),
+                (Operator, "!"),
+                (Value, "3"),
+                (Value, "d"),
+                (Operator, "@"),
+                (Value, "2"),
+                (Value, "e"),
+                (Operator, "?"),
+                (Value, "0.5"),
+            ]
+        );
+        assert_eq!(
+            token_strings("{a b}%3 bd(3,8) c:v=0.5 d~>0.25 1..2 e !"),
+            [
+                (GroupOpen, "{"),
+                (Value, "a"),
+                (Value, "b"),
+                (GroupClose, "}"),
+                (Operator, "%"),
+                (Value, "3"),
+                (Value, "bd"),
+                (Operator, "("),
+                (Value, "3"),
+                (Operator, ","),
+                (Value, "8"),
+                (Operator, ")"),
+                (Value, "c"),
+                (Operator, ":"),
+                (Value, "v"),
+                (Operator, "="),
+                (Value, "0.5"),
+                (Value, "d"),
+                (Operator, "~>"),
+                (Value, "0.25"),
+                (Value, "1"),
+                (Operator, ".."),
+                (Value, "2"),
+                (Value, "e"),
+                (Operator, "!"),
+            ]
+        );
+        assert_eq!(
+            token_strings("a | b . c"),
+            [
+                (Value, "a"),
+                (Separator, "|"),
+                (Value, "b"),
+                (Separator, "."),
+                (Value, "c"),
+            ]
+        );
+        // syntax errors have a position
+        let err = tokenize("a b [c").unwrap_err();
+        assert!(!err.message.is_empty());
+        assert_eq!(err.range, 6..6);
+        let err = tokenize("a b] c").unwrap_err();
+        assert_eq!(err.range, 3..4);
+        Ok(())
+    }
+
     #[test]
     fn event_limit() -> Result<(), String> {
         assert!(Cycle::from("[[a b c d]*100]*100")?.generate().is_err());
@@ -3147,4 +4448,32 @@ mod test {
         )?;
         Ok(())
     }
+
+    #[test]
+    fn references() -> Result<(), String> {
+        let bindings = [
+            ("verse", Cycle::from("a b c")?),
+            ("chorus", Cycle::from("<d e>*2")?),
+        ];
+        let seed = rand::rng().random();
+        let assert_references = |a: &str, b: &str| -> Result<(), String> {
+            let mut a = Cycle::from_with_bindings(a, &bindings)?.with_seed(seed);
+            let mut b = Cycle::from(b)?.with_seed(seed);
+            for _ in 0..4 {
+                assert_eq!(a.generate()?, b.generate()?);
+            }
+            Ok(())
+        };
+        assert_references("$verse", "[a b c]")?;
+        assert_references("[$verse $verse $chorus]", "[[a b c] [a b c] [<d e>*2]]")?;
+        assert_references("<$verse $chorus>", "<[a b c] [<d e>*2]>")?;
+        assert_references("$verse!2 $chorus?", "[a b c]!2 [<d e>*2]?")?;
+        assert_references("{$verse, $chorus}%4", "{[a b c], [<d e>*2]}%4")?;
+        assert_references("a*$verse", "a*[a b c]")?;
+
+        assert!(Cycle::from_with_bindings("$verse $bridge", &bindings).is_err());
+        assert!(Cycle::from("$verse").is_err());
+        assert!(Cycle::from("$").is_err());
+        Ok(())
+    }
 }
@@ -3,10 +3,10 @@
 use std::fmt::Debug;
 
 mod beats;
-pub use beats::{BeatTimeBase, BeatTimeStep};
+pub use beats::{BeatTimeBase, BeatTimeStep, TimeSignature};
 
 mod seconds;
-pub use seconds::{SecondTimeBase, SecondTimeStep};
+pub use seconds::{MsTimeStep, SecondTimeBase, SecondTimeStep};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -17,6 +17,43 @@ pub type SampleTime = u64;
 /// Sample time as real number value, used to keep track of other units as sample time.
 pub type ExactSampleTime = f64;
 
+/// Rounding mode, used to convert exact, fractional sample times to [`SampleTime`]s.
+///
+/// Exact sample times are tracked with their fractional remainders, so the rounding mode only
+/// affects single event positions: rounding errors never accumulate over time.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SampleTimeRounding {
+    /// Truncate fractional sample positions, so events never play earlier than their exact
+    /// time. This is the default.
+    #[default]
+    Floor,
+    /// Round fractional sample positions to the nearest sample, which halves the maximum
+    /// timing error of single events.
+    Nearest,
+}
+
+impl SampleTimeRounding {
+    /// Convert an exact sample time to a sample time with this rounding mode.
+    ///
+    /// Exact sample times which are a tiny bit below the next full sample, because of floating
+    /// point errors which accumulate when summing up step durations, snap to the next sample, so
+    /// events do not drift to earlier samples over time.
+    #[inline]
+    pub fn to_sample_time(self, time: ExactSampleTime) -> SampleTime {
+        const EPSILON: ExactSampleTime = 1e-3;
+        match self {
+            Self::Floor => (time + EPSILON).trunc() as SampleTime,
+            Self::Nearest => (time + EPSILON).round() as SampleTime,
+        }
+    }
+}
+
+/// Convert an exact sample time to a sample time with the default rounding mode.
+#[inline]
+pub(crate) fn to_sample_time(time: ExactSampleTime) -> SampleTime {
+    SampleTimeRounding::default().to_sample_time(time)
+}
+
 // -------------------------------------------------------------------------------------------------
 
 /// Convert sample times to strings in [`SampleTimeBase`] impls.
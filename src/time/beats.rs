@@ -27,6 +27,84 @@ impl BeatTimeBase {
     }
 }
 
+impl BeatTimeBase {
+    /// The time base's time signature: `beats_per_bar` quarter notes per bar.
+    /// Use a custom [`TimeSignature`] to describe other note values or compound meters.
+    pub fn time_signature(&self) -> TimeSignature {
+        TimeSignature {
+            numerator: self.beats_per_bar.max(1),
+            denominator: 4,
+        }
+    }
+}
+
+impl BeatTimeBase {
+    /// Resolution of the ticks in musical position strings: ticks per beat.
+    pub const TICKS_PER_BEAT: u32 = 960;
+
+    /// Format the given sample time as one-based `bars.beats.ticks` musical position string,
+    /// e.g. "1.1.000" for sample time 0. See [`Self::TICKS_PER_BEAT`] for the tick resolution.
+    /// Sample times get rounded to the nearest tick.
+    pub fn format_time(&self, sample_time: SampleTime) -> String {
+        let samples_per_beat = self.samples_per_beat();
+        let total_ticks = if samples_per_beat > 0.0 {
+            (sample_time as f64 / samples_per_beat * Self::TICKS_PER_BEAT as f64).round() as u64
+        } else {
+            0
+        };
+        let total_beats = total_ticks / Self::TICKS_PER_BEAT as u64;
+        let ticks = total_ticks % Self::TICKS_PER_BEAT as u64;
+        let beats_per_bar = self.beats_per_bar.max(1) as u64;
+        let bars = total_beats / beats_per_bar;
+        let beats = total_beats % beats_per_bar;
+        format!("{}.{}.{:03}", bars + 1, beats + 1, ticks)
+    }
+
+    /// Parse a one-based musical position string, as generated by [`Self::format_time`], into
+    /// a sample time. Parts may be separated by `.` or `:`, and beats and ticks are optional,
+    /// so "2", "2.3" and "2:3:480" are valid positions too.
+    pub fn parse_time(&self, position: &str) -> Result<SampleTime, String> {
+        let parts = position
+            .trim()
+            .split(['.', ':'])
+            .map(|part| {
+                part.trim().parse::<u64>().map_err(|err| {
+                    format!("invalid musical position '{}': {}", position.trim(), err)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (bars, beats, ticks) = match parts.as_slice() {
+            [bars] => (*bars, 1, 0),
+            [bars, beats] => (*bars, *beats, 0),
+            [bars, beats, ticks] => (*bars, *beats, *ticks),
+            _ => {
+                return Err(format!(
+                    "invalid musical position '{}': expected 'bars.beats.ticks'",
+                    position.trim()
+                ))
+            }
+        };
+        let beats_per_bar = self.beats_per_bar.max(1) as u64;
+        if bars == 0 || beats == 0 || beats > beats_per_bar {
+            return Err(format!(
+                "invalid musical position '{}': bars must be > 0 and beats in range [1 - {}]",
+                position.trim(),
+                beats_per_bar
+            ));
+        }
+        if ticks >= Self::TICKS_PER_BEAT as u64 {
+            return Err(format!(
+                "invalid musical position '{}': ticks must be < {}",
+                position.trim(),
+                Self::TICKS_PER_BEAT
+            ));
+        }
+        let total_beats = ((bars - 1) * beats_per_bar + (beats - 1)) as f64
+            + ticks as f64 / Self::TICKS_PER_BEAT as f64;
+        Ok((total_beats * self.samples_per_beat()).round() as SampleTime)
+    }
+}
+
 impl From<BeatTimeBase> for SecondTimeBase {
     fn from(val: BeatTimeBase) -> Self {
         SecondTimeBase {
@@ -45,13 +123,109 @@ impl SampleTimeBase for BeatTimeBase {
 impl SampleTimeDisplay for BeatTimeBase {
     /// generate a bar.beat.ppq string representation of the the given sample time.
     fn display(&self, sample_time: SampleTime) -> String {
-        let total_beats = sample_time / self.samples_per_beat() as u64;
-        let total_beats_f = sample_time as f64 / self.samples_per_beat();
-        let beat_fractions = total_beats_f - total_beats as f64;
-        let bars = total_beats / self.beats_per_bar as u64;
-        let beats = total_beats - self.beats_per_bar as u64 * bars;
-        let ppq = (beat_fractions * 960.0 + 0.5) as u64;
-        format!("{}.{}.{:03}", bars + 1, beats + 1, ppq)
+        self.format_time(sample_time)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Musical time signature, such as 4/4, 3/4 or 6/8.
+///
+/// [`BeatTimeBase`] counts bars in quarter note beats only, so it can't tell a 6/8 from a 3/4
+/// bar. A time signature additionally defines how a bar's beats are felt: compound meters,
+/// such as 6/8, 9/8 or 12/8, group their notes into dotted beats of three, so a 6/8 bar has
+/// two dotted quarter beats while a 3/4 bar has three quarter beats.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimeSignature {
+    numerator: u32,
+    denominator: u32,
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self {
+            numerator: 4,
+            denominator: 4,
+        }
+    }
+}
+
+impl TimeSignature {
+    /// Create a new time signature. The numerator must be > 0 and the denominator
+    /// a power of two in range \[1 - 64\].
+    pub fn new(numerator: u32, denominator: u32) -> Result<Self, String> {
+        if numerator == 0 {
+            return Err("time signature numerator must be > 0".to_string());
+        }
+        if !denominator.is_power_of_two() || denominator > 64 {
+            return Err(format!(
+                "time signature denominator must be a power of two in range [1 - 64], got {}",
+                denominator
+            ));
+        }
+        Ok(Self {
+            numerator,
+            denominator,
+        })
+    }
+
+    /// Number of notes in a bar.
+    pub fn numerator(&self) -> u32 {
+        self.numerator
+    }
+    /// Note value of the numerator's notes: 4 = quarter, 8 = eighth notes.
+    pub fn denominator(&self) -> u32 {
+        self.denominator
+    }
+
+    /// True for compound meters such as 6/8, 9/8 or 12/16, which group notes into
+    /// dotted beats of three notes each.
+    pub fn is_compound(&self) -> bool {
+        self.denominator >= 8 && self.numerator > 3 && self.numerator.is_multiple_of(3)
+    }
+
+    /// Number of felt beats in a bar: e.g. 3 for 3/4 and 2 for 6/8.
+    pub fn beats_per_bar(&self) -> u32 {
+        if self.is_compound() {
+            self.numerator / 3
+        } else {
+            self.numerator
+        }
+    }
+
+    /// Length of a single felt beat in quarter notes: e.g. 1 for 3/4 and 1.5 for 6/8.
+    pub fn beat_length(&self) -> f64 {
+        let note_length = 4.0 / self.denominator as f64;
+        if self.is_compound() {
+            note_length * 3.0
+        } else {
+            note_length
+        }
+    }
+
+    /// Length of a bar in quarter notes: e.g. 3 for 3/4 and 6/8.
+    pub fn bar_length(&self) -> f64 {
+        self.numerator as f64 * 4.0 / self.denominator as f64
+    }
+
+    /// Sample times of all felt beats in a bar, relative to the bar start, in the given time
+    /// base. The first beat is the bar's downbeat.
+    pub fn beat_sample_times(&self, time_base: &BeatTimeBase) -> Vec<ExactSampleTime> {
+        let samples_per_beat = self.beat_length() * time_base.samples_per_beat();
+        (0..self.beats_per_bar())
+            .map(|beat| beat as ExactSampleTime * samples_per_beat)
+            .collect()
+    }
+
+    /// Length of a bar in samples in the given time base.
+    pub fn samples_per_bar(&self, time_base: &BeatTimeBase) -> ExactSampleTime {
+        self.bar_length() * time_base.samples_per_beat()
+    }
+}
+
+impl std::fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
     }
 }
 
@@ -123,3 +297,97 @@ impl Default for BeatTimeStep {
         Self::Beats(0.0)
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn time_signature() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        assert_eq!(time_base.time_signature(), TimeSignature::default());
+        assert!(TimeSignature::new(0, 4).is_err());
+        assert!(TimeSignature::new(4, 3).is_err());
+        // 3/4: three quarter beats
+        let three_four = TimeSignature::new(3, 4).unwrap();
+        assert!(!three_four.is_compound());
+        assert_eq!(
+            three_four.beat_sample_times(&time_base),
+            vec![0.0, 22050.0, 44100.0]
+        );
+        assert_eq!(three_four.samples_per_bar(&time_base), 66150.0);
+        // 6/8: two dotted quarter beats with the same bar length
+        let six_eight = TimeSignature::new(6, 8).unwrap();
+        assert!(six_eight.is_compound());
+        assert_eq!(six_eight.beats_per_bar(), 2);
+        assert_eq!(six_eight.beat_length(), 1.5);
+        assert_eq!(six_eight.beat_sample_times(&time_base), vec![0.0, 33075.0]);
+        assert_eq!(six_eight.samples_per_bar(&time_base), 66150.0);
+        assert_eq!(six_eight.to_string(), "6/8");
+        // odd eighth meters are not compound
+        let seven_eight = TimeSignature::new(7, 8).unwrap();
+        assert_eq!(seven_eight.beats_per_bar(), 7);
+        assert_eq!(seven_eight.bar_length(), 3.5);
+    }
+
+    #[test]
+    fn musical_positions() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        // one beat = 22050 samples, one bar = 88200 samples
+        assert_eq!(time_base.format_time(0), "1.1.000");
+        assert_eq!(time_base.format_time(11025), "1.1.480");
+        assert_eq!(time_base.format_time(22050), "1.2.000");
+        assert_eq!(time_base.format_time(88200 + 3 * 22050 + 5512), "2.4.240");
+        // rounds to the nearest tick without overflowing into the next beat
+        assert_eq!(time_base.format_time(22049), "1.2.000");
+        assert_eq!(time_base.format_time(22050 * 16), "5.1.000");
+        // display uses the same format
+        assert_eq!(time_base.display(11025), time_base.format_time(11025));
+
+        assert_eq!(time_base.parse_time("1.1.000"), Ok(0));
+        assert_eq!(
+            time_base.parse_time("2.4.240"),
+            Ok(88200 + 3 * 22050 + 5513)
+        );
+        assert_eq!(time_base.parse_time("2"), Ok(88200));
+        assert_eq!(time_base.parse_time(" 3:2 "), Ok(2 * 88200 + 22050));
+        assert!(time_base.parse_time("").is_err());
+        assert!(time_base.parse_time("0.1.000").is_err());
+        assert!(time_base.parse_time("1.5.000").is_err());
+        assert!(time_base.parse_time("1.1.960").is_err());
+        assert!(time_base.parse_time("1.1.0.0").is_err());
+        assert!(time_base.parse_time("1.x").is_err());
+
+        // round trips
+        for time_base in [
+            time_base,
+            BeatTimeBase {
+                beats_per_min: 93.0,
+                beats_per_bar: 3,
+                samples_per_sec: 48000,
+            },
+        ] {
+            for position in ["1.1.000", "1.3.001", "7.2.959", "123.1.480"] {
+                let sample_time = time_base.parse_time(position).unwrap();
+                assert_eq!(time_base.format_time(sample_time), position);
+            }
+            for sample_time in [0, 1234, 48000, 999_999] {
+                let position = time_base.format_time(sample_time);
+                let parsed = time_base.parse_time(&position).unwrap();
+                // a tick is longer than a sample: round trips are exact up to half a tick
+                let half_tick = time_base.samples_per_beat() / 960.0 / 2.0;
+                assert!((parsed as f64 - sample_time as f64).abs() <= half_tick + 1.0);
+            }
+        }
+    }
+}
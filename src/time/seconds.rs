@@ -29,3 +29,16 @@ impl SampleTimeDisplay for SecondTimeBase {
 
 /// Defines a number of seconds in [`SecondTimeBase`].
 pub type SecondTimeStep = f64;
+
+/// Defines a number of milliseconds in [`SecondTimeBase`].
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, PartialOrd, derive_more::From, derive_more::Into,
+)]
+pub struct MsTimeStep(pub f64);
+
+impl MsTimeStep {
+    /// The step's duration in seconds.
+    pub fn to_seconds(&self) -> SecondTimeStep {
+        self.0 / 1000.0
+    }
+}
@@ -27,6 +27,11 @@ error("Do not try to execute this file. It's just a type definition file.")
 ---- "9 -> "nine"
 ---- "11" -> "eleven"
 ---
+---Chord voicings can be appended to the chord name, separated by `'` characters:
+---- "i", "ii", "iii" or "i<N>" -> inversion: moves the lowest note(s) up an octave
+---- "d<N>" -> drop-N voicing: moves the N-th highest note down an octave, e.g. "d2"
+---- "o" or "o<N>" -> open voicing: moves every second note up by one or N octaves
+---
 ---### examples:
 ---```lua
 ---chord("c4", "minor") --> {"c4", "d#4", "f4"}
@@ -41,6 +46,12 @@ error("Do not try to execute this file. It's just a type definition file.")
 ---note(scale("c4", "major"):chord("i", 3))
 ---note(scale("c4", "major"):chord("i", 3)):volume(0.5)
 ---```
+---```lua
+----- voicings
+---chord("c4", "maj7'i") --> {"e4", "g4", "b4", "c5"}
+---chord("c4", "maj7'd2") --> {"g3", "c4", "e4", "b4"}
+---note("c4'maj7'ii")
+---```
 ---@param key NoteValue e.g. "c4" or 48
 ---@param mode ChordName
 ---@return Note
@@ -11,6 +11,10 @@ error("Do not try to execute this file. It's just a type definition file.")
 ---
 ---Specifies how the cycle currently is running.
 ---@field playback PlaybackState
+---True when the cycle currently is seeking, same as `playback == "seeking"`.
+---@field seeking boolean
+---True when the cycle currently is running, same as `playback == "running"`.
+---@field running boolean
 ---channel/voice index within the cycle. each channel in the cycle gets emitted and thus mapped
 ---separately, starting with the first channel index 1.
 ---@field channel integer
@@ -95,6 +99,8 @@ function Cycle:map(map) end
 ---  allows setting note attributes such as instrument/volume/pan/delay (e.g. `c4:v0.1:p0.5`)
 ---* In bjorklund expressions, operators *within* and on the *right side* are not supported
 ---  (e.g. `bd(<3 2>, 8)` and `bd(3, 8)*2` are *not* supported)
+---* `$name` references a named sub-cycle from the optional `bindings` table, which gets
+---  inserted as a group (e.g. `$verse` with `{ verse = "a b c" }` plays `[a b c]`)
 ---
 ---[Tidal Cycles Reference](https://tidalcycles.org/docs/reference/mini_notation/)
 ---
@@ -115,7 +121,15 @@ function Cycle:map(map) end
 -----Map custom identifiers to notes
 ---cycle("bd(3,8)"):map({ bd = "c4 #1" })
 --- ```
+---```lua
+-----Reuse named sub-cycles
+---cycle("$verse $verse $chorus", {
+---  verse = "c4 e4 g4",
+---  chorus = "<a4 b4> c5"
+---})
+--- ```
 ---@param input string
+---@param bindings { [string]: string }?
 ---@return Cycle
 ---@nodiscard
-function cycle(input) end
+function cycle(input, bindings) end
@@ -0,0 +1,34 @@
+---@meta
+error("Do not try to execute this file. It's just a type definition file.")
+---
+---Part of the pattrns crate: Defines LuaLS annotations for the pattrns euclidean function.
+---
+
+----------------------------------------------------------------------------------------------------
+
+---Generate a [Euclidean Rhythm](https://en.wikipedia.org/wiki/Euclidean_rhythm): distributes the
+---given number of pulses as evenly as possible within the given number of steps. Positive rotation
+---values rotate the rhythm to the left, negative ones to the right.
+---
+---Uses the same implementation as the Euclidean operator in cycles, e.g. `"x(5,8)"`. To create
+---pulse tables with custom values from existing pulses, use `pulse.euclidean` instead.
+---
+---### examples:
+---```lua
+---euclidean(5, 8) --> {true,false,true,true,false,true,true,false}
+---euclidean(3, 8, 3) --> {true,false,false,true,false,true,false,false}
+---```
+---```lua
+----- feed a euclidean rhythm into a pattern's pulse
+---return pattern {
+---  unit = "1/16",
+---  pulse = euclidean(7, 16),
+---  event = "c4"
+---}
+---```
+---@param pulses integer Number of on steps in the rhythm, in range [0 - 4096].
+---@param steps integer Number of total steps in the rhythm, in range [0 - 4096].
+---@param rotation integer? Optional rotation offset. By default 0.
+---@return boolean[]
+---@nodiscard
+function euclidean(pulses, steps, rotation) end
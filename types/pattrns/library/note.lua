@@ -8,10 +8,15 @@ error("Do not try to execute this file. It's just a type definition file.")
 
 ---@class NoteTable
 ---@field key string|number Note key & octave string (or MIDI note number as setter)
----@field instrument number? Instrument/Sample/Patch >= 0
+---@field instrument (number|number[])? Instrument/Sample/Patch >= 0. When an array of instruments is
+---specified, a random one is picked with the global random number generator.
 ---@field volume number? Volume in range [0.0 - 1.0]
 ---@field panning number? Panning factor in range [-1.0 - 1.0] where 0 is center
 ---@field delay number? Delay factor in range [0.0 - 1.0]
+---@field speed number? Fixed playback speed > 0, where 1 is the sample's original speed.
+---When set, the playback speed no longer follows the note's key, e.g. for one-shot drums.
+---@field ratchet integer? Number of evenly spaced retriggers of the note within its step in
+---range [1 - 64]. The first hit plays at the note's delay, the last one ends with the step.
 local NoteTable = {}
 
 ----------------------------------------------------------------------------------------------------
@@ -80,6 +85,22 @@ function Note:panning(panning) end
 ---@nodiscard
 function Note:delay(delay) end
 
+---Get the equal tempered frequencies of the notes in Hz, using the given frequency of A4 as
+---reference (440 Hz by default). Returns one frequency for each note in the note, and nil
+---for empty notes and note offs.
+---
+---### examples:
+---```lua
+---note("a4"):to_hz() --> 440
+---note("c4"):to_hz() --> 261.63
+---note("a3"):to_hz(432) --> 216
+---note("a3", "off", "a4"):to_hz() --> 220, nil, 440
+---```
+---@param a4_hz number? reference frequency of A4 in Hz, 440 by default
+---@return number? ...
+---@nodiscard
+function Note:to_hz(a4_hz) end
+
 ----------------------------------------------------------------------------------------------------
 
 ---@alias NoteValue Note|NoteTable|string|number|nil
@@ -112,6 +133,19 @@ function Note:delay(delay) end
 ---@overload fun(...: NoteValue): Note
 function note(...) end
 
+---Create a note-off, which stops the playing note in the given voice (column) only.
+---Other voices in the note stay empty, so they continue playing.
+---
+---### examples:
+---```lua
+---note_off() --> stops the note in the first voice, same as note("off")
+---note_off(2) --> stops the note in the second voice of a chord only
+---```
+---@param voice integer? 1-based voice index in range [1 - 128], 1 by default
+---@return Note
+---@nodiscard
+function note_off(voice) end
+
 ---Convert a note string or note table to a raw MIDI note number in range 0-127
 ---or 0xFE for nil or empty note strings
 ---or 0xFF for note offs
@@ -126,3 +160,18 @@ function note(...) end
 ---@param note NoteValue
 ---@return integer
 function note_number(note) end
+
+---Create a new note from a frequency in Hz > 0, using the nearest equal tempered note and the
+---given frequency of A4 as reference (440 Hz by default). Frequencies outside of the valid
+---note range get clamped to the lowest or highest note.
+---### examples:
+---```lua
+---note.from_hz(440) --> A4 note
+---note.from_hz(262) --> C4 note
+---note.from_hz(216, 432) --> A3 note
+---```
+---@param hz number frequency in Hz > 0
+---@param a4_hz number? reference frequency of A4 in Hz, 440 by default
+---@return Note
+---@nodiscard
+function note.from_hz(hz, a4_hz) end
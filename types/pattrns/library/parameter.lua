@@ -83,3 +83,60 @@ function parameter.number(id, default, range, name, description) end
 ---@param description ParameterDescription?
 ---@return Parameter
 function parameter.enum(id, default, values, name, description) end
+
+---Creates an enum Parameter like `parameter.enum`, which passes the one-based index of
+---the selected value as "integer" Lua type to contexts, e.g. to use it as array index.
+---
+---### examples:
+---```lua
+---local notes = { "c4", "e4", "g4" }
+---return pattern {
+---  parameter = { parameter.enum_index("note", "e4", notes) },
+---  event = function(context) return notes[context.parameter.note] end
+---}
+---```
+---@param id ParameterId
+---@param default ParameterEnumDefault
+---@param values string[]
+---@param name ParameterName?
+---@param description ParameterDescription?
+---@return Parameter
+function parameter.enum_index(id, default, values, name, description) end
+
+----------------------------------------------------------------------------------------------------
+
+---Parameter change user data, which automates a host parameter. Create parameter changes
+---via the global `parameter_change(...)` function.
+---@class ParameterChange : userdata
+---Parameter id of the change, as passed to `parameter_change`.
+---@field parameter string|integer
+---Value of the parameter change.
+---@field value number
+local ParameterChange = {}
+
+----------------------------------------------------------------------------------------------------
+
+---Create a parameter change event, e.g. to automate a synth or effect parameter from within
+---a pattern's `event` function. Integer ids are passed to the host as they are, string ids as
+---stable 64-bit FNV-1a hashes of the id string. The highest bit of hashed ids is always set, so
+---they never collide with integer ids.
+---
+---Parameter changes can be returned as they are or mixed with notes in tables, e.g.
+---`{ "c4", parameter_change("cutoff", 0.5) }`.
+---
+---### examples:
+---```lua
+---return pattern {
+---  event = function(context)
+---    if context.step % 4 == 1 then
+---      return parameter_change("cutoff", math.random())
+---    end
+---    return "c4"
+---  end
+---}
+---```
+---@param id string|integer
+---@param value number
+---@return ParameterChange
+---@nodiscard
+function parameter_change(id, value) end
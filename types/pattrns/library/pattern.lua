@@ -10,8 +10,9 @@ error("Do not try to execute this file. It's just a type definition file.")
 ---Contains information about what triggered the patterns and allows accessing parameter values.
 ---@class TriggerContext
 ---Note that triggered the pattern, if any. Usually will ne a monophic note.
----To access the raw note number value use: `context.trigger.notes[1].key`
----@field trigger Note?
+---To access the trigger note's values use e.g.: `context.trigger.key` or `context.trigger.volume`
+---To access all notes of polyphonic triggers use: `context.trigger.notes`
+---@field trigger TriggerNote?
 ---Current parameter values: parameter ids are keys, parameter values are values.
 ---To access a parameter with id `enabled` use: `context.parameter.enabled`
 ---@field parameter table<string, number|integer|boolean|string>
@@ -28,6 +29,23 @@ error("Do not try to execute this file. It's just a type definition file.")
 ---@field beats_per_bar integer
 ---Project's audio playback sample rate in samples per second.
 ---@field samples_per_sec integer
+---Smoothed output level of the pattern's played notes in range [0 - 1]. Only available
+---when running in a player, which feeds back levels with a latency of one processing
+---block, so this is a best-effort estimate only. Always 0 when there's no player.
+---@field level number
+---Notes which currently are playing in the pattern's slot, ordered by voice. Like `level`
+---only available when running in a player, which feeds back playing notes with a latency of
+---one processing block. Always empty when there's no player.
+---@field playing_notes NoteTable[]
+---Random number generator of the function. Pulse, gate and event functions each have their
+---own generator, which gets seeded with the pattern's `seed`, when set. Unlike `math.random`,
+---its state is not shared with other patterns, so it produces reproducible results for
+---duplicated patterns too.
+---### examples:
+---```lua
+---local velocity = 0.5 + context.rng:number() * 0.5
+---```
+---@field rng ContextRng
 
 ----------------------------------------------------------------------------------------------------
 
@@ -70,14 +88,103 @@ error("Do not try to execute this file. It's just a type definition file.")
 ---
 ---Specifies how the pattern currently is running.
 ---@field playback PlaybackState
+---True when the pattern currently is seeking, same as `playback == "seeking"`.
+---@field seeking boolean
+---True when the pattern currently is running, same as `playback == "running"`.
+---@field running boolean
 ---Continues step counter, incrementing with each new *emitted* pulse.
 ---Unlike `pulse_step` this does not include skipped, zero values pulses so it basically counts
 ---how often the event function already got called.
 ---Starts from 1 when the pattern starts running or is reset.
 ---@field step integer
+---Flattened values of the pattern's `pulse` array, with subdivisions expanded, so the current
+---pulse's value is `context.pulses[context.pulse_step]` in the first pulse cycle.
+---Only set when `pulse` is a fixed array. When `pulse` is a function, this is nil.
+---@field pulses number[]?
+---Musical position of the current pulse since the pattern started running or got reset,
+---calculated from `pulse_time_step` and the pattern's unit.
+---### examples:
+---```lua
+----- transpose every second bar
+---local transpose = math.floor(context.playback_position.bars) % 2 == 1 and 12 or 0
+---```
+---@field playback_position PlaybackPosition
+---Duration of a single pattern step, as specified by the pattern's unit. Updated when the
+---pattern's tempo changes. Not to be confused with `step_length` in cycle map functions, which
+---is a fraction of the cycle.
+---### examples:
+---```lua
+----- delay the next note by a quarter step, in seconds
+---local delay_seconds = context.step_duration.seconds / 4
+---```
+---@field step_duration StepDuration
+---Schedule a function to run once, the given number of beats after the current pulse.
+---The function gets called with the event context of the first pulse at or after the
+---scheduled time, also when that pulse is a rest, and its resulting notes are emitted along
+---with the pulse's notes. Pending scheduled functions get discarded when the pattern resets.
+---### examples:
+---```lua
+----- trigger a response 2 beats after each note
+---context.schedule(2, function(context)
+---  return "e4"
+---end)
+---```
+---@field schedule fun(beats: number, callback: fun(context: EventContext):NoteValue|ParameterChange)
+
+---Musical position of an event in the pattern.
+---@class PlaybackPosition
+---Position in beats, starting from 0.
+---@field beats number
+---Position in bars, starting from 0. Uses the time base's `beats_per_bar`.
+---@field bars number
+
+---Random number generator in event contexts.
+---@class ContextRng
+local ContextRng = {}
+
+---Returns a random number in range [0 - 1).
+---@return number
+function ContextRng:number() end
+
+---Returns a random integer in range [min - max].
+---@param min integer
+---@param max integer
+---@return integer
+function ContextRng:integer(min, max) end
+
+---Returns a randomly picked value from the given non empty array.
+---@param table any[]
+---@return any
+function ContextRng:choice(table) end
+
+---Duration of a single step in the pattern.
+---@class StepDuration
+---Step duration in samples, using the time base's `samples_per_sec`.
+---@field samples number
+---Step duration in seconds.
+---@field seconds number
+
+---Note which triggered the pattern.
+---@class TriggerNote
+---Key of the first trigger note.
+---@field key string?
+---Instrument of the first trigger note, if any.
+---@field instrument number?
+---Volume of the first trigger note.
+---@field volume number?
+---Panning of the first trigger note.
+---@field panning number?
+---Delay of the first trigger note.
+---@field delay number?
+---All trigger notes, including empty notes, e.g. of polyphonic triggers.
+---@field notes NoteTable[]
 
 ----------------------------------------------------------------------------------------------------
 
+---Gate value which passes the pulse and holds the triggered notes for a number of pulses.
+---@class GateHold
+---@field hold integer Number of pulses the triggered notes are held, >= 1.
+
 ---Single pulse value or a nested subdivision of pulses within a rhythm's pulse.
 ---@alias PulseValue (0|1|number|boolean|nil)|(PulseValue)[]
 
@@ -220,7 +327,8 @@ error("Do not try to execute this file. It's just a type definition file.")
 ---By default a threshold gate, which passes all pulse values greater than zero.
 ---
 ---Functions return true when a pulse value should be passed, and false when the event
----emitter should be skipped.
+---emitter should be skipped. Returning a `{ hold = pulses }` table passes the pulse and holds
+---the triggered notes for the given number of pulses, then stops them with note-offs.
 ---
 ---### examples:
 ---```lua
@@ -236,7 +344,13 @@ error("Do not try to execute this file. It's just a type definition file.")
 ---  return context.pulse_value > 0.5
 ---end
 ---```
----@field gate? (fun(context: GateContext):boolean)|(fun(context: GateContext):fun(context: GateContext):boolean)
+---```lua
+----- hold gate: plays every 4th pulse for 2 pulses
+---gate = function(context)
+---  return (context.pulse_step - 1) % 4 == 0 and { hold = 2 } or false
+---end
+---```
+---@field gate? (fun(context: GateContext):boolean|GateHold)|(fun(context: GateContext):fun(context: GateContext):boolean|GateHold)
 ---
 ---Specify the event values of the pattern. For every pulse in the pulse pattern, an event
 ---is picked from the specified event sequence. When the end of the sequence is reached, it starts
@@ -259,6 +373,10 @@ error("Do not try to execute this file. It's just a type definition file.")
 ---event = {{"c4", "d#4", "g4"}} -- or {"c4'min"}
 ---```
 ---```lua
+----- a chord of c4, e4, g4, followed by a single d4
+---event = { {"c4", "e4", "g4"}, "d4" }
+---```
+---```lua
 ----- a sequence of c4, g4 with volume 0.5
 ---event = sequence{"c4", "g4"}:volume(0.5)
 ---```
@@ -280,6 +398,15 @@ error("Do not try to execute this file. It's just a type definition file.")
 ---end
 ---```
 ---```lua
+----- automate a parameter along with notes
+---event = function(context)
+---  if context.step % 4 == 1 then
+---    return parameter_change("cutoff", math.random())
+---  end
+---  return "c4"
+---end
+---```
+---```lua
 ----- a note pattern
 ---local tritone = scale("c5", "tritone")
 -----[...]
@@ -290,7 +417,79 @@ error("Do not try to execute this file. It's just a type definition file.")
 ----- a tidal cycle
 ---event = cycle("<[a3 c4 e4 a4]*3 [d4 g3 g4 c4]>"),
 ---```
----@field event? Cycle|Sequence|Note|NoteValue|(NoteValue|Note)[]|(fun(context: EventContext):NoteValue)|(fun(context: EventContext):fun(context: EventContext):NoteValue)
+---@field event? Cycle|Sequence|Note|NoteValue|(NoteValue|Note)[]|(fun(context: EventContext):NoteValue|ParameterChange)|(fun(context: EventContext):fun(context: EventContext):NoteValue|ParameterChange)
+---
+---Optional instrument assignment for static event sequences. A single instrument id applies
+---to all events, an array of ids assigns instruments per step. When the end of the instrument
+---array is reached, it starts again from the beginning.
+---
+---Instruments are only applied to notes which have no instrument set already. Not supported
+---with generator functions or cycles: set instruments in the returned notes or cycles instead.
+---
+---### examples:
+---```lua
+----- alternate instruments 1 and 2 for the sequence c4, e4, g4
+---event = {"c4", "e4", "g4"},
+---instrument = {1, 2}
+---```
+---@field instrument? integer|integer[]
+---
+---Optional seed for the random number generators in pulse, gate and event function contexts
+---(`context.rng`). Each function gets its own generator, derived from the seed. Seeded
+---patterns, and all their duplicates, produce the same random numbers and restart their random
+---sequences when the pattern is reset. Negative seeds are distinct seeds. When not set, the seed
+---from `math.randomseed` is used, if any.
+---
+---### examples:
+---```lua
+----- reproducible random melody
+---seed = 1234,
+---event = function(context)
+---  return context.rng:choice({"c4", "e4", "g4"})
+---end
+---```
+---@field seed? integer
+
+---Optional swing amount in range \[0 - 1\], which delays notes on every second step of the
+---pattern: 0 plays straight and 1 delays offbeat notes by a third of a step (triplet feel).
+---Swing is applied on top of note delays and does not change the pattern's step length.
+---
+---### examples:
+---```lua
+----- swinging 8th hihats
+---unit = "1/8",
+---swing = 0.5,
+---event = "c4 #2"
+---```
+---@field swing? number
+
+---Optional swing grid: by default the pattern's steps swing. Set a division to swing notes on
+---a different grid, e.g. 16ths in a pattern which runs at 1/32. Only notes which start on every
+---second step of the grid get delayed, by the swing amount relative to the grid's step length.
+---
+---### examples:
+---```lua
+----- swinging 16ths in a 32th pattern
+---unit = "1/32",
+---swing = 0.5,
+---swing_division = "1/16",
+---event = "c4"
+---```
+---@field swing_division? "bars"|"beats"|"1/1"|"1/2"|"1/4"|"1/8"|"1/16"|"1/32"|"1/64"
+
+---Optional automatic accents: multiplies the volume of all notes on every `every`th step of the
+---pattern with the given `amount` >= 0. Steps are counted from the pattern's start, so the first
+---step always gets accented. Accents are applied on the straight step grid, so they stay on their
+---steps when swinging, and apply on top of note volumes from events and callbacks.
+---
+---### examples:
+---```lua
+----- accent the first 16th of every beat
+---unit = "1/16",
+---accent = { every = 4, amount = 1.3 },
+---event = "c4 v0.7"
+---```
+---@field accent? { every: integer, amount: number }
 
 
 ----------------------------------------------------------------------------------------------------
@@ -0,0 +1,70 @@
+---@meta
+error("Do not try to execute this file. It's just a type definition file.")
+---
+---Part of the pattrns crate: Defines LuaLS annotations for the pattrns random library.
+---
+
+----------------------------------------------------------------------------------------------------
+
+---Table with functions to generate non uniform distributed random numbers, e.g. for organic
+---modulations. All functions, except `perlin`, use the global random number generator, so
+---they get seeded via `math.randomseed` as well.
+---
+---### examples:
+---```lua
+----- humanize note volumes around 0.8
+---return pattern {
+---  event = function(context)
+---    return { key = "c4", volume = random.gauss(0.8, 0.1) }
+---  end
+---}
+---```
+---```lua
+----- slowly drifting panning values
+---return pattern {
+---  event = function(context)
+---    return { key = "c4", panning = random.perlin(context.pulse_time * 0.25) }
+---  end
+---}
+---```
+random = {}
+
+---Generate a normal (gaussian) distributed random number with the given mean and standard
+---deviation. About 68% of all values are within one standard deviation of the mean.
+---
+---### examples:
+---```lua
+---random.gauss() --> mean 0, standard deviation 1
+---random.gauss(60, 2) --> note numbers around 60
+---```
+---@param mean number? Mean value. By default 0.
+---@param stddev number? Standard deviation >= 0. By default 1.
+---@return number
+---@nodiscard
+function random.gauss(mean, stddev) end
+
+---Generate an exponential distributed random number >= 0 with the given rate. The mean of the
+---generated values is `1 / lambda`. Useful to generate e.g. time spans between random events.
+---
+---### examples:
+---```lua
+---random.exp(4) --> values around 0.25, rarely much larger ones
+---```
+---@param lambda number? Rate > 0. By default 1.
+---@return number
+---@nodiscard
+function random.exp(lambda) end
+
+---One dimensional Perlin gradient noise in range [-1, 1] at the given position. Values change
+---smoothly with the position and are 0 at integer positions. Perlin noise does not depend on
+---the random seed: the same position always results in the same value.
+---
+---### examples:
+---```lua
+---random.perlin(0.5) --> the same value in every run
+---random.perlin(context.pulse_step * 0.1) --> smoothly changing values per step
+---```
+---@param x number Noise position.
+---@return number
+---@nodiscard
+function random.perlin(x) end
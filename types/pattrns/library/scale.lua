@@ -81,6 +81,22 @@ function Scale:notes_iter(count) end
 ---@nodiscard
 function Scale:fit(...) end
 
+---Quantize given note value(s) to the nearest note in the scale. Notes exactly between two
+---scale notes are moved down. Returns nil for empty notes and note-offs.
+---
+---### examples:
+---```lua
+---local cmin = scale("c", "minor")
+---cmin:quantize("d#4", "e4", "f#4") --> 51, 51, 53
+----- build melodies from scale notes
+---local cmaj = scale("c4", "major")
+---cmaj:quantize(cmaj.notes[3] + 1) --> 53
+---```
+---@param ... NoteValue
+---@return integer ...
+---@nodiscard
+function Scale:quantize(...) end
+
 ----------------------------------------------------------------------------------------------------
 
 ---Available scale mode names.